@@ -0,0 +1,95 @@
+//! Append-only log of the external side effects (executed commands, API calls
+//! and uploads), one JSON object per line
+use anyhow::Context;
+use serde::Serialize;
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+static AUDIT_LOG: OnceLock<AuditLog> = OnceLock::new();
+
+struct AuditLog {
+    file: Mutex<File>,
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum AuditEvent<'a> {
+    Command {
+        program: String,
+        args: Vec<String>,
+    },
+    ApiCall {
+        method: &'a str,
+        endpoint: &'a str,
+    },
+    Upload {
+        file: &'a Path,
+        destination: &'a str,
+    },
+}
+
+#[derive(Serialize)]
+struct AuditEntry<'a> {
+    timestamp: String,
+    dry_run: bool,
+    #[serde(flatten)]
+    event: AuditEvent<'a>,
+}
+
+/// Starts recording events to the given file; events are dropped until the
+/// log is initialized
+pub fn init(path: &Path, dry_run: bool) -> anyhow::Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+    AUDIT_LOG
+        .set(AuditLog {
+            file: Mutex::new(file),
+            dry_run,
+        })
+        .map_err(|_| anyhow::anyhow!("BUG: audit log has already been initialized"))
+}
+
+pub fn record(event: AuditEvent) {
+    let log = match AUDIT_LOG.get() {
+        Some(log) => log,
+        None => return,
+    };
+
+    let entry = AuditEntry {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        dry_run: log.dry_run,
+        event,
+    };
+    let line = serde_json::to_string(&entry).expect("BUG: audit entry is not serializable");
+    let mut file = log.file.lock().unwrap();
+    if let Err(e) = writeln!(file, "{}", line) {
+        println!("WARN: Failed to write audit log: {}", e);
+    }
+}
+
+pub fn command(cmd: &tokio::process::Command) {
+    let cmd = cmd.as_std();
+    record(AuditEvent::Command {
+        program: cmd.get_program().to_string_lossy().into_owned(),
+        args: cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect(),
+    });
+}
+
+pub fn api_call(method: &str, endpoint: &str) {
+    record(AuditEvent::ApiCall { method, endpoint });
+}
+
+pub fn upload(file: &Path, destination: &str) {
+    record(AuditEvent::Upload { file, destination });
+}
@@ -0,0 +1,202 @@
+use anyhow::{bail, Context};
+use chrono::{Datelike, NaiveDate};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryInto, fmt::Display};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    /// Full year, e.g. 2024
+    FullYear,
+    /// Year without century, e.g. 24
+    ShortYear,
+    Month,
+    /// ISO week number
+    Week,
+    Day,
+    /// Counter of the releases made within the same date period
+    Micro,
+}
+
+impl Token {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "YYYY" => Self::FullYear,
+            "YY" => Self::ShortYear,
+            "MM" => Self::Month,
+            "WW" => Self::Week,
+            "DD" => Self::Day,
+            "MICRO" => Self::Micro,
+            _ => bail!(
+                "Unknown CalVer token `{}`, expected one of YYYY, YY, MM, WW, DD, MICRO",
+                s
+            ),
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::FullYear => "YYYY",
+            Self::ShortYear => "YY",
+            Self::Month => "MM",
+            Self::Week => "WW",
+            Self::Day => "DD",
+            Self::Micro => "MICRO",
+        }
+    }
+
+    fn date_value(self, date: NaiveDate) -> Option<u64> {
+        let value = match self {
+            Self::FullYear => date.year() as u64,
+            Self::ShortYear => (date.year() - 2000) as u64,
+            Self::Month => date.month() as u64,
+            Self::Week => date.iso_week().week() as u64,
+            Self::Day => date.day() as u64,
+            Self::Micro => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Calendar versioning pattern mapped onto the three semver components, e.g.
+/// `YY.MM.MICRO` produces `24.5.0`, `24.5.1`, ... for May 2024 releases
+#[derive(Clone, Debug)]
+pub struct CalVerPattern {
+    tokens: [Token; 3],
+}
+
+/// Date components of the CalVer version, exposed to the templates
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct CalVerContext {
+    pub year: Option<u64>,
+    pub month: Option<u64>,
+    pub week: Option<u64>,
+    pub day: Option<u64>,
+    pub micro: Option<u64>,
+}
+
+fn components(version: &Version) -> [u64; 3] {
+    [version.major, version.minor, version.patch]
+}
+
+impl CalVerPattern {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let tokens = pattern
+            .split('.')
+            .map(Token::parse)
+            .collect::<anyhow::Result<Vec<_>>>()
+            .with_context(|| format!("Invalid CalVer pattern `{}`", pattern))?;
+        let tokens: [Token; 3] = tokens.try_into().map_err(|_| {
+            anyhow::anyhow!(
+                "CalVer pattern `{}` should have exactly 3 components to form semver version",
+                pattern
+            )
+        })?;
+
+        if !matches!(tokens[0], Token::FullYear | Token::ShortYear) {
+            bail!("CalVer pattern `{}` should start with the year", pattern);
+        }
+        if tokens[..2].contains(&Token::Micro) {
+            bail!(
+                "MICRO should be the last component of CalVer pattern `{}`",
+                pattern
+            );
+        }
+
+        Ok(Self { tokens })
+    }
+
+    /// Whether date components of the version correspond to the given date
+    pub fn matches_date(&self, version: &Version, date: NaiveDate) -> bool {
+        self.tokens
+            .iter()
+            .zip(components(version))
+            .all(|(token, value)| token.date_value(date).is_none_or(|d| d == value))
+    }
+
+    /// Next version released at the given date; MICRO continues the previous
+    /// version counter only within the same date period
+    pub fn next(&self, prev: Option<&Version>, date: NaiveDate) -> Version {
+        let same_period = prev.is_some_and(|prev| self.matches_date(prev, date));
+        let prev = prev.map(components);
+        let mut next = [0; 3];
+        for (i, token) in self.tokens.iter().enumerate() {
+            next[i] = match token.date_value(date) {
+                Some(value) => value,
+                None if same_period => prev.map_or(0, |p| p[i] + 1),
+                None => 0,
+            };
+        }
+        Version::new(next[0], next[1], next[2])
+    }
+
+    pub fn context(&self, version: &Version) -> CalVerContext {
+        let mut context = CalVerContext::default();
+        for (token, value) in self.tokens.iter().zip(components(version)) {
+            match token {
+                Token::FullYear => context.year = Some(value),
+                Token::ShortYear => context.year = Some(2000 + value),
+                Token::Month => context.month = Some(value),
+                Token::Week => context.week = Some(value),
+                Token::Day => context.day = Some(value),
+                Token::Micro => context.micro = Some(value),
+            }
+        }
+        context
+    }
+}
+
+impl Display for CalVerPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tokens = self.tokens.map(Token::as_str);
+        write!(f, "{}", tokens.join("."))
+    }
+}
+
+impl<'de> Deserialize<'de> for CalVerPattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de;
+
+        let pattern = String::deserialize(deserializer)?;
+        Self::new(&pattern).map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Str(&pattern), &"valid CalVer pattern")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn next_versions() {
+        let pattern = CalVerPattern::new("YY.MM.MICRO").unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 17).unwrap();
+
+        let next = vec![None, Some("24.5.2"), Some("24.4.7"), Some("23.12.0")]
+            .into_iter()
+            .map(|prev| {
+                let prev = prev.map(|v| Version::parse(v).unwrap());
+                format!(
+                    "{:?} -> {}",
+                    prev.as_ref().map(Version::to_string),
+                    pattern.next(prev.as_ref(), date)
+                )
+            })
+            .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                "None -> 24.5.0",
+                "Some(\"24.5.2\") -> 24.5.3",
+                "Some(\"24.4.7\") -> 24.5.0",
+                "Some(\"23.12.0\") -> 24.5.0",
+            ]
+        "#]]
+        .assert_debug_eq(&next);
+    }
+}
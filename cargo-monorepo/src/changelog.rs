@@ -0,0 +1,635 @@
+//! Changelog capture shared by the release and the standalone checks
+use crate::{
+    config::{ChangelogMapping, ChangelogSource, Config, LabelSection},
+    pattern::FilePattern,
+    registry,
+    template::TextTemplateContext,
+    utils::{metadata_command, workspace_path},
+};
+use anyhow::{anyhow, bail, Context};
+use cargo_metadata::{camino::Utf8Path, Package};
+use semver::Version;
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Changelog maintenance commands")]
+pub struct Command {
+    #[structopt(subcommand)]
+    subcommand: Subcommand,
+}
+
+#[derive(clap::Parser, Debug)]
+enum Subcommand {
+    Check(CheckCommand),
+}
+
+impl Command {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        match self.subcommand {
+            Subcommand::Check(cmd) => cmd.run(config).await,
+        }
+    }
+}
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Check that changelog has non-empty section for the pending version")]
+pub struct CheckCommand {
+    /// Pending version, root crate version from Cargo.toml is used by default
+    #[structopt(long)]
+    version: Option<Version>,
+}
+
+impl CheckCommand {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        let changelog_config = config
+            .changelog
+            .as_ref()
+            .ok_or_else(|| anyhow!("changelog section is missing from the config"))?;
+        if changelog_config.source != ChangelogSource::File {
+            println!(
+                "Changelog is captured with `source = \"{}\"`, nothing to check",
+                changelog_config.source.name()
+            );
+            return Ok(());
+        }
+        let context = pending_template_context(&config, self.version)?;
+
+        let changelog = tokio::fs::read_to_string(workspace_path(&changelog_config.file))
+            .await
+            .with_context(|| format!("Failed to read {}", changelog_config.file.display()))?;
+
+        let markers = changelog_config
+            .marker_templates()
+            .into_iter()
+            .map(|(start, end)| Ok((start.render(&context)?, end.render(&context)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if let Some(heading_template) = &changelog_config.version_heading_template {
+            let start_marker = markers.first().map(|(start, _)| start.as_str());
+            check_version_heading(
+                &changelog,
+                start_marker,
+                &heading_template.render(&context)?,
+            )?;
+        }
+
+        let captured = if markers.is_empty() {
+            changelog
+        } else {
+            capture_regions(&changelog, &markers)?
+        };
+
+        if captured.trim().is_empty() {
+            if !changelog_config.allow_empty_changelog {
+                bail!(
+                    "Changelog for {} {} is empty",
+                    context.root_crate,
+                    context.version
+                );
+            }
+            println!("WARN: empty changelog");
+        }
+
+        println!(
+            "✅ Changelog for {} {} is present",
+            context.root_crate, context.version
+        );
+        Ok(())
+    }
+}
+
+/// Template context for the version which is going to be released, root crate
+/// version from Cargo.toml is used if not specified explicitly
+pub fn pending_template_context(
+    config: &Config,
+    version: Option<Version>,
+) -> anyhow::Result<TextTemplateContext> {
+    let root_crate = &config.workspace.root_crate;
+    let version = match version {
+        Some(version) => version,
+        None => {
+            let metadata = metadata_command()
+                .no_deps()
+                .exec()
+                .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+            metadata
+                .packages
+                .iter()
+                .find(|p| &p.name == root_crate && metadata.workspace_members.contains(&p.id))
+                .map(|p| p.version.clone())
+                .ok_or_else(|| anyhow!("Root crate `{}` is not found in workspace", root_crate))?
+        }
+    };
+
+    Ok(TextTemplateContext {
+        root_crate: root_crate.clone(),
+        version: version.clone(),
+        changelog: None,
+        hotfix: false,
+        registry: registry::registry_name(config.release.as_ref()),
+        registry_url: registry::registry_url(config.release.as_ref()),
+        published_crates: vec![],
+        packages: vec![],
+        crate_notes: None,
+        assets: vec![],
+        calver: config
+            .release
+            .as_ref()
+            .and_then(|r| r.calver())
+            .map(|c| c.context(&version)),
+        prev_version: None,
+        prev_release_url: None,
+        prev_assets: vec![],
+    })
+}
+
+/// Captures text between each pair of markers, joining non-empty regions in
+/// the order of the pairs
+pub fn capture_regions(changelog: &str, markers: &[(String, String)]) -> anyhow::Result<String> {
+    let changelog_lines = changelog.lines().collect::<Vec<_>>();
+    let mut regions = vec![];
+
+    for (begin_marker, end_marker) in markers {
+        let begin_line = changelog_lines
+            .iter()
+            .position(|l| l.contains(begin_marker.as_str()));
+        let end_line = changelog_lines
+            .iter()
+            .position(|l| l.contains(end_marker.as_str()));
+
+        match (begin_line, end_line) {
+            (Some(begin), Some(end)) => {
+                if end <= begin {
+                    bail!(
+                        "Changelog end barker should be placed \
+                        after corresponding begin marker"
+                    );
+                }
+
+                let region = changelog_lines[begin + 1..end].join("\n");
+                if !region.trim().is_empty() {
+                    regions.push(region);
+                }
+            }
+            (None, Some(_)) => {
+                bail!(
+                    "Can't find required changelog begin marker {}",
+                    begin_marker
+                );
+            }
+            (Some(_), None) => {
+                bail!("Can't find required changelog end marker {}", end_marker);
+            }
+            (None, None) => {
+                bail!(
+                    "Can't find required changelog markers {} and {}",
+                    begin_marker,
+                    end_marker
+                );
+            }
+        }
+    }
+
+    Ok(regions.join("\n"))
+}
+
+/// `true` if the release is semver-incompatible with the previous one; the
+/// leftmost non-zero component is the major one, as in cargo requirements
+pub fn is_breaking_release(prev: &Version, version: &Version) -> bool {
+    if prev.major != 0 || version.major != prev.major {
+        version.major > prev.major
+    } else if prev.minor != 0 || version.minor != prev.minor {
+        version.minor > prev.minor
+    } else {
+        version.patch > prev.patch
+    }
+}
+
+/// `true` if the changelog contains the heading (compared ignoring case and
+/// surrounding whitespace) followed by some text before the next heading
+pub fn has_section(changelog: &str, heading: &str) -> bool {
+    let mut lines = changelog.lines().map(str::trim);
+    if !lines.any(|l| l.eq_ignore_ascii_case(heading.trim())) {
+        return false;
+    }
+    lines
+        .take_while(|l| !l.starts_with('#'))
+        .any(|l| !l.is_empty())
+}
+
+/// Checks that heading of the section started by the marker (or of the whole
+/// changelog) mentions the pending version
+pub fn check_version_heading(
+    changelog: &str,
+    start_marker: Option<&str>,
+    expected: &str,
+) -> anyhow::Result<()> {
+    let lines = changelog.lines().collect::<Vec<_>>();
+    let is_heading = |line: &str| line.trim_start().starts_with('#');
+
+    let heading = match start_marker {
+        Some(marker) => {
+            let start = lines
+                .iter()
+                .position(|l| l.contains(marker))
+                .ok_or_else(|| anyhow!("Can't find required changelog begin marker {}", marker))?;
+            // Heading placed right after the marker belongs to the section,
+            // otherwise the section is nested under the closest heading above
+            lines[start + 1..]
+                .iter()
+                .find(|l| !l.trim().is_empty())
+                .filter(|l| is_heading(l))
+                .or_else(|| lines[..start].iter().rev().find(|l| is_heading(l)))
+        }
+        None => lines.iter().find(|l| is_heading(l)),
+    };
+
+    match heading {
+        Some(heading) if heading.contains(expected) => Ok(()),
+        Some(heading) => bail!(
+            "Changelog section heading `{}` does not mention `{}`, \
+            has the section been retitled for the pending version?",
+            heading.trim(),
+            expected
+        ),
+        None => bail!(
+            "Changelog section has no heading to check for `{}`",
+            expected
+        ),
+    }
+}
+
+/// Format for `git log` which is parsed by [`parse_git_log`]
+pub const GIT_LOG_FORMAT: &str = "--format=%h%x1f%s";
+
+#[derive(Debug)]
+pub struct Commit {
+    pub hash: String,
+    pub subject: String,
+    /// Changed files, relative to the workspace root
+    pub files: Vec<String>,
+}
+
+/// Commits and paths which belong to the crate
+pub struct CrateRoute {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub paths: Vec<FilePattern>,
+}
+
+impl CrateRoute {
+    /// Routes crate directory and the extra mapped scopes and paths
+    pub fn for_package(
+        package: &Package,
+        workspace_root: &Utf8Path,
+        mapping: &[ChangelogMapping],
+    ) -> anyhow::Result<Self> {
+        let crate_dir = package
+            .manifest_path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(workspace_root).ok())
+            .ok_or_else(|| anyhow!("Invalid manifest path of `{}` crate", package.name))?;
+        let dir_pattern = match crate_dir.as_str() {
+            "" => "**".to_owned(),
+            dir => format!("{}/**", dir.replace('\\', "/")),
+        };
+
+        let mut route = Self {
+            name: package.name.clone(),
+            scopes: vec![package.name.clone()],
+            paths: vec![FilePattern::new(&dir_pattern)?],
+        };
+        for mapping in mapping.iter().filter(|m| m.crate_name == package.name) {
+            route.scopes.extend(mapping.scopes.iter().cloned());
+            route.paths.extend(mapping.paths.iter().cloned());
+        }
+        Ok(route)
+    }
+
+    pub fn matches(&self, commit: &Commit) -> bool {
+        let scope_matches = commit_scope(&commit.subject)
+            .is_some_and(|scope| self.scopes.iter().any(|s| s == scope));
+        scope_matches
+            || commit
+                .files
+                .iter()
+                .any(|f| self.paths.iter().any(|p| p.matches(f)))
+    }
+}
+
+/// Parses `git log` output produced with [`GIT_LOG_FORMAT`] and `--name-only`
+pub fn parse_git_log(log: &str) -> Vec<Commit> {
+    let mut commits: Vec<Commit> = vec![];
+    for line in log.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        match line.split_once('\x1f') {
+            Some((hash, subject)) => commits.push(Commit {
+                hash: hash.to_owned(),
+                subject: subject.to_owned(),
+                files: vec![],
+            }),
+            None => {
+                if let Some(commit) = commits.last_mut() {
+                    commit.files.push(line.replace('\\', "/"));
+                }
+            }
+        }
+    }
+    commits
+}
+
+/// Scope of the conventional commit, e.g. `net` for `fix(net): timeout`
+pub fn commit_scope(subject: &str) -> Option<&str> {
+    let (prefix, _) = subject.split_once(':')?;
+    let (_, scope) = prefix.trim_end_matches('!').split_once('(')?;
+    scope.strip_suffix(')')
+}
+
+/// Groups commits into per-crate sections; commits which don't belong to any
+/// of the crates are dropped
+pub fn commits_changelog(commits: &[Commit], routes: &[CrateRoute]) -> String {
+    let mut sections = vec![];
+    for route in routes {
+        let entries = commits
+            .iter()
+            .filter(|c| route.matches(c))
+            .map(|c| format!("- {} ({})", c.subject, c.hash))
+            .collect::<Vec<_>>();
+        if entries.is_empty() {
+            continue;
+        }
+        let section = if routes.len() == 1 {
+            entries.join("\n")
+        } else {
+            format!("### {}\n{}", route.name, entries.join("\n"))
+        };
+        sections.push(section);
+    }
+    sections.join("\n\n")
+}
+
+/// Number of the PR which merged the commit, from the merge commit subject
+/// (`Merge pull request #12 from ...`) or the squash subject (`Title (#12)`)
+pub fn pull_request_number(subject: &str) -> Option<u64> {
+    let number = match subject.strip_prefix("Merge pull request #") {
+        Some(rest) => rest.split_whitespace().next()?,
+        None => subject.trim_end().strip_suffix(')')?.rsplit_once("(#")?.1,
+    };
+    number.parse().ok()
+}
+
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub labels: Vec<String>,
+}
+
+/// Groups PRs into label sections in the configured order; PRs without
+/// matching labels go to `other_title` section or are dropped if it is empty
+pub fn pull_requests_changelog(
+    pull_requests: &[PullRequest],
+    sections: &[LabelSection],
+    other_title: &str,
+) -> String {
+    let section_index = |pr: &PullRequest| {
+        sections
+            .iter()
+            .position(|s| s.labels.iter().any(|l| pr.labels.contains(l)))
+    };
+    let titles = sections
+        .iter()
+        .map(|s| s.title.as_str())
+        .chain(Some(other_title).filter(|t| !t.is_empty()));
+    titles
+        .enumerate()
+        .filter_map(|(index, title)| {
+            let entries = pull_requests
+                .iter()
+                .filter(|pr| section_index(pr).unwrap_or(sections.len()) == index)
+                .map(|pr| format!("- {} (#{})", pr.title, pr.number))
+                .collect::<Vec<_>>();
+            if entries.is_empty() {
+                None
+            } else {
+                Some(format!("### {}\n{}", title, entries.join("\n")))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn multiple_regions() {
+        let changelog = "\
+# Changelog
+<!-- fixed -->
+- Crash on empty workspace
+<!-- /fixed -->
+<!-- added -->
+- `check` command
+<!-- /added -->
+<!-- removed -->
+<!-- /removed -->
+";
+        let markers = [
+            ("added", "/added"),
+            ("removed", "/removed"),
+            ("fixed", "/fixed"),
+        ]
+        .iter()
+        .map(|(b, e)| (format!("<!-- {} -->", b), format!("<!-- {} -->", e)))
+        .collect::<Vec<_>>();
+
+        expect![[r#"
+            - `check` command
+            - Crash on empty workspace"#]]
+        .assert_eq(&capture_regions(changelog, &markers).unwrap());
+    }
+
+    #[test]
+    fn commits_routing() {
+        let log = "\
+a1b2c3d\x1ffix(net): reconnect on timeout
+
+crates/core/src/lib.rs
+
+e4f5a6b\x1fdocs: update readme
+
+README.md
+
+c7d8e9f\x1frefactor: share buffers
+
+crates/net/src/buf.rs
+crates/core/src/buf.rs
+";
+        let route = |name: &str, path: &str| CrateRoute {
+            name: name.to_owned(),
+            scopes: vec![name.to_owned()],
+            paths: vec![FilePattern::new(path).unwrap()],
+        };
+        let routes = vec![
+            route("net", "crates/net/**"),
+            route("core", "crates/core/**"),
+        ];
+
+        expect![[r#"
+            ### net
+            - fix(net): reconnect on timeout (a1b2c3d)
+            - refactor: share buffers (c7d8e9f)
+
+            ### core
+            - fix(net): reconnect on timeout (a1b2c3d)
+            - refactor: share buffers (c7d8e9f)"#]]
+        .assert_eq(&commits_changelog(&parse_git_log(log), &routes));
+    }
+
+    #[test]
+    fn version_heading() {
+        let changelog = "\
+# Changelog
+
+## Unreleased
+<!-- start -->
+- Sparse index support
+<!-- end -->
+
+## [1.2.0] - 2024-05-01
+- Nightly channel
+";
+
+        let results = vec![
+            check_version_heading(changelog, Some("<!-- start -->"), "[1.3.0]"),
+            check_version_heading(changelog, Some("<!-- start -->"), "Unreleased"),
+            check_version_heading(
+                "<!-- start -->\n## [1.3.0]\n- Fix",
+                Some("<!-- start -->"),
+                "[1.3.0]",
+            ),
+            check_version_heading("- Fix", None, "[1.3.0]"),
+        ]
+        .into_iter()
+        .map(|r| r.map_err(|e| e.to_string()))
+        .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                Err(
+                    "Changelog section heading `## Unreleased` does not mention `[1.3.0]`, has the section been retitled for the pending version?",
+                ),
+                Ok(
+                    (),
+                ),
+                Ok(
+                    (),
+                ),
+                Err(
+                    "Changelog section has no heading to check for `[1.3.0]`",
+                ),
+            ]
+        "#]].assert_debug_eq(&results);
+    }
+
+    #[test]
+    fn pull_requests_sections() {
+        let numbers = vec![
+            "Merge pull request #12 from user/branch",
+            "Add sparse index support (#34)",
+            "Bump version",
+        ]
+        .into_iter()
+        .map(pull_request_number)
+        .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                Some(
+                    12,
+                ),
+                Some(
+                    34,
+                ),
+                None,
+            ]
+        "#]]
+        .assert_debug_eq(&numbers);
+
+        let pr = |number: u64, title: &str, labels: &[&str]| PullRequest {
+            number,
+            title: title.to_owned(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+        };
+        let pull_requests = vec![
+            pr(1, "Fix timeout", &["fix"]),
+            pr(2, "Drop old API", &["feature", "breaking"]),
+            pr(3, "Update CI", &[]),
+            pr(4, "Retries", &["feature"]),
+        ];
+        let sections = vec![
+            ("Breaking", "breaking"),
+            ("Features", "feature"),
+            ("Fixes", "fix"),
+        ]
+        .into_iter()
+        .map(|(title, label)| LabelSection {
+            title: title.to_owned(),
+            labels: vec![label.to_owned()],
+        })
+        .collect::<Vec<_>>();
+        expect![[r#"
+            ### Breaking
+            - Drop old API (#2)
+
+            ### Features
+            - Retries (#4)
+
+            ### Fixes
+            - Fix timeout (#1)
+
+            ### Other
+            - Update CI (#3)"#]]
+        .assert_eq(&pull_requests_changelog(&pull_requests, &sections, "Other"));
+    }
+
+    #[test]
+    fn migration_section() {
+        let releases = vec![
+            ("1.2.0", "2.0.0"),
+            ("0.3.1", "0.4.0"),
+            ("0.3.1", "0.3.2"),
+            ("0.0.1", "0.0.2"),
+        ]
+        .into_iter()
+        .map(|(prev, version)| {
+            is_breaking_release(
+                &Version::parse(prev).unwrap(),
+                &Version::parse(version).unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                true,
+                true,
+                false,
+                true,
+            ]
+        "#]]
+        .assert_debug_eq(&releases);
+
+        let changelog = "### Changed\n- New API\n\n## migration\n\n- Use `Reader::open`\n";
+        let empty = "## Migration\n\n## Fixed\n- Crash\n";
+        let sections = vec![changelog, empty, "- New API"]
+            .into_iter()
+            .map(|c| has_section(c, "## Migration"))
+            .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                true,
+                false,
+                false,
+            ]
+        "#]]
+        .assert_debug_eq(&sections);
+    }
+}
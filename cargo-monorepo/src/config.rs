@@ -1,7 +1,14 @@
-use crate::{github, template::TextTemplate};
-use anyhow::bail;
+use crate::{
+    calver::CalVerPattern, github, pattern::FilePattern, registry, template::TextTemplate,
+};
+use anyhow::{anyhow, bail, Context};
+use chrono::NaiveDate;
+use semver::Version;
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
 
 #[derive(Deserialize, Clone)]
 pub struct Config {
@@ -15,6 +22,22 @@ pub struct Config {
     pub artifacts: Option<Artifacts>,
     /// Release command related options
     pub release: Option<Release>,
+    /// Notifications of the external services after the release
+    pub post_release: Option<PostRelease>,
+    /// Files rendered from templates at release time
+    #[serde(default, rename = "generated-file")]
+    pub generated_files: Vec<GeneratedFile>,
+    /// Release train schedule, used with `release --train`
+    pub train: Option<Train>,
+    /// Independently versioned crate groups, released with `--group`
+    #[serde(default)]
+    pub group: BTreeMap<String, Group>,
+    /// Release metrics export
+    pub metrics: Option<Metrics>,
+    /// Upload of the release logs on failure
+    pub failure_log: Option<FailureLog>,
+    /// Commands executed around the release actions
+    pub hooks: Option<Hooks>,
 }
 
 impl Config {
@@ -23,18 +46,121 @@ impl Config {
             return Ok(());
         }
         let release = self.release.as_ref().unwrap();
-        if release.registry.is_some() && release.check_version_raised {
-            // `cargo search` allows to specify custom index/registry, however
-            // some registries (e.g. Cloudsmith) don't implement cargo search properly.
-            // More interestingly, Cloudsmith's publish succeeds even if same version
-            // is already exist... So disable this for now to make sure everything is
-            // fine
+        if release.registry.is_some()
+            && release.check_version_raised
+            && release.registry_index_url.is_none()
+        {
+            // Some registries (e.g. Cloudsmith) don't implement cargo search
+            // properly, so released versions are queried from the sparse index
+            bail!(
+                "release.registry_index_url should be specified to query last released version \
+                from the custom registry, or set `release.check_version_raised` to false in the \
+                config to approve skip of this step"
+            );
+        }
+
+        if release.cache_prime_from.is_some() && release.cache_dir.is_none() {
+            bail!(
+                "release.cache_dir should be specified to be able to use release.cache_prime_from"
+            );
+        }
+
+        if release.registry.is_none() && release.registry_api.is_some() {
+            bail!("release.registry should be specified to be able to use release.registry_api");
+        }
+
+        if release.registry.is_some() && release.mirror.is_some() {
+            bail!("release.mirror is supported only for crates published to crates.io");
+        }
+
+        if release
+            .npm
+            .as_ref()
+            .is_some_and(|npm| npm.crates.is_empty())
+        {
+            bail!("release.npm.crates should not be empty");
+        }
+
+        let announcement_pr = release
+            .announcement
+            .as_ref()
+            .and_then(|a| a.pull_request.as_ref());
+        if announcement_pr.is_some() && self.github.is_none() {
             bail!(
-                "Querying last released version is not yet supported for custom registries, \
-                set `release.check_version_raised` to false in the config to approve skip of this step"
+                "github.repo should be specified to be able to use \
+                release.announcement.pull_request"
             );
         }
 
+        if release.nightly.is_some() && self.github.is_none() {
+            bail!("github.repo should be specified to be able to use release.nightly");
+        }
+        if let Some(lock) = &release.lock {
+            if self.github.is_none() {
+                bail!("github.repo should be specified to be able to use release.lock");
+            }
+            if !lock.lock_ref.starts_with("refs/") || lock.lock_ref.matches('/').count() < 2 {
+                bail!("release.lock.lock_ref should be a fully qualified ref, e.g. refs/locks/release");
+            }
+        }
+
+        if release
+            .readme
+            .as_ref()
+            .is_some_and(|r| r.rewrite_relative_links)
+            && self.github.is_none()
+        {
+            bail!("github.repo should be specified to be able to use release.readme.rewrite_relative_links");
+        }
+
+        if let Some(policy) = &release.crate_metadata {
+            // crates.io limit
+            if policy.required_keywords.len() > 5 || policy.required_categories.len() > 5 {
+                bail!("release.crate_metadata should require at most 5 keywords and 5 categories");
+            }
+        }
+
+        if release
+            .version_command
+            .as_ref()
+            .is_some_and(|c| c.trim().is_empty())
+        {
+            bail!("release.version_command should not be empty");
+        }
+
+        if release.skip_unchanged {
+            if release.github.is_none() {
+                bail!("release.github should be specified to find the previous release tag for release.skip_unchanged");
+            }
+            if release.bump_dependents {
+                bail!("release.skip_unchanged can't be used with release.bump_dependents");
+            }
+        }
+
+        if release.version_from == VersionSource::ConventionalCommits {
+            if release.version_command.is_some() {
+                bail!("release.version_command can't be used with version_from = \"conventional-commits\"");
+            }
+            if release.calver().is_some() {
+                bail!("CalVer versions can't be inferred from conventional commits");
+            }
+        }
+
+        if release.rollback_yank_crates && !release.rollback_on_failure {
+            bail!("release.rollback_yank_crates requires release.rollback_on_failure");
+        }
+        for policy in &release.path_policies {
+            if policy.paths.is_empty() {
+                bail!("release.path_policies rule should have at least one path");
+            }
+            if policy.require_changelog_section.is_none() && !policy.block {
+                bail!("release.path_policies rule should either require a changelog section or block the release");
+            }
+            if policy.require_changelog_section.is_some() && self.changelog.is_none() {
+                bail!("changelog section should be specified to require changelog sections in release.path_policies");
+            }
+        }
+
         if let Some(release_github) = &release.github {
             if self.github.is_none() {
                 bail!("github.repo should be specified to be able to use release.github");
@@ -45,12 +171,40 @@ impl Config {
                     release.github.release_page_upload_artifacts is set to true"
                 );
             }
+            if let Some(large_assets) = &release_github.large_assets {
+                if large_assets.attempts == 0 {
+                    bail!("release.github.large_assets.attempts should be greater than 0");
+                }
+                if large_assets.part_bytes == Some(0) {
+                    bail!("release.github.large_assets.part_bytes should be greater than 0");
+                }
+            }
             if release_github.create_release_page && !release_github.create_tag {
                 bail!(
                     "github.create_tag should be enabled when \
                     github.create_release_page is required"
                 );
             }
+            if release_github.attestation.is_some() && !release_github.release_page_upload_artifacts
+            {
+                bail!(
+                    "github.release_page_upload_artifacts should be enabled when \
+                    github.attestation is used"
+                );
+            }
+            if let Some(manifest) = &release_github.update_manifest {
+                if !(release_github.create_release_page
+                    && release_github.release_page_upload_artifacts)
+                {
+                    bail!(
+                        "github.create_release_page and github.release_page_upload_artifacts \
+                        should be enabled when github.update_manifest is used"
+                    );
+                }
+                if manifest.platforms.is_empty() {
+                    bail!("github.update_manifest.platforms should not be empty");
+                }
+            }
         }
 
         Ok(())
@@ -64,20 +218,452 @@ impl Config {
         if changelog.start_marker_template.is_some() ^ changelog.end_marker_template.is_some() {
             bail!("Both changelog_start_pattern and changelog_end_pattern should be specified");
         }
+        if changelog.source != ChangelogSource::File {
+            let source = changelog.source.name();
+            if !changelog.marker_templates().is_empty() {
+                bail!(
+                    "changelog markers can't be used with `source = \"{}\"`",
+                    source
+                );
+            }
+            if changelog.version_heading_template.is_some() {
+                bail!(
+                    "changelog.version_heading_template can't be used with `source = \"{}\"`",
+                    source
+                );
+            }
+            if changelog.promote_unreleased.is_some() {
+                bail!(
+                    "changelog.promote_unreleased can't be used with `source = \"{}\"`",
+                    source
+                );
+            }
+        }
+        if changelog
+            .lint
+            .as_ref()
+            .is_some_and(|l| l.command.is_empty())
+        {
+            bail!("changelog.lint.command should not be empty");
+        }
+        let release_github = self.release.as_ref().and_then(|r| r.github.as_ref());
+        if changelog.source == ChangelogSource::TagMessage && release_github.is_none() {
+            bail!(
+                "release.github should be specified to be able to use \
+                `source = \"tag-message\"`, the tag name is rendered from its tag_name_template"
+            );
+        }
+        if changelog.source == ChangelogSource::PullRequests && self.github.is_none() {
+            bail!(
+                "github section should be specified to be able to use `source = \"pull-requests\"`"
+            );
+        }
+        if changelog.label_sections.iter().any(|s| s.labels.is_empty()) {
+            bail!("changelog.label_sections should have at least one label each");
+        }
+        Ok(())
+    }
+
+    fn validate_artifacts(&self) -> anyhow::Result<()> {
+        let reproducibility = self
+            .artifacts
+            .as_ref()
+            .and_then(|a| a.reproducibility.as_ref());
+        if let Some(reproducibility) = reproducibility {
+            if reproducibility.build_command.is_empty() {
+                bail!("artifacts.reproducibility.build_command should not be empty");
+            }
+        }
+
+        let delta_patches = self
+            .artifacts
+            .as_ref()
+            .and_then(|a| a.delta_patches.as_ref());
+        if let Some(delta_patches) = delta_patches {
+            if self.github.is_none() {
+                bail!("github.repo should be specified to be able to use artifacts.delta_patches");
+            }
+            if delta_patches.patterns.is_empty() {
+                bail!("artifacts.delta_patches.patterns should not be empty");
+            }
+        }
+
+        let size_limits = self.artifacts.iter().flat_map(|a| a.size_limits.iter());
+        for limit in size_limits {
+            if limit.min_size.is_none() && limit.max_size.is_none() {
+                bail!(
+                    "Either min_size or max_size should be specified for `{}` artifact size limit",
+                    limit.pattern
+                );
+            }
+            if let (Some(min), Some(max)) = (limit.min_size, limit.max_size) {
+                if min > max {
+                    bail!(
+                        "min_size should not exceed max_size for `{}` artifact size limit",
+                        limit.pattern
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_groups(&self) -> anyhow::Result<()> {
+        let mut owners = HashMap::new();
+        for (name, group) in &self.group {
+            for member in group.all_members() {
+                if let Some(other) = owners.insert(member, name) {
+                    bail!(
+                        "Crate `{}` belongs to both `{}` and `{}` release groups",
+                        member,
+                        other,
+                        name
+                    );
+                }
+            }
+
+            let has_github = self.release.as_ref().is_some_and(|r| r.github.is_some());
+            if group.github.is_some() && !has_github {
+                bail!(
+                    "group.{}.github requires release.github section to be specified",
+                    name
+                );
+            }
+
+            if let Some(changelog) = &group.changelog {
+                if changelog.file.is_none() && self.changelog.is_none() {
+                    bail!(
+                        "group.{}.changelog.file should be specified when \
+                        top-level changelog section is missing",
+                        name
+                    );
+                }
+                if changelog.start_marker_template.is_some()
+                    ^ changelog.end_marker_template.is_some()
+                {
+                    bail!(
+                        "Both start_marker_template and end_marker_template should be \
+                        specified for group.{}.changelog",
+                        name
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds config for the independent release of the given group
+    pub fn for_group(&self, name: &str) -> anyhow::Result<Config> {
+        let group = self.group.get(name).ok_or_else(|| {
+            let available = self.group.keys().cloned().collect::<Vec<_>>().join(", ");
+            anyhow!(
+                "Release group `{}` is not defined, available groups: {}",
+                name,
+                available
+            )
+        })?;
+
+        let mut config = self.clone();
+        config.group.clear();
+        config.workspace.root_crate = group.root_crate.clone();
+        config.workspace.members = group.members.clone();
+
+        if let Some(tag_name_template) = &group.tag_name_template {
+            if let Some(github) = config.release.as_mut().and_then(|r| r.github.as_mut()) {
+                github.tag_name_template = tag_name_template.clone();
+            }
+        }
+
+        if let Some(group_github) = &group.github {
+            if let Some(github) = config.release.as_mut().and_then(|r| r.github.as_mut()) {
+                if let Some(create_tag) = group_github.create_tag {
+                    github.create_tag = create_tag;
+                }
+                if let Some(create_release_page) = group_github.create_release_page {
+                    github.create_release_page = create_release_page;
+                }
+                if let Some(upload_artifacts) = group_github.release_page_upload_artifacts {
+                    github.release_page_upload_artifacts = upload_artifacts;
+                }
+                if let Some(title_template) = &group_github.release_page_title_template {
+                    github.release_page_title_template = title_template.clone();
+                }
+                if let Some(body_template) = &group_github.release_page_body_template {
+                    github.release_page_body_template = body_template.clone();
+                }
+            }
+        }
+
+        if let Some(group_changelog) = &group.changelog {
+            let changelog = match config.changelog.take() {
+                Some(mut changelog) => {
+                    if let Some(file) = &group_changelog.file {
+                        changelog.file = file.clone();
+                    }
+                    if group_changelog.start_marker_template.is_some() {
+                        changelog.start_marker_template =
+                            group_changelog.start_marker_template.clone();
+                        changelog.end_marker_template = group_changelog.end_marker_template.clone();
+                        // Sections mark regions of the top-level root crate
+                        changelog.sections.clear();
+                    }
+                    changelog
+                }
+                None => Changelog {
+                    // Presence is checked by the config validation
+                    file: group_changelog.file.clone().unwrap(),
+                    source: ChangelogSource::File,
+                    mapping: vec![],
+                    label_sections: default_label_sections(),
+                    other_changes_title: default_other_changes_title(),
+                    start_marker_template: group_changelog.start_marker_template.clone(),
+                    end_marker_template: group_changelog.end_marker_template.clone(),
+                    sections: vec![],
+                    version_heading_template: None,
+                    promote_unreleased: None,
+                    print_to_stdout: false,
+                    allow_empty_changelog: false,
+                    lint: None,
+                    migration_heading: None,
+                    crate_notes: None,
+                },
+            };
+            config.changelog = Some(changelog);
+        }
+
+        // Overrides could break rules which hold for the top-level sections
+        config
+            .validate()
+            .with_context(|| format!("Invalid config of `{}` release group", name))?;
+        Ok(config)
+    }
+
+    fn validate_train(&self) -> anyhow::Result<()> {
+        let train = match &self.train {
+            Some(train) => train,
+            None => return Ok(()),
+        };
+        if train.cadence_days == 0 {
+            bail!("train.cadence_days should be greater than zero");
+        }
+        if train.window_days == 0 || train.window_days > train.cadence_days {
+            bail!("train.window_days should be in range from 1 to train.cadence_days");
+        }
+        Ok(())
+    }
+
+    fn validate_metrics(&self) -> anyhow::Result<()> {
+        if let Some(metrics) = &self.metrics {
+            if metrics.pushgateway.is_none() && metrics.statsd.is_none() {
+                bail!("Either metrics.pushgateway or metrics.statsd should be specified");
+            }
+        }
         Ok(())
     }
 
+    fn validate_hooks(&self) -> anyhow::Result<()> {
+        if let Some(hooks) = &self.hooks {
+            if hooks.pre_publish_package.is_empty() && hooks.post_publish_package.is_empty() {
+                bail!("Either hooks.pre_publish_package or hooks.post_publish_package should be specified");
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_failure_log(&self) -> anyhow::Result<()> {
+        if let Some(failure_log) = &self.failure_log {
+            if failure_log.gist == failure_log.paste_url.is_some() {
+                bail!("Either failure_log.gist or failure_log.paste_url should be specified");
+            }
+        }
+        Ok(())
+    }
+
+    /// Env vars with secrets which should be provided for the release
+    pub fn required_secrets(&self) -> Vec<String> {
+        let release = self.release.as_ref();
+
+        let mut secrets = vec![registry::registry_token_var(
+            release.and_then(|r| r.registry.as_deref()),
+        )];
+        if let Some(release) = release.filter(|r| r.route_by_publish) {
+            secrets.extend(
+                release
+                    .registries
+                    .keys()
+                    .map(|r| Some(r.as_str()).filter(|r| *r != registry::CRATES_IO_REGISTRY_NAME))
+                    .map(registry::registry_token_var),
+            );
+        }
+        if self.github.is_some() {
+            secrets.push(github::GITHUB_TOKEN_VAR.to_owned());
+        }
+        if release.is_some_and(|r| r.registry_api.is_some()) {
+            secrets.push(registry::CLOUDSMITH_API_KEY_VAR.to_owned());
+        }
+        if let Some(token_env) = release
+            .and_then(|r| r.mirror.as_ref())
+            .and_then(|m| m.token_env.clone())
+        {
+            secrets.push(token_env);
+        }
+        if let Some(npm) = release.and_then(|r| r.npm.as_ref()) {
+            secrets.push(npm.token_env.clone());
+        }
+        if let Some(post_release) = &self.post_release {
+            secrets.extend(post_release.http.iter().filter_map(|h| h.token_env.clone()));
+        }
+        secrets
+    }
+
     pub fn validate(&self) -> anyhow::Result<()> {
+        if self
+            .workspace
+            .deprecated
+            .contains(&self.workspace.root_crate)
+        {
+            bail!("Root crate can't be listed in workspace.deprecated");
+        }
+        self.validate_groups()?;
+        self.validate_train()?;
         self.validate_release()?;
+        self.validate_artifacts()?;
         self.validate_changelog()?;
+        self.validate_metrics()?;
+        self.validate_failure_log()?;
+        self.validate_hooks()?;
         Ok(())
     }
 }
 
+#[derive(Deserialize, Clone)]
+pub struct Metrics {
+    /// Prometheus pushgateway base URL
+    pub pushgateway: Option<String>,
+    /// StatsD endpoint in form "host:port"
+    pub statsd: Option<String>,
+    /// Pushgateway job name
+    #[serde(default = "default_metrics_job")]
+    pub job: String,
+    /// Prefix of all metric names
+    #[serde(default = "default_metrics_prefix")]
+    pub prefix: String,
+}
+
+/// Hook commands are executed in the workspace root; package being published is
+/// passed via `MONOREPO_PACKAGE` and `MONOREPO_PACKAGE_VERSION` env vars
+#[derive(Deserialize, Clone)]
+pub struct Hooks {
+    /// Executed before every crate publish, failure aborts the release
+    #[serde(default)]
+    pub pre_publish_package: Vec<String>,
+    /// Executed after every crate publish
+    #[serde(default)]
+    pub post_publish_package: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct FailureLog {
+    /// Upload to the private gist, GitHub token should have `gist` scope
+    #[serde(default)]
+    pub gist: bool,
+    /// Endpoint which accepts the log as POST body and responds with the
+    /// paste URL
+    pub paste_url: Option<String>,
+    /// Env var with the bearer token of the paste endpoint
+    pub token_env: Option<String>,
+    /// Upload only when running in CI (`CI` env var is set)
+    #[serde(default = "default_bool_true")]
+    pub ci_only: bool,
+}
+
 #[derive(Deserialize, Clone)]
 pub struct Workspace {
     /// Main workspace crate which will be used for validation and naming
     pub root_crate: String,
+    /// Workspace root relative to the config file, all relative paths in the
+    /// config are resolved against it; config file directory by default
+    pub path: Option<PathBuf>,
+    /// Crates released together with the root crate, all publishable
+    /// workspace crates are released if empty
+    #[serde(default)]
+    pub members: Vec<String>,
+    /// Crates retired with `cargo monorepo deprecate`, never released again
+    #[serde(default)]
+    pub deprecated: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Group {
+    /// Crate which defines group version and is used for naming
+    pub root_crate: String,
+    /// Other crates which are released together with the root crate
+    #[serde(default)]
+    pub members: Vec<String>,
+    /// Overrides release.github.tag_name_template
+    pub tag_name_template: Option<TextTemplate>,
+    pub changelog: Option<GroupChangelog>,
+    /// Overrides of the release.github settings
+    pub github: Option<GroupGithubRelease>,
+}
+
+impl Group {
+    pub fn all_members(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.root_crate).chain(self.members.iter())
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GroupGithubRelease {
+    pub create_tag: Option<bool>,
+    pub create_release_page: Option<bool>,
+    pub release_page_upload_artifacts: Option<bool>,
+    pub release_page_title_template: Option<TextTemplate>,
+    pub release_page_body_template: Option<TextTemplate>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GroupChangelog {
+    /// Overrides changelog.file
+    pub file: Option<PathBuf>,
+    pub start_marker_template: Option<TextTemplate>,
+    pub end_marker_template: Option<TextTemplate>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Train {
+    /// Date of any (past or future) train release, cadence is counted from it
+    pub anchor_date: NaiveDate,
+    #[serde(default = "default_train_cadence_days")]
+    pub cadence_days: u32,
+    /// How many days release window stays open, starting from the train date
+    #[serde(default = "default_train_window_days")]
+    pub window_days: u32,
+    /// Branch which train releases should be cut from
+    pub branch: Option<String>,
+    /// Version bump applied to the last published version
+    #[serde(default = "default_train_version_bump")]
+    pub version_bump: VersionBump,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionBump {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl VersionBump {
+    pub fn apply(self, version: &Version) -> Version {
+        match self {
+            VersionBump::Major => Version::new(version.major + 1, 0, 0),
+            VersionBump::Minor => Version::new(version.major, version.minor + 1, 0),
+            VersionBump::Patch => Version::new(version.major, version.minor, version.patch + 1),
+        }
+    }
 }
 
 #[derive(Deserialize, Clone)]
@@ -92,10 +678,158 @@ pub struct Release {
     pub check_version_raised: bool,
     #[serde(default = "default_bool_true")]
     pub allow_non_path_dev_dependencies: bool,
+    /// Validate bin crates with the full `cargo publish --dry-run` (packaged
+    /// crate is built) instead of skipping them; their workspace dependencies
+    /// should be resolvable from the registry
+    #[serde(default)]
+    pub validate_bin_crates: bool,
+    /// Options of the `cargo publish` verification build
+    #[serde(default)]
+    pub publish_verification: PublishVerification,
+    /// Target directory of the validation builds (publish verification,
+    /// rustdoc) kept between runs, e.g. restored from the CI cache
+    pub cache_dir: Option<PathBuf>,
+    /// Directory copied to `cache_dir` when it does not exist yet
+    pub cache_prime_from: Option<PathBuf>,
     pub registry: Option<String>,
+    /// Web UI URL of the custom registry, crate name appended to it should
+    /// point to the crate page
+    pub registry_url: Option<String>,
+    /// Registry API used to check that packages are not published yet
+    pub registry_api: Option<RegistryApi>,
+    /// Sparse index URL of the custom registry, used to query its released
+    /// versions and polled after publish until the published version is
+    /// resolvable
+    pub registry_index_url: Option<String>,
+    /// Release only crates with unpublished versions instead of requiring a
+    /// single workspace version; their dependents are bumped with `bump`
+    #[serde(default)]
+    pub bump_dependents: bool,
+    /// Publish only crates changed since the previous release tag
+    #[serde(default)]
+    pub skip_unchanged: bool,
+    /// Delete the created GitHub release and tag when the release fails
+    #[serde(default)]
+    pub rollback_on_failure: bool,
+    /// Also yank the crates published by the failed release
+    #[serde(default)]
+    pub rollback_yank_crates: bool,
+    /// Git remotes which must contain the released commit, e.g. mirrors or
+    /// non-GitHub primary remote
+    #[serde(default)]
+    pub commit_pushed_remotes: Vec<String>,
+    /// Rules applied to the files changed since the previous release tag
+    #[serde(default)]
+    pub path_policies: Vec<PathPolicy>,
+    /// Delay before publishing the next crate to the registry which can't be
+    /// polled for the published version
     #[serde(default = "default_publish_interval_seconds")]
     pub publish_interval_seconds: usize,
+    /// Max time to wait for the published version to appear in the registry
+    /// index before publishing the next crate
+    #[serde(default = "default_publish_timeout_seconds")]
+    pub publish_timeout_seconds: u64,
+    #[serde(default)]
+    pub version_scheme: VersionScheme,
+    /// Calendar versioning pattern used with `version_scheme = "calver"`
+    #[serde(default = "default_calver_pattern")]
+    pub calver_pattern: CalVerPattern,
+    /// Command line (split on whitespace) which prints the pending version to
+    /// stdout, used instead of the root crate version from Cargo.toml
+    pub version_command: Option<String>,
+    /// Source of the pending version
+    #[serde(default)]
+    pub version_from: VersionSource,
+    /// Publish every crate to the first registry of its `publish` field
+    /// instead of the single `registry`
+    #[serde(default)]
+    pub route_by_publish: bool,
+    /// Settings of the registries crates are routed to by `route_by_publish`
+    #[serde(default)]
+    pub registries: BTreeMap<String, RegistryTarget>,
     pub github: Option<GithubRelease>,
+    pub docs: Option<DocsRelease>,
+    pub mirror: Option<MirrorRelease>,
+    pub git_notes: Option<GitNotesRelease>,
+    /// Repository-level releases history, updated and committed after the
+    /// release
+    pub releases_file: Option<ReleasesFile>,
+    pub npm: Option<NpmRelease>,
+    pub announcement: Option<Announcement>,
+    /// Rolling prerelease published with `release --channel nightly`
+    pub nightly: Option<NightlyRelease>,
+    /// GitHub ref used as a lease, so concurrent releases can't interleave
+    pub lock: Option<ReleaseLock>,
+    /// Completed releases recorded in git notes, so re-runs are skipped
+    pub dedupe: Option<ReleaseDedupe>,
+    /// Validate that crate READMEs render on crates.io
+    pub readme: Option<ReadmeCheck>,
+    /// Keywords and categories shared by all published crates
+    pub crate_metadata: Option<CrateMetadataPolicy>,
+    /// Validate that every feature of the published crates is documented
+    pub feature_docs: Option<FeatureDocsCheck>,
+    /// Build docs of the published crates denying broken intra-doc links
+    pub rustdoc: Option<RustdocCheck>,
+    /// Path to append JSON lines with every external action to
+    pub audit_log: Option<PathBuf>,
+    /// Path to write JSON release report to
+    pub report_file: Option<PathBuf>,
+    /// Path of the publish journal, `target/monorepo/publish-journal.jsonl`
+    /// by default
+    pub publish_journal: Option<PathBuf>,
+    /// Path of the release state file used by `--resume`,
+    /// `target/monorepo/.monorepo-release-state.json` by default, so the
+    /// working tree stays clean for `cargo publish`
+    pub state_file: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct PublishVerification {
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub all_features: bool,
+    #[serde(default)]
+    pub no_default_features: bool,
+    /// Cargo `--config` overrides of the verification build, e.g.
+    /// `profile.dev.debug = false`, as `cargo publish` has no `--profile`
+    #[serde(default)]
+    pub config: Vec<String>,
+    /// Target directory shared by verification builds of all crates, so the
+    /// dependency graph is not cold-built for every crate; `verify` directory
+    /// of `release.cache_dir` or `target/monorepo` by default
+    pub target_dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PathPolicy {
+    /// Workspace-relative paths the rule applies to, e.g. `migrations/**`
+    pub paths: Vec<FilePattern>,
+    /// Changelog section which is required if any of the paths is changed,
+    /// e.g. `## Database`
+    pub require_changelog_section: Option<String>,
+    /// Block the release if any of the paths is changed
+    #[serde(default)]
+    pub block: bool,
+    /// Explanation shown when the rule fails
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RegistryTarget {
+    /// Overrides release.publish_interval_seconds for crates of the registry
+    pub publish_interval_seconds: Option<usize>,
+    /// Web UI URL of the registry, crate name appended to it should point to
+    /// the crate page
+    pub registry_url: Option<String>,
+    /// Sparse index URL of the registry
+    pub registry_index_url: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum RegistryApi {
+    Cloudsmith { owner: String, repository: String },
 }
 
 #[derive(Deserialize, Clone)]
@@ -107,6 +841,17 @@ pub struct GithubRelease {
     #[serde(default = "default_tag_name_template")]
     pub tag_name_template: TextTemplate,
     #[serde(default)]
+    pub tag_method: TagMethod,
+    /// Annotated tag message, used with `tag_method = "git"`
+    #[serde(default = "default_tag_message_template")]
+    pub tag_message_template: TextTemplate,
+    /// Longer tag messages are truncated to this size
+    #[serde(default = "default_tag_message_max_bytes")]
+    pub tag_message_max_bytes: usize,
+    /// Remote to push git tag to
+    #[serde(default = "default_git_remote")]
+    pub tag_remote: String,
+    #[serde(default)]
     pub create_release_page: bool,
     #[serde(default = "default_bool_true")]
     pub release_page_upload_artifacts: bool,
@@ -116,17 +861,497 @@ pub struct GithubRelease {
     pub release_page_body_template: TextTemplate,
     #[serde(default)]
     pub print_to_stdout: bool,
+    /// Cleanup of the assets attached to the old prereleases
+    pub asset_retention: Option<AssetRetention>,
+    /// Attestations of the uploaded assets, created only in GitHub Actions
+    pub attestation: Option<Attestation>,
+    /// Auto-updater manifest uploaded along with the release assets
+    pub update_manifest: Option<UpdateManifest>,
+    /// Upload of the assets which are too big for a single streamed request
+    pub large_assets: Option<LargeAssets>,
+    /// Tag is created outside of the release (e.g. by another CI job) and
+    /// verified instead of being created
+    pub external_tag: Option<ExternalTag>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ExternalTag {
+    /// Fetch the tag from `tag_remote` before the verification
+    #[serde(default = "default_bool_true")]
+    pub fetch: bool,
+    /// GPG key ids or fingerprints allowed to sign the tag; signature is not
+    /// required if empty
+    #[serde(default)]
+    pub allowed_signing_keys: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct LargeAssets {
+    /// Assets of this size and bigger are uploaded with retries
+    #[serde(default = "default_large_asset_threshold_bytes")]
+    pub threshold_bytes: u64,
+    /// Upload attempts of the whole asset and of every part
+    #[serde(default = "default_large_asset_attempts")]
+    pub attempts: usize,
+    #[serde(default = "default_large_asset_retry_interval_seconds")]
+    pub retry_interval_seconds: u64,
+    /// When the whole asset can't be uploaded, it is split into parts of
+    /// this size uploaded as `<name>.partNNN` assets, joined back with
+    /// `cat <name>.part* > <name>`
+    pub part_bytes: Option<u64>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct UpdateManifest {
+    /// Asset name, stable across releases to be reachable via
+    /// `releases/latest/download/<name>`
+    #[serde(default = "default_update_manifest_name")]
+    pub name: String,
+    /// Updater platform key (e.g. `linux-x86_64`) to the artifact file name
+    /// pattern
+    pub platforms: BTreeMap<String, FilePattern>,
+    /// Signature of the artifact is read from the artifact with this suffix,
+    /// e.g. `app.tar.gz.sig`
+    #[serde(default = "default_update_signature_suffix")]
+    pub signature_suffix: String,
+    /// Put release changelog to the manifest `notes`
+    #[serde(default = "default_bool_true")]
+    pub include_notes: bool,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Attestation {
+    /// SLSA build provenance of the workflow run
+    #[serde(default = "default_bool_true")]
+    pub provenance: bool,
+    /// SPDX JSON document attested as SBOM of every asset
+    pub sbom: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AssetRetention {
+    /// How many of the most recent prereleases keep their assets
+    pub keep_prereleases: usize,
+    /// Only prereleases with matching tags are pruned, e.g. `*-nightly.*`
+    pub tag_pattern: Option<FilePattern>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionScheme {
+    #[default]
+    Semver,
+    /// Calendar versioning, see `release.calver_pattern`
+    Calver,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum VersionSource {
+    /// Root crate version from Cargo.toml
+    #[default]
+    Manifest,
+    /// Bump of the last release tag version inferred from the conventional
+    /// commits made after it
+    ConventionalCommits,
+}
+
+impl Release {
+    /// CalVer pattern if the calendar versioning is used
+    pub fn calver(&self) -> Option<&CalVerPattern> {
+        (self.version_scheme == VersionScheme::Calver).then_some(&self.calver_pattern)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMethod {
+    /// Lightweight tag created via GitHub API
+    #[default]
+    Github,
+    /// Annotated tag created locally and pushed to the remote
+    Git,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct DocsRelease {
+    /// Branch which will be overwritten with the generated documentation
+    #[serde(default = "default_docs_branch")]
+    pub branch: String,
+    #[serde(default = "default_git_remote")]
+    pub remote: String,
+    /// Crate to redirect to from the index page, root crate is used by default
+    pub index_crate: Option<String>,
+    #[serde(default = "default_docs_commit_message_template")]
+    pub commit_message_template: TextTemplate,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct NightlyRelease {
+    /// Tag of the rolling prerelease, moved to the released commit every time
+    #[serde(default = "default_nightly_tag")]
+    pub tag: String,
+    #[serde(default = "default_nightly_title_template")]
+    pub title_template: TextTemplate,
+    #[serde(default = "default_nightly_body_template")]
+    pub body_template: TextTemplate,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ReleaseLock {
+    /// Fully qualified ref which exists while the release is in progress
+    #[serde(default = "default_lock_ref")]
+    pub lock_ref: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct CrateMetadataPolicy {
+    #[serde(default)]
+    pub required_keywords: Vec<String>,
+    #[serde(default)]
+    pub required_categories: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RustdocCheck {
+    /// Build docs with `--all-features`, as docs.rs is often configured to
+    #[serde(default)]
+    pub all_features: bool,
+    /// Deny all rustdoc warnings, not only broken intra-doc links
+    #[serde(default)]
+    pub deny_warnings: bool,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct FeatureDocsCheck {
+    /// Feature is also documented when its name is mentioned as `` `name` ``
+    /// in the crate README; `[package.metadata.features]` table keys are
+    /// always accepted
+    #[serde(default = "default_bool_true")]
+    pub search_readme: bool,
+    /// Features which do not require documentation, `default` is never checked
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ReadmeCheck {
+    #[serde(default = "default_readme_max_size_bytes")]
+    pub max_size_bytes: u64,
+    /// Rewrite relative links and images to absolute GitHub URLs of the
+    /// released commit during publish instead of rejecting them
+    #[serde(default)]
+    pub rewrite_relative_links: bool,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ReleaseDedupe {
+    /// Notes ref which released versions are appended to, `refs/notes/` prefix
+    /// is added automatically
+    #[serde(default = "default_dedupe_notes_ref")]
+    pub notes_ref: String,
+    #[serde(default = "default_git_remote")]
+    pub remote: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GitNotesRelease {
+    /// Notes ref which JSON release report is attached to, `refs/notes/` prefix
+    /// is added automatically
+    #[serde(default = "default_git_notes_ref")]
+    pub notes_ref: String,
+    #[serde(default = "default_git_remote")]
+    pub remote: String,
+    /// Push notes ref to the remote after the note has been added
+    #[serde(default = "default_bool_true")]
+    pub push: bool,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ReleasesFile {
+    #[serde(default = "default_releases_file_path")]
+    pub path: PathBuf,
+    #[serde(default)]
+    pub format: ReleasesFileFormat,
+    /// Markdown entry template; `{{date}}`, `{{highlights}}` (top-level
+    /// changelog items) and `{{release_url}}` are available in addition to
+    /// the usual context
+    #[serde(default = "default_releases_entry_template")]
+    pub entry_template: TextTemplate,
+    /// How many changelog items are listed as highlights
+    #[serde(default = "default_releases_highlights")]
+    pub highlights: usize,
+    /// Commit and push the updated file
+    #[serde(default = "default_bool_true")]
+    pub commit: bool,
+    #[serde(default = "default_git_remote")]
+    pub remote: String,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleasesFileFormat {
+    /// Entries are inserted under the document title, newest first
+    #[default]
+    Markdown,
+    /// JSON array of entries, newest first, e.g. website news feed
+    Json,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct NpmRelease {
+    /// Wasm crates which are built with `wasm-pack` and published as npm packages
+    pub crates: Vec<String>,
+    /// `wasm-pack build --target` value
+    #[serde(default = "default_npm_target")]
+    pub target: String,
+    /// Scope of the npm packages, without `@`
+    pub scope: Option<String>,
+    /// npm registry URL, npmjs is used by default
+    pub registry: Option<String>,
+    /// Env var which contains npm registry auth token
+    #[serde(default = "default_npm_token_env")]
+    pub token_env: String,
+    /// Publish scoped packages as public
+    #[serde(default = "default_bool_true")]
+    pub public: bool,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Announcement {
+    /// Handlebars template file, built-in template is used if not specified
+    pub template: Option<PathBuf>,
+    /// Announcement file path, relative to the website repo root if
+    /// `pull_request` is specified
+    #[serde(default = "default_announcement_path_template")]
+    pub path_template: TextTemplate,
+    /// Maximal number of changelog entries to put into highlights
+    #[serde(default = "default_announcement_highlights")]
+    pub highlights: usize,
+    pub pull_request: Option<AnnouncementPullRequest>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AnnouncementPullRequest {
+    /// Website repo in form "owner/repo-name"
+    pub repo: github::Repo,
+    #[serde(default = "default_announcement_base_branch")]
+    pub base: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GeneratedFile {
+    /// Path relative to the workspace root, e.g. `dist/VERSION`
+    pub path: PathBuf,
+    pub template: TextTemplate,
+    /// Commit and push the file before anything is released, so the released
+    /// commit contains it
+    #[serde(default)]
+    pub commit: bool,
+    /// Attach the file to the release artifacts
+    #[serde(default)]
+    pub artifact: bool,
+    /// Remote to push committed file to
+    #[serde(default = "default_git_remote")]
+    pub remote: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PostRelease {
+    /// Templated HTTP calls, e.g. CDN purge, docs rebuild webhook or search
+    /// index refresh
+    #[serde(default)]
+    pub http: Vec<PostReleaseHttp>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PostReleaseHttp {
+    /// Short description used in the release output
+    pub name: String,
+    #[serde(default)]
+    pub method: HttpMethod,
+    pub url: TextTemplate,
+    pub body: Option<TextTemplate>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, TextTemplate>,
+    /// Env var which contains bearer token for the call
+    pub token_env: Option<String>,
+    /// Fail the release if the call fails, otherwise only a warning is printed
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    #[default]
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MirrorRelease {
+    /// Base URL of the artifact store, every published `.crate` file is
+    /// uploaded via PUT to `<url>/<name>/<name>-<version>.crate`
+    pub url: String,
+    /// Env var which contains bearer token for the artifact store
+    pub token_env: Option<String>,
+    /// How many times to retry download of the just published crate
+    #[serde(default = "default_mirror_download_attempts")]
+    pub download_attempts: usize,
+    #[serde(default = "default_mirror_retry_interval_seconds")]
+    pub retry_interval_seconds: u64,
 }
 
 #[derive(Deserialize, Clone)]
 pub struct Changelog {
+    #[serde(default = "default_changelog_file")]
     pub file: PathBuf,
+    #[serde(default)]
+    pub source: ChangelogSource,
+    /// Overrides of commit routing to crates for `source = "commits"`
+    #[serde(default)]
+    pub mapping: Vec<ChangelogMapping>,
+    /// Sections of the notes for `source = "pull-requests"` in the output
+    /// order; PR goes to the first section with any of its labels
+    #[serde(default = "default_label_sections")]
+    pub label_sections: Vec<LabelSection>,
+    /// Section of the PRs without matching labels, omitted if empty
+    #[serde(default = "default_other_changes_title")]
+    pub other_changes_title: String,
     pub start_marker_template: Option<TextTemplate>,
     pub end_marker_template: Option<TextTemplate>,
+    /// Additional marker-delimited regions, captured after the main one and
+    /// concatenated in the listed order
+    #[serde(default)]
+    pub sections: Vec<ChangelogSection>,
+    /// Text which heading of the captured section should contain, e.g.
+    /// `[{{version}}]`, so a forgotten "Unreleased" title is caught
+    pub version_heading_template: Option<TextTemplate>,
+    /// Rename "Unreleased" heading to the version heading before the release
+    pub promote_unreleased: Option<PromoteUnreleased>,
     #[serde(default)]
     pub print_to_stdout: bool,
     #[serde(default)]
     pub allow_empty_changelog: bool,
+    /// External spell/style checker of the captured changelog
+    pub lint: Option<ChangelogLint>,
+    /// Heading of the section which the changelog of the semver-incompatible
+    /// release should contain, e.g. `## Migration`
+    pub migration_heading: Option<String>,
+    /// Release notes maintained by the crate owners, added to the release
+    /// page body under per-crate headings
+    pub crate_notes: Option<CrateReleaseNotes>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct CrateReleaseNotes {
+    /// Notes file in the crate directory, preferred over the doc comment
+    #[serde(default = "default_crate_notes_file")]
+    pub file: PathBuf,
+    /// Heading of the section in the crate root `//!` doc comment
+    #[serde(default = "default_crate_notes_heading")]
+    pub heading: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ChangelogLint {
+    /// Linter command, e.g. `["vale", "--minAlertLevel=error"]`; path of the
+    /// file with the captured changelog is appended as the last argument
+    pub command: Vec<String>,
+    /// Extension of the linted file, linters pick the syntax by it
+    #[serde(default = "default_changelog_lint_extension")]
+    pub extension: String,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangelogSource {
+    /// Marker-delimited regions of the changelog file
+    #[default]
+    File,
+    /// Commit subjects since the previous release, grouped by crate
+    Commits,
+    /// Message of the annotated release tag, created before the release
+    #[serde(rename = "tag-message")]
+    TagMessage,
+    /// Titles of the merged pull requests, grouped by labels
+    #[serde(rename = "pull-requests")]
+    PullRequests,
+}
+
+impl ChangelogSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Commits => "commits",
+            Self::TagMessage => "tag-message",
+            Self::PullRequests => "pull-requests",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ChangelogMapping {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    /// Conventional commit scopes routed to the crate, in addition to its name
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Workspace-relative paths routed to the crate, in addition to its directory
+    #[serde(default)]
+    pub paths: Vec<FilePattern>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PromoteUnreleased {
+    /// Heading text of the unreleased changes, matched ignoring case and
+    /// square brackets, so `## [Unreleased]` is found as well
+    #[serde(default = "default_unreleased_heading")]
+    pub heading: String,
+    /// Replacement heading; `{{date}}` is the release date in `YYYY-MM-DD`
+    /// format
+    #[serde(default = "default_promoted_heading_template")]
+    pub heading_template: TextTemplate,
+    /// Commit the promoted changelog and push it, so the release is made from
+    /// the commit which contains it
+    #[serde(default = "default_bool_true")]
+    pub commit: bool,
+    #[serde(default = "default_git_remote")]
+    pub remote: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct LabelSection {
+    pub title: String,
+    pub labels: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ChangelogSection {
+    pub start_marker_template: TextTemplate,
+    pub end_marker_template: TextTemplate,
+}
+
+impl Changelog {
+    /// All configured marker pairs in capture order, empty if the whole file
+    /// should be captured
+    pub fn marker_templates(&self) -> Vec<(&TextTemplate, &TextTemplate)> {
+        let main = match (&self.start_marker_template, &self.end_marker_template) {
+            (Some(start), Some(end)) => Some((start, end)),
+            _ => None,
+        };
+        main.into_iter()
+            .chain(
+                self.sections
+                    .iter()
+                    .map(|s| (&s.start_marker_template, &s.end_marker_template)),
+            )
+            .collect()
+    }
 }
 
 #[derive(Deserialize, Clone)]
@@ -134,6 +1359,84 @@ pub struct Artifacts {
     pub directory: PathBuf,
     #[serde(default = "default_bool_true")]
     pub check_not_empty: bool,
+    /// Attach workspace Cargo.lock to the release artifacts
+    #[serde(default)]
+    pub include_cargo_lock: bool,
+    /// Attach `dependencies.json` with the resolved dependency graph to the
+    /// release artifacts and the release report
+    #[serde(default)]
+    pub dependency_snapshot: bool,
+    pub source_archive: Option<SourceArchive>,
+    pub reproducibility: Option<Reproducibility>,
+    pub c_api: Option<CApi>,
+    /// Binary patches from the previous release assets to the new artifacts
+    pub delta_patches: Option<DeltaPatches>,
+    #[serde(default)]
+    pub size_limits: Vec<ArtifactSizeLimit>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct DeltaPatches {
+    /// Glob patterns matched against artifact file name, e.g. `*.tar.gz`
+    pub patterns: Vec<FilePattern>,
+    #[serde(default)]
+    pub tool: DeltaTool,
+    /// Skip artifacts which have no counterpart in the previous release
+    /// instead of failing the release
+    #[serde(default)]
+    pub allow_missing: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DeltaTool {
+    /// `zstd --patch-from`, applied with `zstd -d --long=31 --patch-from`
+    #[default]
+    Zstd,
+    /// `bsdiff`, applied with `bspatch`
+    Bsdiff,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ArtifactSizeLimit {
+    /// Glob pattern matched against artifact file name
+    pub pattern: FilePattern,
+    /// Minimal allowed artifact size in bytes
+    pub min_size: Option<u64>,
+    /// Maximal allowed artifact size in bytes
+    pub max_size: Option<u64>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Reproducibility {
+    /// Command (program and arguments) which rebuilds release artifacts
+    pub build_command: Vec<String>,
+    /// Directory where `build_command` places rebuilt artifacts
+    pub output_directory: PathBuf,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct CApi {
+    /// Crate with `cdylib` and/or `staticlib` crate type
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    /// Generated header file name, `<lib_name>.h` by default
+    pub header: Option<String>,
+    /// cbindgen config, `cbindgen.toml` from the crate directory is used if present
+    pub cbindgen_config: Option<PathBuf>,
+    /// Target triples to build libraries for, host target is used if empty
+    #[serde(default)]
+    pub targets: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SourceArchive {
+    /// Archive name without extension, `.tar.gz` will be appended
+    #[serde(default = "default_source_archive_name_template")]
+    pub name_template: TextTemplate,
+    /// Include `cargo vendor`-ed dependencies into the archive
+    #[serde(default)]
+    pub vendor: bool,
 }
 
 fn default_bool_true() -> bool {
@@ -144,14 +1447,253 @@ fn default_tag_name_template() -> TextTemplate {
     TextTemplate::new("v{{version}}").unwrap()
 }
 
+fn default_tag_message_template() -> TextTemplate {
+    TextTemplate::new("{{root_crate}} v{{version}}\n\n{{changelog}}").unwrap()
+}
+
+fn default_update_manifest_name() -> String {
+    "latest.json".to_owned()
+}
+
+fn default_update_signature_suffix() -> String {
+    ".sig".to_owned()
+}
+
+fn default_tag_message_max_bytes() -> usize {
+    64 * 1024
+}
+
 fn default_release_page_title_template() -> TextTemplate {
     TextTemplate::new("{{root_crate}} v{{version}}").unwrap()
 }
 
 fn default_release_page_body_template() -> TextTemplate {
-    TextTemplate::new("{{changelog}}").unwrap()
+    TextTemplate::new("{{changelog}}{{#if crate_notes}}\n\n{{crate_notes}}{{/if}}").unwrap()
+}
+
+fn default_crate_notes_file() -> PathBuf {
+    PathBuf::from("RELEASE_NOTES.md")
+}
+
+fn default_crate_notes_heading() -> String {
+    "# Release notes".to_owned()
+}
+
+fn default_dedupe_notes_ref() -> String {
+    "monorepo-releases".to_owned()
+}
+
+fn default_readme_max_size_bytes() -> u64 {
+    512 * 1024
+}
+
+fn default_lock_ref() -> String {
+    "refs/monorepo/release-lock".to_owned()
+}
+
+fn default_nightly_tag() -> String {
+    "nightly".to_owned()
+}
+
+fn default_nightly_title_template() -> TextTemplate {
+    TextTemplate::new("{{root_crate}} nightly {{version}}").unwrap()
+}
+
+fn default_nightly_body_template() -> TextTemplate {
+    TextTemplate::new("{{#if changelog}}{{changelog}}{{/if}}").unwrap()
+}
+
+fn default_changelog_file() -> PathBuf {
+    PathBuf::from("CHANGELOG.md")
+}
+
+fn default_unreleased_heading() -> String {
+    "## Unreleased".to_owned()
+}
+
+fn default_promoted_heading_template() -> TextTemplate {
+    TextTemplate::new("## [{{version}}] - {{date}}").unwrap()
+}
+
+fn default_source_archive_name_template() -> TextTemplate {
+    TextTemplate::new("{{root_crate}}-{{version}}").unwrap()
 }
 
 fn default_publish_interval_seconds() -> usize {
     30
 }
+
+fn default_publish_timeout_seconds() -> u64 {
+    300
+}
+
+fn default_docs_branch() -> String {
+    "gh-pages".to_owned()
+}
+
+fn default_announcement_path_template() -> TextTemplate {
+    TextTemplate::new("announcements/{{root_crate}}-{{version}}.md").unwrap()
+}
+
+fn default_announcement_highlights() -> usize {
+    5
+}
+
+fn default_announcement_base_branch() -> String {
+    "main".to_owned()
+}
+
+fn default_metrics_job() -> String {
+    "cargo_monorepo".to_owned()
+}
+
+fn default_metrics_prefix() -> String {
+    "monorepo_release".to_owned()
+}
+
+fn default_npm_target() -> String {
+    "bundler".to_owned()
+}
+
+fn default_npm_token_env() -> String {
+    "NPM_TOKEN".to_owned()
+}
+
+fn default_git_notes_ref() -> String {
+    "releases".to_owned()
+}
+
+fn default_label_sections() -> Vec<LabelSection> {
+    vec![
+        ("Breaking changes", "breaking"),
+        ("Features", "feature"),
+        ("Fixes", "fix"),
+        ("Documentation", "docs"),
+    ]
+    .into_iter()
+    .map(|(title, label)| LabelSection {
+        title: title.to_owned(),
+        labels: vec![label.to_owned()],
+    })
+    .collect()
+}
+
+fn default_other_changes_title() -> String {
+    "Other changes".to_owned()
+}
+
+fn default_changelog_lint_extension() -> String {
+    "md".to_owned()
+}
+
+fn default_large_asset_threshold_bytes() -> u64 {
+    512 * 1024 * 1024
+}
+
+fn default_large_asset_attempts() -> usize {
+    3
+}
+
+fn default_large_asset_retry_interval_seconds() -> u64 {
+    10
+}
+
+fn default_releases_file_path() -> PathBuf {
+    PathBuf::from("RELEASES.md")
+}
+
+fn default_releases_entry_template() -> TextTemplate {
+    TextTemplate::new(
+        "## {{root_crate}} {{version}} ({{date}})\n\n\
+        {{#each highlights}}- {{{this}}}\n{{/each}}\
+        {{#if release_url}}\n[Release notes]({{release_url}})\n{{/if}}",
+    )
+    .unwrap()
+}
+
+fn default_releases_highlights() -> usize {
+    5
+}
+
+fn default_git_remote() -> String {
+    "origin".to_owned()
+}
+
+fn default_docs_commit_message_template() -> TextTemplate {
+    TextTemplate::new("Deploy {{root_crate}} v{{version}} documentation").unwrap()
+}
+
+fn default_mirror_download_attempts() -> usize {
+    5
+}
+
+fn default_mirror_retry_interval_seconds() -> u64 {
+    10
+}
+
+fn default_train_cadence_days() -> u32 {
+    14
+}
+
+fn default_train_window_days() -> u32 {
+    1
+}
+
+fn default_calver_pattern() -> CalVerPattern {
+    CalVerPattern::new("YY.MM.MICRO").unwrap()
+}
+
+fn default_train_version_bump() -> VersionBump {
+    VersionBump::Minor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUPS: &str = r#"
+        [workspace]
+        root_crate = "app"
+
+        [github]
+        repo = "owner/app"
+
+        [release.github]
+        create_tag = true
+        create_release_page = true
+        release_page_upload_artifacts = false
+
+        [group.cli]
+        root_crate = "app-cli"
+        tag_name_template = "cli-v{{version}}"
+
+        [group.cli.github]
+        create_release_page = false
+    "#;
+
+    #[test]
+    fn group_overrides() {
+        let config: Config = toml::from_str(GROUPS).unwrap();
+        config.validate().unwrap();
+
+        let cli = config.for_group("cli").unwrap();
+        let github = cli.release.unwrap().github.unwrap();
+        assert_eq!(cli.workspace.root_crate, "app-cli");
+        assert!(cli.group.is_empty());
+        assert!(github.create_tag);
+        assert!(!github.create_release_page);
+        assert!(!github.release_page_upload_artifacts);
+    }
+
+    #[test]
+    fn invalid_group_overrides() {
+        let config = GROUPS.replace(
+            "create_release_page = false",
+            "create_tag = false\nrelease_page_upload_artifacts = true",
+        );
+        let config: Config = toml::from_str(&config).unwrap();
+        config.validate().unwrap();
+        let error = format!("{:#}", config.for_group("cli").err().unwrap());
+        assert!(error.starts_with("Invalid config of `cli` release group: "));
+    }
+}
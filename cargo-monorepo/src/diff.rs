@@ -0,0 +1,214 @@
+use crate::{
+    changelog::{parse_git_log, Commit, CrateRoute, GIT_LOG_FORMAT},
+    config::Config,
+    utils::{metadata_command, run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, Context};
+use std::{collections::BTreeSet, fmt::Write as _};
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Show commits and changed files of every crate since the previous release")]
+pub struct Command {
+    /// Revision to compare with, the latest tag reachable from HEAD by default
+    #[structopt(long)]
+    since: Option<String>,
+    /// Print markdown suitable for release review issues
+    #[structopt(long)]
+    markdown: bool,
+}
+
+impl Command {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        let metadata = metadata_command()
+            .no_deps()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let mapping = config
+            .changelog
+            .as_ref()
+            .map(|c| c.mapping.as_slice())
+            .unwrap_or_default();
+
+        let routes = metadata
+            .packages
+            .iter()
+            .filter(|p| metadata.workspace_members.contains(&p.id))
+            .map(|p| CrateRoute::for_package(p, &metadata.workspace_root, mapping))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let since = match self.since {
+            Some(since) => Some(since),
+            None => latest_tag().await,
+        };
+        let range = match &since {
+            Some(since) => format!("{}..HEAD", since),
+            None => "HEAD".to_owned(),
+        };
+
+        let mut log = workspace_command("git");
+        log.current_dir(&metadata.workspace_root).args([
+            "log",
+            "--relative",
+            "--name-only",
+            GIT_LOG_FORMAT,
+            &range,
+        ]);
+        let log = run_and_capture_stdout(&mut log)
+            .await
+            .with_context(|| format!("Failed to query commits in {}", range))?;
+        let commits = parse_git_log(&log);
+
+        let since = since.as_deref().unwrap_or("the first commit");
+        if self.markdown {
+            print!("{}", markdown_diff(&commits, &routes, since));
+        } else {
+            print!("{}", text_diff(&commits, &routes, since));
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn latest_tag() -> Option<String> {
+    let mut cmd = workspace_command("git");
+    cmd.args(["describe", "--tags", "--abbrev=0"]);
+    run_and_capture_stdout(&mut cmd)
+        .await
+        .ok()
+        .map(|tag| tag.trim().to_owned())
+}
+
+struct CrateDiff<'a> {
+    name: &'a str,
+    commits: Vec<&'a Commit>,
+    files: BTreeSet<&'a str>,
+}
+
+fn crate_diffs<'a>(commits: &'a [Commit], routes: &'a [CrateRoute]) -> Vec<CrateDiff<'a>> {
+    routes
+        .iter()
+        .map(|route| {
+            let commits = commits
+                .iter()
+                .filter(|c| route.matches(c))
+                .collect::<Vec<_>>();
+            let files = commits
+                .iter()
+                .flat_map(|c| &c.files)
+                .filter(|f| route.paths.iter().any(|p| p.matches(f)))
+                .map(String::as_str)
+                .collect();
+            CrateDiff {
+                name: &route.name,
+                commits,
+                files,
+            }
+        })
+        .collect()
+}
+
+fn text_diff(commits: &[Commit], routes: &[CrateRoute], since: &str) -> String {
+    let mut out = format!("Changes since {}\n", since);
+    for diff in crate_diffs(commits, routes) {
+        if diff.commits.is_empty() {
+            let _ = writeln!(out, "\n{}: no changes", diff.name);
+            continue;
+        }
+        let _ = writeln!(
+            out,
+            "\n{}: {} commit(s), {} file(s) changed",
+            diff.name,
+            diff.commits.len(),
+            diff.files.len()
+        );
+        for commit in &diff.commits {
+            let _ = writeln!(out, "  {} {}", commit.hash, commit.subject);
+        }
+        for file in &diff.files {
+            let _ = writeln!(out, "  M {}", file);
+        }
+    }
+    out
+}
+
+fn markdown_diff(commits: &[Commit], routes: &[CrateRoute], since: &str) -> String {
+    let mut out = format!("# Changes since `{}`\n", since);
+    for diff in crate_diffs(commits, routes) {
+        let _ = writeln!(out, "\n## {}\n", diff.name);
+        if diff.commits.is_empty() {
+            let _ = writeln!(out, "_No changes_");
+            continue;
+        }
+        let _ = writeln!(out, "**Commits**\n");
+        for commit in &diff.commits {
+            let _ = writeln!(out, "- {} ({})", commit.subject, commit.hash);
+        }
+        let _ = writeln!(out, "\n**Changed files**\n");
+        for file in &diff.files {
+            let _ = writeln!(out, "- `{}`", file);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::FilePattern;
+    use expect_test::expect;
+
+    #[test]
+    fn markdown_per_crate() {
+        let log = "\
+a1b2c3d\x1ffix(net): reconnect on timeout
+
+crates/net/src/lib.rs
+crates/core/src/lib.rs
+
+e4f5a6b\x1fdocs: update readme
+
+README.md
+";
+        let routes = vec![
+            ("core", "crates/core/**"),
+            ("net", "crates/net/**"),
+            ("cli", "crates/cli/**"),
+        ]
+        .into_iter()
+        .map(|(name, path)| CrateRoute {
+            name: name.to_owned(),
+            scopes: vec![name.to_owned()],
+            paths: vec![FilePattern::new(path).unwrap()],
+        })
+        .collect::<Vec<_>>();
+
+        expect![[r#"
+            # Changes since `v1.0.0`
+
+            ## core
+
+            **Commits**
+
+            - fix(net): reconnect on timeout (a1b2c3d)
+
+            **Changed files**
+
+            - `crates/core/src/lib.rs`
+
+            ## net
+
+            **Commits**
+
+            - fix(net): reconnect on timeout (a1b2c3d)
+
+            **Changed files**
+
+            - `crates/net/src/lib.rs`
+
+            ## cli
+
+            _No changes_
+        "#]]
+        .assert_eq(&markdown_diff(&parse_git_log(log), &routes, "v1.0.0"));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    changelog::pending_template_context,
+    config::{ChangelogSource, Config},
+    registry,
+    utils::{run_and_capture_stdout, workspace_command, workspace_path},
+};
+use anyhow::{anyhow, bail, Context};
+use std::time::Duration;
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Check that release environment prerequisites are met")]
+pub struct Command {}
+
+impl Command {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        let mut failed = 0;
+        let mut report = |check: &str, result: anyhow::Result<String>| match result {
+            Ok(details) => println!("✅ {}: {}", check, details),
+            Err(e) => {
+                println!("❌ {}: {:#}", check, e);
+                failed += 1;
+            }
+        };
+
+        report("git", tool_version("git").await);
+        report("git repository", git_toplevel().await);
+        report("cargo", tool_version("cargo").await);
+        report("config", config.validate().map(|_| "valid".to_owned()));
+
+        for var in config.required_secrets() {
+            let result = match std::env::var(&var) {
+                Ok(value) if !value.is_empty() => Ok("set".to_owned()),
+                _ => Err(anyhow!("env var is not set")),
+            };
+            report(&format!("token {}", var), result);
+        }
+
+        match registry::registry_api_url(config.release.as_ref()) {
+            Some(url) => report("registry reachable", check_reachable(url).await),
+            None => println!("➖ registry reachable: no API configured for custom registry"),
+        }
+        if config.github.is_some() {
+            report("GitHub reachable", check_reachable(GITHUB_API_URL).await);
+        }
+
+        if config.changelog.is_some() {
+            report("changelog markers", check_changelog_markers(&config).await);
+        }
+
+        if failed != 0 {
+            bail!("{} check(s) failed", failed);
+        }
+
+        println!("Environment is ready for the release");
+        Ok(())
+    }
+}
+
+async fn tool_version(tool: &str) -> anyhow::Result<String> {
+    let mut cmd = workspace_command(tool);
+    cmd.arg("--version");
+    let version = run_and_capture_stdout(&mut cmd)
+        .await
+        .with_context(|| format!("`{}` is not available", tool))?;
+    Ok(version.trim().to_owned())
+}
+
+async fn git_toplevel() -> anyhow::Result<String> {
+    let mut cmd = workspace_command("git");
+    cmd.args(["rev-parse", "--show-toplevel"]);
+    let toplevel = run_and_capture_stdout(&mut cmd)
+        .await
+        .with_context(|| "Current directory is not a git repository")?;
+    Ok(toplevel.trim().to_owned())
+}
+
+/// Any HTTP response is fine, only connectivity is checked
+async fn check_reachable(url: &str) -> anyhow::Result<String> {
+    let response = reqwest::Client::builder()
+        .timeout(NETWORK_TIMEOUT)
+        .user_agent(env!("CARGO_PKG_NAME"))
+        .build()?
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("{} is not reachable", url))?;
+    Ok(format!("{} ({})", url, response.status()))
+}
+
+/// Markers are rendered for the current root crate version, which is expected
+/// to be the version pending for release
+async fn check_changelog_markers(config: &Config) -> anyhow::Result<String> {
+    let changelog_config = config
+        .changelog
+        .as_ref()
+        .ok_or_else(|| anyhow!("Changelog is not configured"))?;
+    match changelog_config.source {
+        ChangelogSource::File => {}
+        ChangelogSource::Commits => return Ok("changelog is generated from commits".to_owned()),
+        ChangelogSource::PullRequests => {
+            return Ok("changelog is generated from pull requests".to_owned())
+        }
+        ChangelogSource::TagMessage => {
+            return Ok("changelog is captured from the release tag message".to_owned())
+        }
+    }
+
+    let changelog = tokio::fs::read_to_string(workspace_path(&changelog_config.file))
+        .await
+        .with_context(|| format!("Failed to read {}", changelog_config.file.display()))?;
+
+    let marker_templates = changelog_config.marker_templates();
+    if marker_templates.is_empty() {
+        return Ok("markers are not configured, whole file is used".to_owned());
+    }
+
+    let context = pending_template_context(config, None)?;
+    for (start_template, end_template) in marker_templates {
+        let start_marker = start_template.render(&context)?;
+        let end_marker = end_template.render(&context)?;
+        check_markers(&changelog, &start_marker, &end_marker)?;
+    }
+    Ok(format!(
+        "found for {} {}",
+        context.root_crate, context.version
+    ))
+}
+
+fn check_markers(changelog: &str, start_marker: &str, end_marker: &str) -> anyhow::Result<()> {
+    let position = |marker: &str| changelog.lines().position(|l| l.contains(marker));
+    match (position(start_marker), position(end_marker)) {
+        (Some(start), Some(end)) if start < end => Ok(()),
+        (Some(_), Some(_)) => bail!("End marker {} is placed before start marker", end_marker),
+        (None, _) => bail!("Start marker {} is missing", start_marker),
+        (_, None) => bail!("End marker {} is missing", end_marker),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn changelog_markers() {
+        let changelog = "<!-- end -->\n## 1.0.0\n<!-- start -->\n";
+
+        let errors = vec![
+            check_markers(changelog, "<!-- start -->", "<!-- end -->"),
+            check_markers(changelog, "<!-- begin -->", "<!-- end -->"),
+            check_markers(changelog, "<!-- start -->", "<!-- stop -->"),
+        ]
+        .into_iter()
+        .map(|r| r.unwrap_err().to_string())
+        .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                "End marker <!-- end --> is placed before start marker",
+                "Start marker <!-- begin --> is missing",
+                "End marker <!-- stop --> is missing",
+            ]
+        "#]]
+        .assert_debug_eq(&errors);
+    }
+}
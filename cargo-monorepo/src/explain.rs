@@ -0,0 +1,267 @@
+//! Error codes attached to validation failures; `explain` subcommand prints
+//! the remediation recipe of the code
+use crate::i18n::tr;
+use anyhow::anyhow;
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    VersionSkew,
+    VersionNotRaised,
+    CalVerMismatch,
+    MaintenanceLineMismatch,
+    RegistryNotAllowed,
+    RegistryTokenMissing,
+    AlreadyPublished,
+    InvalidReadme,
+    CrateMetadataPolicy,
+    UndocumentedFeatures,
+    OutOfWorkspacePathDependency,
+    EmptyChangelog,
+    NoChangesSinceRelease,
+    DevDependencyVersion,
+    TrainWindowClosed,
+    ChangelogLint,
+    MissingMigrationNotes,
+}
+
+impl ErrorCode {
+    const ALL: [ErrorCode; 17] = [
+        Self::VersionSkew,
+        Self::VersionNotRaised,
+        Self::CalVerMismatch,
+        Self::MaintenanceLineMismatch,
+        Self::RegistryNotAllowed,
+        Self::RegistryTokenMissing,
+        Self::AlreadyPublished,
+        Self::InvalidReadme,
+        Self::CrateMetadataPolicy,
+        Self::UndocumentedFeatures,
+        Self::OutOfWorkspacePathDependency,
+        Self::EmptyChangelog,
+        Self::NoChangesSinceRelease,
+        Self::DevDependencyVersion,
+        Self::TrainWindowClosed,
+        Self::ChangelogLint,
+        Self::MissingMigrationNotes,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::VersionSkew => "E001",
+            Self::VersionNotRaised => "E002",
+            Self::CalVerMismatch => "E003",
+            Self::MaintenanceLineMismatch => "E004",
+            Self::RegistryNotAllowed => "E005",
+            Self::RegistryTokenMissing => "E006",
+            Self::AlreadyPublished => "E007",
+            Self::InvalidReadme => "E008",
+            Self::CrateMetadataPolicy => "E009",
+            Self::UndocumentedFeatures => "E010",
+            Self::OutOfWorkspacePathDependency => "E011",
+            Self::EmptyChangelog => "E012",
+            Self::NoChangesSinceRelease => "E013",
+            Self::DevDependencyVersion => "E014",
+            Self::TrainWindowClosed => "E015",
+            Self::ChangelogLint => "E016",
+            Self::MissingMigrationNotes => "E017",
+        }
+    }
+
+    pub fn summary(self) -> &'static str {
+        match self {
+            Self::VersionSkew => "released crates have inconsistent versions",
+            Self::VersionNotRaised => "pending version is not greater than the published one",
+            Self::CalVerMismatch => "pending version does not match the CalVer pattern",
+            Self::MaintenanceLineMismatch => "pending version is outside of the maintenance line",
+            Self::RegistryNotAllowed => "crate does not allow publish to the release registry",
+            Self::RegistryTokenMissing => "registry token is not set",
+            Self::AlreadyPublished => "crate version is already published",
+            Self::InvalidReadme => "crate README would not render on crates.io",
+            Self::CrateMetadataPolicy => "crate misses required keywords or categories",
+            Self::UndocumentedFeatures => "crate has undocumented features",
+            Self::OutOfWorkspacePathDependency => {
+                "crate depends on a path crate outside the workspace"
+            }
+            Self::EmptyChangelog => "changelog is empty",
+            Self::NoChangesSinceRelease => "no commits since the previous release",
+            Self::DevDependencyVersion => "in-workspace dev-dependency has a version",
+            Self::TrainWindowClosed => "release train window is closed",
+            Self::ChangelogLint => "changelog linter reported problems",
+            Self::MissingMigrationNotes => "breaking release has no migration notes",
+        }
+    }
+
+    pub fn remediation(self) -> &'static str {
+        match self {
+            Self::VersionSkew => {
+                "All released crates share the root crate version, and requirements between \
+                them should match the released versions.\n\
+                1. Run `cargo monorepo status` to list the skewed crates.\n\
+                2. Set `version` of every released crate to the root crate version.\n\
+                3. Update requirements on the released crates, e.g. with \
+                `release.bump_dependents = true`."
+            }
+            Self::VersionNotRaised => {
+                "Root crate version should be greater than the last published one.\n\
+                1. Bump `version` of the released crates.\n\
+                2. For maintenance releases pass the maintenance line to look up the \
+                previous release within it."
+            }
+            Self::CalVerMismatch => {
+                "Date components of the version should correspond to the release date.\n\
+                1. Set the version suggested in the error message.\n\
+                2. Check `release.calver` pattern if the suggestion looks wrong."
+            }
+            Self::MaintenanceLineMismatch => {
+                "Maintenance release version should belong to the requested line.\n\
+                1. Check out the maintenance branch of the line.\n\
+                2. Bump the patch version within the line."
+            }
+            Self::RegistryNotAllowed => {
+                "`publish` field of the crate does not list the release registry.\n\
+                1. Add the registry to `publish` of the crate, or\n\
+                2. Set `publish = false` and exclude the crate from the release, or\n\
+                3. Enable `release.route_by_publish` to publish to the crate's own registry."
+            }
+            Self::RegistryTokenMissing => {
+                "Routed crates are published with the token of their registry.\n\
+                1. Export the env var named in the error, e.g. \
+                `CARGO_REGISTRIES_<NAME>_TOKEN`."
+            }
+            Self::AlreadyPublished => {
+                "Registry already has the pending version of the crate.\n\
+                1. Bump the version, or\n\
+                2. Resume the interrupted release with `--resume` to skip published crates."
+            }
+            Self::InvalidReadme => {
+                "crates.io renders README as is, relative links are broken there.\n\
+                1. Make sure the `readme` field points to an existing file.\n\
+                2. Shrink README below `release.readme.max_size_bytes`.\n\
+                3. Replace relative links with absolute ones, or enable \
+                `release.readme.rewrite_relative_links`."
+            }
+            Self::CrateMetadataPolicy => {
+                "Released crates should be discoverable as a family.\n\
+                1. Add the listed keywords/categories to the crate manifest, see \
+                `release.crate_metadata`."
+            }
+            Self::UndocumentedFeatures => {
+                "Every crate feature should be described for the users.\n\
+                1. Describe the feature in `[package.metadata.features]`, or\n\
+                2. Mention the feature in the crate README."
+            }
+            Self::OutOfWorkspacePathDependency => {
+                "Path dependencies outside the workspace do not resolve once published.\n\
+                1. Move the dependency into the workspace, or\n\
+                2. Publish the dependency and depend on its registry version."
+            }
+            Self::EmptyChangelog => {
+                "Release notes are captured from the changelog file.\n\
+                1. Describe the changes between the configured changelog markers, or\n\
+                2. Set `changelog.allow_empty_changelog = true`."
+            }
+            Self::NoChangesSinceRelease => {
+                "Generated changelog has no commits affecting the released crates.\n\
+                1. Check that the previous release tag is correct, or\n\
+                2. Set `changelog.allow_empty_changelog = true`."
+            }
+            Self::DevDependencyVersion => {
+                "In-workspace dev-dependencies are stripped by `cargo publish` only when \
+                they have no version; otherwise publish waits for the unreleased version.\n\
+                1. Remove `version` from in-workspace dev-dependencies, keep `path` only, or\n\
+                2. Set `release.allow_non_path_dev_dependencies = true`."
+            }
+            Self::TrainWindowClosed => {
+                "Train releases are cut only within the scheduled window.\n\
+                1. Wait for the next train date from the error message, or\n\
+                2. Pass `--ignore-train-window` for an urgent release."
+            }
+            Self::ChangelogLint => {
+                "Release notes are permanent once published as a GitHub release.\n\
+                1. Fix the reported problems in the changelog, or\n\
+                2. Teach the linter the project vocabulary, e.g. `_typos.toml` or vale \
+                accept list."
+            }
+            Self::MissingMigrationNotes => {
+                "Semver-incompatible release should tell users how to upgrade.\n\
+                1. Add the section with `changelog.migration_heading` to the changelog of \
+                the release, or\n\
+                2. Bump a compatible version if the release has no breaking changes."
+            }
+        }
+    }
+
+    pub fn parse(code: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|c| c.code().eq_ignore_ascii_case(code.trim()))
+    }
+
+    /// Validation failure tagged with the code
+    pub fn error(self, message: impl Display) -> anyhow::Error {
+        anyhow!(
+            "[{}] {} ({})",
+            self,
+            message,
+            tr!(
+                "run `cargo monorepo explain {code}` for details",
+                code = self
+            )
+        )
+    }
+
+    fn explanation(self) -> String {
+        format!("{}: {}\n\n{}", self, self.summary(), self.remediation())
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Explain validation error code
+#[derive(clap::Parser, Debug)]
+pub struct Command {
+    /// Error code, e.g. E001; all codes are listed if omitted
+    code: Option<String>,
+}
+
+impl Command {
+    /// Explanations do not depend on the config
+    pub fn run(self) -> anyhow::Result<()> {
+        match &self.code {
+            Some(code) => {
+                let code = ErrorCode::parse(code)
+                    .ok_or_else(|| anyhow!("Unknown error code `{}`", code))?;
+                println!("{}", code.explanation());
+            }
+            None => {
+                for code in ErrorCode::ALL {
+                    println!("{}: {}", code, code.summary());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn error_code_explanation() {
+        let code = ErrorCode::parse("e014").unwrap();
+        expect![[r#"
+            E014: in-workspace dev-dependency has a version
+
+            In-workspace dev-dependencies are stripped by `cargo publish` only when they have no version; otherwise publish waits for the unreleased version.
+            1. Remove `version` from in-workspace dev-dependencies, keep `path` only, or
+            2. Set `release.allow_non_path_dev_dependencies = true`."#]].assert_eq(&code.explanation());
+    }
+}
@@ -0,0 +1,470 @@
+use crate::{config::Config, utils::metadata_command};
+use anyhow::{anyhow, Context};
+use std::{fmt::Write as _, path::PathBuf};
+
+const INSTALL_COMMAND: &str =
+    "cargo install --locked --git https://github.com/pacmancoder/cargo-monorepo cargo-monorepo";
+
+/// Targets which release binaries are built for
+const BUILD_TARGETS: &[BuildTarget] = &[
+    BuildTarget {
+        triple: "x86_64-unknown-linux-gnu",
+        runner: "ubuntu-latest",
+        exe_suffix: "",
+    },
+    BuildTarget {
+        triple: "x86_64-pc-windows-msvc",
+        runner: "windows-latest",
+        exe_suffix: ".exe",
+    },
+    BuildTarget {
+        triple: "x86_64-apple-darwin",
+        runner: "macos-latest",
+        exe_suffix: "",
+    },
+];
+
+struct BuildTarget {
+    triple: &'static str,
+    runner: &'static str,
+    exe_suffix: &'static str,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Provider {
+    Github,
+    Gitlab,
+}
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Generate CI release pipeline")]
+pub struct Command {
+    /// CI provider to generate pipeline for
+    #[structopt(long, value_enum)]
+    provider: Provider,
+    /// Write pipeline to the file instead of stdout
+    #[structopt(long)]
+    output: Option<PathBuf>,
+}
+
+impl Command {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        let metadata = metadata_command()
+            .no_deps()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+
+        // Binaries of the published crates are built as release artifacts
+        let bins = metadata
+            .packages
+            .iter()
+            .filter(|p| {
+                metadata.workspace_members.contains(&p.id)
+                    && !matches!(&p.publish, Some(registries) if registries.is_empty())
+            })
+            .flat_map(|p| &p.targets)
+            .filter(|t| t.kind.iter().any(|k| k == "bin"))
+            .map(|t| t.name.clone())
+            .collect::<Vec<_>>();
+
+        let pipeline = CiPipeline::new(&config, bins);
+        let content = match self.provider {
+            Provider::Github => pipeline.render_github(),
+            Provider::Gitlab => pipeline.render_gitlab(),
+        };
+
+        match &self.output {
+            Some(path) => {
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(path, content)
+                    .await
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                println!("📝 CI pipeline has been written to {}", path.display());
+            }
+            None => print!("{}", content),
+        }
+
+        Ok(())
+    }
+}
+
+/// Provider-independent description of the release pipeline
+struct CiPipeline {
+    /// Binary targets to build for every target platform
+    bins: Vec<String>,
+    /// Where `release` expects artifacts to be placed
+    artifacts_directory: Option<String>,
+    /// Env vars with secrets required by the release
+    secrets: Vec<String>,
+    /// Release groups which can be selected on pipeline start
+    groups: Vec<String>,
+    /// Release pushes to the repo (tags, docs)
+    writes_repo: bool,
+}
+
+impl CiPipeline {
+    fn new(config: &Config, bins: Vec<String>) -> Self {
+        let release = config.release.as_ref();
+        let secrets = config.required_secrets();
+
+        let writes_repo = release
+            .is_some_and(|r| r.docs.is_some() || r.github.as_ref().is_some_and(|g| g.create_tag));
+
+        let artifacts_directory = config
+            .artifacts
+            .as_ref()
+            .map(|a| a.directory.to_string_lossy().replace('\\', "/"));
+
+        Self {
+            bins: if artifacts_directory.is_some() {
+                bins
+            } else {
+                vec![]
+            },
+            artifacts_directory,
+            secrets,
+            groups: config.group.keys().cloned().collect(),
+            writes_repo,
+        }
+    }
+
+    fn release_command(&self, group_var: &str) -> String {
+        let mut command = "cargo-monorepo release --confirm".to_owned();
+        if !self.groups.is_empty() {
+            write!(command, " --group {}", group_var).unwrap();
+        }
+        command
+    }
+
+    fn render_github(&self) -> String {
+        let mut out = String::new();
+        let mut line = |indent: usize, text: &str| {
+            writeln!(out, "{:indent$}{}", "", text, indent = indent * 2).unwrap();
+        };
+
+        line(
+            0,
+            "# Generated by `cargo-monorepo generate ci --provider github`",
+        );
+        line(0, "name: release");
+        line(0, "");
+        line(0, "on:");
+        line(1, "workflow_dispatch:");
+        if !self.groups.is_empty() {
+            line(2, "inputs:");
+            line(3, "group:");
+            line(4, "description: Release group");
+            line(4, "required: true");
+            line(4, "type: choice");
+            line(4, "options:");
+            for group in &self.groups {
+                line(5, &format!("- {}", group));
+            }
+        }
+        line(0, "");
+        line(0, "jobs:");
+
+        if !self.bins.is_empty() {
+            line(1, "build:");
+            line(2, "strategy:");
+            line(3, "matrix:");
+            line(4, "include:");
+            for target in BUILD_TARGETS {
+                line(5, &format!("- target: {}", target.triple));
+                line(6, &format!("os: {}", target.runner));
+                line(6, &format!("exe: \"{}\"", target.exe_suffix));
+            }
+            line(2, "runs-on: ${{ matrix.os }}");
+            line(2, "steps:");
+            line(3, "- uses: actions/checkout@v4");
+            line(
+                3,
+                "- run: cargo build --release --target ${{ matrix.target }}",
+            );
+            line(3, "- name: Collect binaries");
+            line(4, "shell: bash");
+            line(4, "run: |");
+            line(5, "mkdir -p dist");
+            for bin in &self.bins {
+                line(
+                    5,
+                    &format!(
+                        "cp \"target/${{{{ matrix.target }}}}/release/{bin}${{{{ matrix.exe }}}}\" \
+                        \"dist/{bin}-${{{{ matrix.target }}}}${{{{ matrix.exe }}}}\"",
+                        bin = bin
+                    ),
+                );
+            }
+            line(3, "- uses: actions/upload-artifact@v4");
+            line(4, "with:");
+            line(5, "name: artifacts-${{ matrix.target }}");
+            line(5, "path: dist/");
+            line(0, "");
+        }
+
+        line(1, "release:");
+        if !self.bins.is_empty() {
+            line(2, "needs: build");
+        }
+        line(2, "runs-on: ubuntu-latest");
+        if self.writes_repo {
+            line(2, "permissions:");
+            line(3, "contents: write");
+        }
+        line(2, "env:");
+        for secret in &self.secrets {
+            line(3, &format!("{0}: ${{{{ secrets.{0} }}}}", secret));
+        }
+        line(2, "steps:");
+        line(3, "- uses: actions/checkout@v4");
+        line(4, "with:");
+        line(5, "fetch-depth: 0");
+        if let (Some(directory), false) = (&self.artifacts_directory, self.bins.is_empty()) {
+            line(3, "- uses: actions/download-artifact@v4");
+            line(4, "with:");
+            line(5, "pattern: artifacts-*");
+            line(5, "merge-multiple: true");
+            line(5, &format!("path: {}", directory));
+        }
+        line(3, &format!("- run: {}", INSTALL_COMMAND));
+        line(
+            3,
+            &format!("- run: {}", self.release_command("${{ inputs.group }}")),
+        );
+
+        out
+    }
+
+    fn render_gitlab(&self) -> String {
+        // Shared GitLab runners are Linux-only, so binaries are built just for it
+        let target = &BUILD_TARGETS[0];
+
+        let mut out = String::new();
+        let mut line = |indent: usize, text: &str| {
+            writeln!(out, "{:indent$}{}", "", text, indent = indent * 2).unwrap();
+        };
+
+        line(
+            0,
+            "# Generated by `cargo-monorepo generate ci --provider gitlab`",
+        );
+        line(
+            0,
+            &format!("# Required CI/CD variables: {}", self.secrets.join(", ")),
+        );
+        line(0, "stages:");
+        if !self.bins.is_empty() {
+            line(1, "- build");
+        }
+        line(1, "- release");
+        if !self.groups.is_empty() {
+            line(0, "");
+            line(0, "variables:");
+            line(1, "RELEASE_GROUP:");
+            line(2, "description: Release group");
+            line(2, &format!("value: {}", self.groups[0]));
+            line(2, "options:");
+            for group in &self.groups {
+                line(3, &format!("- {}", group));
+            }
+        }
+        line(0, "");
+
+        if let (Some(directory), false) = (&self.artifacts_directory, self.bins.is_empty()) {
+            line(0, "build:");
+            line(1, "stage: build");
+            line(1, "image: rust:latest");
+            line(1, "rules:");
+            line(2, "- if: $CI_PIPELINE_SOURCE == \"web\"");
+            line(1, "script:");
+            line(2, "- cargo build --release");
+            line(2, &format!("- mkdir -p {}", directory));
+            for bin in &self.bins {
+                line(
+                    2,
+                    &format!(
+                        "- cp target/release/{bin} {dir}/{bin}-{target}",
+                        bin = bin,
+                        dir = directory,
+                        target = target.triple
+                    ),
+                );
+            }
+            line(1, "artifacts:");
+            line(2, "paths:");
+            line(3, &format!("- {}/", directory));
+            line(0, "");
+        }
+
+        line(0, "release:");
+        line(1, "stage: release");
+        line(1, "image: rust:latest");
+        if !self.bins.is_empty() {
+            line(1, "needs: [build]");
+        }
+        line(1, "rules:");
+        line(2, "- if: $CI_PIPELINE_SOURCE == \"web\"");
+        line(1, "variables:");
+        line(2, "GIT_DEPTH: 0");
+        line(1, "script:");
+        line(2, &format!("- {}", INSTALL_COMMAND));
+        line(
+            2,
+            &format!("- {}", self.release_command("\"$RELEASE_GROUP\"")),
+        );
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn github_workflow() {
+        let config: Config = toml::from_str(
+            r#"
+[workspace]
+root_crate = "foo"
+
+[github]
+repo = "owner/foo"
+
+[artifacts]
+directory = "target/artifacts"
+
+[release]
+[release.github]
+create_tag = true
+"#,
+        )
+        .unwrap();
+        let pipeline = CiPipeline::new(&config, vec!["foo-cli".to_owned()]);
+
+        expect![[r#"
+            # Generated by `cargo-monorepo generate ci --provider github`
+            name: release
+
+            on:
+              workflow_dispatch:
+
+            jobs:
+              build:
+                strategy:
+                  matrix:
+                    include:
+                      - target: x86_64-unknown-linux-gnu
+                        os: ubuntu-latest
+                        exe: ""
+                      - target: x86_64-pc-windows-msvc
+                        os: windows-latest
+                        exe: ".exe"
+                      - target: x86_64-apple-darwin
+                        os: macos-latest
+                        exe: ""
+                runs-on: ${{ matrix.os }}
+                steps:
+                  - uses: actions/checkout@v4
+                  - run: cargo build --release --target ${{ matrix.target }}
+                  - name: Collect binaries
+                    shell: bash
+                    run: |
+                      mkdir -p dist
+                      cp "target/${{ matrix.target }}/release/foo-cli${{ matrix.exe }}" "dist/foo-cli-${{ matrix.target }}${{ matrix.exe }}"
+                  - uses: actions/upload-artifact@v4
+                    with:
+                      name: artifacts-${{ matrix.target }}
+                      path: dist/
+
+              release:
+                needs: build
+                runs-on: ubuntu-latest
+                permissions:
+                  contents: write
+                env:
+                  CARGO_REGISTRY_TOKEN: ${{ secrets.CARGO_REGISTRY_TOKEN }}
+                  GITHUB_TOKEN: ${{ secrets.GITHUB_TOKEN }}
+                steps:
+                  - uses: actions/checkout@v4
+                    with:
+                      fetch-depth: 0
+                  - uses: actions/download-artifact@v4
+                    with:
+                      pattern: artifacts-*
+                      merge-multiple: true
+                      path: target/artifacts
+                  - run: cargo install --locked --git https://github.com/pacmancoder/cargo-monorepo cargo-monorepo
+                  - run: cargo-monorepo release --confirm
+        "#]]
+        .assert_eq(&pipeline.render_github());
+    }
+
+    #[test]
+    fn gitlab_pipeline() {
+        let config: Config = toml::from_str(
+            r#"
+[workspace]
+root_crate = "foo"
+
+[github]
+repo = "owner/foo"
+
+[artifacts]
+directory = "target/artifacts"
+
+[release]
+registry = "internal"
+check_version_raised = false
+
+[group.tools]
+root_crate = "foo-cli"
+"#,
+        )
+        .unwrap();
+        let pipeline = CiPipeline::new(&config, vec!["foo-cli".to_owned()]);
+
+        expect![[r##"
+            # Generated by `cargo-monorepo generate ci --provider gitlab`
+            # Required CI/CD variables: CARGO_REGISTRIES_INTERNAL_TOKEN, GITHUB_TOKEN
+            stages:
+              - build
+              - release
+
+            variables:
+              RELEASE_GROUP:
+                description: Release group
+                value: tools
+                options:
+                  - tools
+
+            build:
+              stage: build
+              image: rust:latest
+              rules:
+                - if: $CI_PIPELINE_SOURCE == "web"
+              script:
+                - cargo build --release
+                - mkdir -p target/artifacts
+                - cp target/release/foo-cli target/artifacts/foo-cli-x86_64-unknown-linux-gnu
+              artifacts:
+                paths:
+                  - target/artifacts/
+
+            release:
+              stage: release
+              image: rust:latest
+              needs: [build]
+              rules:
+                - if: $CI_PIPELINE_SOURCE == "web"
+              variables:
+                GIT_DEPTH: 0
+              script:
+                - cargo install --locked --git https://github.com/pacmancoder/cargo-monorepo cargo-monorepo
+                - cargo-monorepo release --confirm --group "$RELEASE_GROUP"
+        "##]].assert_eq(&pipeline.render_gitlab());
+    }
+}
@@ -0,0 +1,24 @@
+//! Generators of repo boilerplate derived from monorepo.toml
+mod ci;
+
+use crate::config::Config;
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Generate repo boilerplate from the monorepo config")]
+pub struct Command {
+    #[structopt(subcommand)]
+    subcommand: Subcommand,
+}
+
+#[derive(clap::Parser, Debug)]
+enum Subcommand {
+    Ci(ci::Command),
+}
+
+impl Command {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        match self.subcommand {
+            Subcommand::Ci(cmd) => cmd.run(config).await,
+        }
+    }
+}
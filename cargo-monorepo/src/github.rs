@@ -1,8 +1,11 @@
+use crate::config::LargeAssets;
 use anyhow::Context;
 use octocrab::{models::ReleaseId, Octocrab};
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, path::Path, str::FromStr};
 
+pub const GITHUB_TOKEN_VAR: &str = "GITHUB_TOKEN";
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Repo {
     pub owner: String,
@@ -81,6 +84,109 @@ impl Default for ReleasePageBodySource {
     }
 }
 
+/// Repo permission required by the configured release steps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenPermission {
+    /// Tags, releases and asset uploads
+    ContentsWrite,
+    /// Opening pull requests
+    PullRequestsWrite,
+}
+
+impl Display for TokenPermission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenPermission::ContentsWrite => write!(f, "contents:write"),
+            TokenPermission::PullRequestsWrite => write!(f, "pull-requests:write"),
+        }
+    }
+}
+
+/// Access of the token to the repo as reported by GitHub
+#[derive(Debug)]
+pub struct TokenAccess {
+    /// OAuth scopes of the classic token, `None` for fine-grained and app
+    /// tokens which don't report them
+    pub scopes: Option<Vec<String>>,
+    /// Whether the token is allowed to push to the repo
+    pub push: bool,
+}
+
+/// Permissions which are definitely not granted; pull request permission of
+/// fine-grained tokens can't be queried, so it is assumed to be granted
+pub fn missing_permissions(
+    access: &TokenAccess,
+    required: &[TokenPermission],
+) -> Vec<TokenPermission> {
+    let repo_scope = access
+        .scopes
+        .as_ref()
+        .is_none_or(|scopes| scopes.iter().any(|s| s == "repo" || s == "public_repo"));
+    required
+        .iter()
+        .copied()
+        .filter(|permission| {
+            !repo_scope || (*permission == TokenPermission::ContentsWrite && !access.push)
+        })
+        .collect()
+}
+
+pub async fn query_token_access(octocrab: &Octocrab, repo: &Repo) -> anyhow::Result<TokenAccess> {
+    #[derive(Deserialize)]
+    struct Permissions {
+        push: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct RepoInfo {
+        permissions: Option<Permissions>,
+    }
+
+    let route = format!("repos/{}/{}", repo.owner, repo.name);
+    crate::audit::api_call("GET", &route);
+    let response = octocrab
+        ._get(octocrab.absolute_url(&route)?, None::<&()>)
+        .await
+        .with_context(|| format!("Failed to query {} repo", repo))?;
+    match response.status() {
+        reqwest::StatusCode::UNAUTHORIZED => anyhow::bail!("GitHub token is invalid or expired"),
+        reqwest::StatusCode::NOT_FOUND => {
+            anyhow::bail!(
+                "{} repo is not found or not accessible with the token",
+                repo
+            )
+        }
+        status if !status.is_success() => {
+            anyhow::bail!(
+                "Failed to query {} repo: server responded with {}",
+                repo,
+                status
+            )
+        }
+        _ => {}
+    }
+
+    let scopes = response
+        .headers()
+        .get("x-oauth-scopes")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_owned())
+                .filter(|s| !s.is_empty())
+                .collect()
+        });
+    let info: RepoInfo = response
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse {} repo info", repo))?;
+
+    Ok(TokenAccess {
+        scopes,
+        push: info.permissions.is_some_and(|p| p.push),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,6 +234,16 @@ mod tests {
         .assert_debug_eq(&"/name".parse::<Repo>());
     }
 
+    #[test]
+    fn asset_upload_url_escaping() {
+        let url = asset_upload_url(
+            &Repo::new("owner", "repo"),
+            ReleaseId(42),
+            "app windows+x64 ü.zip",
+        );
+        expect!["https://uploads.github.com/repos/owner/repo/releases/42/assets?name=app+windows%2Bx64+%C3%BC.zip"].assert_eq(url.as_str());
+    }
+
     #[test]
     fn repo_roundtrip() {
         let test_toml = TestToml {
@@ -169,17 +285,63 @@ mod tests {
         "#]]
             .assert_debug_eq(&toml::from_str::<TestToml>(invalid_toml));
     }
-}
 
-pub async fn upload_github_release_asset(
-    octocrab: &Octocrab,
-    repo: &Repo,
-    release_id: ReleaseId,
-    file_path: &Path,
-) -> anyhow::Result<()> {
-    let file = std::path::Path::new(file_path);
-    let file_name = file.file_name().unwrap().to_str().unwrap();
+    #[test]
+    fn token_permissions() {
+        let required = [
+            TokenPermission::ContentsWrite,
+            TokenPermission::PullRequestsWrite,
+        ];
+        let access = |scopes: Option<&[&str]>, push| TokenAccess {
+            scopes: scopes.map(|s| s.iter().map(|s| s.to_string()).collect()),
+            push,
+        };
+
+        let missing = [
+            access(Some(&["repo", "workflow"]), true),
+            access(Some(&["read:org"]), true),
+            access(None, false),
+            access(None, true),
+        ]
+        .iter()
+        .map(|a| missing_permissions(a, &required))
+        .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                [],
+                [
+                    ContentsWrite,
+                    PullRequestsWrite,
+                ],
+                [
+                    ContentsWrite,
+                ],
+                [],
+            ]
+        "#]]
+        .assert_debug_eq(&missing);
+    }
+
+    #[test]
+    fn large_asset_parts() {
+        let parts = asset_parts("installer.iso", 2500, 1000)
+            .into_iter()
+            .map(|p| format!("{} {}+{}", p.name, p.offset, p.len))
+            .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                "installer.iso.part001 0+1000",
+                "installer.iso.part002 1000+1000",
+                "installer.iso.part003 2000+500",
+            ]
+        "#]]
+        .assert_debug_eq(&parts);
+    }
+}
 
+/// Asset name is passed via query and may contain spaces or non-ASCII chars
+fn asset_upload_url(repo: &Repo, release_id: ReleaseId, file_name: &str) -> url::Url {
     let release_upload_url = format!(
         "https://uploads.github.com/repos/{owner}/{repo}/releases/{release_id}/assets",
         owner = repo.owner,
@@ -188,19 +350,103 @@ pub async fn upload_github_release_asset(
     );
     let mut release_upload_url =
         url::Url::from_str(&release_upload_url).expect("BUG: Invalid asset upload url");
-    release_upload_url.set_query(Some(format!("{}={}", "name", file_name).as_str()));
-    let file_size = std::fs::metadata(file)
-        .expect("Can't get asset metadata")
-        .len();
-    let file = tokio::fs::File::open(file)
+    release_upload_url
+        .query_pairs_mut()
+        .append_pair("name", file_name);
+    release_upload_url
+}
+
+/// Asset attached to the GitHub release
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub size: u64,
+    pub browser_download_url: String,
+}
+
+/// GitHub release created by the release
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CreatedReleasePage {
+    pub id: u64,
+    pub html_url: String,
+}
+
+fn asset_file_name(file_path: &Path) -> anyhow::Result<String> {
+    file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow::anyhow!("Invalid asset path {}", file_path.display()))
+}
+
+fn asset_file_size(file_path: &Path) -> anyhow::Result<u64> {
+    Ok(std::fs::metadata(file_path)
+        .with_context(|| format!("Can't get asset metadata for {}", file_path.display()))?
+        .len())
+}
+
+pub async fn upload_github_release_asset(
+    octocrab: &Octocrab,
+    repo: &Repo,
+    release_id: ReleaseId,
+    file_path: &Path,
+) -> anyhow::Result<ReleaseAsset> {
+    let part = AssetPart {
+        name: asset_file_name(file_path)?,
+        offset: 0,
+        len: asset_file_size(file_path)?,
+    };
+    upload_asset_part(octocrab, repo, release_id, file_path, &part).await
+}
+
+/// Byte range of the asset file uploaded as a separate asset
+#[derive(Debug, PartialEq, Eq)]
+struct AssetPart {
+    name: String,
+    offset: u64,
+    len: u64,
+}
+
+/// Part numbers are zero-padded, so `<name>.part*` glob lists them in order
+fn asset_parts(name: &str, size: u64, part_bytes: u64) -> Vec<AssetPart> {
+    let count = size.div_ceil(part_bytes).max(1);
+    let width = count.to_string().len().max(3);
+    (0..count)
+        .map(|i| {
+            let offset = i * part_bytes;
+            AssetPart {
+                name: format!("{}.part{:0width$}", name, i + 1, width = width),
+                offset,
+                len: part_bytes.min(size - offset),
+            }
+        })
+        .collect()
+}
+
+async fn upload_asset_part(
+    octocrab: &Octocrab,
+    repo: &Repo,
+    release_id: ReleaseId,
+    file_path: &Path,
+    part: &AssetPart,
+) -> anyhow::Result<ReleaseAsset> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let file = file_path;
+    let release_upload_url = asset_upload_url(repo, release_id, &part.name);
+    crate::audit::upload(file, release_upload_url.as_str());
+    let mut file = tokio::fs::File::open(file)
         .await
-        .expect("Failed to open asset file");
-    let stream = tokio_util::codec::FramedRead::new(file, tokio_util::codec::BytesCodec::new());
+        .with_context(|| format!("Failed to open asset file {}", file.display()))?;
+    file.seek(std::io::SeekFrom::Start(part.offset)).await?;
+    let stream = tokio_util::codec::FramedRead::new(
+        file.take(part.len),
+        tokio_util::codec::BytesCodec::new(),
+    );
     let body = reqwest::Body::wrap_stream(stream);
     let builder = octocrab
         .request_builder(release_upload_url.as_str(), reqwest::Method::POST)
         .header("Content-Type", "application/octet-stream")
-        .header("Content-Length", file_size.to_string());
+        .header("Content-Length", part.len.to_string());
     let resp = builder
         .body(body)
         .send()
@@ -208,7 +454,164 @@ pub async fn upload_github_release_asset(
         .with_context(|| "Failed to send upload artifact request")?;
 
     resp.error_for_status()
-        .with_context(|| "Artifact upload failed")?;
+        .with_context(|| "Artifact upload failed")?
+        .json()
+        .await
+        .with_context(|| "Failed to parse uploaded asset")
+}
+
+/// Interrupted upload leaves the asset in the `starter` state, which blocks
+/// the next upload with the same name
+async fn delete_partial_asset(
+    octocrab: &Octocrab,
+    repo: &Repo,
+    release_id: ReleaseId,
+    name: &str,
+) -> anyhow::Result<()> {
+    #[derive(Deserialize)]
+    struct Asset {
+        id: u64,
+        name: String,
+        state: String,
+    }
+
+    let route = format!(
+        "repos/{}/{}/releases/{}/assets?per_page=100",
+        repo.owner, repo.name, release_id
+    );
+    crate::audit::api_call("GET", &route);
+    let assets: Vec<Asset> = octocrab
+        ._get(octocrab.absolute_url(&route)?, None::<&()>)
+        .await
+        .with_context(|| "Failed to query release assets")?
+        .error_for_status()
+        .with_context(|| "Failed to query release assets")?
+        .json()
+        .await
+        .with_context(|| "Failed to parse release assets")?;
+    for asset in assets.iter().filter(|a| a.name == name) {
+        println!(
+            "\tDeleting {} asset `{}` left by the failed upload",
+            asset.state, asset.name
+        );
+        delete_github_release_asset(octocrab, repo, asset.id).await?;
+    }
+    Ok(())
+}
+
+async fn upload_asset_part_with_retries(
+    octocrab: &Octocrab,
+    repo: &Repo,
+    release_id: ReleaseId,
+    file_path: &Path,
+    part: &AssetPart,
+    large_assets: &LargeAssets,
+) -> anyhow::Result<ReleaseAsset> {
+    let mut attempt = 1;
+    loop {
+        match upload_asset_part(octocrab, repo, release_id, file_path, part).await {
+            Ok(asset) => return Ok(asset),
+            Err(e) if attempt < large_assets.attempts => {
+                println!(
+                    "\tWARN: upload of `{}` failed (attempt {}/{}): {:#}",
+                    part.name, attempt, large_assets.attempts, e
+                );
+                tokio::time::sleep(std::time::Duration::from_secs(
+                    large_assets.retry_interval_seconds,
+                ))
+                .await;
+                delete_partial_asset(octocrab, repo, release_id, &part.name).await?;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Uploads release artifact; large artifacts are retried and, if configured,
+/// split into parts when the whole upload keeps failing
+pub async fn upload_release_artifact(
+    octocrab: &Octocrab,
+    repo: &Repo,
+    release_id: ReleaseId,
+    file_path: &Path,
+    large_assets: Option<&LargeAssets>,
+) -> anyhow::Result<Vec<ReleaseAsset>> {
+    let name = asset_file_name(file_path)?;
+    let size = asset_file_size(file_path)?;
+    let large_assets = match large_assets.filter(|l| size >= l.threshold_bytes) {
+        Some(large_assets) => large_assets,
+        None => {
+            let asset = upload_github_release_asset(octocrab, repo, release_id, file_path).await?;
+            return Ok(vec![asset]);
+        }
+    };
+
+    println!("\t{} is {} bytes, uploading as a large asset", name, size);
+    let whole = AssetPart {
+        name: name.clone(),
+        offset: 0,
+        len: size,
+    };
+    let error = match upload_asset_part_with_retries(
+        octocrab,
+        repo,
+        release_id,
+        file_path,
+        &whole,
+        large_assets,
+    )
+    .await
+    {
+        Ok(asset) => return Ok(vec![asset]),
+        Err(e) => e,
+    };
+    let part_bytes = match large_assets.part_bytes {
+        Some(part_bytes) => part_bytes,
+        None => return Err(error),
+    };
 
+    println!(
+        "\tWARN: {} can't be uploaded as a whole ({:#}), falling back to multi-part upload",
+        name, error
+    );
+    delete_partial_asset(octocrab, repo, release_id, &name).await?;
+    let mut assets = vec![];
+    for part in asset_parts(&name, size, part_bytes) {
+        println!("\tUploading `{}`", part.name);
+        let asset = upload_asset_part_with_retries(
+            octocrab,
+            repo,
+            release_id,
+            file_path,
+            &part,
+            large_assets,
+        )
+        .await?;
+        assets.push(asset);
+    }
+    Ok(assets)
+}
+
+pub async fn delete_github_release_asset(
+    octocrab: &Octocrab,
+    repo: &Repo,
+    asset_id: u64,
+) -> anyhow::Result<()> {
+    let route = format!(
+        "repos/{}/{}/releases/assets/{}",
+        repo.owner, repo.name, asset_id
+    );
+    crate::audit::api_call("DELETE", &route);
+    let response = octocrab
+        ._delete(octocrab.absolute_url(&route)?, None::<&()>)
+        .await
+        .with_context(|| "Failed to send delete asset request")?;
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "Failed to delete asset: server responded with {}",
+            response.status()
+        );
+    }
     Ok(())
 }
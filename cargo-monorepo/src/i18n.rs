@@ -0,0 +1,138 @@
+//! Localization of the user-facing messages. English messages are used as
+//! catalog keys, `{name}` placeholders are filled with the named arguments of
+//! [`tr!`]; messages missing from the catalog are printed in English
+use anyhow::bail;
+use std::{collections::BTreeMap, fmt::Display, sync::OnceLock};
+
+type Catalog = BTreeMap<String, String>;
+
+/// Bundled catalogs, English is the source language
+const CATALOGS: &[(&str, &str)] = &[("de", include_str!("locales/de.toml"))];
+
+pub const LANG_ENV: &str = "CARGO_MONOREPO_LANG";
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+fn parse_catalog(content: &str) -> anyhow::Result<Catalog> {
+    Ok(toml::from_str(content)?)
+}
+
+/// `de_DE.UTF-8` and `de-AT` are resolved to `de`
+fn language_code(lang: &str) -> String {
+    lang.split(['_', '-', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Selects the language of the output; `--lang` takes precedence over
+/// `CARGO_MONOREPO_LANG`
+pub fn set_lang(lang: Option<&str>) -> anyhow::Result<()> {
+    let lang = match lang {
+        Some(lang) => lang.to_owned(),
+        None => match std::env::var(LANG_ENV) {
+            Ok(lang) if !lang.is_empty() => lang,
+            _ => return Ok(()),
+        },
+    };
+
+    let code = language_code(&lang);
+    if code == "en" {
+        return Ok(());
+    }
+    let content = match CATALOGS.iter().find(|(c, _)| *c == code) {
+        Some((_, content)) => content,
+        None => {
+            let available = CATALOGS.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+            bail!(
+                "Unsupported language `{}`, available languages: en, {}",
+                lang,
+                available.join(", ")
+            );
+        }
+    };
+    let catalog = parse_catalog(content)
+        .unwrap_or_else(|e| panic!("BUG: invalid bundled `{}` catalog: {}", code, e));
+    let _ = CATALOG.set(catalog);
+    Ok(())
+}
+
+fn localize(catalog: Option<&Catalog>, message: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut localized = catalog
+        .and_then(|c| c.get(message))
+        .map_or(message, String::as_str)
+        .to_owned();
+    for (name, value) in args {
+        localized = localized.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    localized
+}
+
+pub fn translate(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    localize(CATALOG.get(), message, args)
+}
+
+/// Localized message, e.g. `tr!("Tag {tag} has been created", tag = tag)`
+macro_rules! tr {
+    ($message:literal) => {
+        $crate::i18n::translate($message, &[])
+    };
+    ($message:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate(
+            $message,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+pub(crate) use tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    /// Translations should not reference placeholders missing from the
+    /// English message
+    #[test]
+    fn bundled_catalogs() {
+        let mut problems = vec![];
+        for (code, content) in CATALOGS {
+            let catalog = parse_catalog(content).unwrap();
+            for (message, translation) in &catalog {
+                let placeholders = translation
+                    .split('{')
+                    .skip(1)
+                    .filter_map(|s| s.split_once('}'))
+                    .map(|(name, _)| format!("{{{}}}", name));
+                for placeholder in placeholders {
+                    if !message.contains(&placeholder) {
+                        problems.push(format!("{}: `{}` {}", code, message, placeholder));
+                    }
+                }
+            }
+        }
+        expect![[r#"
+            []
+        "#]]
+        .assert_debug_eq(&problems);
+
+        let catalog = parse_catalog(CATALOGS[0].1).unwrap();
+        let version = semver::Version::new(1, 2, 0);
+        let messages = [
+            localize(
+                Some(&catalog),
+                "Creating new tag for version {version}",
+                &[("version", &version)],
+            ),
+            localize(Some(&catalog), "Not translated {n}", &[("n", &1)]),
+        ];
+        expect![[r#"
+            [
+                "Neuer Tag für Version 1.2.0 wird erstellt",
+                "Not translated 1",
+            ]
+        "#]]
+        .assert_debug_eq(&messages);
+    }
+}
@@ -1,23 +1,44 @@
+pub(crate) mod audit;
+pub(crate) mod calver;
 pub(crate) mod cargo;
+pub(crate) mod changelog;
 pub(crate) mod config;
 pub(crate) mod github;
+pub(crate) mod i18n;
+pub(crate) mod pattern;
+pub(crate) mod registry;
 pub(crate) mod template;
 pub(crate) mod utils;
 
+mod diff;
+mod doctor;
+mod explain;
+mod generate;
+mod outdated;
+mod preview;
 mod release;
+mod scaffold;
+mod status;
 
 use crate::config::Config;
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use clap::Parser as _;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(clap::Parser, Debug)]
 #[structopt(about = env!("CARGO_PKG_DESCRIPTION"))]
 struct Args {
-    /// Explicitly set manifest to process instead of
-    /// choosing manifest in current working directory
-    #[structopt(long, default_value = "monorepo.toml")]
-    manifest_path: PathBuf,
+    /// Explicitly set manifest to process instead of looking for
+    /// `monorepo.toml` in the current directory and its parents
+    #[structopt(long)]
+    manifest_path: Option<PathBuf>,
+    /// Workspace root, overrides `workspace.path` from the config
+    #[structopt(long)]
+    workspace_root: Option<PathBuf>,
+    /// Language of the output, e.g. `de`; `CARGO_MONOREPO_LANG` env var is
+    /// used if not specified
+    #[structopt(long)]
+    lang: Option<String>,
     #[structopt(subcommand)]
     subcommand: Subcommand,
 }
@@ -26,34 +47,120 @@ struct Args {
 #[structopt(about = env!("CARGO_PKG_DESCRIPTION"))]
 enum Subcommand {
     Release(release::Command),
+    OutdatedWorkspace(outdated::Command),
+    New(scaffold::Command),
+    Generate(generate::Command),
+    Doctor(doctor::Command),
+    Changelog(changelog::Command),
+    Diff(diff::Command),
+    PreviewCrate(preview::Command),
+    Status(status::Command),
+    Promote(release::PromoteCommand),
+    Bump(release::BumpCommand),
+    Changed(release::ChangedCommand),
+    Deprecate(release::DeprecateCommand),
+    Export(release::ExportCommand),
+    Yank(release::YankCommand),
+    Plan(release::PlanCommand),
+    Explain(explain::Command),
+}
+
+const MANIFEST_NAME: &str = "monorepo.toml";
+
+/// Looks for the manifest the same way cargo looks for `Cargo.toml`
+fn find_manifest() -> anyhow::Result<PathBuf> {
+    let cwd = std::env::current_dir().with_context(|| "Failed to get current directory")?;
+    cwd.ancestors()
+        .map(|dir| dir.join(MANIFEST_NAME))
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            anyhow!(
+                "{} is not found in {} or any parent directory",
+                MANIFEST_NAME,
+                cwd.display()
+            )
+        })
 }
 
 async fn run(args: Args) -> anyhow::Result<()> {
-    let manifest_path_str = args.manifest_path.display();
+    i18n::set_lang(args.lang.as_deref())?;
+
+    let args = match args.subcommand {
+        Subcommand::Explain(cmd) => return cmd.run(),
+        _ => args,
+    };
 
-    let config_content = tokio::fs::read_to_string(&args.manifest_path)
+    let manifest_path = match args.manifest_path {
+        Some(path) => path,
+        None => find_manifest()?,
+    };
+    let manifest_path_str = manifest_path.display();
+
+    let config_content = tokio::fs::read_to_string(&manifest_path)
         .await
         .with_context(|| format!("Failed to read {} config", manifest_path_str))?;
 
     let config: Config = toml::from_str(&config_content)
         .with_context(|| format!("Failed to parse {}", manifest_path_str))?;
 
-    config
-        .validate()
-        .with_context(|| "Config validation failed")?;
-
-    if let Some(working_dir) = args.manifest_path.parent() {
-        std::env::set_current_dir(working_dir).expect("Failed to set working dir");
+    // Doctor reports config validation failures along with other checks
+    if !matches!(args.subcommand, Subcommand::Doctor(_)) {
+        config
+            .validate()
+            .with_context(|| "Config validation failed")?;
     }
 
+    // Process working directory is left intact, so relative paths passed on
+    // the command line keep pointing where the user expects
+    let workspace_root = match &args.workspace_root {
+        Some(root) => root.clone(),
+        None => manifest_path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(
+                config
+                    .workspace
+                    .path
+                    .as_deref()
+                    .unwrap_or_else(|| Path::new("")),
+            ),
+    };
+    let workspace_root = workspace_root
+        .join(".")
+        .canonicalize()
+        .with_context(|| format!("Invalid workspace root {}", workspace_root.display()))?;
+    utils::set_workspace_root(workspace_root);
+
     match args.subcommand {
         Subcommand::Release(cmd) => cmd.run(config).await,
+        Subcommand::OutdatedWorkspace(cmd) => cmd.run(config).await,
+        Subcommand::New(cmd) => cmd.run(config).await,
+        Subcommand::Generate(cmd) => cmd.run(config).await,
+        Subcommand::Doctor(cmd) => cmd.run(config).await,
+        Subcommand::Changelog(cmd) => cmd.run(config).await,
+        Subcommand::Diff(cmd) => cmd.run(config).await,
+        Subcommand::PreviewCrate(cmd) => cmd.run(config).await,
+        Subcommand::Status(cmd) => cmd.run(config).await,
+        Subcommand::Promote(cmd) => cmd.run(config).await,
+        Subcommand::Bump(cmd) => cmd.run(config).await,
+        Subcommand::Changed(cmd) => cmd.run(config).await,
+        Subcommand::Deprecate(cmd) => cmd.run(config, &manifest_path).await,
+        Subcommand::Export(cmd) => cmd.run(config).await,
+        Subcommand::Yank(cmd) => cmd.run(config).await,
+        Subcommand::Plan(cmd) => cmd.run(config).await,
+        Subcommand::Explain(cmd) => cmd.run(),
     }
 }
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
-    let args = Args::parse();
+    // Cargo passes subcommand name as the first argument when invoked as
+    // `cargo monorepo`
+    let mut raw_args = std::env::args_os().collect::<Vec<_>>();
+    if raw_args.get(1).is_some_and(|arg| arg == "monorepo") {
+        raw_args.remove(1);
+    }
+    let args = Args::parse_from(raw_args);
     if let Err(e) = run(args).await {
         println!("❌ {:#}", e);
         std::process::exit(1);
@@ -0,0 +1,245 @@
+use crate::{
+    config::Config,
+    registry::{download_crate, query_last_released_version},
+    utils::{metadata_command, run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, bail, Context};
+use cargo_metadata::Package;
+use flate2::read::GzDecoder;
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Files which are generated by `cargo package` and can't be compared
+/// with the local package sources
+const GENERATED_PACKAGE_FILES: &[&str] = &["Cargo.toml", "Cargo.lock", ".cargo_vcs_info.json"];
+/// Original manifest is stored under this name in the published package
+const ORIGINAL_MANIFEST: &str = "Cargo.toml.orig";
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Show workspace crates which have changed since their last published version")]
+pub struct Command {
+    /// Print list of changed files for every outdated crate
+    #[structopt(long)]
+    verbose: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum FileChange {
+    Added(PathBuf),
+    Removed(PathBuf),
+    Modified(PathBuf),
+}
+
+impl std::fmt::Display for FileChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileChange::Added(path) => write!(f, "+ {}", path.display()),
+            FileChange::Removed(path) => write!(f, "- {}", path.display()),
+            FileChange::Modified(path) => write!(f, "~ {}", path.display()),
+        }
+    }
+}
+
+pub(crate) type PackageFiles = BTreeMap<PathBuf, Vec<u8>>;
+
+impl Command {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        if let Some(registry) = config.release.as_ref().and_then(|r| r.registry.as_ref()) {
+            bail!(
+                "Published package comparison is only supported for crates.io, but `{}` registry is configured",
+                registry
+            );
+        }
+
+        let metadata = metadata_command()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+
+        let mut packages = metadata
+            .packages
+            .iter()
+            .filter(|p| {
+                // for publish = false, package.publish would contain Some(vec![])
+                metadata.workspace_members.contains(&p.id)
+                    && !matches!(&p.publish, Some(registries) if registries.is_empty())
+            })
+            .collect::<Vec<_>>();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut outdated = vec![];
+        for package in packages {
+            let prev_version = query_last_released_version(&package.name).await?;
+            let prev_version = match prev_version {
+                Some(v) => v,
+                None => {
+                    println!("🆕 {} has never been published", package.name);
+                    outdated.push(package.name.clone());
+                    continue;
+                }
+            };
+
+            let published = download_crate(&package.name, &prev_version).await?;
+            let published = unpack_crate(&published)
+                .with_context(|| format!("Failed to unpack {} v{}", package.name, prev_version))?;
+            let local = collect_local_package_files(package).await?;
+
+            let changes = diff_package_files(&local, &published);
+            if changes.is_empty() {
+                println!(
+                    "✅ {} is up to date with published v{}",
+                    package.name, prev_version
+                );
+                continue;
+            }
+
+            println!(
+                "📦 {} has {} changed file(s) since published v{}",
+                package.name,
+                changes.len(),
+                prev_version
+            );
+            if self.verbose {
+                for change in &changes {
+                    println!("\t{}", change);
+                }
+            }
+            outdated.push(package.name.clone());
+        }
+
+        if outdated.is_empty() {
+            println!("🎉 All workspace crates are up to date with their published versions");
+        } else {
+            println!("🚧 Crates which need a release: {}", outdated.join(", "));
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads package files from the `.crate` archive; paths are relative to the
+/// package root
+pub(crate) fn unpack_crate(data: &[u8]) -> anyhow::Result<PackageFiles> {
+    let mut archive = tar::Archive::new(GzDecoder::new(data));
+    let mut files = PackageFiles::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        // Every file is stored under `<name>-<version>/` directory
+        let path = entry.path()?.components().skip(1).collect::<PathBuf>();
+        let mut content = vec![];
+        entry.read_to_end(&mut content)?;
+        files.insert(path, content);
+    }
+
+    Ok(files)
+}
+
+async fn collect_local_package_files(package: &Package) -> anyhow::Result<PackageFiles> {
+    let manifest_path: &Path = package.manifest_path.as_ref();
+    let package_root = manifest_path
+        .parent()
+        .ok_or_else(|| anyhow!("Invalid manifest path of {}", package.name))?;
+
+    let mut cmd = workspace_command("cargo");
+    cmd.args(["package", "--list", "--allow-dirty", "--manifest-path"]);
+    cmd.arg(manifest_path);
+    let list = run_and_capture_stdout(&mut cmd)
+        .await
+        .with_context(|| format!("Failed to list package files of {}", package.name))?;
+
+    let mut files = PackageFiles::new();
+    for path in list.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if GENERATED_PACKAGE_FILES.contains(&path) {
+            continue;
+        }
+        let source_path = if path == ORIGINAL_MANIFEST {
+            package_root.join("Cargo.toml")
+        } else {
+            package_root.join(path)
+        };
+        let content = tokio::fs::read(&source_path)
+            .await
+            .with_context(|| format!("Failed to read {}", source_path.display()))?;
+        files.insert(PathBuf::from(path), content);
+    }
+
+    Ok(files)
+}
+
+fn diff_package_files(local: &PackageFiles, published: &PackageFiles) -> Vec<FileChange> {
+    let is_generated = |path: &Path| {
+        GENERATED_PACKAGE_FILES
+            .iter()
+            .any(|generated| path == Path::new(generated))
+    };
+
+    let mut changes = vec![];
+    for (path, content) in local {
+        match published.get(path) {
+            None => changes.push(FileChange::Added(path.clone())),
+            Some(published_content) if published_content != content => {
+                changes.push(FileChange::Modified(path.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    for path in published.keys() {
+        if !local.contains_key(path) && !is_generated(path) {
+            changes.push(FileChange::Removed(path.clone()));
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    fn files(entries: &[(&str, &str)]) -> PackageFiles {
+        entries
+            .iter()
+            .map(|(path, content)| (PathBuf::from(path), content.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn package_files_diff() {
+        let published = files(&[
+            ("Cargo.toml", "normalized"),
+            ("Cargo.toml.orig", "original"),
+            (".cargo_vcs_info.json", "{}"),
+            ("src/lib.rs", "lib"),
+            ("src/old.rs", "old"),
+            ("README.md", "readme"),
+        ]);
+        let local = files(&[
+            ("Cargo.toml.orig", "original"),
+            ("src/lib.rs", "lib v2"),
+            ("src/new.rs", "new"),
+            ("README.md", "readme"),
+        ]);
+
+        let changes = diff_package_files(&local, &published)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                "~ src/lib.rs",
+                "+ src/new.rs",
+                "- src/old.rs",
+            ]
+        "#]]
+        .assert_debug_eq(&changes);
+
+        assert!(diff_package_files(&local, &local).is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::fmt::Display;
+
+/// Glob-style file pattern, e.g. `*-linux-*.tar.gz` or `crates/net/**`
+#[derive(Clone, Debug)]
+pub struct FilePattern {
+    pattern: glob::Pattern,
+}
+
+impl FilePattern {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let pattern = glob::Pattern::new(pattern)
+            .with_context(|| format!("Invalid file pattern: {}", pattern))?;
+        Ok(Self { pattern })
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        self.pattern.matches(path)
+    }
+}
+
+impl Display for FilePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+impl<'de> Deserialize<'de> for FilePattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de;
+
+        let pattern = String::deserialize(deserializer)?;
+        Self::new(&pattern).map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Str(&pattern), &"valid glob pattern")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[derive(Deserialize)]
+    struct TestToml {
+        pattern: FilePattern,
+    }
+
+    #[test]
+    fn pattern_matching() {
+        let pattern = toml::from_str::<TestToml>(r#"pattern = "*-linux-*""#)
+            .unwrap()
+            .pattern;
+
+        assert!(pattern.matches("app-linux-x86_64.tar.gz"));
+        assert!(!pattern.matches("app-windows-x86_64.zip"));
+
+        let pattern = FilePattern::new("crates/net/**").unwrap();
+        assert!(pattern.matches("crates/net/src/lib.rs"));
+        assert!(!pattern.matches("crates/core/src/lib.rs"));
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let result = FilePattern::new("***").map(|_| ());
+        expect![[r#"
+            Err(
+                "Invalid file pattern: ***",
+            )
+        "#]]
+        .assert_debug_eq(&result.map_err(|e| e.to_string()));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{
+    config::Config,
+    outdated::{unpack_crate, PackageFiles},
+    utils::{metadata_command, run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, Context};
+use std::{fmt::Write as _, path::Path};
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Package the crate and show roughly what its crates.io page would display")]
+pub struct Command {
+    /// Name of the workspace crate
+    name: String,
+    /// Package the crate with uncommitted changes
+    #[structopt(long)]
+    allow_dirty: bool,
+    /// Don't print README of the packaged crate
+    #[structopt(long)]
+    no_readme: bool,
+}
+
+/// crates.io page summary built from the packaged manifest and files
+#[derive(Debug)]
+struct CratePreview {
+    name: String,
+    version: String,
+    description: Option<String>,
+    license: Option<String>,
+    /// Links shown in the page sidebar: homepage, documentation, repository
+    links: Vec<(&'static str, String)>,
+    keywords: Vec<String>,
+    categories: Vec<String>,
+    /// Feature names, `default` first
+    features: Vec<String>,
+    readme: Option<String>,
+    file_count: usize,
+    unpacked_size: u64,
+    crate_size: u64,
+}
+
+impl CratePreview {
+    fn new(files: &PackageFiles, crate_size: u64) -> anyhow::Result<Self> {
+        let manifest = files
+            .get(Path::new("Cargo.toml"))
+            .ok_or_else(|| anyhow!("Packaged crate has no Cargo.toml"))?;
+        let manifest: toml::Value = toml::from_str(std::str::from_utf8(manifest)?)
+            .with_context(|| "Failed to parse packaged Cargo.toml")?;
+        let package = manifest
+            .get("package")
+            .ok_or_else(|| anyhow!("Packaged Cargo.toml has no [package] section"))?;
+
+        let string = |key: &str| package.get(key).and_then(|v| v.as_str()).map(str::to_owned);
+        let strings = |key: &str| {
+            package
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_str().map(str::to_owned))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+
+        let links = vec!["homepage", "documentation", "repository"]
+            .into_iter()
+            .filter_map(|key| string(key).map(|url| (key, url)))
+            .collect();
+
+        let mut features = manifest
+            .get("features")
+            .and_then(|f| f.as_table())
+            .map(|f| f.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        features.sort_by_key(|f| (f != "default", f.clone()));
+
+        // Packaged manifest keeps readme path relative to the package root
+        let readme = string("readme")
+            .and_then(|path| files.get(Path::new(&path)))
+            .map(|content| String::from_utf8_lossy(content).into_owned());
+
+        Ok(CratePreview {
+            name: string("name").unwrap_or_default(),
+            version: string("version").unwrap_or_default(),
+            description: string("description"),
+            license: string("license").or_else(|| string("license-file")),
+            links,
+            keywords: strings("keywords"),
+            categories: strings("categories"),
+            features,
+            readme,
+            file_count: files.len(),
+            unpacked_size: files.values().map(|content| content.len() as u64).sum(),
+            crate_size,
+        })
+    }
+
+    fn render(&self, with_readme: bool) -> String {
+        let or_missing = |value: &Option<String>| {
+            value
+                .clone()
+                .unwrap_or_else(|| "⚠️ not specified".to_owned())
+        };
+        let list = |values: &[String]| {
+            if values.is_empty() {
+                "-".to_owned()
+            } else {
+                values.join(", ")
+            }
+        };
+
+        let mut out = String::new();
+        writeln!(out, "📦 {} v{}", self.name, self.version).unwrap();
+        writeln!(out, "{}", or_missing(&self.description)).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "License:    {}", or_missing(&self.license)).unwrap();
+        for (kind, url) in &self.links {
+            writeln!(out, "{:<12}{}", format!("{}:", capitalize(kind)), url).unwrap();
+        }
+        writeln!(out, "Keywords:   {}", list(&self.keywords)).unwrap();
+        writeln!(out, "Categories: {}", list(&self.categories)).unwrap();
+        writeln!(out, "Features:   {}", list(&self.features)).unwrap();
+        writeln!(
+            out,
+            "Files:      {} ({} bytes unpacked, {} bytes .crate)",
+            self.file_count, self.unpacked_size, self.crate_size
+        )
+        .unwrap();
+
+        if with_readme {
+            writeln!(out).unwrap();
+            match &self.readme {
+                Some(readme) => {
+                    writeln!(out, "---- README ----").unwrap();
+                    write!(out, "{}", readme).unwrap();
+                    if !readme.ends_with('\n') {
+                        writeln!(out).unwrap();
+                    }
+                    writeln!(out, "----------------").unwrap();
+                }
+                None => writeln!(out, "⚠️ No README is packaged").unwrap(),
+            }
+        }
+
+        out
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl Command {
+    pub async fn run(self, _config: Config) -> anyhow::Result<()> {
+        let metadata = metadata_command()
+            .no_deps()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let package = metadata
+            .packages
+            .iter()
+            .find(|p| metadata.workspace_members.contains(&p.id) && p.name == self.name)
+            .ok_or_else(|| anyhow!("{} is not a workspace crate", self.name))?;
+
+        let mut cmd = workspace_command("cargo");
+        cmd.args(["package", "--no-verify", "--manifest-path"])
+            .arg(package.manifest_path.as_std_path());
+        if self.allow_dirty {
+            cmd.arg("--allow-dirty");
+        }
+        run_and_capture_stdout(&mut cmd)
+            .await
+            .with_context(|| format!("Failed to package {}", package.name))?;
+
+        let crate_path = metadata
+            .target_directory
+            .join("package")
+            .join(format!("{}-{}.crate", package.name, package.version));
+        let data = tokio::fs::read(&crate_path)
+            .await
+            .with_context(|| format!("Failed to read {}", crate_path))?;
+        let files =
+            unpack_crate(&data).with_context(|| format!("Failed to unpack {}", crate_path))?;
+
+        let preview = CratePreview::new(&files, data.len() as u64)?;
+        print!("{}", preview.render(!self.no_readme));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn packaged_crate_preview() {
+        let manifest = r#"
+[package]
+name = "foo"
+version = "1.2.0"
+description = "Foo utilities"
+license = "MIT"
+repository = "https://github.com/owner/foo"
+readme = "README.md"
+keywords = ["foo", "utils"]
+
+[features]
+serde = []
+default = ["std"]
+std = []
+"#;
+        let files = vec![
+            ("Cargo.toml", manifest),
+            ("README.md", "# foo\n\nUtilities\n"),
+            ("src/lib.rs", "pub fn foo() {}\n"),
+        ]
+        .into_iter()
+        .map(|(path, content)| (path.into(), content.as_bytes().to_vec()))
+        .collect::<PackageFiles>();
+
+        let preview = CratePreview::new(&files, 1024).unwrap();
+        expect![[r#"
+            📦 foo v1.2.0
+            Foo utilities
+
+            License:    MIT
+            Repository: https://github.com/owner/foo
+            Keywords:   foo, utils
+            Categories: -
+            Features:   default, serde, std
+            Files:      3 (264 bytes unpacked, 1024 bytes .crate)
+
+            ---- README ----
+            # foo
+
+            Utilities
+            ----------------
+        "#]]
+        .assert_eq(&preview.render(true));
+    }
+}
@@ -0,0 +1,624 @@
+use crate::{audit, config};
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use semver::Version;
+use serde::Deserialize;
+use std::{env, time::Duration};
+
+const CRATES_IO_DOWNLOAD_URL: &str = "https://static.crates.io/crates";
+const CRATES_IO_API_URL: &str = "https://crates.io/api/v1";
+const CRATES_IO_INDEX_URL: &str = "https://index.crates.io";
+const CRATES_IO_NAME: &str = "crates.io";
+/// Name of crates.io in the `publish` field of the package
+pub const CRATES_IO_REGISTRY_NAME: &str = "crates-io";
+const CRATES_IO_CRATES_URL: &str = "https://crates.io/crates";
+const CLOUDSMITH_API_URL: &str = "https://api.cloudsmith.io/v1";
+pub const CLOUDSMITH_API_KEY_VAR: &str = "CLOUDSMITH_API_KEY";
+const INDEX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// crates.io requires the user agent identifying the client
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Env var which is used by cargo to get token of the given registry
+pub fn registry_token_var(registry: Option<&str>) -> String {
+    use convert_case::{Case, Casing};
+
+    registry
+        .map(|r| format!("CARGO_REGISTRIES_{}_TOKEN", r.to_case(Case::UpperSnake)))
+        .unwrap_or_else(|| "CARGO_REGISTRY_TOKEN".to_owned())
+}
+
+/// Registry the package is published to, `None` stands for crates.io; with
+/// `route_by_publish` it is the first registry of the package `publish` field
+pub fn publish_registry(release: &config::Release, publish: Option<&[String]>) -> Option<String> {
+    match publish.and_then(|p| p.first()) {
+        Some(first) if release.route_by_publish => {
+            Some(first.clone()).filter(|r| r != CRATES_IO_REGISTRY_NAME)
+        }
+        _ => release.registry.clone(),
+    }
+}
+
+/// Delay before publishing the next crate to the registry
+pub fn publish_interval(release: &config::Release, registry: Option<&str>) -> usize {
+    registry
+        .and_then(|r| release.registries.get(r))
+        .and_then(|target| target.publish_interval_seconds)
+        .unwrap_or(release.publish_interval_seconds)
+}
+
+/// Sparse index URL of the registry, `None` if it is not configured for the
+/// custom registry
+fn index_url(release: &config::Release, registry: Option<&str>) -> Option<String> {
+    let url = match registry {
+        None => Some(CRATES_IO_INDEX_URL),
+        Some(registry) if release.registry.as_deref() == Some(registry) => {
+            release.registry_index_url.as_deref()
+        }
+        Some(registry) => release
+            .registries
+            .get(registry)
+            .and_then(|target| target.registry_index_url.as_deref()),
+    }?;
+    Some(
+        url.trim_start_matches("sparse+")
+            .trim_end_matches('/')
+            .to_owned(),
+    )
+}
+
+/// Path of the crate file in the sparse index, e.g. `se/rd/serde`
+fn index_path(crate_name: &str) -> String {
+    let name = crate_name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+#[derive(Deserialize)]
+struct IndexEntry {
+    vers: Version,
+    #[serde(default)]
+    yanked: bool,
+}
+
+/// Entries of the index file, one JSON entry per line
+fn index_entries(index_file: &str) -> Vec<IndexEntry> {
+    index_file
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn sparse_index(release: &config::Release, registry: Option<&str>) -> Option<SparseIndex> {
+    Some(SparseIndex {
+        url: index_url(release, registry)?,
+        // Index of the private registry requires the registry token
+        token: registry.and_then(|r| env::var(registry_token_var(Some(r))).ok()),
+    })
+}
+
+/// Queries the crate versions from the sparse index of the custom registry,
+/// `None` if the crate is not published
+pub async fn query_index_crate(
+    release: &config::Release,
+    registry: &str,
+    crate_name: &str,
+) -> anyhow::Result<Option<RegistryCrate>> {
+    let index = sparse_index(release, Some(registry)).with_context(|| {
+        format!(
+            "Sparse index URL of `{}` registry is not configured",
+            registry
+        )
+    })?;
+    Ok(index.fetch(crate_name).await?.map(|file| RegistryCrate {
+        versions: index_entries(&file)
+            .into_iter()
+            .map(|entry| CrateVersion {
+                num: entry.vers,
+                yanked: entry.yanked,
+            })
+            .collect(),
+    }))
+}
+
+/// Waits until the published version is resolvable from the registry index,
+/// so crates depending on it can be published; registries which can't be
+/// polled are waited for `publish_interval_seconds` instead
+pub async fn wait_until_published(
+    release: &config::Release,
+    registry: Option<&str>,
+    crate_name: &str,
+    version: &Version,
+) -> anyhow::Result<()> {
+    let poller: Option<Box<dyn RegistryAdapter>> = match sparse_index(release, registry) {
+        Some(index) => Some(Box::new(index)),
+        None if registry == release.registry.as_deref() => registry_adapter(release)?,
+        None => None,
+    };
+    let poller = match poller {
+        Some(poller) => poller,
+        None => {
+            let interval = publish_interval(release, registry);
+            println!(
+                "Waiting for {} seconds before publishing next crate...",
+                interval
+            );
+            tokio::time::sleep(Duration::from_secs(interval as u64)).await;
+            return Ok(());
+        }
+    };
+
+    println!(
+        "Waiting for {} v{} to appear in the registry index...",
+        crate_name, version
+    );
+    let deadline =
+        tokio::time::Instant::now() + Duration::from_secs(release.publish_timeout_seconds);
+    while !poller.version_exists(crate_name, version).await? {
+        if tokio::time::Instant::now() >= deadline {
+            bail!(
+                "{} v{} has not appeared in the registry index within {} seconds",
+                crate_name,
+                version,
+                release.publish_timeout_seconds
+            );
+        }
+        tokio::time::sleep(INDEX_POLL_INTERVAL).await;
+    }
+    Ok(())
+}
+
+/// Crate page in the registry UI, if known
+pub fn crate_page_url(
+    release: Option<&config::Release>,
+    registry: Option<&str>,
+    name: &str,
+) -> Option<String> {
+    let base_url = match (release, registry) {
+        (Some(release), Some(registry)) if release.registry.as_deref() != Some(registry) => release
+            .registries
+            .get(registry)
+            .and_then(|target| target.registry_url.as_ref())
+            .map(|url| url.trim_end_matches('/').to_owned()),
+        (_, None) => Some(CRATES_IO_CRATES_URL.to_owned()),
+        _ => registry_url(release),
+    };
+    base_url.map(|url| format!("{}/{}", url, name))
+}
+
+/// Name of the registry crates are published to
+pub fn registry_name(release: Option<&config::Release>) -> String {
+    release
+        .and_then(|r| r.registry.clone())
+        .unwrap_or_else(|| CRATES_IO_NAME.to_owned())
+}
+
+/// URL of the registry crate pages, `None` if it is not configured for the
+/// custom registry
+pub fn registry_url(release: Option<&config::Release>) -> Option<String> {
+    match release {
+        Some(config::Release {
+            registry: Some(_),
+            registry_url,
+            ..
+        }) => registry_url
+            .as_ref()
+            .map(|url| url.trim_end_matches('/').to_owned()),
+        _ => Some(CRATES_IO_CRATES_URL.to_owned()),
+    }
+}
+
+/// docs.rs page of the crate, `None` for custom registries as docs.rs builds
+/// only crates.io packages
+pub fn docs_url(registry: Option<&str>, name: &str, version: &Version) -> Option<String> {
+    match registry {
+        Some(_) => None,
+        None => Some(format!("https://docs.rs/{}/{}", name, version)),
+    }
+}
+
+/// Base URL of the API used to query the configured release registry, `None`
+/// if the custom registry has no configured API
+pub fn registry_api_url(release: Option<&config::Release>) -> Option<&'static str> {
+    match release.map(|r| (&r.registry, &r.registry_api)) {
+        None | Some((None, _)) => Some(CRATES_IO_API_URL),
+        Some((Some(_), Some(config::RegistryApi::Cloudsmith { .. }))) => Some(CLOUDSMITH_API_URL),
+        Some((Some(_), None)) => None,
+    }
+}
+
+/// Registry-specific API used to query published packages
+#[async_trait]
+pub trait RegistryAdapter: Send + Sync {
+    async fn version_exists(&self, crate_name: &str, version: &Version) -> anyhow::Result<bool>;
+}
+
+/// Returns adapter for the configured release registry, `None` if the custom
+/// registry has no configured API
+pub fn registry_adapter(
+    release: &config::Release,
+) -> anyhow::Result<Option<Box<dyn RegistryAdapter>>> {
+    let adapter: Box<dyn RegistryAdapter> = match (&release.registry, &release.registry_api) {
+        (None, _) => Box::new(CratesIo),
+        (Some(_), Some(config::RegistryApi::Cloudsmith { owner, repository })) => {
+            let api_key = env::var(CLOUDSMITH_API_KEY_VAR).with_context(|| {
+                format!(
+                    "Cloudsmith API key is missing, please specify it via {} env var",
+                    CLOUDSMITH_API_KEY_VAR
+                )
+            })?;
+            Box::new(Cloudsmith {
+                owner: owner.clone(),
+                repository: repository.clone(),
+                api_key,
+            })
+        }
+        (Some(_), None) => return Ok(None),
+    };
+
+    Ok(Some(adapter))
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::new()
+}
+
+struct CratesIo;
+
+#[async_trait]
+impl RegistryAdapter for CratesIo {
+    async fn version_exists(&self, crate_name: &str, version: &Version) -> anyhow::Result<bool> {
+        let url = format!("{}/crates/{}/{}", CRATES_IO_API_URL, crate_name, version);
+        audit::api_call("GET", &url);
+        let response = http_client()
+            .get(&url)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .send()
+            .await
+            .with_context(|| format!("Failed to query crates.io for {}", crate_name))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => bail!(
+                "Failed to query crates.io for {}: registry responded with {}",
+                crate_name,
+                status
+            ),
+        }
+    }
+}
+
+/// Registry sparse index, which reflects what cargo can actually resolve
+struct SparseIndex {
+    url: String,
+    token: Option<String>,
+}
+
+impl SparseIndex {
+    /// Index file of the crate, `None` if the crate is not published
+    async fn fetch(&self, crate_name: &str) -> anyhow::Result<Option<String>> {
+        let url = format!("{}/{}", self.url, index_path(crate_name));
+        audit::api_call("GET", &url);
+        let mut request = http_client()
+            .get(&url)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            // Index files are cached by CDN
+            .header(reqwest::header::CACHE_CONTROL, "no-cache");
+        if let Some(token) = &self.token {
+            request = request.header(reqwest::header::AUTHORIZATION, token);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to query registry index for {}", crate_name))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => response
+                .text()
+                .await
+                .map(Some)
+                .with_context(|| format!("Failed to read index file of {}", crate_name)),
+            status => bail!(
+                "Failed to query registry index for {}: index responded with {}",
+                crate_name,
+                status
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl RegistryAdapter for SparseIndex {
+    async fn version_exists(&self, crate_name: &str, version: &Version) -> anyhow::Result<bool> {
+        Ok(self
+            .fetch(crate_name)
+            .await?
+            .is_some_and(|file| index_entries(&file).iter().any(|e| &e.vers == version)))
+    }
+}
+
+/// Cloudsmith accepts publish of already existing version, so package
+/// presence is checked via its packages API
+struct Cloudsmith {
+    owner: String,
+    repository: String,
+    api_key: String,
+}
+
+#[async_trait]
+impl RegistryAdapter for Cloudsmith {
+    async fn version_exists(&self, crate_name: &str, version: &Version) -> anyhow::Result<bool> {
+        let url = format!(
+            "{}/packages/{}/{}/",
+            CLOUDSMITH_API_URL, self.owner, self.repository
+        );
+        let query = format!("format:cargo name:^{}$ version:^{}$", crate_name, version);
+        audit::api_call("GET", &url);
+        let response = http_client()
+            .get(&url)
+            .query(&[("query", query)])
+            .header("X-Api-Key", &self.api_key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to query Cloudsmith for {}", crate_name))?;
+
+        if !response.status().is_success() {
+            bail!(
+                "Failed to query Cloudsmith for {}: API responded with {}",
+                crate_name,
+                response.status()
+            );
+        }
+
+        let packages: Vec<serde_json::Value> = response
+            .json()
+            .await
+            .with_context(|| "Failed to parse Cloudsmith packages response")?;
+
+        Ok(!packages.is_empty())
+    }
+}
+
+/// Crate versions published to the registry
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct RegistryCrate {
+    pub versions: Vec<CrateVersion>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CrateVersion {
+    pub num: Version,
+    pub yanked: bool,
+}
+
+impl RegistryCrate {
+    /// Versions which are not yanked
+    pub fn released_versions(&self) -> impl Iterator<Item = &Version> {
+        self.versions.iter().filter(|v| !v.yanked).map(|v| &v.num)
+    }
+
+    /// Latest version which is not yanked, prereleases included
+    pub fn latest_version(&self) -> Option<&Version> {
+        self.released_versions().max()
+    }
+
+    /// `None` if the version is not published
+    pub fn is_yanked(&self, version: &Version) -> Option<bool> {
+        self.versions
+            .iter()
+            .find(|v| &v.num == version)
+            .map(|v| v.yanked)
+    }
+}
+
+/// Queries the crate versions published to crates.io, `None` if the crate
+/// is not published
+pub async fn query_crate(crate_name: &str) -> anyhow::Result<Option<RegistryCrate>> {
+    let url = format!("{}/crates/{}", CRATES_IO_API_URL, crate_name);
+    audit::api_call("GET", &url);
+    let response = http_client()
+        .get(&url)
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .send()
+        .await
+        .with_context(|| format!("Failed to query crates.io for {}", crate_name))?;
+
+    match response.status() {
+        StatusCode::NOT_FOUND => return Ok(None),
+        status if !status.is_success() => bail!(
+            "Failed to query crates.io for {}: registry responded with {}",
+            crate_name,
+            status
+        ),
+        _ => {}
+    }
+
+    response
+        .json()
+        .await
+        .map(Some)
+        .with_context(|| format!("Failed to parse crates.io response for {}", crate_name))
+}
+
+/// Queries the crate versions from crates.io or from the sparse index of the
+/// custom registry, `None` if the crate is not published
+pub async fn query_registry_crate(
+    release: &config::Release,
+    registry: Option<&str>,
+    crate_name: &str,
+) -> anyhow::Result<Option<RegistryCrate>> {
+    match registry {
+        Some(registry) => query_index_crate(release, registry, crate_name).await,
+        None => query_crate(crate_name).await,
+    }
+}
+
+/// Latest non-yanked version of the crate published to crates.io
+pub async fn query_last_released_version(crate_name: &str) -> anyhow::Result<Option<Version>> {
+    Ok(query_crate(crate_name)
+        .await?
+        .and_then(|c| c.latest_version().cloned()))
+}
+
+/// Queries all non-yanked versions of the crate published to crates.io
+pub async fn query_released_versions(crate_name: &str) -> anyhow::Result<Vec<Version>> {
+    Ok(query_crate(crate_name)
+        .await?
+        .map(|c| c.released_versions().cloned().collect())
+        .unwrap_or_default())
+}
+
+/// Downloads published `.crate` archive from crates.io
+pub async fn download_crate(crate_name: &str, version: &Version) -> anyhow::Result<Vec<u8>> {
+    let url = format!(
+        "{}/{}/{}-{}.crate",
+        CRATES_IO_DOWNLOAD_URL, crate_name, crate_name, version
+    );
+    audit::api_call("GET", &url);
+
+    let response = reqwest::Client::new()
+        .get(&url)
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {} v{}", crate_name, version))?;
+
+    if !response.status().is_success() {
+        bail!(
+            "Failed to download {} v{}: registry responded with {}",
+            crate_name,
+            version,
+            response.status()
+        );
+    }
+
+    Ok(response.bytes().await?.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn publish_routing() {
+        let release: config::Release = toml::from_str(
+            r#"
+            registry = "internal"
+            registry_url = "https://crates.internal.dev"
+            publish_interval_seconds = 10
+            route_by_publish = true
+
+            [registries.partner]
+            publish_interval_seconds = 60
+            registry_url = "https://partner.dev/crates/"
+            "#,
+        )
+        .unwrap();
+
+        let routes = vec![
+            None,
+            Some(vec!["crates-io".to_owned()]),
+            Some(vec!["partner".to_owned(), "internal".to_owned()]),
+        ]
+        .into_iter()
+        .map(|publish| {
+            let registry = publish_registry(&release, publish.as_deref());
+            format!(
+                "{:?} -> {:?}, interval {}, page {:?}",
+                publish,
+                registry,
+                publish_interval(&release, registry.as_deref()),
+                crate_page_url(Some(&release), registry.as_deref(), "foo"),
+            )
+        })
+        .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                "None -> Some(\"internal\"), interval 10, page Some(\"https://crates.internal.dev/foo\")",
+                "Some([\"crates-io\"]) -> None, interval 10, page Some(\"https://crates.io/crates/foo\")",
+                "Some([\"partner\", \"internal\"]) -> Some(\"partner\"), interval 60, page Some(\"https://partner.dev/crates/foo\")",
+            ]
+        "#]].assert_debug_eq(&routes);
+    }
+
+    #[test]
+    fn published_crate_versions() {
+        let published: RegistryCrate = serde_json::from_str(
+            r#"{"versions": [
+                {"num": "1.1.0", "yanked": true},
+                {"num": "1.1.0-rc.1", "yanked": false},
+                {"num": "1.0.0", "yanked": false}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            published.latest_version(),
+            Some(&Version::parse("1.1.0-rc.1").unwrap())
+        );
+        assert_eq!(published.is_yanked(&Version::new(1, 1, 0)), Some(true));
+        assert_eq!(published.is_yanked(&Version::new(1, 2, 0)), None);
+    }
+
+    #[test]
+    fn sparse_index_lookup() {
+        let release: config::Release = toml::from_str(
+            r#"
+            registry = "internal"
+            registry_index_url = "sparse+https://crates.internal.dev/index/"
+            "#,
+        )
+        .unwrap();
+        let urls = vec![None, Some("internal"), Some("partner")]
+            .into_iter()
+            .map(|registry| index_url(&release, registry))
+            .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                Some(
+                    "https://index.crates.io",
+                ),
+                Some(
+                    "https://crates.internal.dev/index",
+                ),
+                None,
+            ]
+        "#]]
+        .assert_debug_eq(&urls);
+
+        let paths = vec!["a", "ab", "abc", "Serde_json"]
+            .into_iter()
+            .map(index_path)
+            .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                "1/a",
+                "2/ab",
+                "3/a/abc",
+                "se/rd/serde_json",
+            ]
+        "#]]
+        .assert_debug_eq(&paths);
+
+        let index_file = concat!(
+            r#"{"name":"foo","vers":"1.0.0","deps":[],"cksum":"00","features":{},"yanked":false}"#,
+            "\n",
+            r#"{"name":"foo","vers":"1.1.0-rc.1","deps":[],"cksum":"00","features":{},"yanked":true}"#,
+        );
+        let entries = index_entries(index_file)
+            .into_iter()
+            .map(|e| (e.vers.to_string(), e.yanked))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![("1.0.0".to_owned(), false), ("1.1.0-rc.1".to_owned(), true)]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{anyhow, bail};
+use semver::Version;
+use std::fmt::Display;
+
+/// Version line of the maintenance branch, e.g. `1.x` or `1.8.x`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenanceLine {
+    major: u64,
+    minor: Option<u64>,
+}
+
+impl MaintenanceLine {
+    /// Parses version line from the last segment of the branch name,
+    /// e.g. `release/1.x`
+    pub fn from_branch(branch: &str) -> anyhow::Result<Self> {
+        let invalid_branch = || {
+            anyhow!(
+                "Failed to parse version line from `{}` branch name, expected name \
+                like `release/1.x` or `release/1.8.x`",
+                branch
+            )
+        };
+
+        let line = branch.rsplit('/').next().unwrap_or(branch);
+        let line = line.strip_prefix('v').unwrap_or(line);
+        let line = line.strip_suffix(".x").ok_or_else(invalid_branch)?;
+
+        let mut parts = line.split('.');
+        let major = parts
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid_branch)?;
+        let minor = parts
+            .next()
+            .map(|s| s.parse().map_err(|_| invalid_branch()))
+            .transpose()?;
+        if parts.next().is_some() {
+            bail!(invalid_branch());
+        }
+
+        Ok(Self { major, minor })
+    }
+
+    pub fn contains(&self, version: &Version) -> bool {
+        version.major == self.major && (self.minor.is_none() || self.minor == Some(version.minor))
+    }
+}
+
+impl Display for MaintenanceLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.minor {
+            Some(minor) => write!(f, "{}.{}.x", self.major, minor),
+            None => write!(f, "{}.x", self.major),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn parse_maintenance_line() {
+        let line = MaintenanceLine::from_branch("release/1.x").unwrap();
+        assert_eq!(line.to_string(), "1.x");
+        assert!(line.contains(&Version::new(1, 8, 4)));
+        assert!(!line.contains(&Version::new(2, 1, 0)));
+
+        let line = MaintenanceLine::from_branch("maintenance/v1.8.x").unwrap();
+        assert_eq!(line.to_string(), "1.8.x");
+        assert!(line.contains(&Version::new(1, 8, 4)));
+        assert!(!line.contains(&Version::new(1, 9, 0)));
+
+        let result = MaintenanceLine::from_branch("feature/foo").map_err(|e| e.to_string());
+        expect![[r#"
+            Err(
+                "Failed to parse version line from `feature/foo` branch name, expected name like `release/1.x` or `release/1.8.x`",
+            )
+        "#]]
+        .assert_debug_eq(&result);
+    }
+}
@@ -0,0 +1,171 @@
+//! Version bump of all released workspace crates in one pass, so manifests
+//! are not edited by hand before the release
+use super::{
+    context::packages_to_publish,
+    conventional::ConventionalBump,
+    dependents::{bump_dependents, restore_manifests, set_workspace_version},
+    step::versions_consistent,
+};
+use crate::{
+    config::{Config, VersionBump, VersionSource},
+    explain::ErrorCode,
+    utils::metadata_command,
+};
+use anyhow::{anyhow, bail};
+use semver::Version;
+use std::{collections::BTreeSet, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BumpTarget {
+    Level(VersionBump),
+    Exact(Version),
+}
+
+impl FromStr for BumpTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "major" => Self::Level(VersionBump::Major),
+            "minor" => Self::Level(VersionBump::Minor),
+            "patch" => Self::Level(VersionBump::Patch),
+            _ => Self::Exact(Version::parse(s).map_err(|_| {
+                anyhow!("Expected patch, minor, major or exact version, got `{}`", s)
+            })?),
+        })
+    }
+}
+
+impl BumpTarget {
+    fn next_version(&self, current: &Version) -> anyhow::Result<Version> {
+        match self {
+            Self::Level(bump) => Ok(bump.apply(current)),
+            Self::Exact(version) if version <= current => bail!(
+                "New version {} should be greater than the current version {}",
+                version,
+                current
+            ),
+            Self::Exact(version) => Ok(version.clone()),
+        }
+    }
+}
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Bump version of the released workspace crates and requirements on them")]
+pub struct BumpCommand {
+    /// `patch`, `minor`, `major` or exact version; inferred from the commits
+    /// with `release.version_from = "conventional-commits"` if omitted. With
+    /// `release.bump_dependents` dependents of the raised crates are bumped
+    target: Option<BumpTarget>,
+}
+
+impl BumpCommand {
+    async fn inferred_target(config: &Config) -> anyhow::Result<BumpTarget> {
+        let version_from = config
+            .release
+            .as_ref()
+            .map(|r| r.version_from)
+            .unwrap_or_default();
+        if version_from != VersionSource::ConventionalCommits {
+            bail!(
+                "Bump target should be specified unless the version is inferred \
+                with release.version_from = \"conventional-commits\""
+            );
+        }
+        let inferred = ConventionalBump::query().await?;
+        println!(
+            "🔍 Commits since `{}` require {:?} bump",
+            inferred.tag, inferred.bump
+        );
+        Ok(BumpTarget::Level(inferred.bump))
+    }
+
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        if config.release.as_ref().is_some_and(|r| r.bump_dependents) {
+            // Crates are versioned independently, only dependents of the
+            // raised crates are bumped
+            if self.target.is_some() {
+                bail!(
+                    "Bump target can't be specified with release.bump_dependents, \
+                    dependents of the raised crates are bumped instead"
+                );
+            }
+            return bump_dependents(&config).await;
+        }
+
+        let metadata = metadata_command()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let root_crate = &config.workspace.root_crate;
+        let current = &metadata
+            .packages
+            .iter()
+            .find(|p| &p.name == root_crate)
+            .ok_or_else(|| anyhow!("Root crate {} is not found in the workspace", root_crate))?
+            .version;
+        let target = match self.target {
+            Some(target) => target,
+            None => Self::inferred_target(&config).await?,
+        };
+        let version = target.next_version(current)?;
+
+        println!("⬆️ Bumping workspace version {} -> {}", current, version);
+        let members = packages_to_publish(&metadata, &config)
+            .into_iter()
+            .map(|p| p.name.as_str())
+            .collect::<BTreeSet<_>>();
+        let originals = set_workspace_version(&metadata, &members, current, &version).await?;
+
+        println!("🔍 Checking crates version consistency...");
+        let metadata = metadata_command()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        if !versions_consistent(&packages_to_publish(&metadata, &config), &version, false) {
+            restore_manifests(&originals).await;
+            return Err(ErrorCode::VersionSkew
+                .error("Bumped crates are inconsistent, manifests have been restored"));
+        }
+
+        println!(
+            "🚀 Workspace version has been bumped to {}, {} manifest(s) updated",
+            version,
+            originals.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn bump_targets() {
+        let current = Version::new(1, 4, 2);
+        let next = ["patch", "minor", "major", "2.0.0-rc.1", "1.4.0", "next"]
+            .iter()
+            .map(|target| {
+                let next = target
+                    .parse::<BumpTarget>()
+                    .and_then(|t| t.next_version(&current));
+                match next {
+                    Ok(version) => format!("{} -> {}", target, version),
+                    Err(e) => format!("{} -> {}", target, e),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                "patch -> 1.4.3",
+                "minor -> 1.5.0",
+                "major -> 2.0.0",
+                "2.0.0-rc.1 -> 2.0.0-rc.1",
+                "1.4.0 -> New version 1.4.0 should be greater than the current version 1.4.2",
+                "next -> Expected patch, minor, major or exact version, got `next`",
+            ]
+        "#]]
+        .assert_debug_eq(&next);
+    }
+}
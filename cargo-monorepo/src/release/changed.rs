@@ -0,0 +1,230 @@
+//! Detection of the crates changed since the previous release, so crates
+//! with the same source as the already published version are not published
+//! again
+use super::{context::packages_to_publish, conventional::tag_version};
+use crate::{
+    config::Config,
+    diff::latest_tag,
+    utils::{metadata_command, run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, Context};
+use cargo_metadata::{DependencyKind, Metadata, Package};
+use semver::Version;
+use std::collections::BTreeSet;
+
+/// Crate directory relative to the workspace root, empty for the root crate
+fn crate_dir(metadata: &Metadata, package: &Package) -> anyhow::Result<String> {
+    package
+        .manifest_path
+        .parent()
+        .and_then(|dir| dir.strip_prefix(&metadata.workspace_root).ok())
+        .map(|dir| dir.as_str().replace('\\', "/"))
+        .ok_or_else(|| anyhow!("Invalid manifest path of `{}` crate", package.name))
+}
+
+/// The crate which directory is the closest parent of the file, so the files
+/// of nested crates don't belong to the outer one
+fn file_owner<'a>(file: &str, dirs: &[(&'a str, String)]) -> Option<&'a str> {
+    dirs.iter()
+        .filter(|(_, dir)| dir.is_empty() || file.starts_with(&format!("{}/", dir)))
+        .max_by_key(|(_, dir)| dir.len())
+        .map(|(name, _)| *name)
+}
+
+/// `true` if `git diff -U0` of the manifest only replaces the previous
+/// version with the new one, e.g. in `version` and dependency requirements
+fn version_only_diff(diff: &str, prev_version: &Version, version: &Version) -> bool {
+    let (prev_version, version) = (prev_version.to_string(), version.to_string());
+    let mut removed = vec![];
+    let mut added = vec![];
+    for line in diff.lines() {
+        if line.starts_with("---") || line.starts_with("+++") {
+            continue;
+        }
+        if let Some(line) = line.strip_prefix('-') {
+            removed.push(line.replace(&prev_version, &version));
+        } else if let Some(line) = line.strip_prefix('+') {
+            added.push(line.to_owned());
+        }
+    }
+    removed.sort();
+    added.sort();
+    removed == added
+}
+
+async fn git_diff(metadata: &Metadata, args: &[&str]) -> anyhow::Result<String> {
+    let mut cmd = workspace_command("git");
+    cmd.current_dir(&metadata.workspace_root)
+        .arg("diff")
+        .args(args);
+    run_and_capture_stdout(&mut cmd)
+        .await
+        .with_context(|| format!("Failed to run git diff {}", args.join(" ")))
+}
+
+/// Names of the released crates changed since `since` revision. Unchanged
+/// crates are still released if the new requirements of the changed crates
+/// on them don't match the published version
+pub async fn changed_crates(
+    metadata: &Metadata,
+    config: &Config,
+    since: &str,
+    prev_version: Option<&Version>,
+) -> anyhow::Result<BTreeSet<String>> {
+    let files = git_diff(metadata, &["--name-only", "--relative", since, "HEAD"]).await?;
+    let files = files.lines().map(str::trim).filter(|f| !f.is_empty());
+
+    let members = metadata
+        .packages
+        .iter()
+        .filter(|p| metadata.workspace_members.contains(&p.id))
+        .map(|p| Ok((p.name.as_str(), crate_dir(metadata, p)?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mapping = config
+        .changelog
+        .as_ref()
+        .map(|c| c.mapping.as_slice())
+        .unwrap_or_default();
+
+    let packages = packages_to_publish(metadata, config);
+    let mut changed = BTreeSet::new();
+    for file in files {
+        let owner = file_owner(file, &members);
+        let mapped = mapping
+            .iter()
+            .filter(|m| m.paths.iter().any(|p| p.matches(file)))
+            .map(|m| m.crate_name.as_str());
+        for name in owner.into_iter().chain(mapped) {
+            let package = match packages.iter().find(|p| p.name == name) {
+                Some(package) => package,
+                None => continue,
+            };
+            if changed.contains(name) {
+                continue;
+            }
+            let manifest = match crate_dir(metadata, package)? {
+                dir if dir.is_empty() => "Cargo.toml".to_owned(),
+                dir => format!("{}/Cargo.toml", dir),
+            };
+            if let (true, Some(prev_version)) = (file == manifest, prev_version) {
+                let diff =
+                    git_diff(metadata, &["-U0", "--relative", since, "HEAD", "--", file]).await?;
+                if version_only_diff(&diff, prev_version, &package.version) {
+                    continue;
+                }
+            }
+            changed.insert(name.to_owned());
+        }
+    }
+
+    // Unchanged dependency is released anyway if its published version does
+    // not satisfy the requirement of the changed crate
+    loop {
+        let required = packages
+            .iter()
+            .filter(|p| changed.contains(&p.name))
+            .flat_map(|p| &p.dependencies)
+            .filter(|d| d.kind != DependencyKind::Development && !changed.contains(&d.name))
+            .filter(|d| packages.iter().any(|p| p.name == d.name))
+            .filter(|d| !prev_version.is_some_and(|v| d.req.matches(v)))
+            .map(|d| d.name.clone())
+            .collect::<Vec<_>>();
+        if required.is_empty() {
+            break;
+        }
+        changed.extend(required);
+    }
+
+    Ok(changed)
+}
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "List released crates changed since the previous release")]
+pub struct ChangedCommand {
+    /// Revision of the previous release, the latest tag reachable from HEAD
+    /// by default
+    #[structopt(long)]
+    since: Option<String>,
+}
+
+impl ChangedCommand {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        let metadata = metadata_command()
+            .no_deps()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let since = match self.since {
+            Some(since) => since,
+            None => latest_tag()
+                .await
+                .ok_or_else(|| anyhow!("No tags found, please specify --since"))?,
+        };
+        let prev_version = tag_version(&since);
+
+        let changed = changed_crates(&metadata, &config, &since, prev_version.as_ref()).await?;
+        println!("Changed crates since `{}`:", since);
+        for package in packages_to_publish(&metadata, &config) {
+            if changed.contains(&package.name) {
+                println!("  ✏️ {}", package.name);
+            } else {
+                println!("  ✅ {} (unchanged)", package.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn changed_files() {
+        let dirs = vec![
+            ("app", String::new()),
+            ("app-core", "crates/core".to_owned()),
+        ];
+        let owners = vec![
+            "src/main.rs",
+            "crates/core/src/lib.rs",
+            "crates/core-extra.md",
+        ]
+        .into_iter()
+        .map(|f| file_owner(f, &dirs))
+        .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                Some(
+                    "app",
+                ),
+                Some(
+                    "app-core",
+                ),
+                Some(
+                    "app",
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&owners);
+
+        let (prev, new) = (Version::new(1, 0, 0), Version::new(1, 1, 0));
+        let bump = "--- a/Cargo.toml\n+++ b/Cargo.toml\n@@ -3 +3 @@\n\
+            -version = \"1.0.0\"\n+version = \"1.1.0\"\n\
+            @@ -9 +9 @@\n-core = { path = \"core\", version = \"1.0.0\" }\n\
+            +core = { path = \"core\", version = \"1.1.0\" }\n";
+        let feature = "@@ -3 +3,2 @@\n-version = \"1.0.0\"\n+version = \"1.1.0\"\n\
+            +serde = \"1\"\n";
+        let diffs = vec![bump, feature]
+            .into_iter()
+            .map(|diff| version_only_diff(diff, &prev, &new))
+            .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                true,
+                false,
+            ]
+        "#]]
+        .assert_debug_eq(&diffs);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::utils::shorten_commit;
+use chrono::NaiveDate;
+use semver::{BuildMetadata, Prerelease, Version};
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReleaseChannel {
+    /// Regular release of the workspace version
+    #[default]
+    Stable,
+    /// Snapshot of the current commit published as a rolling GitHub prerelease
+    Nightly,
+}
+
+/// Nightly version derived from the workspace one, e.g. `1.4.0-nightly.20240601+1a2b3c4`
+pub fn nightly_version(version: &Version, date: NaiveDate, commit: &str) -> Version {
+    let mut nightly = Version::new(version.major, version.minor, version.patch);
+    nightly.pre = Prerelease::new(&format!("nightly.{}", date.format("%Y%m%d")))
+        .expect("BUG: invalid nightly prerelease");
+    nightly.build =
+        BuildMetadata::new(&shorten_commit(commit)).expect("BUG: invalid nightly build metadata");
+    nightly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn nightly_version_format() {
+        let version = nightly_version(
+            &Version::parse("1.4.0-rc.1").unwrap(),
+            NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+            "1a2b3c4d5e6f",
+        );
+        expect!["1.4.0-nightly.20240601+1a2b3c4"].assert_eq(&version.to_string());
+    }
+}
@@ -1,27 +1,92 @@
+use super::{
+    branch::MaintenanceLine,
+    channel::ReleaseChannel,
+    journal,
+    report::{AssetDigest, PublishedCrate, ReleaseReport, ReproducibilityReport},
+    resource::{
+        Artifacts, Attestations, CApiArtifacts, Changelog, CrateNotes, CreatedRelease,
+        CurrentCommit, DependencySnapshot, GeneratedArtifacts, GithubTag, PatchArtifacts,
+        PendingVersion, PrevRelease, PrevVersion, PublishedCrates, PublishedRootCrate, Resource,
+        ResourceId, Resources, SourceArtifacts, UploadedAssets,
+    },
+};
 use crate::{
     config::{self, Config},
-    template::TextTemplateContext,
+    github::ReleaseAsset,
+    registry,
+    template::{AssetContext, PackageContext, PublishedCrateContext, TextTemplateContext},
+    utils::{run_and_capture_stdout, sha256_file, workspace_command, workspace_path},
 };
 use anyhow::{anyhow, Context};
 use cargo_metadata::{Metadata, Package};
 use octocrab::Octocrab as GithubClient;
 use semver::Version;
-use std::path::PathBuf;
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+/// Publishable workspace packages; limited to the root crate and listed
+/// members if `workspace.members` is specified
+pub fn packages_to_publish<'a>(metadata: &'a Metadata, config: &Config) -> Vec<&'a Package> {
+    let members = &config.workspace.members;
+
+    metadata
+        .packages
+        .iter()
+        .filter(|p| {
+            // for publish = false, package.publish would contain Some(vec![])
+            metadata.workspace_members.contains(&p.id)
+                && p.publish.as_ref().map_or(true, |r| !r.is_empty())
+                && !config.workspace.deprecated.contains(&p.name)
+                && (members.is_empty()
+                    || p.name == config.workspace.root_crate
+                    || members.contains(&p.name))
+        })
+        .collect()
+}
 
+/// Shared state of the release process. Values produced by the steps are
+/// stored as typed set-once resources, so independent steps can be executed
+/// concurrently
 pub struct ReleaseContext {
     pub dry_run: bool,
     pub nopublish: bool,
     pub config: Config,
-    pub crates_io_token: Option<String>,
-    github_token: Option<String>,
-    pub current_commit: Option<String>,
-    pub metadata: Option<Metadata>,
-    pub version: Option<Version>,
-    pub prev_version: Option<Option<Version>>,
-    pub changelog: Option<String>,
-    pub artifacts: Option<Vec<PathBuf>>,
-    github_release_tag: Option<String>,
-    github_client: Option<GithubClient>,
+    /// Set when releasing from the maintenance branch of the older version line
+    pub maintenance_line: Option<MaintenanceLine>,
+    /// Emergency release with relaxed requirements
+    pub hotfix: bool,
+    /// Validation steps bypassed with `--force-skip-validation`
+    pub forced_skips: Vec<String>,
+    /// Release only to GitHub, the registry is not accessed at all
+    pub github_only: bool,
+    /// Publish crates only, GitHub and other forge steps are skipped
+    pub publish_only: bool,
+    /// Continue interrupted release, completed release steps and crates from
+    /// the publish journal are skipped
+    pub resume: bool,
+    /// Dry run without tokens and API calls, only local checks are executed
+    pub offline: bool,
+    /// Tag of the release candidate promoted to the final version; manifests
+    /// are rewritten to the final version, so the working tree is dirty
+    pub promoted_from: Option<String>,
+    pub channel: ReleaseChannel,
+    crates_io_token: OnceLock<String>,
+    github_token: OnceLock<String>,
+    resources: Resources,
+    /// Total size of the files uploaded during the release
+    uploaded_bytes: AtomicU64,
+    /// Release step has been started, so remote state could be changed
+    release_started: AtomicBool,
+    /// Commit made by the release itself, e.g. with the promoted changelog;
+    /// it is released instead of the validated one
+    release_commit: Mutex<Option<String>>,
+    /// Actions which were skipped in dry run mode
+    planned_actions: Mutex<Vec<String>>,
 }
 
 impl ReleaseContext {
@@ -30,19 +95,62 @@ impl ReleaseContext {
             dry_run,
             nopublish,
             config,
-            crates_io_token: None,
-            github_token: None,
-            current_commit: None,
-            metadata: None,
-            version: None,
-            prev_version: None,
-            changelog: None,
-            artifacts: None,
-            github_release_tag: None,
-            github_client: None,
+            maintenance_line: None,
+            hotfix: false,
+            forced_skips: vec![],
+            resume: false,
+            offline: false,
+            promoted_from: None,
+            github_only: false,
+            publish_only: false,
+            channel: ReleaseChannel::Stable,
+            crates_io_token: OnceLock::new(),
+            github_token: OnceLock::new(),
+            resources: Resources::default(),
+            uploaded_bytes: AtomicU64::new(0),
+            release_started: AtomicBool::new(false),
+            release_commit: Mutex::new(None),
+            planned_actions: Mutex::new(vec![]),
         }
     }
 
+    /// Allocates storage for the resources provided by the release plan steps
+    pub fn init_resources(&mut self, provided: impl IntoIterator<Item = ResourceId>) {
+        self.resources = Resources::new(provided);
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    pub fn provide<R: Resource>(&self, value: R) -> anyhow::Result<()> {
+        self.resources.provide(value)
+    }
+
+    pub fn record_upload(&self, bytes: u64) {
+        self.uploaded_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn uploaded_bytes(&self) -> u64 {
+        self.uploaded_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn mark_release_started(&self) {
+        self.release_started.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_release_started(&self) -> bool {
+        self.release_started.load(Ordering::Relaxed)
+    }
+
+    /// Reports action skipped in dry run mode; should describe it with the
+    /// exact values which would be used for the real release
+    pub fn would(&self, action: impl Into<String>) {
+        let action = action.into();
+        println!("WOULD: {}", action);
+        self.planned_actions.lock().unwrap().push(action);
+    }
+
     pub fn is_dry_run(&self) -> bool {
         self.dry_run
     }
@@ -76,6 +184,122 @@ impl ReleaseContext {
             .ok_or_else(|| anyhow!("release section is missing from the config"))
     }
 
+    pub fn release_docs_config(&self) -> anyhow::Result<&config::DocsRelease> {
+        self.release_config()?
+            .docs
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.docs section is missing from the config"))
+    }
+
+    pub fn release_git_notes_config(&self) -> anyhow::Result<&config::GitNotesRelease> {
+        self.release_config()?
+            .git_notes
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.git_notes section is missing from the config"))
+    }
+
+    pub fn release_releases_file_config(&self) -> anyhow::Result<&config::ReleasesFile> {
+        self.release_config()?
+            .releases_file
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.releases_file section is missing from the config"))
+    }
+
+    pub fn release_npm_config(&self) -> anyhow::Result<&config::NpmRelease> {
+        self.release_config()?
+            .npm
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.npm section is missing from the config"))
+    }
+
+    pub fn release_dedupe_config(&self) -> anyhow::Result<&config::ReleaseDedupe> {
+        self.release_config()?
+            .dedupe
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.dedupe section is missing from the config"))
+    }
+
+    pub fn release_crate_metadata_config(&self) -> anyhow::Result<&config::CrateMetadataPolicy> {
+        self.release_config()?
+            .crate_metadata
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.crate_metadata section is missing from the config"))
+    }
+
+    /// Target directory of the compiling validation build, kept in
+    /// `release.cache_dir` if it is configured
+    pub fn validation_target_dir(&self, name: &str) -> anyhow::Result<PathBuf> {
+        Ok(match &self.release_config()?.cache_dir {
+            Some(cache_dir) => workspace_path(cache_dir).join(name),
+            None => self
+                .cargo_metadata()
+                .target_directory
+                .join("monorepo")
+                .join(name)
+                .into_std_path_buf(),
+        })
+    }
+
+    pub fn release_rustdoc_config(&self) -> anyhow::Result<&config::RustdocCheck> {
+        self.release_config()?
+            .rustdoc
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.rustdoc section is missing from the config"))
+    }
+
+    pub fn release_feature_docs_config(&self) -> anyhow::Result<&config::FeatureDocsCheck> {
+        self.release_config()?
+            .feature_docs
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.feature_docs section is missing from the config"))
+    }
+
+    pub fn release_readme_config(&self) -> anyhow::Result<&config::ReadmeCheck> {
+        self.release_config()?
+            .readme
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.readme section is missing from the config"))
+    }
+
+    pub fn release_lock_config(&self) -> anyhow::Result<&config::ReleaseLock> {
+        self.release_config()?
+            .lock
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.lock section is missing from the config"))
+    }
+
+    pub fn release_nightly_config(&self) -> anyhow::Result<&config::NightlyRelease> {
+        self.release_config()?
+            .nightly
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.nightly section is missing from the config"))
+    }
+
+    pub fn is_nightly(&self) -> bool {
+        self.channel == ReleaseChannel::Nightly
+    }
+
+    pub fn release_announcement_config(&self) -> anyhow::Result<&config::Announcement> {
+        self.release_config()?
+            .announcement
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.announcement section is missing from the config"))
+    }
+
+    pub fn release_mirror_config(&self) -> anyhow::Result<&config::MirrorRelease> {
+        self.release_config()?
+            .mirror
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.mirror section is missing from the config"))
+    }
+
+    pub fn post_release_config(&self) -> anyhow::Result<&config::PostRelease> {
+        self.config
+            .post_release
+            .as_ref()
+            .ok_or_else(|| anyhow!("post_release section is missing from the config"))
+    }
+
     pub fn artifacts_config(&self) -> anyhow::Result<&config::Artifacts> {
         self.config
             .artifacts
@@ -90,22 +314,52 @@ impl ReleaseContext {
             .ok_or_else(|| anyhow!("changelog section is missing from the config"))
     }
 
-    pub fn current_commit(&self) -> anyhow::Result<String> {
-        self.current_commit
-            .clone()
-            .ok_or_else(|| anyhow!("Current commit is queried yet"))
+    pub fn current_commit(&self) -> String {
+        match self.try_current_commit() {
+            Some(commit) => commit,
+            None => self.resources.get::<CurrentCommit>().0.clone(),
+        }
+    }
+
+    /// Released commit, `None` until it is resolved by the init step
+    pub fn try_current_commit(&self) -> Option<String> {
+        let release_commit = self.release_commit.lock().unwrap().clone();
+        release_commit.or_else(|| {
+            self.resources
+                .try_get::<CurrentCommit>()
+                .map(|c| c.0.clone())
+        })
     }
 
-    pub fn cargo_metadata(&self) -> anyhow::Result<&Metadata> {
-        self.metadata
+    pub fn set_release_commit(&self, commit: String) {
+        *self.release_commit.lock().unwrap() = Some(commit);
+    }
+
+    /// Release tag is created before the release when the changelog is
+    /// captured from its message or the tag is created externally
+    pub fn release_tag_exists(&self) -> bool {
+        let tag_message = self
+            .config
+            .changelog
             .as_ref()
-            .ok_or_else(|| anyhow!("Cargo metadata is not yet queried"))
+            .is_some_and(|c| c.source == config::ChangelogSource::TagMessage);
+        let external_tag = self
+            .config
+            .release
+            .as_ref()
+            .and_then(|r| r.github.as_ref())
+            .is_some_and(|g| g.external_tag.is_some());
+        tag_message || external_tag
+    }
+
+    pub fn cargo_metadata(&self) -> &Metadata {
+        self.resources.get()
     }
 
-    pub fn workspace_package_names(&self) -> anyhow::Result<Vec<String>> {
-        let metadata = self.cargo_metadata()?;
+    pub fn workspace_package_names(&self) -> Vec<String> {
+        let metadata = self.cargo_metadata();
         let workspace_package_ids = &metadata.workspace_members;
-        let names = metadata
+        metadata
             .packages
             .iter()
             .filter_map(|p| {
@@ -113,30 +367,17 @@ impl ReleaseContext {
                     .contains(&p.id)
                     .then(|| p.name.clone())
             })
-            .collect();
-        Ok(names)
+            .collect()
     }
 
-    pub fn packages_to_publish(&self) -> anyhow::Result<Vec<&Package>> {
-        let metadata = self.cargo_metadata()?;
-
-        let packages = metadata
-            .packages
-            .iter()
-            .filter(|p| {
-                // for publish = false, package.publish would contain Some(vec![])
-                metadata.workspace_members.contains(&p.id)
-                    && p.publish.as_ref().map_or(true, |r| !r.is_empty())
-            })
-            .collect();
-
-        Ok(packages)
+    pub fn packages_to_publish(&self) -> Vec<&Package> {
+        packages_to_publish(self.cargo_metadata(), &self.config)
     }
 
     pub fn ordered_packages_to_publish(&self) -> anyhow::Result<Vec<&Package>> {
-        let metadata = self.cargo_metadata()?;
+        let metadata = self.cargo_metadata();
         let sorted = crate::cargo::sort_workspace(metadata)?;
-        let packages_to_publish = self.packages_to_publish()?;
+        let packages_to_publish = self.packages_to_publish();
         let mut ordered_packages = vec![];
 
         for s in sorted {
@@ -152,51 +393,331 @@ impl ReleaseContext {
         Ok(ordered_packages)
     }
 
-    pub fn version(&self) -> anyhow::Result<Version> {
-        self.version
-            .clone()
-            .ok_or_else(|| anyhow!("Pending version is not queried yet"))
+    pub fn version(&self) -> &Version {
+        &self.resources.get::<PendingVersion>().0
     }
 
-    pub fn github_client(&self) -> anyhow::Result<&GithubClient> {
-        self.github_client
-            .as_ref()
-            .ok_or_else(|| anyhow!("GitHub client is not initialized"))
+    pub fn changelog(&self) -> Option<&str> {
+        self.resources.try_get::<Changelog>().map(|c| c.0.as_str())
     }
 
-    pub fn artifacts(&self) -> anyhow::Result<&[PathBuf]> {
-        self.artifacts
-            .as_deref()
-            .ok_or_else(|| anyhow!("Artifacts list is empty"))
+    /// Versions of the root crate published to the release registry, `None`
+    /// if they have not been queried by the version validation
+    pub fn published_root_crate(&self) -> Option<&registry::RegistryCrate> {
+        self.resources
+            .try_get::<PublishedRootCrate>()
+            .and_then(|c| c.0.as_ref())
     }
 
-    pub fn text_template_context(&self) -> anyhow::Result<TextTemplateContext> {
-        let ctx = TextTemplateContext {
-            root_crate: self.root_crate_name(),
-            version: self.version()?,
-            changelog: self.changelog.clone(),
+    /// Page of the GitHub release created by this run, `None` in dry run
+    pub fn release_url(&self) -> Option<&str> {
+        self.resources
+            .try_get::<CreatedRelease>()
+            .and_then(|r| r.0.as_ref())
+            .map(|r| r.html_url.as_str())
+    }
+
+    pub fn github_client(&self) -> &GithubClient {
+        self.resources.get()
+    }
+
+    pub fn artifacts(&self) -> &[PathBuf] {
+        &self.resources.get::<Artifacts>().0
+    }
+
+    /// All artifacts which should be attached to the release, including
+    /// generated source artifacts
+    pub fn release_artifacts(&self) -> Vec<PathBuf> {
+        let artifacts = self.resources.try_get::<Artifacts>().map(|a| &a.0);
+        let source_artifacts = self.resources.try_get::<SourceArtifacts>().map(|a| &a.0);
+        let c_api_artifacts = self.resources.try_get::<CApiArtifacts>().map(|a| &a.0);
+        let patch_artifacts = self.resources.try_get::<PatchArtifacts>().map(|a| &a.0);
+        let generated_artifacts = self.resources.try_get::<GeneratedArtifacts>().map(|a| &a.0);
+        let snapshot = self
+            .resources
+            .try_get::<DependencySnapshot>()
+            .map(|s| &s.file);
+        artifacts
+            .into_iter()
+            .chain(source_artifacts)
+            .chain(c_api_artifacts)
+            .chain(patch_artifacts)
+            .chain(generated_artifacts)
+            .flatten()
+            .chain(snapshot)
+            .cloned()
+            .collect()
+    }
+
+    pub fn github_release_tag(&self) -> &str {
+        &self.resources.get::<GithubTag>().0
+    }
+
+    pub fn uploaded_assets(&self) -> &[ReleaseAsset] {
+        &self.resources.get::<UploadedAssets>().0
+    }
+
+    /// Tag of the previous release, `None` if the previous version is unknown
+    pub fn prev_release_tag(&self) -> anyhow::Result<Option<String>> {
+        match (
+            &self.resources.get::<PrevVersion>().0,
+            &self.release_config()?.github,
+        ) {
+            (Some(prev_version), Some(github)) => {
+                let context = TextTemplateContext {
+                    version: prev_version.clone(),
+                    calver: self
+                        .release_config()?
+                        .calver()
+                        .map(|c| c.context(prev_version)),
+                    ..self.text_template_context()
+                };
+                Ok(Some(github.tag_name_template.render(&context)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Commits since the previous release tag, or all commits up to the current
+    /// one if the tag is unknown
+    pub async fn release_commit_range(&self) -> anyhow::Result<String> {
+        let prev_tag = self.prev_release_tag()?;
+
+        let commit = self.current_commit();
+        let prev_tag = match prev_tag {
+            Some(prev_tag) => prev_tag,
+            None => return Ok(commit),
+        };
+
+        let mut verify = workspace_command("git");
+        verify.args(["rev-parse", "--verify", "--quiet"]);
+        verify.arg(format!("refs/tags/{}", prev_tag));
+        if run_and_capture_stdout(&mut verify).await.is_ok() {
+            Ok(format!("{}..{}", prev_tag, commit))
+        } else {
+            println!(
+                "\tWARN: previous release tag `{}` is not found, using whole history",
+                prev_tag
+            );
+            Ok(commit)
+        }
+    }
+
+    /// Registry the workspace crate is published to, `None` stands for crates.io
+    pub fn crate_registry(&self, name: &str) -> Option<String> {
+        let release = self.config.release.as_ref()?;
+        let publish = self
+            .resources
+            .try_get::<Metadata>()
+            .and_then(|m| m.packages.iter().find(|p| p.name == name))
+            .and_then(|p| p.publish.as_deref());
+        registry::publish_registry(release, publish)
+    }
+
+    /// Workspace crates for templates, empty until metadata is loaded
+    fn package_contexts(&self) -> Vec<PackageContext> {
+        let metadata = match self.resources.try_get::<Metadata>() {
+            Some(metadata) => metadata,
+            None => return vec![],
         };
+        let published = packages_to_publish(metadata, &self.config);
+        metadata
+            .packages
+            .iter()
+            .filter(|p| metadata.workspace_members.contains(&p.id))
+            .map(|p| PackageContext {
+                name: p.name.clone(),
+                version: p.version.clone(),
+                description: p.description.clone(),
+                publish: published.iter().any(|published| published.id == p.id),
+            })
+            .collect()
+    }
+
+    pub fn text_template_context(&self) -> TextTemplateContext {
+        let release = self.config.release.as_ref();
+        let registry_url = registry::registry_url(release);
+        let prev_release = self
+            .resources
+            .try_get::<PrevRelease>()
+            .and_then(|r| r.0.as_ref());
+        TextTemplateContext {
+            root_crate: self.root_crate_name(),
+            version: self.version().clone(),
+            changelog: self.changelog().map(str::to_owned),
+            hotfix: self.hotfix,
+            registry: registry::registry_name(release),
+            registry_url: registry_url.clone(),
+            published_crates: self
+                .resources
+                .try_get::<PublishedCrates>()
+                .map(|p| {
+                    p.0.iter()
+                        .map(|(name, version)| {
+                            let registry = self.crate_registry(name);
+                            PublishedCrateContext {
+                                name: name.clone(),
+                                version: version.clone(),
+                                registry_url: registry::crate_page_url(
+                                    release,
+                                    registry.as_deref(),
+                                    name,
+                                ),
+                                docs_url: registry::docs_url(registry.as_deref(), name, version),
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            packages: self.package_contexts(),
+            crate_notes: self
+                .resources
+                .try_get::<CrateNotes>()
+                .map(|n| aggregate_crate_notes(&n.0))
+                .filter(|n| !n.is_empty()),
+            assets: self
+                .resources
+                .try_get::<UploadedAssets>()
+                .map(|a| a.0.iter().map(asset_context).collect())
+                .unwrap_or_default(),
+            calver: release
+                .and_then(|r| r.calver())
+                .map(|c| c.context(self.version())),
+            prev_version: self
+                .resources
+                .try_get::<PrevVersion>()
+                .and_then(|v| v.0.clone()),
+            prev_release_url: prev_release.map(|r| r.html_url.clone()),
+            prev_assets: prev_release
+                .map(|r| r.assets.iter().map(asset_context).collect())
+                .unwrap_or_default(),
+        }
+    }
 
-        Ok(ctx)
+    pub fn set_crates_io_token(&self, token: String) -> anyhow::Result<()> {
+        self.crates_io_token
+            .set(token)
+            .map_err(|_| anyhow!("BUG: Crate registry token has already been set"))
     }
 
-    pub fn set_github_token(&mut self, token: String) -> anyhow::Result<()> {
+    /// Stores GitHub token and provides GitHub client created with it
+    pub fn set_github_token(&self, token: String) -> anyhow::Result<()> {
         let github_client = GithubClient::builder()
             .personal_token(token.clone())
             .build()
             .with_context(|| "Failed to create GitHub client")?;
-        self.github_token = Some(token);
-        self.github_client = Some(github_client);
-        Ok(())
+        self.github_token
+            .set(token)
+            .map_err(|_| anyhow!("BUG: GitHub token has already been set"))?;
+        self.provide(github_client)
+    }
+
+    /// Client for the dry run without GitHub token, only public data is
+    /// accessible
+    pub fn set_anonymous_github_client(&self) -> anyhow::Result<()> {
+        let github_client = GithubClient::builder()
+            .build()
+            .with_context(|| "Failed to create GitHub client")?;
+        self.provide(github_client)
+    }
+
+    /// Published crates of the current release from the publish journal,
+    /// including the ones published before the failure of the interrupted run
+    fn journaled_crates(&self) -> Vec<PublishedCrate> {
+        let (release, metadata) = match (
+            self.config.release.as_ref(),
+            self.resources.try_get::<Metadata>(),
+        ) {
+            (Some(release), Some(metadata)) => (release, metadata),
+            _ => return vec![],
+        };
+        let entries = match journal::read_journal(&journal::journal_path(release, metadata)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                println!("\tWARN: {:#}", e);
+                return vec![];
+            }
+        };
+        let packages = self.packages_to_publish();
+        entries
+            .into_iter()
+            .filter(|e| {
+                packages
+                    .iter()
+                    .any(|p| p.name == e.name && p.version == e.version)
+            })
+            .map(|e| PublishedCrate {
+                name: e.name,
+                version: e.version,
+                registry: e.registry,
+                published_at: e.published_at,
+                checksum: e.checksum,
+            })
+            .collect()
     }
 
-    pub fn set_github_release_tag(&mut self, tag: String) {
-        self.github_release_tag = Some(tag);
+    /// Collects release report from the current context state
+    pub fn report(&self, success: bool) -> ReleaseReport {
+        ReleaseReport {
+            root_crate: self.root_crate_name(),
+            version: self
+                .resources
+                .try_get::<PendingVersion>()
+                .map(|v| v.0.clone()),
+            prev_version: self
+                .resources
+                .try_get::<PrevVersion>()
+                .and_then(|v| v.0.clone()),
+            commit: self
+                .resources
+                .try_get::<CurrentCommit>()
+                .map(|c| c.0.clone()),
+            release_url: self.release_url().map(str::to_owned),
+            dry_run: self.dry_run,
+            hotfix: self.hotfix,
+            forced_skips: self.forced_skips.clone(),
+            success,
+            reproducible_build: self.resources.try_get::<ReproducibilityReport>().cloned(),
+            published_crates: self.journaled_crates(),
+            // Artifacts are checked to be readable files when collected
+            assets: self
+                .release_artifacts()
+                .iter()
+                .filter_map(|path| {
+                    Some(AssetDigest {
+                        name: path.file_name()?.to_string_lossy().into_owned(),
+                        sha256: sha256_file(path).ok()?,
+                    })
+                })
+                .collect(),
+            attestations: self
+                .resources
+                .try_get::<Attestations>()
+                .map(|a| a.0.clone())
+                .unwrap_or_default(),
+            dependencies: self
+                .resources
+                .try_get::<DependencySnapshot>()
+                .map(|s| s.packages.clone())
+                .unwrap_or_default(),
+            planned_actions: self.planned_actions.lock().unwrap().clone(),
+        }
     }
+}
+
+/// Notes of every crate under its own heading
+fn aggregate_crate_notes(notes: &[(String, String)]) -> String {
+    notes
+        .iter()
+        .map(|(name, notes)| format!("### {}\n\n{}\n", name, notes))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    pub fn github_release_tag(&self) -> anyhow::Result<String> {
-        self.github_release_tag
-            .clone()
-            .with_context(|| "GitHub tag is not created yet")
+fn asset_context(asset: &ReleaseAsset) -> AssetContext {
+    AssetContext {
+        name: asset.name.clone(),
+        size: asset.size,
+        browser_download_url: asset.browser_download_url.clone(),
     }
 }
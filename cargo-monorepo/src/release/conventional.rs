@@ -0,0 +1,177 @@
+//! Pending version inferred from the conventional commits since the last
+//! release tag, used with `release.version_from = "conventional-commits"`
+use crate::{
+    config::VersionBump,
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, Context};
+use semver::Version;
+
+/// Commit messages are separated by the record separator in the log
+const COMMIT_SEPARATOR: char = '\x1e';
+
+/// Version bump required by the single commit message, `None` for commits
+/// which don't affect the public API (docs, chore, etc.)
+fn commit_bump(message: &str) -> Option<VersionBump> {
+    let (subject, body) = message
+        .trim()
+        .split_once('\n')
+        .unwrap_or((message.trim(), ""));
+    let (prefix, _) = subject.split_once(':')?;
+    let breaking = prefix.ends_with('!')
+        || body
+            .lines()
+            .any(|l| l.starts_with("BREAKING CHANGE:") || l.starts_with("BREAKING-CHANGE:"));
+    if breaking {
+        return Some(VersionBump::Major);
+    }
+    let kind = prefix.split('(').next().unwrap_or(prefix).trim();
+    match kind {
+        "feat" => Some(VersionBump::Minor),
+        "fix" => Some(VersionBump::Patch),
+        _ => None,
+    }
+}
+
+/// The largest bump required by the commits
+fn infer_bump<'a>(messages: impl IntoIterator<Item = &'a str>) -> Option<VersionBump> {
+    let rank = |bump: &VersionBump| match bump {
+        VersionBump::Patch => 0,
+        VersionBump::Minor => 1,
+        VersionBump::Major => 2,
+    };
+    messages
+        .into_iter()
+        .filter_map(commit_bump)
+        .max_by_key(rank)
+}
+
+/// Version of the release tag, e.g. `1.2.0` for `v1.2.0` or `app-1.2.0`
+pub fn tag_version(tag: &str) -> Option<Version> {
+    let start = tag.find(|c: char| c.is_ascii_digit())?;
+    Version::parse(&tag[start..]).ok()
+}
+
+/// The latest tag reachable from HEAD
+async fn last_release_tag() -> Option<String> {
+    let mut cmd = workspace_command("git");
+    cmd.args(["describe", "--tags", "--abbrev=0"]);
+    run_and_capture_stdout(&mut cmd)
+        .await
+        .ok()
+        .map(|tag| tag.trim().to_owned())
+}
+
+/// Last release tag with its version and the bump required by the commits
+/// made after it
+pub struct ConventionalBump {
+    pub tag: String,
+    pub prev_version: Version,
+    pub bump: VersionBump,
+}
+
+impl ConventionalBump {
+    pub async fn query() -> anyhow::Result<Self> {
+        let tag = last_release_tag().await.ok_or_else(|| {
+            anyhow!("Release tag is not found, version can't be inferred from the commits")
+        })?;
+        let prev_version = tag_version(&tag)
+            .ok_or_else(|| anyhow!("Release tag `{}` does not contain a version", tag))?;
+
+        let range = format!("{}..HEAD", tag);
+        let mut log = workspace_command("git");
+        log.args(["log", "--format=%B%x1e", &range]);
+        let log = run_and_capture_stdout(&mut log)
+            .await
+            .with_context(|| format!("Failed to query commits in {}", range))?;
+        let bump = infer_bump(log.split(COMMIT_SEPARATOR)).ok_or_else(|| {
+            anyhow!(
+                "None of the commits since `{}` is a feature, fix or breaking change",
+                tag
+            )
+        })?;
+
+        Ok(Self {
+            tag,
+            prev_version,
+            bump,
+        })
+    }
+
+    pub fn version(&self) -> Version {
+        self.bump.apply(&self.prev_version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn commit_bumps() {
+        let bumps = vec![
+            "feat(net): retries",
+            "fix: timeout",
+            "docs: typo",
+            "refactor!: drop old API",
+            "fix(io): close files\n\nBREAKING CHANGE: `Reader` is not `Clone`",
+            "Merge branch 'main'",
+        ]
+        .into_iter()
+        .map(commit_bump)
+        .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                Some(
+                    Minor,
+                ),
+                Some(
+                    Patch,
+                ),
+                None,
+                Some(
+                    Major,
+                ),
+                Some(
+                    Major,
+                ),
+                None,
+            ]
+        "#]]
+        .assert_debug_eq(&bumps);
+
+        let inferred = infer_bump(vec!["fix: a", "feat: b", "chore: c"]);
+        expect![[r#"
+            Some(
+                Minor,
+            )
+        "#]]
+        .assert_debug_eq(&inferred);
+
+        let versions = vec!["v1.2.0", "app-0.3.1", "nightly"]
+            .into_iter()
+            .map(tag_version)
+            .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                Some(
+                    Version {
+                        major: 1,
+                        minor: 2,
+                        patch: 0,
+                    },
+                ),
+                Some(
+                    Version {
+                        major: 0,
+                        minor: 3,
+                        patch: 1,
+                    },
+                ),
+                None,
+            ]
+        "#]]
+        .assert_debug_eq(&versions);
+    }
+}
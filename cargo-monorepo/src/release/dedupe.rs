@@ -0,0 +1,100 @@
+//! Completed releases are recorded as `<root crate> <version>` lines in git
+//! notes of the released commit, so an accidental re-run is a no-op
+use crate::{
+    config::ReleaseDedupe,
+    utils::{metadata_command, run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, Context};
+use semver::Version;
+
+pub fn release_record(root_crate: &str, version: &Version) -> String {
+    format!("{} {}", root_crate, version)
+}
+
+fn is_recorded(notes: &str, record: &str) -> bool {
+    notes.lines().any(|line| line.trim() == record)
+}
+
+/// Returns the already released commit and version, if the current ones have
+/// been released before
+pub async fn find_completed_release(
+    dedupe: &ReleaseDedupe,
+    root_crate: &str,
+) -> anyhow::Result<Option<(String, Version)>> {
+    // Notes ref is missing on the remote until the first release
+    let refspec = format!("+refs/notes/{0}:refs/notes/{0}", dedupe.notes_ref);
+    let mut fetch = workspace_command("git");
+    fetch.args(["fetch", &dedupe.remote, &refspec]);
+    let _ = run_and_capture_stdout(&mut fetch).await;
+
+    let mut rev_parse = workspace_command("git");
+    rev_parse.args(["rev-parse", "--verify", "HEAD"]);
+    let commit = run_and_capture_stdout(&mut rev_parse)
+        .await
+        .with_context(|| "Failed to get current git commit")?
+        .trim()
+        .to_owned();
+
+    let metadata = metadata_command()
+        .no_deps()
+        .exec()
+        .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+    let version = metadata
+        .packages
+        .iter()
+        .find(|p| p.name == root_crate)
+        .map(|p| p.version.clone())
+        .ok_or_else(|| anyhow!("Failed to find root crate ({}) in workspace", root_crate))?;
+
+    let mut show = workspace_command("git");
+    show.args([
+        "notes",
+        &format!("--ref={}", dedupe.notes_ref),
+        "show",
+        &commit,
+    ]);
+    let notes = match run_and_capture_stdout(&mut show).await {
+        Ok(notes) => notes,
+        Err(_) => return Ok(None),
+    };
+
+    Ok(is_recorded(&notes, &release_record(root_crate, &version)).then_some((commit, version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn recorded_releases() {
+        let notes = "monorepo 1.0.0\nmonorepo-tools 0.3.1\n";
+
+        let recorded = vec![
+            release_record("monorepo", &Version::new(1, 0, 0)),
+            release_record("monorepo", &Version::new(1, 0, 1)),
+            release_record("monorepo-tools", &Version::new(0, 3, 1)),
+        ]
+        .into_iter()
+        .map(|record| (is_recorded(notes, &record), record))
+        .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                (
+                    true,
+                    "monorepo 1.0.0",
+                ),
+                (
+                    false,
+                    "monorepo 1.0.1",
+                ),
+                (
+                    true,
+                    "monorepo-tools 0.3.1",
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&recorded);
+    }
+}
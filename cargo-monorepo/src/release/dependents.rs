@@ -0,0 +1,580 @@
+//! Automatic version bumping of crates which depend on the raised crates
+use crate::{
+    config::Config,
+    registry::{publish_registry, query_registry_crate},
+    utils::metadata_command,
+};
+use anyhow::{anyhow, bail, Context};
+use cargo_metadata::{DependencyKind, Metadata};
+use semver::Version;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+use toml_edit::{value, Document, Item};
+
+struct CrateState {
+    version: Version,
+    published: Option<Version>,
+    /// Non-dev dependencies on the other considered crates
+    dependencies: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct ReleaseSet {
+    /// Crates with unpublished versions
+    raised: BTreeSet<String>,
+    /// Crates which should be bumped because they depend on the released crates
+    bumps: BTreeMap<String, Version>,
+}
+
+impl ReleaseSet {
+    fn contains(&self, name: &str) -> bool {
+        self.raised.contains(name) || self.bumps.contains_key(name)
+    }
+}
+
+/// Determines minimal consistent set of crates to release; release is limited
+/// to this set by updating `workspace.members`. Versions of the dependent crates
+/// should already be bumped by [`bump_dependents`]
+pub async fn prepare_release_set(config: &mut Config) -> anyhow::Result<()> {
+    println!("🔍 Looking for crates with unpublished versions...");
+
+    let (_, crates, release_set) = find_release_set(config).await?;
+
+    if !release_set.bumps.is_empty() {
+        print_bumps(&crates, &release_set);
+        bail!(
+            "Dependent crates should be bumped, run `cargo monorepo bump` and commit \
+            updated manifests before running release again"
+        );
+    }
+
+    check_root_crate(&release_set, &config.workspace.root_crate)?;
+
+    println!(
+        "\t📦 Crates to release: {}",
+        release_set
+            .raised
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    config.workspace.members = release_set.raised.into_iter().collect();
+
+    Ok(())
+}
+
+/// Bumps versions of the crates which (transitively) depend on the crates with
+/// unpublished versions and rewrites requirements on them
+pub async fn bump_dependents(config: &Config) -> anyhow::Result<()> {
+    println!("🔍 Looking for crates with unpublished versions...");
+
+    let (metadata, crates, release_set) = find_release_set(config).await?;
+
+    if release_set.bumps.is_empty() {
+        println!("✅ Dependent crates are already bumped, nothing to do");
+        return Ok(());
+    }
+
+    print_bumps(&crates, &release_set);
+    // Checked before manifests are touched, the root crate is released anyway
+    // when it is one of the dependents
+    check_root_crate(&release_set, &config.workspace.root_crate)?;
+    update_manifests(&metadata, &crates, &release_set).await?;
+
+    println!(
+        "🚀 Versions of {} dependent crate(s) have been bumped, review and commit \
+        updated manifests",
+        release_set.bumps.len()
+    );
+    Ok(())
+}
+
+async fn find_release_set(
+    config: &Config,
+) -> anyhow::Result<(Metadata, BTreeMap<String, CrateState>, ReleaseSet)> {
+    let metadata = metadata_command()
+        .exec()
+        .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+
+    let crates = collect_crate_states(&metadata, config).await?;
+    let release_set = plan_release_set(&crates);
+
+    if release_set.raised.is_empty() {
+        bail!("None of the workspace crates has unpublished version, nothing to release");
+    }
+
+    Ok((metadata, crates, release_set))
+}
+
+fn print_bumps(crates: &BTreeMap<String, CrateState>, release_set: &ReleaseSet) {
+    for (name, version) in &release_set.bumps {
+        println!(
+            "\t⬆️ {} v{} -> v{} (depends on released crates)",
+            name, crates[name].version, version
+        );
+    }
+}
+
+/// Released version is the version of the root crate, so it should be either
+/// raised or bumped as a dependent of the raised crates
+fn check_root_crate(release_set: &ReleaseSet, root_crate: &str) -> anyhow::Result<()> {
+    if !release_set.contains(root_crate) {
+        bail!(
+            "Version of the root crate {} should be raised to release other crates",
+            root_crate
+        );
+    }
+    Ok(())
+}
+
+async fn collect_crate_states(
+    metadata: &Metadata,
+    config: &Config,
+) -> anyhow::Result<BTreeMap<String, CrateState>> {
+    let members = &config.workspace.members;
+    let packages = metadata
+        .packages
+        .iter()
+        .filter(|p| {
+            metadata.workspace_members.contains(&p.id)
+                && !matches!(&p.publish, Some(registries) if registries.is_empty())
+                && (members.is_empty()
+                    || p.name == config.workspace.root_crate
+                    || members.contains(&p.name))
+        })
+        .collect::<Vec<_>>();
+    let names = packages.iter().map(|p| &p.name).collect::<BTreeSet<_>>();
+
+    let release = config
+        .release
+        .as_ref()
+        .ok_or_else(|| anyhow!("release section is missing from the config"))?;
+    let mut crates = BTreeMap::new();
+    for package in &packages {
+        // Crates are looked up in the registry they are published to
+        let registry = publish_registry(release, package.publish.as_deref());
+        let published = query_registry_crate(release, registry.as_deref(), &package.name)
+            .await?
+            .and_then(|c| c.latest_version().cloned());
+        let dependencies = package
+            .dependencies
+            .iter()
+            .filter(|d| d.kind != DependencyKind::Development && names.contains(&d.name))
+            .map(|d| d.name.clone())
+            .collect();
+        let state = CrateState {
+            version: package.version.clone(),
+            published,
+            dependencies,
+        };
+        crates.insert(package.name.clone(), state);
+    }
+
+    Ok(crates)
+}
+
+fn plan_release_set(crates: &BTreeMap<String, CrateState>) -> ReleaseSet {
+    let mut release_set = ReleaseSet::default();
+
+    for (name, state) in crates {
+        let raised = !matches!(&state.published, Some(published) if state.version <= *published);
+        if raised {
+            release_set.raised.insert(name.clone());
+        }
+    }
+
+    if release_set.raised.is_empty() {
+        return release_set;
+    }
+
+    // Propagate bumps until every dependent of a released crate is released
+    loop {
+        let dependents = crates
+            .iter()
+            .filter(|(name, state)| {
+                !release_set.contains(name)
+                    && state.dependencies.iter().any(|d| release_set.contains(d))
+            })
+            .map(|(name, state)| {
+                let base = state
+                    .published
+                    .as_ref()
+                    .filter(|published| **published > state.version)
+                    .unwrap_or(&state.version);
+                (
+                    name.clone(),
+                    Version::new(base.major, base.minor, base.patch + 1),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if dependents.is_empty() {
+            break;
+        }
+        release_set.bumps.extend(dependents);
+    }
+
+    release_set
+}
+
+async fn update_manifests(
+    metadata: &Metadata,
+    crates: &BTreeMap<String, CrateState>,
+    release_set: &ReleaseSet,
+) -> anyhow::Result<()> {
+    let new_version = |name: &str| {
+        release_set.bumps.get(name).or_else(|| {
+            release_set
+                .raised
+                .contains(name)
+                .then(|| &crates[name].version)
+        })
+    };
+
+    let mut manifests = metadata
+        .packages
+        .iter()
+        .filter(|p| metadata.workspace_members.contains(&p.id))
+        .map(|p| (p.name.as_str(), p.manifest_path.as_std_path()))
+        .collect::<Vec<_>>();
+    let workspace_manifest = metadata.workspace_root.join("Cargo.toml");
+    if !manifests
+        .iter()
+        .any(|(_, path)| *path == workspace_manifest.as_std_path())
+    {
+        manifests.push(("", workspace_manifest.as_std_path()));
+    }
+
+    for (name, manifest_path) in manifests {
+        let package_version = release_set.bumps.get(name);
+        update_manifest(manifest_path, package_version, &new_version).await?;
+    }
+
+    Ok(())
+}
+
+async fn update_manifest<'a>(
+    path: &Path,
+    package_version: Option<&Version>,
+    new_version: &impl Fn(&str) -> Option<&'a Version>,
+) -> anyhow::Result<()> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut manifest = content
+        .parse::<Document>()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    if let Some(version) = package_version {
+        let explicit_version = manifest
+            .get("package")
+            .and_then(|p| p.get("version"))
+            .and_then(Item::as_str)
+            .is_some();
+        if !explicit_version {
+            bail!(
+                "Package version in {} is inherited from the workspace and can't be bumped",
+                path.display()
+            );
+        }
+        manifest["package"]["version"] = value(version.to_string());
+    }
+
+    update_requirements(&mut manifest, new_version);
+
+    let updated = manifest.to_string();
+    if updated != content {
+        tokio::fs::write(path, updated)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("\t✏️ Updated {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Replaces `from` version of the workspace crates (including the inherited
+/// `workspace.package.version`) and requirements on them with `to`; returns
+/// original content of the updated manifests
+pub async fn replace_workspace_version(
+    metadata: &Metadata,
+    from: &Version,
+    to: &Version,
+) -> anyhow::Result<Vec<(PathBuf, String)>> {
+    let members = metadata
+        .packages
+        .iter()
+        .filter(|p| metadata.workspace_members.contains(&p.id) && &p.version == from)
+        .map(|p| p.name.as_str())
+        .collect::<BTreeSet<_>>();
+    set_workspace_version(metadata, &members, from, to).await
+}
+
+/// Sets `to` version of the `members` crates and requirements on them;
+/// inherited `workspace.package.version` is replaced if it is `from`. All
+/// manifests are updated or none of them; original content of the updated
+/// manifests is returned
+pub async fn set_workspace_version(
+    metadata: &Metadata,
+    members: &BTreeSet<&str>,
+    from: &Version,
+    to: &Version,
+) -> anyhow::Result<Vec<(PathBuf, String)>> {
+    let new_version = |name: &str| members.contains(name).then_some(to);
+
+    let mut manifests = metadata
+        .packages
+        .iter()
+        .filter(|p| metadata.workspace_members.contains(&p.id))
+        .map(|p| p.manifest_path.clone().into_std_path_buf())
+        .collect::<Vec<_>>();
+    let workspace_manifest = metadata
+        .workspace_root
+        .join("Cargo.toml")
+        .into_std_path_buf();
+    if !manifests.contains(&workspace_manifest) {
+        manifests.push(workspace_manifest);
+    }
+
+    // Everything is parsed and updated in memory before the first write
+    let mut updates = vec![];
+    for path in manifests {
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut manifest = content
+            .parse::<Document>()
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        let package_name = manifest
+            .get("package")
+            .and_then(|p| p.get("name"))
+            .and_then(Item::as_str)
+            .map(str::to_owned);
+        // `Item::get_mut` inserts missing keys, tables are accessed instead
+        if let Some(version) = manifest
+            .get_mut("package")
+            .and_then(Item::as_table_like_mut)
+            .and_then(|p| p.get_mut("version"))
+        {
+            let member = package_name.is_some_and(|name| members.contains(name.as_str()));
+            // Inherited `version.workspace = true` is not a string
+            if member && version.is_str() {
+                *version = value(to.to_string());
+            }
+        }
+        if let Some(version) = manifest
+            .get_mut("workspace")
+            .and_then(Item::as_table_like_mut)
+            .and_then(|w| w.get_mut("package"))
+            .and_then(Item::as_table_like_mut)
+            .and_then(|p| p.get_mut("version"))
+        {
+            if version.as_str() == Some(from.to_string().as_str()) {
+                *version = value(to.to_string());
+            }
+        }
+        update_requirements(&mut manifest, &new_version);
+
+        let updated = manifest.to_string();
+        if updated != content {
+            updates.push((path, content, updated));
+        }
+    }
+
+    let mut originals = vec![];
+    for (path, content, updated) in updates {
+        if let Err(e) = tokio::fs::write(&path, updated).await {
+            restore_manifests(&originals).await;
+            return Err(e).with_context(|| format!("Failed to write {}", path.display()));
+        }
+        println!("\t✏️ Updated {}", path.display());
+        originals.push((path, content));
+    }
+
+    Ok(originals)
+}
+
+/// Best-effort rollback of the partially applied update
+pub async fn restore_manifests(originals: &[(PathBuf, String)]) {
+    for (path, content) in originals {
+        if let Err(e) = tokio::fs::write(path, content).await {
+            println!("\tWARN: failed to restore {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn update_requirements<'a>(
+    manifest: &mut Document,
+    new_version: &impl Fn(&str) -> Option<&'a Version>,
+) {
+    update_dependency_tables(manifest.as_table_mut(), new_version);
+    if let Some(workspace) = manifest
+        .get_mut("workspace")
+        .and_then(Item::as_table_like_mut)
+    {
+        if let Some(deps) = workspace
+            .get_mut("dependencies")
+            .and_then(Item::as_table_like_mut)
+        {
+            update_dependencies(deps, new_version);
+        }
+    }
+    if let Some(targets) = manifest.get_mut("target").and_then(Item::as_table_like_mut) {
+        for (_, target) in targets.iter_mut() {
+            if let Some(target) = target.as_table_like_mut() {
+                update_dependency_tables(target, new_version);
+            }
+        }
+    }
+}
+
+fn update_dependency_tables<'a>(
+    table: &mut dyn toml_edit::TableLike,
+    new_version: &impl Fn(&str) -> Option<&'a Version>,
+) {
+    for key in ["dependencies", "build-dependencies", "dev-dependencies"] {
+        if let Some(deps) = table.get_mut(key).and_then(Item::as_table_like_mut) {
+            update_dependencies(deps, new_version);
+        }
+    }
+}
+
+fn update_dependencies<'a>(
+    deps: &mut dyn toml_edit::TableLike,
+    new_version: &impl Fn(&str) -> Option<&'a Version>,
+) {
+    for (key, dep) in deps.iter_mut() {
+        if let Some(version) = new_version(key.get()) {
+            if dep.is_str() {
+                *dep = value(version.to_string());
+            }
+        }
+
+        let dep = match dep.as_table_like_mut() {
+            Some(dep) => dep,
+            None => continue,
+        };
+        let package = dep
+            .get("package")
+            .and_then(Item::as_str)
+            .unwrap_or_else(|| key.get())
+            .to_owned();
+        // Path-only dependencies and `workspace = true` ones have no requirement
+        // to update
+        if let (Some(version), Some(req)) = (new_version(&package), dep.get_mut("version")) {
+            *req = value(version.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    fn state(version: &str, published: Option<&str>, dependencies: &[&str]) -> CrateState {
+        CrateState {
+            version: version.parse().unwrap(),
+            published: published.map(|v| v.parse().unwrap()),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn dependents_are_bumped_transitively() {
+        let crates = vec![
+            ("leaf", state("1.3.0", Some("1.2.0"), &[])),
+            ("middle", state("1.2.0", Some("1.2.0"), &["leaf"])),
+            ("root", state("1.2.0", Some("1.2.1"), &["middle"])),
+            ("unrelated", state("0.4.0", Some("0.4.0"), &[])),
+        ]
+        .into_iter()
+        .map(|(name, state)| (name.to_owned(), state))
+        .collect();
+
+        expect![[r#"
+            ReleaseSet {
+                raised: {
+                    "leaf",
+                },
+                bumps: {
+                    "middle": Version {
+                        major: 1,
+                        minor: 2,
+                        patch: 1,
+                    },
+                    "root": Version {
+                        major: 1,
+                        minor: 2,
+                        patch: 2,
+                    },
+                },
+            }
+        "#]]
+        .assert_debug_eq(&plan_release_set(&crates));
+    }
+
+    #[test]
+    fn bumped_root_crate() {
+        let crates = vec![
+            ("leaf", state("1.3.0", Some("1.2.0"), &[])),
+            ("root", state("1.2.0", Some("1.2.0"), &["leaf"])),
+            ("tool", state("0.4.0", Some("0.4.0"), &[])),
+        ]
+        .into_iter()
+        .map(|(name, state)| (name.to_owned(), state))
+        .collect();
+        let release_set = plan_release_set(&crates);
+
+        assert!(check_root_crate(&release_set, "root").is_ok());
+        expect!["Version of the root crate tool should be raised to release other crates"]
+            .assert_eq(
+                &check_root_crate(&release_set, "tool")
+                    .unwrap_err()
+                    .to_string(),
+            );
+    }
+
+    #[test]
+    fn manifest_requirements_update() {
+        let mut manifest = r#"
+[package]
+name = "middle"
+version = "1.2.0"
+
+[dependencies]
+leaf = "1.2"
+renamed = { package = "leaf", version = "1.2", features = ["x"] }
+serde = "1.0"
+
+[target.'cfg(unix)'.dev-dependencies]
+leaf = { path = "../leaf", version = "1.2" }
+"#
+        .parse::<Document>()
+        .unwrap();
+
+        let leaf_version = Version::new(1, 3, 0);
+        let new_version = |name: &str| (name == "leaf").then_some(&leaf_version);
+        update_requirements(&mut manifest, &new_version);
+
+        expect![[r#"
+
+            [package]
+            name = "middle"
+            version = "1.2.0"
+
+            [dependencies]
+            leaf = "1.3.0"
+            renamed = { package = "leaf", version = "1.3.0", features = ["x"] }
+            serde = "1.0"
+
+            [target.'cfg(unix)'.dev-dependencies]
+            leaf = { path = "../leaf", version = "1.3.0" }
+        "#]]
+        .assert_eq(&manifest.to_string());
+    }
+}
@@ -0,0 +1,316 @@
+//! Retirement of the workspace crate: the final version with the deprecation
+//! notice is published, older versions are yanked and the crate is excluded
+//! from the future releases
+use super::{context::packages_to_publish, step::readme_path};
+use crate::{
+    audit,
+    config::Config,
+    registry::{publish_registry, query_released_versions},
+    utils::{metadata_command, workspace_command},
+};
+use anyhow::{anyhow, bail, Context};
+use cargo_metadata::DependencyKind;
+use semver::Version;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use toml_edit::{table, value, Array, Document, Item};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum YankPolicy {
+    None,
+    Prereleases,
+    All,
+}
+
+impl FromStr for YankPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "prereleases" => Ok(Self::Prereleases),
+            "all" => Ok(Self::All),
+            _ => bail!("Expected none, prereleases or all, got `{}`", s),
+        }
+    }
+}
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Publish the final deprecated version of the crate and retire it")]
+pub struct DeprecateCommand {
+    /// Workspace crate to retire
+    #[structopt(name = "crate")]
+    crate_name: String,
+    /// Deprecation notice added to the README and the crate description
+    #[structopt(
+        long,
+        default_value = "This crate is deprecated and no longer maintained"
+    )]
+    message: String,
+    /// Previously published versions to yank: `none`, `prereleases` or `all`
+    #[structopt(long, default_value = "none")]
+    yank: YankPolicy,
+    /// Actually execute command instead of dry run
+    #[structopt(long)]
+    confirm: bool,
+}
+
+/// README with the notice placed before its content
+fn readme_with_notice(readme: &str, message: &str) -> String {
+    format!("> **⚠️ Deprecated:** {}\n\n{}", message, readme)
+}
+
+fn versions_to_yank(versions: &[Version], policy: YankPolicy, last: &Version) -> Vec<Version> {
+    let mut yanked = versions
+        .iter()
+        .filter(|v| *v != last)
+        .filter(|v| match policy {
+            YankPolicy::None => false,
+            YankPolicy::Prereleases => !v.pre.is_empty(),
+            YankPolicy::All => true,
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    yanked.sort();
+    yanked
+}
+
+/// Original content of the edited files, restored after the dry run
+struct EditedFiles(Vec<(PathBuf, String)>);
+
+impl EditedFiles {
+    fn edit(
+        &mut self,
+        path: &Path,
+        f: impl FnOnce(&str) -> anyhow::Result<String>,
+    ) -> anyhow::Result<()> {
+        let original = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let edited = f(&original)?;
+        std::fs::write(path, edited)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        self.0.push((path.to_owned(), original));
+        Ok(())
+    }
+
+    fn restore(self) {
+        for (path, content) in self.0 {
+            if let Err(e) = std::fs::write(&path, content) {
+                println!("WARN: failed to restore {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Sets the final version, deprecated description and maintenance badge
+fn deprecate_manifest(manifest: &str, version: &Version, message: &str) -> anyhow::Result<String> {
+    let mut manifest = manifest.parse::<Document>()?;
+    let package = manifest
+        .get_mut("package")
+        .and_then(Item::as_table_like_mut)
+        .ok_or_else(|| anyhow!("[package] section is missing"))?;
+    if !package.get("version").is_some_and(Item::is_str) {
+        bail!("Crate version should be set explicitly, not inherited from the workspace");
+    }
+    package.insert("version", value(version.to_string()));
+    let description = match package.get("description").and_then(Item::as_str) {
+        Some(description) => format!(
+            "DEPRECATED: {}. {}",
+            message.trim_end_matches('.'),
+            description
+        ),
+        None => format!("DEPRECATED: {}", message),
+    };
+    package.insert("description", value(description));
+    let badges = manifest.entry("badges").or_insert(table());
+    badges["maintenance"]["status"] = value("deprecated");
+    Ok(manifest.to_string())
+}
+
+fn exclude_from_config(config: &str, crate_name: &str) -> anyhow::Result<String> {
+    let mut config = config.parse::<Document>()?;
+    let deprecated = config["workspace"]["deprecated"].or_insert(value(Array::new()));
+    deprecated
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("workspace.deprecated should be an array"))?
+        .push(crate_name);
+    Ok(config.to_string())
+}
+
+pub(super) async fn run_cargo(args: &[&str]) -> anyhow::Result<()> {
+    let mut cmd = workspace_command("cargo");
+    cmd.args(args);
+    println!("EXEC: cargo {}", args.join(" "));
+    audit::command(&cmd);
+    let status = cmd
+        .spawn()
+        .with_context(|| "Failed to spawn cargo")?
+        .wait()
+        .await
+        .with_context(|| "Failed to run cargo")?;
+    if !status.success() {
+        bail!("cargo {} failed", args[0]);
+    }
+    Ok(())
+}
+
+impl DeprecateCommand {
+    pub async fn run(self, config: Config, config_path: &Path) -> anyhow::Result<()> {
+        if self.crate_name == config.workspace.root_crate {
+            bail!("Root crate can't be deprecated");
+        }
+        let release = config
+            .release
+            .as_ref()
+            .ok_or_else(|| anyhow!("release section is missing from the config"))?;
+        let metadata = metadata_command()
+            .no_deps()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let package = packages_to_publish(&metadata, &config)
+            .into_iter()
+            .find(|p| p.name == self.crate_name)
+            .ok_or_else(|| anyhow!("`{}` is not released from the workspace", self.crate_name))?;
+
+        let dependents = packages_to_publish(&metadata, &config)
+            .into_iter()
+            .filter(|p| {
+                p.dependencies
+                    .iter()
+                    .any(|d| d.name == package.name && d.kind != DependencyKind::Development)
+            })
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>();
+        if !dependents.is_empty() {
+            bail!(
+                "`{}` is still used by {}, remove these dependencies first",
+                package.name,
+                dependents.join(", ")
+            );
+        }
+
+        let registry = publish_registry(release, package.publish.as_deref());
+        let version = Version::new(
+            package.version.major,
+            package.version.minor,
+            package.version.patch + 1,
+        );
+        println!(
+            "🪦 Deprecating {} with the final version {}",
+            package.name, version
+        );
+
+        let mut edited = EditedFiles(vec![]);
+        edited.edit(package.manifest_path.as_std_path(), |manifest| {
+            deprecate_manifest(manifest, &version, &self.message)
+        })?;
+        if let Some(readme) = readme_path(package) {
+            edited.edit(readme.as_std_path(), |readme| {
+                Ok(readme_with_notice(readme, &self.message))
+            })?;
+        }
+
+        let manifest_path = package.manifest_path.as_str();
+        let mut publish = vec!["publish", "--manifest-path", manifest_path, "--allow-dirty"];
+        if let Some(registry) = &registry {
+            publish.extend(["--registry", registry.as_str()].iter());
+        }
+        if !self.confirm {
+            publish.push("--dry-run");
+        }
+        let published = run_cargo(&publish).await;
+        if !self.confirm || published.is_err() {
+            edited.restore();
+            published?;
+        } else {
+            println!("✅ {} v{} has been published", package.name, version);
+        }
+
+        if self.yank != YankPolicy::None {
+            if registry.is_some() {
+                bail!("Yanking is supported only for crates published to crates.io");
+            }
+            let versions = query_released_versions(&package.name).await?;
+            for yanked in versions_to_yank(&versions, self.yank, &version) {
+                let yanked = yanked.to_string();
+                let yank = ["yank", package.name.as_str(), "--version", yanked.as_str()];
+                if self.confirm {
+                    run_cargo(&yank).await?;
+                } else {
+                    println!("WOULD: cargo {}", yank.join(" "));
+                }
+            }
+        }
+
+        if self.confirm {
+            let config_content = std::fs::read_to_string(config_path)
+                .with_context(|| format!("Failed to read {}", config_path.display()))?;
+            std::fs::write(
+                config_path,
+                exclude_from_config(&config_content, &package.name)?,
+            )
+            .with_context(|| format!("Failed to write {}", config_path.display()))?;
+            println!(
+                "🚀 {} has been retired; commit the updated manifests and {}",
+                package.name,
+                config_path.display()
+            );
+        } else {
+            println!(
+                "WOULD: add {} to workspace.deprecated in {}",
+                package.name,
+                config_path.display()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn deprecated_crate() {
+        let manifest =
+            "[package]\nname = \"old\"\nversion = \"1.2.0\"\ndescription = \"Old parser\"\n";
+        let manifest = deprecate_manifest(manifest, &Version::new(1, 2, 1), "Use `new` instead");
+        expect![[r#"
+            Ok(
+                "[package]\nname = \"old\"\nversion = \"1.2.1\"\ndescription = \"DEPRECATED: Use `new` instead. Old parser\"\n\n[badges]\nmaintenance = { status = \"deprecated\" }\n",
+            )
+        "#]].assert_debug_eq(&manifest.map_err(|e| e.to_string()));
+
+        let versions = vec!["1.0.0", "1.1.0-rc.1", "1.2.0", "1.2.1"]
+            .into_iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect::<Vec<_>>();
+        let last = Version::new(1, 2, 1);
+        let yanked = vec![YankPolicy::Prereleases, YankPolicy::All]
+            .into_iter()
+            .map(|policy| {
+                versions_to_yank(&versions, policy, &last)
+                    .iter()
+                    .map(Version::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        expect![[r#"
+            [
+                [
+                    "1.1.0-rc.1",
+                ],
+                [
+                    "1.0.0",
+                    "1.1.0-rc.1",
+                    "1.2.0",
+                ],
+            ]
+        "#]]
+        .assert_debug_eq(&yanked);
+    }
+}
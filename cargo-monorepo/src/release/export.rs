@@ -0,0 +1,206 @@
+//! Machine-readable export of the workspace graph and release plan for the
+//! external tooling, so it does not re-implement the crate filtering rules
+use super::{context::packages_to_publish, plan::ReleasePlan, ReleaseExecutor, StepKind};
+use crate::{
+    cargo::sort_workspace, config::Config, registry::publish_registry, utils::metadata_command,
+};
+use anyhow::anyhow;
+use cargo_metadata::{DependencyKind, Metadata};
+use serde::Serialize;
+
+/// Version of the exported JSON layout, raised on incompatible changes
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Export workspace data for the external tooling")]
+pub struct ExportCommand {
+    #[structopt(subcommand)]
+    subcommand: Subcommand,
+}
+
+#[derive(clap::Parser, Debug)]
+enum Subcommand {
+    Metadata(MetadataCommand),
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    Json,
+}
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Export workspace graph, publish order and release plan")]
+struct MetadataCommand {
+    #[structopt(long, value_enum, default_value = "json")]
+    format: Format,
+}
+
+/// Exported workspace metadata, `schema_version` 1
+#[derive(Serialize)]
+struct WorkspaceExport {
+    schema_version: u32,
+    workspace_root: String,
+    root_crate: String,
+    /// Pending version of the root crate
+    version: String,
+    /// All workspace members
+    crates: Vec<CrateExport>,
+    /// Names of the released crates in publish order
+    publish_order: Vec<String>,
+    /// Steps of the release with the current config; steps in the same wave
+    /// are executed concurrently, waves are executed in order
+    release_plan: Vec<Vec<StepExport>>,
+}
+
+#[derive(Serialize)]
+struct CrateExport {
+    name: String,
+    version: String,
+    manifest_path: String,
+    /// Crate is published by the release
+    released: bool,
+    /// Registry the crate is published to, `null` for crates.io
+    registry: Option<String>,
+    /// Dependencies on the other workspace crates
+    dependencies: Vec<DependencyExport>,
+}
+
+#[derive(Serialize)]
+struct DependencyExport {
+    name: String,
+    req: String,
+    /// `normal`, `dev` or `build`
+    kind: &'static str,
+}
+
+#[derive(Serialize)]
+pub(super) struct StepExport {
+    pub name: &'static str,
+    /// `validation` or `release`
+    pub kind: &'static str,
+}
+
+fn dependency_kind(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev",
+        DependencyKind::Build => "build",
+        _ => "normal",
+    }
+}
+
+fn export_crates(metadata: &Metadata, config: &Config) -> Vec<CrateExport> {
+    let released = packages_to_publish(metadata, config);
+    let members = metadata
+        .packages
+        .iter()
+        .filter(|p| metadata.workspace_members.contains(&p.id))
+        .collect::<Vec<_>>();
+    members
+        .iter()
+        .map(|p| CrateExport {
+            name: p.name.clone(),
+            version: p.version.to_string(),
+            manifest_path: p.manifest_path.to_string(),
+            released: released.iter().any(|r| r.id == p.id),
+            registry: config
+                .release
+                .as_ref()
+                .and_then(|r| publish_registry(r, p.publish.as_deref())),
+            dependencies: p
+                .dependencies
+                .iter()
+                .filter(|d| members.iter().any(|m| m.name == d.name))
+                .map(|d| DependencyExport {
+                    name: d.name.clone(),
+                    req: d.req.to_string(),
+                    kind: dependency_kind(d.kind),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn export_release_plan(config: &Config) -> anyhow::Result<Vec<Vec<StepExport>>> {
+    if config.release.is_none() {
+        return Ok(vec![]);
+    }
+    let plan = ReleaseExecutor::new(config.clone(), false, false, None).plan()?;
+    Ok(export_waves(&plan))
+}
+
+pub(super) fn export_waves(plan: &ReleasePlan) -> Vec<Vec<StepExport>> {
+    plan.waves()
+        .iter()
+        .map(|wave| {
+            wave.iter()
+                .map(|step| StepExport {
+                    name: step.name(),
+                    kind: match step.kind() {
+                        StepKind::Validation => "validation",
+                        StepKind::Release => "release",
+                    },
+                })
+                .collect()
+        })
+        .collect()
+}
+
+impl MetadataCommand {
+    async fn run(self, config: Config) -> anyhow::Result<()> {
+        let metadata = metadata_command()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let root_crate = &config.workspace.root_crate;
+        let version = metadata
+            .packages
+            .iter()
+            .find(|p| &p.name == root_crate && metadata.workspace_members.contains(&p.id))
+            .map(|p| p.version.to_string())
+            .ok_or_else(|| anyhow!("Root crate `{}` is not found in workspace", root_crate))?;
+
+        let released = packages_to_publish(&metadata, &config);
+        let publish_order = sort_workspace(&metadata)?
+            .into_iter()
+            .filter_map(|id| released.iter().find(|p| p.id == id))
+            .map(|p| p.name.clone())
+            .collect();
+
+        let export = WorkspaceExport {
+            schema_version: SCHEMA_VERSION,
+            workspace_root: metadata.workspace_root.to_string(),
+            root_crate: root_crate.clone(),
+            version,
+            crates: export_crates(&metadata, &config),
+            publish_order,
+            release_plan: export_release_plan(&config)?,
+        };
+        match self.format {
+            Format::Json => println!("{}", serde_json::to_string_pretty(&export)?),
+        }
+        Ok(())
+    }
+}
+
+impl ExportCommand {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        match self.subcommand {
+            Subcommand::Metadata(cmd) => cmd.run(config).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn exported_release_plan() {
+        let config: Config = toml::from_str(
+            "[workspace]\nroot_crate = \"app\"\n[release]\ncheck_version_raised = false\n",
+        )
+        .unwrap();
+        let plan = export_release_plan(&config).unwrap();
+        expect![[r#"[[{"name":"init","kind":"validation"}],[{"name":"validate-version","kind":"validation"},{"name":"validate-not-published","kind":"validation"},{"name":"validate-publish","kind":"validation"}],[{"name":"publish","kind":"release"}]]"#]].assert_eq(&serde_json::to_string(&plan).unwrap());
+    }
+}
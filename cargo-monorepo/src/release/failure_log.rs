@@ -0,0 +1,145 @@
+//! Upload of the release report and audit log to a private gist or a paste
+//! service on failure, so the failure can be shared without CI log archives
+use super::ReleaseContext;
+use crate::{audit, config, github::GITHUB_TOKEN_VAR, utils::workspace_path};
+use anyhow::{anyhow, bail, Context};
+use octocrab::Octocrab;
+use std::collections::BTreeMap;
+
+/// Log files to upload, file name -> content
+type LogFiles = BTreeMap<String, String>;
+
+/// Files joined into a single paste, each one preceded by its name
+fn paste_content(files: &LogFiles) -> String {
+    files
+        .iter()
+        .map(|(name, content)| format!("==> {} <==\n{}\n", name, content.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn upload_gist(description: &str, files: &LogFiles) -> anyhow::Result<String> {
+    let token = std::env::var(GITHUB_TOKEN_VAR)
+        .with_context(|| format!("{} is required to create gist", GITHUB_TOKEN_VAR))?;
+    let github = Octocrab::builder().personal_token(token).build()?;
+    let request = serde_json::json!({
+        "description": description,
+        "public": false,
+        "files": files
+            .iter()
+            .map(|(name, content)| (name.clone(), serde_json::json!({ "content": content })))
+            .collect::<serde_json::Map<_, _>>(),
+    });
+    audit::api_call("POST", "gists");
+    let gist: serde_json::Value = github
+        .post("gists", Some(&request))
+        .await
+        .with_context(|| "Failed to create gist")?;
+    gist["html_url"]
+        .as_str()
+        .map(str::to_owned)
+        .ok_or_else(|| anyhow!("Gist URL is missing from the GitHub response"))
+}
+
+async fn upload_paste(
+    config: &config::FailureLog,
+    url: &str,
+    files: &LogFiles,
+) -> anyhow::Result<String> {
+    let mut request = reqwest::Client::new().post(url).body(paste_content(files));
+    if let Some(token_env) = &config.token_env {
+        let token = std::env::var(token_env)
+            .with_context(|| format!("Paste token is missing from {} env var", token_env))?;
+        request = request.bearer_auth(token);
+    }
+    audit::api_call("POST", url);
+    let response = request
+        .send()
+        .await
+        .with_context(|| "Failed to upload paste")?;
+    if !response.status().is_success() {
+        bail!(
+            "Failed to upload paste: server responded with {}",
+            response.status()
+        );
+    }
+    Ok(response.text().await?.trim().to_owned())
+}
+
+/// Uploads the log files, `None` if the upload is skipped outside of CI
+async fn upload(
+    config: &config::FailureLog,
+    description: &str,
+    files: &LogFiles,
+) -> anyhow::Result<Option<String>> {
+    if config.ci_only && std::env::var_os("CI").is_none() {
+        return Ok(None);
+    }
+    let url = match &config.paste_url {
+        Some(paste_url) => upload_paste(config, paste_url, files).await?,
+        None => upload_gist(description, files).await?,
+    };
+    Ok(Some(url))
+}
+
+/// Uploads the failed release report, error and audit log and prints the URL
+pub async fn upload_failure_log(
+    ctx: &ReleaseContext,
+    config: &config::FailureLog,
+    error: &anyhow::Error,
+) -> anyhow::Result<()> {
+    let mut files = LogFiles::new();
+    files.insert("error.txt".to_owned(), format!("{:?}", error));
+    files.insert(
+        "release-report.json".to_owned(),
+        ctx.report(false).to_json()?,
+    );
+    let audit_log = ctx
+        .config
+        .release
+        .as_ref()
+        .and_then(|r| r.audit_log.as_ref())
+        .map(workspace_path);
+    if let Some(audit_log) = audit_log {
+        match tokio::fs::read_to_string(&audit_log).await {
+            Ok(content) => {
+                files.insert("audit.jsonl".to_owned(), content);
+            }
+            Err(e) => println!("WARN: Failed to read {}: {}", audit_log.display(), e),
+        }
+    }
+
+    let description = format!("{} release failure", ctx.root_crate_name());
+    if let Some(url) = upload(config, &description, &files).await? {
+        println!("🔗 Release logs have been uploaded to {}", url);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn paste_files() {
+        let files = vec![
+            ("release-report.json", "{\n  \"success\": false\n}\n"),
+            ("audit.jsonl", "{\"kind\":\"command\"}\n"),
+        ]
+        .into_iter()
+        .map(|(name, content)| (name.to_owned(), content.to_owned()))
+        .collect();
+
+        expect![[r#"
+            ==> audit.jsonl <==
+            {"kind":"command"}
+
+            ==> release-report.json <==
+            {
+              "success": false
+            }
+        "#]]
+        .assert_eq(&paste_content(&files));
+    }
+}
@@ -0,0 +1,122 @@
+//! Publish journal is a JSON lines file appended right after each successful
+//! crate publish; it survives interrupted releases, so `--resume` can skip
+//! already published crates, and it is the source of the published crates
+//! in the release report
+use crate::{config, utils::workspace_path};
+use anyhow::Context;
+use cargo_metadata::Metadata;
+use chrono::{DateTime, Utc};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{io::Write as _, path::PathBuf};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub name: String,
+    pub version: Version,
+    /// `None` stands for crates.io
+    pub registry: Option<String>,
+    pub published_at: DateTime<Utc>,
+    /// sha256 of the published `.crate` archive
+    pub checksum: Option<String>,
+}
+
+pub fn journal_path(release: &config::Release, metadata: &Metadata) -> PathBuf {
+    match &release.publish_journal {
+        Some(path) => workspace_path(path),
+        None => metadata
+            .target_directory
+            .join("monorepo")
+            .join("publish-journal.jsonl")
+            .into(),
+    }
+}
+
+fn parse_journal(content: &str) -> anyhow::Result<Vec<JournalEntry>> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Invalid publish journal entry `{}`", line))
+        })
+        .collect()
+}
+
+/// Reads journal entries, missing journal is treated as empty
+pub fn read_journal(path: &std::path::Path) -> anyhow::Result<Vec<JournalEntry>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => parse_journal(&content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => {
+            Err(e).with_context(|| format!("Failed to read publish journal {}", path.display()))
+        }
+    }
+}
+
+pub fn is_journaled(entries: &[JournalEntry], name: &str, version: &Version) -> bool {
+    entries
+        .iter()
+        .any(|e| e.name == name && &e.version == version)
+}
+
+/// Starts the journal of the new release
+pub fn reset_journal(path: &std::path::Path) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, "")
+        .with_context(|| format!("Failed to reset publish journal {}", path.display()))
+}
+
+pub fn append_journal(path: &std::path::Path, entry: &JournalEntry) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to append to publish journal {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn journaled_crates() {
+        let journal = r#"{"name":"foo-core","version":"1.2.0","registry":null,"published_at":"2024-05-01T10:00:00Z","checksum":"abc"}
+
+{"name":"foo","version":"1.2.0","registry":"internal","published_at":"2024-05-01T10:01:00Z","checksum":null}
+"#;
+        let entries = parse_journal(journal).unwrap();
+
+        let journaled = vec![("foo-core", "1.2.0"), ("foo", "1.2.0"), ("foo", "1.1.0")]
+            .into_iter()
+            .map(|(name, version)| {
+                let version = Version::parse(version).unwrap();
+                (name, is_journaled(&entries, name, &version))
+            })
+            .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                (
+                    "foo-core",
+                    true,
+                ),
+                (
+                    "foo",
+                    true,
+                ),
+                (
+                    "foo",
+                    false,
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&journaled);
+    }
+}
@@ -0,0 +1,145 @@
+//! Export of the release health metrics to Prometheus pushgateway or StatsD
+use crate::{audit, config};
+use anyhow::{bail, Context};
+use std::{fmt::Write as _, time::Duration};
+
+pub struct ReleaseMetrics {
+    pub root_crate: String,
+    pub success: bool,
+    pub dry_run: bool,
+    pub duration: Duration,
+    pub step_durations: Vec<(String, Duration)>,
+    pub published_crates: usize,
+    pub uploaded_bytes: u64,
+}
+
+impl ReleaseMetrics {
+    /// Exports metrics to all configured endpoints
+    pub async fn push(&self, config: &config::Metrics) -> anyhow::Result<()> {
+        if let Some(pushgateway) = &config.pushgateway {
+            let url = format!(
+                "{}/metrics/job/{}/root_crate/{}",
+                pushgateway.trim_end_matches('/'),
+                config.job,
+                self.root_crate
+            );
+            audit::api_call("PUT", &url);
+            let response = reqwest::Client::new()
+                .put(&url)
+                .body(self.to_prometheus(&config.prefix))
+                .send()
+                .await
+                .with_context(|| "Failed to push metrics to pushgateway")?;
+            if !response.status().is_success() {
+                bail!(
+                    "Failed to push metrics to pushgateway: server responded with {}",
+                    response.status()
+                );
+            }
+        }
+
+        if let Some(statsd) = &config.statsd {
+            let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+            socket
+                .connect(statsd)
+                .await
+                .with_context(|| format!("Failed to connect to StatsD at {}", statsd))?;
+            audit::api_call("STATSD", statsd);
+            for line in self.to_statsd(&config.prefix) {
+                socket
+                    .send(line.as_bytes())
+                    .await
+                    .with_context(|| "Failed to send metrics to StatsD")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn to_prometheus(&self, prefix: &str) -> String {
+        let mut out = String::new();
+        let mut gauge = |name: &str, labels: &str, value: f64| {
+            writeln!(out, "{}_{}{} {}", prefix, name, labels, value).unwrap();
+        };
+
+        gauge("success", "", bool_value(self.success));
+        gauge("dry_run", "", bool_value(self.dry_run));
+        gauge("duration_seconds", "", self.duration.as_secs_f64());
+        for (step, duration) in &self.step_durations {
+            gauge(
+                "step_duration_seconds",
+                &format!("{{step=\"{}\"}}", step),
+                duration.as_secs_f64(),
+            );
+        }
+        gauge("published_crates", "", self.published_crates as f64);
+        gauge("uploaded_bytes", "", self.uploaded_bytes as f64);
+
+        out
+    }
+
+    fn to_statsd(&self, prefix: &str) -> Vec<String> {
+        let mut lines = vec![
+            format!("{}.success:{}|g", prefix, bool_value(self.success)),
+            format!("{}.duration:{}|ms", prefix, self.duration.as_millis()),
+        ];
+        for (step, duration) in &self.step_durations {
+            lines.push(format!(
+                "{}.step.{}.duration:{}|ms",
+                prefix,
+                step,
+                duration.as_millis()
+            ));
+        }
+        lines.push(format!(
+            "{}.published_crates:{}|g",
+            prefix, self.published_crates
+        ));
+        lines.push(format!(
+            "{}.uploaded_bytes:{}|g",
+            prefix, self.uploaded_bytes
+        ));
+        lines
+    }
+}
+
+fn bool_value(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn prometheus_format() {
+        let metrics = ReleaseMetrics {
+            root_crate: "foo".to_owned(),
+            success: true,
+            dry_run: false,
+            duration: Duration::from_millis(12500),
+            step_durations: vec![
+                ("init".to_owned(), Duration::from_millis(500)),
+                ("publish".to_owned(), Duration::from_secs(12)),
+            ],
+            published_crates: 2,
+            uploaded_bytes: 4096,
+        };
+
+        expect![[r#"
+            monorepo_release_success 1
+            monorepo_release_dry_run 0
+            monorepo_release_duration_seconds 12.5
+            monorepo_release_step_duration_seconds{step="init"} 0.5
+            monorepo_release_step_duration_seconds{step="publish"} 12
+            monorepo_release_published_crates 2
+            monorepo_release_uploaded_bytes 4096
+        "#]]
+        .assert_eq(&metrics.to_prometheus("monorepo_release"));
+    }
+}
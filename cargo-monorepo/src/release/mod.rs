@@ -1,76 +1,573 @@
+mod branch;
+mod bump;
+mod changed;
+mod channel;
 mod context;
+mod conventional;
+mod dedupe;
+mod dependents;
+mod deprecate;
+mod export;
+mod failure_log;
+mod journal;
+mod metrics;
+mod plan;
+mod plan_command;
+mod rc;
+mod readme;
+mod report;
+mod resource;
+mod rollback;
+mod state;
 mod step;
+mod train;
+mod yank;
 
-use self::context::ReleaseContext;
-use crate::config::Config;
+pub use self::{
+    bump::BumpCommand, changed::ChangedCommand, deprecate::DeprecateCommand, export::ExportCommand,
+    plan_command::PlanCommand, rc::PromoteCommand, yank::YankCommand,
+};
+
+use self::{
+    branch::MaintenanceLine,
+    channel::ReleaseChannel,
+    context::ReleaseContext,
+    metrics::ReleaseMetrics,
+    plan::ReleasePlan,
+    resource::{PendingVersion, PublishedCrates, ResourceId},
+    state::{state_path, ReleaseState},
+};
+use crate::{
+    audit,
+    config::{ChangelogSource, Config, TagMethod},
+    i18n::tr,
+    utils::{open_in_browser, set_github_output, workspace_path},
+};
+use anyhow::{bail, Context};
 use async_trait::async_trait;
-use std::collections::VecDeque;
+use cargo_metadata::Metadata;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 #[derive(clap::Parser, Debug)]
 #[structopt(about = "Automatically prepare new repo release")]
 pub struct Command {
     /// Actually execute command instead of dry run
-    #[structopt(long)]
+    #[structopt(long, conflicts_with = "offline")]
     confirm: bool,
-    /// Do not publish packages to the registry
+    /// Stop release after the named step has been executed
+    #[structopt(long)]
+    until_step: Option<String>,
+    /// Release even if the current commit and version have already been
+    /// released according to `[release.dedupe]`
     #[structopt(long)]
+    force: bool,
+    /// Continue interrupted release; completed release steps recorded in the
+    /// release state file are skipped, crates recorded in the publish journal
+    /// or already present in the registry are not published again
+    #[structopt(long)]
+    resume: bool,
+    /// Open the created GitHub release page in the browser
+    #[structopt(long)]
+    open: bool,
+    #[command(flatten)]
+    options: ReleaseOptions,
+}
+
+/// Options which define the release steps, shared by `release` and `plan`
+/// so the plan matches the release
+#[derive(clap::Args, Debug)]
+pub struct ReleaseOptions {
+    /// Do not publish packages to the registry
+    #[arg(long)]
     nopublish: bool,
+    /// Release only crates of the given `[group.<name>]` config section
+    #[arg(long)]
+    group: Option<String>,
+    /// Release from the maintenance branch of the older version line, e.g.
+    /// `release/1.x`; released version is compared only with the same line
+    #[arg(long)]
+    branch: Option<String>,
+    /// Emergency release: changelog is not required and steps can be skipped
+    #[arg(long)]
+    hotfix: bool,
+    /// Skip the named release step, only allowed for hotfix releases
+    #[arg(long, requires = "hotfix")]
+    skip_step: Vec<String>,
+    /// Bypass the named misfiring validation step for this release only;
+    /// confirmation is asked in the interactive terminal
+    #[arg(long, value_name = "CHECK")]
+    force_skip_validation: Vec<String>,
+    /// Validate release against `[train]` schedule and version pattern
+    #[arg(long)]
+    train: bool,
+    /// Allow train release outside of the scheduled window
+    #[arg(long, requires = "train")]
+    ignore_train_window: bool,
+    /// Release channel; nightly publishes the current commit as a rolling
+    /// GitHub prerelease without publishing crates or creating version tags
+    #[arg(
+        long,
+        value_enum,
+        default_value = "stable",
+        conflicts_with_all = ["hotfix", "train", "branch"]
+    )]
+    channel: ReleaseChannel,
+    /// Create tags, release page and upload artifacts without publishing
+    /// crates or accessing the registry
+    #[arg(long, conflicts_with = "nopublish")]
+    github_only: bool,
+    /// Validate and publish crates only; tags, release page, docs and other
+    /// forge steps are skipped even if configured
+    #[arg(long, conflicts_with_all = ["nopublish", "github_only"])]
+    publish_only: bool,
+    /// Dry run without tokens and network access to GitHub and the registry;
+    /// checks which need them are skipped
+    #[arg(long)]
+    offline: bool,
+}
+
+impl ReleaseOptions {
+    /// Creates executor with the steps selected by the options, `config`
+    /// should already be resolved for the release group
+    fn executor(
+        &self,
+        config: Config,
+        dry_run: bool,
+        until_step: Option<String>,
+    ) -> anyhow::Result<ReleaseExecutor> {
+        let maintenance_line = self
+            .branch
+            .as_deref()
+            .map(MaintenanceLine::from_branch)
+            .transpose()?;
+        let mut executor = ReleaseExecutor::new(config, dry_run, self.nopublish, until_step)
+            .with_maintenance_line(maintenance_line)
+            .with_channel(self.channel);
+        if self.hotfix {
+            executor = executor.with_hotfix(self.skip_step.clone());
+        }
+        if self.github_only {
+            if executor.context.config.github.is_none() {
+                bail!("github section should be specified in the config to use --github-only");
+            }
+            executor = executor.with_github_only();
+        }
+        if self.publish_only {
+            if self.channel == ReleaseChannel::Nightly {
+                bail!("--publish-only can't be used for nightly releases");
+            }
+            executor = executor.with_publish_only();
+        }
+        if self.offline {
+            executor = executor.with_offline();
+        }
+        if !self.force_skip_validation.is_empty() {
+            executor = executor.with_forced_skips(self.force_skip_validation.clone());
+        }
+        if self.train {
+            if executor.context.config.train.is_none() {
+                bail!("train section should be specified in the config to use --train");
+            }
+            executor = executor.with_train(self.ignore_train_window);
+        }
+        Ok(executor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepKind {
+    /// Step which does not change anything outside of the local machine;
+    /// independent validation steps are executed concurrently
+    Validation,
+    /// Step which changes remote state (registry, GitHub, etc.); release steps
+    /// are executed one by one and only after all validation steps succeeded
+    Release,
 }
 
 #[async_trait]
-trait ReleaseStep {
+trait ReleaseStep: Send + Sync {
+    /// Unique kebab-case step name, used to reference step from the CLI
+    fn name(&self) -> &'static str;
+    fn kind(&self) -> StepKind;
+    /// Resources which should be provided by other steps before this step starts
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![]
+    }
+    /// Resources which are guaranteed to be provided by the step on success
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![]
+    }
     fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String>;
     fn success_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String>;
 
-    async fn execute(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()>;
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()>;
+}
+
+/// Asks to type the check name when running in the interactive terminal, so
+/// the validation is not bypassed by a copy-pasted command by accident
+fn confirm_forced_skip(name: &str) -> anyhow::Result<()> {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+    print!(
+        "{} ",
+        tr!(
+            "Type `{name}` to confirm skipping the validation:",
+            name = name
+        )
+    );
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    if answer.trim() != name {
+        bail!("Skipping of `{}` validation has not been confirmed", name);
+    }
+    Ok(())
 }
 
 struct ReleaseExecutor {
     context: ReleaseContext,
-    steps: VecDeque<Box<dyn ReleaseStep>>,
+    steps: Vec<Box<dyn ReleaseStep>>,
+    until_step: Option<String>,
+    skipped_steps: Vec<String>,
+    train: Option<step::ValidateReleaseTrain>,
+    open_release: bool,
 }
 
 impl ReleaseExecutor {
-    pub fn new(config: Config, dry_run: bool, nopublish: bool) -> Self {
+    pub fn new(config: Config, dry_run: bool, nopublish: bool, until_step: Option<String>) -> Self {
         Self {
             context: ReleaseContext::new(config, dry_run, nopublish),
             steps: Default::default(),
+            until_step,
+            skipped_steps: Default::default(),
+            train: None,
+            open_release: false,
         }
     }
 
+    pub fn with_maintenance_line(mut self, line: Option<MaintenanceLine>) -> Self {
+        self.context.maintenance_line = line;
+        self
+    }
+
+    pub fn with_hotfix(mut self, skipped_steps: Vec<String>) -> Self {
+        self.context.hotfix = true;
+        self.skipped_steps = skipped_steps;
+        self
+    }
+
+    pub fn with_github_only(mut self) -> Self {
+        self.context.github_only = true;
+        self
+    }
+
+    pub fn with_forced_skips(mut self, checks: Vec<String>) -> Self {
+        self.context.forced_skips = checks;
+        self
+    }
+
+    pub fn with_publish_only(mut self) -> Self {
+        self.context.publish_only = true;
+        self
+    }
+
+    pub fn with_resume(mut self) -> Self {
+        self.context.resume = true;
+        self
+    }
+
+    pub fn with_promotion(mut self, rc_tag: String) -> Self {
+        self.context.promoted_from = Some(rc_tag);
+        self
+    }
+
+    pub fn with_offline(mut self) -> Self {
+        self.context.offline = true;
+        self
+    }
+
+    pub fn with_channel(mut self, channel: ReleaseChannel) -> Self {
+        self.context.channel = channel;
+        self
+    }
+
+    pub fn with_open_release(mut self) -> Self {
+        self.open_release = true;
+        self
+    }
+
+    pub fn with_train(mut self, ignore_window: bool) -> Self {
+        self.train = Some(step::ValidateReleaseTrain::new(ignore_window));
+        self
+    }
+
+    fn skip_steps(&mut self) -> anyhow::Result<()> {
+        for name in &self.skipped_steps {
+            if !self.steps.iter().any(|s| s.name() == name) {
+                let available = self
+                    .steps
+                    .iter()
+                    .map(|s| s.name())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                bail!(
+                    "Step `{}` can't be skipped as it is not part of the release, available steps: {}",
+                    name,
+                    available
+                );
+            }
+        }
+        let skipped_steps = &self.skipped_steps;
+        self.steps
+            .retain(|s| !skipped_steps.iter().any(|n| n == s.name()));
+
+        for name in &self.context.forced_skips {
+            let step = match self.steps.iter().find(|s| s.name() == name) {
+                Some(step) => step,
+                None => bail!("Validation `{}` is not part of the release", name),
+            };
+            if step.kind() != StepKind::Validation {
+                bail!(
+                    "`{}` is not a validation step and can't be force-skipped",
+                    name
+                );
+            }
+            if !step.provides().is_empty() {
+                bail!(
+                    "`{}` validation can't be skipped, as other steps depend on its results",
+                    name
+                );
+            }
+        }
+        let forced_skips = &self.context.forced_skips;
+        self.steps
+            .retain(|s| !forced_skips.iter().any(|n| n == s.name()));
+        Ok(())
+    }
+
     fn add_step(&mut self, step: impl ReleaseStep + 'static) {
-        self.steps.push_back(Box::new(step));
+        self.steps.push(Box::new(step));
+    }
+
+    /// Nightly release shares validation with the stable one, but publishes
+    /// only the rolling GitHub prerelease
+    fn build_nightly_steps(&mut self) -> anyhow::Result<()> {
+        self.context.release_nightly_config()?;
+
+        self.add_step(step::Init::new(true));
+        if let Some(artifacts) = self.context.config.artifacts.clone() {
+            self.add_step(step::CollectArtifacts);
+            if artifacts.include_cargo_lock || artifacts.source_archive.is_some() {
+                self.add_step(step::CollectSourceArtifacts);
+            }
+            if artifacts.c_api.is_some() {
+                self.add_step(step::BuildCApiArtifacts);
+            }
+            if artifacts.dependency_snapshot {
+                self.add_step(step::SnapshotDependencies);
+            }
+        }
+        // Changelog file sections are written for the stable versions only
+        if let Some(changelog) = &self.context.config.changelog {
+            if changelog.source == ChangelogSource::Commits {
+                self.add_step(step::CaptureChangelog::new(ChangelogSource::Commits));
+            }
+        }
+        if !self.context.offline {
+            let release = self.context.release_config()?;
+            let github = release
+                .github
+                .as_ref()
+                .is_some_and(|g| g.check_commit_pushed);
+            let remotes = release.commit_pushed_remotes.clone();
+            if github || !remotes.is_empty() {
+                self.add_step(step::ValidateCommitPushed::new(github, remotes));
+            }
+        }
+        self.add_step(step::VaidateVersion);
+        self.add_step(step::ValidatePathDependencies);
+
+        if self.context.release_config()?.lock.is_some() && !self.context.offline {
+            self.add_step(step::AcquireReleaseLock);
+        }
+        self.add_step(step::PublishNightlyRelease);
+        let asset_retention = self
+            .context
+            .release_config()?
+            .github
+            .as_ref()
+            .is_some_and(|g| g.asset_retention.is_some());
+        if asset_retention {
+            self.add_step(step::PruneReleaseAssets);
+        }
+
+        Ok(())
     }
 
     fn build_steps(&mut self) -> anyhow::Result<()> {
+        if self.context.is_nightly() {
+            return self.build_nightly_steps();
+        }
+
+        // Forge steps are skipped for publish-only release
+        let forge = !self.context.publish_only;
+
         // Validation steps
-        self.add_step(step::Init);
-        if self.context.config.artifacts.is_some() {
+        self.add_step(step::Init::new(
+            self.context.config.github.is_some() && forge,
+        ));
+        if let Some(artifacts) = self.context.config.artifacts.clone() {
             self.add_step(step::CollectArtifacts);
+            if artifacts.reproducibility.is_some() {
+                self.add_step(step::VerifyReproducibleBuild);
+            }
+            if artifacts.include_cargo_lock || artifacts.source_archive.is_some() {
+                self.add_step(step::CollectSourceArtifacts);
+            }
+            if artifacts.c_api.is_some() {
+                self.add_step(step::BuildCApiArtifacts);
+            }
+            if artifacts.dependency_snapshot {
+                self.add_step(step::SnapshotDependencies);
+            }
+            if artifacts.delta_patches.is_some() && forge && !self.context.offline {
+                self.add_step(step::CreateDeltaPatches);
+            }
         }
+        if !self.context.config.generated_files.is_empty() {
+            self.add_step(step::RenderGeneratedFiles);
+        }
+        let mut changelog_captured = false;
         if self.context.config.changelog.is_some() {
-            self.add_step(step::CaptureChangelog);
+            let source = self.context.changelog_config()?.source;
+            // Pull requests are queried with the GitHub client, which is not
+            // initialized for publish-only release
+            if source != ChangelogSource::PullRequests || forge {
+                self.add_step(step::CaptureChangelog::new(source));
+                changelog_captured = true;
+                if self.context.changelog_config()?.migration_heading.is_some() {
+                    self.add_step(step::ValidateMigrationNotes);
+                }
+            }
+            if self.context.changelog_config()?.crate_notes.is_some() {
+                self.add_step(step::CollectCrateNotes);
+            }
+        }
+        if !self.context.release_config()?.path_policies.is_empty() {
+            self.add_step(step::ValidatePathPolicies::new(changelog_captured));
+        }
+        if !self.context.offline {
+            let release = self.context.release_config()?;
+            let github = forge
+                && release
+                    .github
+                    .as_ref()
+                    .is_some_and(|g| g.check_commit_pushed);
+            let remotes = release.commit_pushed_remotes.clone();
+            if github || !remotes.is_empty() {
+                self.add_step(step::ValidateCommitPushed::new(github, remotes));
+            }
         }
         if let Some(github) = &self.context.release_config()?.github {
-            if github.check_commit_pushed {
-                self.add_step(step::ValidateCommitPushedToGithub);
+            let online = forge && !self.context.offline;
+            let (create_release_page, external_tag) =
+                (github.create_release_page, github.external_tag.is_some());
+            if external_tag && forge {
+                self.add_step(step::VerifyExternalTag);
+            }
+            if create_release_page && online {
+                self.add_step(step::FetchPrevRelease);
             }
         }
         self.add_step(step::VaidateVersion);
-        self.add_step(step::CargoPublish::validate_only());
-        if !(self.context.is_dry_run() || self.context.is_nopublish()) {
-            self.add_step(step::CargoPublish::new());
+        if self.context.release_config()?.readme.is_some() {
+            self.add_step(step::ValidateReadme);
+        }
+        if self.context.release_config()?.crate_metadata.is_some() {
+            self.add_step(step::ValidateCrateMetadata);
+        }
+        if self.context.release_config()?.feature_docs.is_some() {
+            self.add_step(step::ValidateFeatureDocs);
+        }
+        if self.context.release_config()?.rustdoc.is_some() {
+            self.add_step(step::ValidateRustdoc);
         }
-        if self.context.release_config()?.github.is_some() {
+        if let Some(train) = self.train.take() {
+            self.add_step(train);
+        }
+        let skip_unchanged = self.context.release_config()?.skip_unchanged;
+        if skip_unchanged {
+            self.add_step(step::DetectChangedCrates);
+        }
+        let github_only = self.context.github_only;
+        // Registry is not accessed in offline mode
+        let registry = !(github_only || self.context.offline);
+        let release_config = self.context.release_config()?;
+        if (release_config.registry.is_none() || release_config.registry_api.is_some()) && registry
+        {
+            self.add_step(step::ValidateNotPublished);
+        }
+        if registry {
+            self.add_step(step::CargoPublish::validate_only(skip_unchanged));
+        }
+        // Lock is acquired by the first release step
+        if self.context.release_config()?.lock.is_some() && forge && !self.context.offline {
+            self.add_step(step::AcquireReleaseLock);
+        }
+        // Commits made by the release precede everything released from them
+        let promote = self
+            .context
+            .config
+            .changelog
+            .as_ref()
+            .is_some_and(|c| c.promote_unreleased.is_some());
+        if promote {
+            self.add_step(step::PromoteChangelog);
+        }
+        if self.context.config.generated_files.iter().any(|f| f.commit) {
+            self.add_step(step::CommitGeneratedFiles);
+        }
+        if !(self.context.is_dry_run() || self.context.is_nopublish() || github_only) {
+            self.add_step(step::CargoPublish::new(skip_unchanged));
+            if self.context.release_config()?.mirror.is_some() {
+                self.add_step(step::MirrorToRegistry);
+            }
+        }
+        if self.context.release_config()?.npm.is_some()
+            && !(self.context.is_nopublish() || github_only)
+        {
+            self.add_step(step::PublishNpmPackages);
+        }
+        if self.context.release_config()?.github.is_some() && forge {
+            let github = self.context.release_config()?.github.as_ref().unwrap();
+            // External tag is not created, but the tag step provides its name
+            let create_tag = github.create_tag || github.external_tag.is_some();
+            let tag_method = github.tag_method;
+            let update_manifest = github.update_manifest.is_some();
+            if create_tag {
+                match tag_method {
+                    TagMethod::Github => self.add_step(step::CreateTagOnGithub),
+                    TagMethod::Git => self.add_step(step::CreateGitTag),
+                }
+            }
             if self
                 .context
                 .release_config()?
                 .github
                 .as_ref()
                 .unwrap()
-                .create_tag
+                .create_release_page
             {
-                self.add_step(step::CreateTagOnGithub);
+                self.add_step(step::CreateGithubRelease);
+                if update_manifest {
+                    self.add_step(step::PublishUpdateManifest);
+                }
             }
             if self
                 .context
@@ -78,10 +575,56 @@ impl ReleaseExecutor {
                 .github
                 .as_ref()
                 .unwrap()
-                .create_release_page
+                .attestation
+                .is_some()
             {
-                self.add_step(step::CreateGithubRelease);
+                self.add_step(step::AttestReleaseAssets);
             }
+            if self
+                .context
+                .release_config()?
+                .github
+                .as_ref()
+                .unwrap()
+                .asset_retention
+                .is_some()
+            {
+                self.add_step(step::PruneReleaseAssets);
+            }
+        }
+        if self.context.release_config()?.docs.is_some() && forge {
+            self.add_step(step::DeployDocs);
+        }
+        if let Some(announcement) = &self.context.release_config()?.announcement {
+            let open_pr = announcement.pull_request.is_some();
+            if forge {
+                self.add_step(step::GenerateAnnouncement::new(open_pr));
+            }
+        }
+        if self.context.release_config()?.git_notes.is_some() {
+            self.add_step(step::AddReleaseNote);
+        }
+        if self.context.release_config()?.releases_file.is_some() {
+            let release_page = forge
+                && self
+                    .context
+                    .release_config()?
+                    .github
+                    .as_ref()
+                    .is_some_and(|g| g.create_release_page);
+            self.add_step(step::UpdateReleasesFile::new(release_page));
+        }
+        if self
+            .context
+            .config
+            .post_release
+            .as_ref()
+            .is_some_and(|p| !p.http.is_empty())
+        {
+            self.add_step(step::NotifyServices);
+        }
+        if self.context.release_config()?.dedupe.is_some() {
+            self.add_step(step::RecordCompletedRelease);
         }
         // Release steps
         // TODO
@@ -89,39 +632,498 @@ impl ReleaseExecutor {
         Ok(())
     }
 
+    async fn execute_plan(
+        context: &ReleaseContext,
+        plan: ReleasePlan,
+        paused: bool,
+        step_durations: &Mutex<Vec<(String, Duration)>>,
+    ) -> anyhow::Result<()> {
+        // State is recorded only for the release steps, validation steps are
+        // always executed again
+        let record_state = !context.is_dry_run() && context.config.release.is_some();
+        let mut state_file = None;
+        let mut state: Option<ReleaseState> = None;
+
+        for wave in plan.waves() {
+            let release_wave = wave.iter().all(|s| s.kind() == StepKind::Release);
+            if release_wave && record_state && state.is_none() {
+                // Resources are provided by the init step, which precedes every
+                // release step
+                let resources = context.resources();
+                let (metadata, version, commit) = match (
+                    resources.try_get::<Metadata>(),
+                    resources.try_get::<PendingVersion>(),
+                    context.try_current_commit(),
+                ) {
+                    (Some(metadata), Some(version), Some(commit)) => (metadata, version, commit),
+                    _ => bail!("BUG: release steps are started before the init step"),
+                };
+                let path = state_path(context.release_config()?, metadata);
+                let current =
+                    ReleaseState::new(context.root_crate_name(), version.0.clone(), commit);
+                let resumed_state = if context.resume {
+                    ReleaseState::read(&path)?
+                } else {
+                    None
+                };
+                let current = match resumed_state {
+                    Some(resumed) => {
+                        resumed.check_matches(&current)?;
+                        resumed
+                    }
+                    None => current,
+                };
+                current.write(&path)?;
+                state = Some(current);
+                state_file = Some(path);
+            }
+            if let (true, Some(state)) = (release_wave, &state) {
+                // Release steps are executed one by one
+                let step = &wave[0];
+                if state.is_completed(step.name()) {
+                    state.restore(&step.provides(), context.resources())?;
+                    println!(
+                        "⏭️ {}",
+                        tr!(
+                            "`{step}` step has been completed before, skipping",
+                            step = step.name()
+                        )
+                    );
+                    continue;
+                }
+            }
+
+            if release_wave {
+                context.mark_release_started();
+            }
+            for step in wave {
+                println!("🧪️ {}", step.start_message(context)?);
+            }
+
+            let executions = wave.iter().map(|step| async move {
+                let started = Instant::now();
+                let result = step.execute(context).await;
+                step_durations
+                    .lock()
+                    .unwrap()
+                    .push((step.name().to_owned(), started.elapsed()));
+                result?;
+                for resource in step.provides() {
+                    if !context.resources().is_provided(&resource) {
+                        bail!(
+                            "BUG: step `{}` has not provided declared {}",
+                            step.name(),
+                            resource
+                        );
+                    }
+                }
+                println!("✅ {}", step.success_message(context)?);
+                anyhow::Ok(())
+            });
+            futures::future::try_join_all(executions).await?;
+
+            if let (true, Some(state), Some(path)) = (release_wave, &mut state, &state_file) {
+                let step = &wave[0];
+                let recorded = state.record(step.name(), &step.provides(), context.resources());
+                // Resumed release starts from the commit made by the release
+                let commit = context
+                    .try_current_commit()
+                    .unwrap_or_else(|| state.commit.clone());
+                if recorded || state.commit != commit {
+                    state.commit = commit;
+                    state.write(path)?;
+                }
+            }
+        }
+
+        // Paused release is continued with `--resume`
+        if let (Some(path), Some(_), false) = (&state_file, &state, paused) {
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to remove release state {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Release plan derived from the config, nothing is executed
+    fn plan(self) -> anyhow::Result<ReleasePlan> {
+        self.into_plan().map(|(_, plan)| plan)
+    }
+
+    /// Plan along with the context it has been built for, without executing
+    /// any step
+    fn into_plan(mut self) -> anyhow::Result<(ReleaseContext, ReleasePlan)> {
+        self.build_steps()?;
+        self.skip_steps()?;
+        Ok((self.context, ReleasePlan::new(self.steps)?))
+    }
+
     pub async fn execute(mut self) -> anyhow::Result<()> {
         self.build_steps()?;
+        self.skip_steps()?;
+        for name in &self.skipped_steps {
+            println!("⏭️ {}", tr!("Skipping `{name}` step", name = name));
+        }
+        for name in &self.context.forced_skips {
+            confirm_forced_skip(name)?;
+            println!(
+                "⚠️ {}",
+                tr!("Forcibly skipping `{name}` validation", name = name)
+            );
+        }
 
         let Self {
-            mut context, steps, ..
+            mut context,
+            steps,
+            until_step,
+            open_release,
+            ..
         } = self;
 
-        for step in steps {
-            println!("🧪️ {}", step.start_message(&context)?);
-            step.execute(&mut context).await?;
-            println!("✅ {}", step.success_message(&context)?);
+        let mut plan = ReleasePlan::new(steps)?;
+        if let Some(until_step) = &until_step {
+            plan.truncate_after(until_step)?;
+        }
+        context.init_resources(plan.provided_resources());
+
+        let started = Instant::now();
+        let step_durations = Mutex::new(vec![]);
+        let result =
+            Self::execute_plan(&context, plan, until_step.is_some(), &step_durations).await;
+        let rollback_on_failure = context
+            .config
+            .release
+            .as_ref()
+            .is_some_and(|r| r.rollback_on_failure);
+        // Nothing has been changed if the release failed on validation
+        let rollback = rollback_on_failure && !context.is_dry_run() && context.is_release_started();
+        if result.is_err() && rollback {
+            println!("⏪ {}", tr!("Rolling back the failed release"));
+            match rollback::rollback(&context).await {
+                Ok(()) => println!("⏪ {}", tr!("Release has been rolled back")),
+                Err(e) => println!("WARN: {:#}", e),
+            }
+        }
+        if let Err(e) = step::release_lock(&context).await {
+            println!("WARN: {:#}", e);
+        }
+
+        let metrics_config = context.config.metrics.as_ref().filter(|_| !context.offline);
+        if let Some(metrics_config) = metrics_config {
+            let metrics = ReleaseMetrics {
+                root_crate: context.root_crate_name(),
+                success: result.is_ok(),
+                dry_run: context.is_dry_run(),
+                duration: started.elapsed(),
+                step_durations: step_durations.into_inner().unwrap(),
+                published_crates: context
+                    .resources()
+                    .try_get::<PublishedCrates>()
+                    .map_or(0, |p| p.0.len()),
+                uploaded_bytes: context.uploaded_bytes(),
+            };
+            match metrics.push(metrics_config).await {
+                Ok(()) => println!("📈 {}", tr!("Release metrics have been pushed")),
+                Err(e) => println!(
+                    "WARN: {}",
+                    tr!(
+                        "Failed to push release metrics: {error}",
+                        error = format!("{:#}", e)
+                    )
+                ),
+            }
+        }
+
+        let report_file = context
+            .config
+            .release
+            .as_ref()
+            .and_then(|r| r.report_file.as_ref())
+            .map(workspace_path);
+        if let Some(report_file) = report_file {
+            context.report(result.is_ok()).write(&report_file).await?;
+            println!(
+                "📝 Release report has been written to {}",
+                report_file.display()
+            );
+        }
+
+        let failure_log = context.config.failure_log.as_ref();
+        if let (Err(e), Some(failure_log)) = (&result, failure_log.filter(|_| !context.offline)) {
+            if let Err(e) = failure_log::upload_failure_log(&context, failure_log, e).await {
+                println!("WARN: Failed to upload release logs: {:#}", e);
+            }
         }
 
-        println!(
-            "🚀 Workspace version {} has been released!",
-            context.version()?,
-        );
+        result?;
+
+        if let Some(release_url) = context.release_url() {
+            println!("🔗 {}", tr!("GitHub release: {url}", url = release_url));
+            set_github_output("release_url", release_url)?;
+            if open_release {
+                if let Err(e) = open_in_browser(release_url).await {
+                    println!("WARN: Failed to open the release page: {:#}", e);
+                }
+            }
+        }
+
+        if let Some(until_step) = until_step {
+            println!(
+                "⏸️ {}",
+                tr!(
+                    "Release has been stopped after `{step}` step",
+                    step = until_step
+                )
+            );
+            return Ok(());
+        }
+
+        if let Some(PendingVersion(version)) = context.resources().try_get() {
+            println!(
+                "🚀 {}",
+                tr!(
+                    "Workspace version {version} has been released!",
+                    version = version
+                )
+            );
+        }
 
         Ok(())
     }
 }
 
+/// Config of the released crate group, the group is required if the workspace
+/// is split into groups
+fn group_config(config: Config, group: Option<&str>) -> anyhow::Result<Config> {
+    match group {
+        Some(group) => config.for_group(group),
+        None if !config.group.is_empty() => {
+            let available = config.group.keys().cloned().collect::<Vec<_>>().join(", ");
+            bail!(
+                "Workspace is split into release groups, please specify one with --group ({})",
+                available
+            );
+        }
+        None => Ok(config),
+    }
+}
+
 impl Command {
     pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        if let Some(group) = &self.options.group {
+            println!(
+                "📂 {}",
+                tr!("Releasing `{group}` crate group", group = group)
+            );
+        }
+        let config = group_config(config, self.options.group.as_deref())?;
+
+        let audit_log = config.release.as_ref().and_then(|r| r.audit_log.as_ref());
+        if let Some(audit_log) = audit_log {
+            audit::init(&workspace_path(audit_log), !self.confirm)?;
+        }
+
+        let bump_dependents = config
+            .release
+            .as_ref()
+            .map(|r| r.bump_dependents)
+            .unwrap_or_default();
+        let mut config = config;
+        // Release set is looked up in the registry and the completed release
+        // in the fetched remote, both are skipped offline
+        let offline = self.options.offline;
+        if bump_dependents && offline {
+            println!(
+                "🔌 {}",
+                tr!("Crates with unpublished versions are not looked up offline")
+            );
+        } else if bump_dependents {
+            dependents::prepare_release_set(&mut config).await?;
+        }
+
+        let dedupe = config
+            .release
+            .as_ref()
+            .and_then(|r| r.dedupe.as_ref())
+            .filter(|_| !offline);
+        if let (Some(dedupe), ReleaseChannel::Stable) = (dedupe, self.options.channel) {
+            let completed =
+                dedupe::find_completed_release(dedupe, &config.workspace.root_crate).await?;
+            if let Some((commit, version)) = completed {
+                if !self.force {
+                    println!(
+                        "⏭️ {} v{} has already been released from commit {}, nothing to do \
+                        (use --force to release it again)",
+                        config.workspace.root_crate, version, commit
+                    );
+                    return Ok(());
+                }
+                println!(
+                    "⚠️ {} v{} has already been released from commit {}, releasing again",
+                    config.workspace.root_crate, version, commit
+                );
+            }
+        }
+
+        let options = &self.options;
+        let mut executor = options.executor(config, !self.confirm, self.until_step)?;
+        if options.channel == ReleaseChannel::Nightly {
+            println!(
+                "🌙 {}",
+                tr!("Running nightly release, crates will not be published")
+            );
+        }
         if self.confirm {
-            println!("📦 Running release in production mode!");
+            println!("📦 {}", tr!("Running release in production mode!"));
         } else {
-            println!("🤖 Running release in dry-run mode!");
+            println!("🤖 {}", tr!("Running release in dry-run mode!"));
+        }
+        if let Some(line) = &executor.context.maintenance_line {
+            println!(
+                "🩹 {}",
+                tr!(
+                    "Releasing maintenance version from {line} line",
+                    line = line
+                )
+            );
+        }
+        if options.hotfix {
+            println!(
+                "🚑 {}",
+                tr!("Running hotfix release, changelog requirements are relaxed")
+            );
+        }
+        if options.github_only {
+            println!(
+                "🐙 {}",
+                tr!("Running GitHub-only release, crates will not be published")
+            );
+        }
+        if options.publish_only {
+            println!(
+                "📦 {}",
+                tr!("Running publish-only release, forge steps will be skipped")
+            );
+        }
+        if options.offline {
+            println!(
+                "🔌 {}",
+                tr!("Running offline, tokens are not used and network checks are skipped")
+            );
+        }
+        if self.resume {
+            println!(
+                "⏯️ {}",
+                tr!("Resuming release, already published crates will be skipped")
+            );
+            executor = executor.with_resume();
+        }
+        if self.open {
+            executor = executor.with_open_release();
         }
-
-        let executor = ReleaseExecutor::new(config, !self.confirm, self.nopublish);
         executor.execute().await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release::resource::CurrentCommit;
+    use semver::Version;
+
+    /// Provides what the real init step does, without querying the workspace
+    struct TestInit;
+
+    #[async_trait]
+    impl ReleaseStep for TestInit {
+        fn name(&self) -> &'static str {
+            "init"
+        }
+
+        fn kind(&self) -> StepKind {
+            StepKind::Validation
+        }
+
+        fn provides(&self) -> Vec<ResourceId> {
+            vec![
+                ResourceId::of::<Metadata>(),
+                ResourceId::of::<PendingVersion>(),
+                ResourceId::of::<CurrentCommit>(),
+            ]
+        }
+
+        fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+
+        fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+
+        async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+            let metadata = serde_json::from_str(
+                r#"{"packages":[],"workspace_members":[],"resolve":null,
+                "workspace_root":"/ws","target_directory":"/ws/target","version":1}"#,
+            )?;
+            ctx.provide::<Metadata>(metadata)?;
+            ctx.provide(PendingVersion(Version::new(1, 2, 0)))?;
+            ctx.provide(CurrentCommit("1a2b3c".to_owned()))
+        }
+    }
+
+    struct TestPublish;
+
+    #[async_trait]
+    impl ReleaseStep for TestPublish {
+        fn name(&self) -> &'static str {
+            "publish"
+        }
+
+        fn kind(&self) -> StepKind {
+            StepKind::Release
+        }
+
+        fn requires(&self) -> Vec<ResourceId> {
+            vec![ResourceId::of::<PendingVersion>()]
+        }
+
+        fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+
+        fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+
+        async fn execute(&self, _: &ReleaseContext) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn confirmed_plan_records_state() {
+        let state_file = std::env::temp_dir().join(format!(
+            "monorepo-release-state-{}.json",
+            std::process::id()
+        ));
+        let config: Config = toml::from_str(&format!(
+            "[workspace]\nroot_crate = \"app\"\n[release]\nstate_file = {:?}\n",
+            state_file
+        ))
+        .unwrap();
+        let mut context = ReleaseContext::new(config, false, false);
+        let plan = ReleasePlan::new(vec![Box::new(TestPublish), Box::new(TestInit)]).unwrap();
+        context.init_resources(plan.provided_resources());
+
+        // Paused release keeps the state for `--resume`
+        ReleaseExecutor::execute_plan(&context, plan, true, &Mutex::new(vec![]))
+            .await
+            .unwrap();
+        let state = ReleaseState::read(&state_file).unwrap().unwrap();
+        std::fs::remove_file(&state_file).unwrap();
+        assert_eq!(state.version, Version::new(1, 2, 0));
+        assert_eq!(state.completed_steps, vec!["publish".to_owned()]);
+    }
+}
@@ -0,0 +1,345 @@
+use super::{ReleaseStep, ResourceId, StepKind};
+use anyhow::{anyhow, bail};
+use std::collections::{HashMap, HashSet};
+
+/// Release steps ordered by their declared dependencies.
+///
+/// Plan consists of waves: steps in the same wave do not depend on each other
+/// and can be executed concurrently, every wave depends only on the previous
+/// ones. Validation steps always precede release steps, and each release step
+/// forms its own wave to keep remote side effects strictly sequential.
+pub struct ReleasePlan {
+    waves: Vec<Vec<Box<dyn ReleaseStep>>>,
+}
+
+impl ReleasePlan {
+    pub fn new(steps: Vec<Box<dyn ReleaseStep>>) -> anyhow::Result<Self> {
+        let mut names = HashSet::new();
+        let mut producers = HashMap::new();
+        for step in &steps {
+            if !names.insert(step.name()) {
+                bail!("BUG: step `{}` is added to the plan twice", step.name());
+            }
+            for resource in step.provides() {
+                if let Some(other) = producers.insert(resource, step.name()) {
+                    bail!(
+                        "BUG: {} is provided by both `{}` and `{}` steps",
+                        resource,
+                        other,
+                        step.name()
+                    );
+                }
+            }
+        }
+
+        for step in &steps {
+            for resource in step.requires() {
+                let producer = producers.get(&resource).ok_or_else(|| {
+                    anyhow!(
+                        "Step `{}` requires {} which is not provided by any step in the release plan",
+                        step.name(),
+                        resource
+                    )
+                })?;
+                let producer_kind = steps
+                    .iter()
+                    .find(|s| s.name() == *producer)
+                    .map(|s| s.kind());
+                if step.kind() == StepKind::Validation && producer_kind == Some(StepKind::Release) {
+                    bail!(
+                        "Validation step `{}` can't depend on {} provided by release step `{}`",
+                        step.name(),
+                        resource,
+                        producer
+                    );
+                }
+            }
+        }
+
+        let (validation, release): (Vec<_>, Vec<_>) = steps
+            .into_iter()
+            .partition(|s| s.kind() == StepKind::Validation);
+
+        let mut provided = HashSet::new();
+        let mut waves = vec![];
+
+        let mut pending = validation;
+        while !pending.is_empty() {
+            let (ready, blocked): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .partition(|s| is_ready(s.as_ref(), &provided));
+            if ready.is_empty() {
+                bail!(
+                    "Circular dependency between steps: {}",
+                    step_names(&blocked)
+                );
+            }
+            provided.extend(ready.iter().flat_map(|s| s.provides()));
+            waves.push(ready);
+            pending = blocked;
+        }
+
+        let mut pending = release;
+        while !pending.is_empty() {
+            let position = pending
+                .iter()
+                .position(|s| is_ready(s.as_ref(), &provided))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Circular dependency between steps: {}",
+                        step_names(&pending)
+                    )
+                })?;
+            let step = pending.remove(position);
+            provided.extend(step.provides());
+            waves.push(vec![step]);
+        }
+
+        Ok(Self { waves })
+    }
+
+    pub fn waves(&self) -> &[Vec<Box<dyn ReleaseStep>>] {
+        &self.waves
+    }
+
+    pub fn provided_resources(&self) -> Vec<ResourceId> {
+        self.waves
+            .iter()
+            .flatten()
+            .flat_map(|s| s.provides())
+            .collect()
+    }
+
+    /// Removes everything which would be executed after the given step
+    pub fn truncate_after(&mut self, step_name: &str) -> anyhow::Result<()> {
+        let wave_index = self
+            .waves
+            .iter()
+            .position(|w| w.iter().any(|s| s.name() == step_name))
+            .ok_or_else(|| {
+                let available = self
+                    .waves
+                    .iter()
+                    .flatten()
+                    .map(|s| s.name())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                anyhow!(
+                    "Step `{}` is not part of the release plan, available steps: {}",
+                    step_name,
+                    available
+                )
+            })?;
+
+        self.waves.truncate(wave_index + 1);
+        self.waves[wave_index].retain(|s| s.name() == step_name);
+
+        Ok(())
+    }
+}
+
+fn is_ready(step: &dyn ReleaseStep, provided: &HashSet<ResourceId>) -> bool {
+    step.requires().iter().all(|r| provided.contains(r))
+}
+
+fn step_names(steps: &[Box<dyn ReleaseStep>]) -> String {
+    steps
+        .iter()
+        .map(|s| format!("`{}`", s.name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release::{
+        resource::{Artifacts, Changelog, GithubTag, PendingVersion},
+        ReleaseContext,
+    };
+    use async_trait::async_trait;
+    use cargo_metadata::Metadata;
+    use expect_test::expect;
+
+    struct TestStep {
+        name: &'static str,
+        kind: StepKind,
+        requires: Vec<ResourceId>,
+        provides: Vec<ResourceId>,
+    }
+
+    #[async_trait]
+    impl ReleaseStep for TestStep {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn kind(&self) -> StepKind {
+            self.kind
+        }
+
+        fn requires(&self) -> Vec<ResourceId> {
+            self.requires.clone()
+        }
+
+        fn provides(&self) -> Vec<ResourceId> {
+            self.provides.clone()
+        }
+
+        fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+
+        fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+
+        async fn execute(&self, _: &ReleaseContext) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn step(
+        name: &'static str,
+        kind: StepKind,
+        requires: &[ResourceId],
+        provides: &[ResourceId],
+    ) -> Box<dyn ReleaseStep> {
+        Box::new(TestStep {
+            name,
+            kind,
+            requires: requires.to_vec(),
+            provides: provides.to_vec(),
+        })
+    }
+
+    fn plan_layout(plan: &ReleasePlan) -> Vec<Vec<&'static str>> {
+        plan.waves()
+            .iter()
+            .map(|w| w.iter().map(|s| s.name()).collect())
+            .collect()
+    }
+
+    fn sample_steps() -> Vec<Box<dyn ReleaseStep>> {
+        use StepKind::*;
+
+        let metadata = ResourceId::of::<Metadata>();
+        let version = ResourceId::of::<PendingVersion>();
+        let tag = ResourceId::of::<GithubTag>();
+        let changelog = ResourceId::of::<Changelog>();
+        let artifacts = ResourceId::of::<Artifacts>();
+
+        vec![
+            step("publish", Release, &[metadata], &[]),
+            step("release-page", Release, &[tag], &[]),
+            step("tag", Release, &[version], &[tag]),
+            step("changelog", Validation, &[version], &[changelog]),
+            step("artifacts", Validation, &[], &[artifacts]),
+            step("init", Validation, &[], &[metadata, version]),
+            step("validate-version", Validation, &[metadata, version], &[]),
+        ]
+    }
+
+    #[test]
+    fn independent_steps_share_wave() {
+        let plan = ReleasePlan::new(sample_steps()).unwrap();
+
+        expect![[r#"
+            [
+                [
+                    "artifacts",
+                    "init",
+                ],
+                [
+                    "changelog",
+                    "validate-version",
+                ],
+                [
+                    "publish",
+                ],
+                [
+                    "tag",
+                ],
+                [
+                    "release-page",
+                ],
+            ]
+        "#]]
+        .assert_debug_eq(&plan_layout(&plan));
+    }
+
+    #[test]
+    fn truncate_plan() {
+        let mut plan = ReleasePlan::new(sample_steps()).unwrap();
+        plan.truncate_after("changelog").unwrap();
+
+        expect![[r#"
+            [
+                [
+                    "artifacts",
+                    "init",
+                ],
+                [
+                    "changelog",
+                ],
+            ]
+        "#]]
+        .assert_debug_eq(&plan_layout(&plan));
+
+        let result = plan.truncate_after("tag").map_err(|e| e.to_string());
+        expect![[r#"
+            Err(
+                "Step `tag` is not part of the release plan, available steps: artifacts, init, changelog",
+            )
+        "#]]
+        .assert_debug_eq(&result);
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let steps = vec![step(
+            "release-page",
+            StepKind::Release,
+            &[ResourceId::of::<GithubTag>()],
+            &[],
+        )];
+        let result = ReleasePlan::new(steps)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+
+        expect![[r#"
+            Err(
+                "Step `release-page` requires GitHub tag which is not provided by any step in the release plan",
+            )
+        "#]]
+        .assert_debug_eq(&result);
+    }
+
+    #[test]
+    fn validation_depends_on_release() {
+        let steps = vec![
+            step(
+                "tag",
+                StepKind::Release,
+                &[],
+                &[ResourceId::of::<GithubTag>()],
+            ),
+            step(
+                "check",
+                StepKind::Validation,
+                &[ResourceId::of::<GithubTag>()],
+                &[],
+            ),
+        ];
+        let result = ReleasePlan::new(steps)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+
+        expect![[r#"
+            Err(
+                "Validation step `check` can't depend on GitHub tag provided by release step `tag`",
+            )
+        "#]]
+        .assert_debug_eq(&result);
+    }
+}
@@ -0,0 +1,224 @@
+//! Read-only preview of the release: steps, version, publish order and names
+//! of the created tag and release page, resolved the same way the release does
+use super::{
+    channel::nightly_version,
+    export::{export_waves, StepExport},
+    group_config,
+    resource::{CreatedRelease, GithubTag, PendingVersion},
+    step, ReleaseOptions, ResourceId,
+};
+use crate::{config::Config, utils::metadata_command};
+use anyhow::anyhow;
+use serde::Serialize;
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Show release plan without executing anything")]
+pub struct PlanCommand {
+    /// Print the plan as JSON
+    #[structopt(long)]
+    json: bool,
+    #[command(flatten)]
+    options: ReleaseOptions,
+}
+
+#[derive(Serialize)]
+struct PlanExport {
+    root_crate: String,
+    version: String,
+    /// Tag created (or verified) by the release, `null` if there is none
+    tag: Option<String>,
+    /// Title of the GitHub release page, `null` if it is not created
+    release_title: Option<String>,
+    /// Released crates in publish order, empty if crates are not published
+    publish_order: Vec<PublishExport>,
+    /// Steps in the same wave are executed concurrently
+    steps: Vec<Vec<StepExport>>,
+}
+
+#[derive(Serialize)]
+struct PublishExport {
+    name: String,
+    version: String,
+    /// Registry the crate is published to, `null` for crates.io
+    registry: Option<String>,
+}
+
+fn render_plan(plan: &PlanExport) -> String {
+    let mut lines = vec![format!(
+        "Release plan of {} v{}",
+        plan.root_crate, plan.version
+    )];
+    if let Some(tag) = &plan.tag {
+        lines.push(format!("Tag: {}", tag));
+    }
+    if let Some(title) = &plan.release_title {
+        lines.push(format!("GitHub release: {}", title));
+    }
+    if plan.publish_order.is_empty() {
+        lines.push("Crates are not published".to_owned());
+    } else {
+        lines.push("Publish order:".to_owned());
+        for (i, c) in plan.publish_order.iter().enumerate() {
+            let registry = c.registry.as_deref().unwrap_or("crates.io");
+            lines.push(format!(
+                "\t{}. {} v{} ({})",
+                i + 1,
+                c.name,
+                c.version,
+                registry
+            ));
+        }
+    }
+    lines.push("Steps:".to_owned());
+    for (i, wave) in plan.steps.iter().enumerate() {
+        let names = wave.iter().map(|s| s.name).collect::<Vec<_>>().join(", ");
+        let kind = wave.first().map(|s| s.kind).unwrap_or_default();
+        lines.push(format!("\t{}. {} ({})", i + 1, names, kind));
+    }
+    lines.join("\n")
+}
+
+impl PlanCommand {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        let config = group_config(config, self.options.group.as_deref())?;
+        let release = config
+            .release
+            .clone()
+            .ok_or_else(|| anyhow!("release section is missing from the config"))?;
+
+        // Steps are built as for the confirmed release, dry run omits publishing
+        let executor = self.options.executor(config, false, None)?;
+        let (mut context, plan) = executor.into_plan()?;
+
+        let metadata = metadata_command()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let root_crate = context.root_crate_name();
+        let manifest_version = metadata
+            .packages
+            .iter()
+            .find(|p| p.name == root_crate && metadata.workspace_members.contains(&p.id))
+            .map(|p| p.version.clone())
+            .ok_or_else(|| anyhow!("Root crate `{}` is not found in workspace", root_crate))?;
+        let (mut version, _) = step::resolve_version(&release, &manifest_version).await?;
+        if context.is_nightly() {
+            let commit = step::get_current_commit().await?;
+            version = nightly_version(&version, chrono::Utc::now().date_naive(), &commit);
+        }
+
+        // Resources are filled the same way as by the init step, so names are
+        // rendered by the release code itself
+        let provided = plan.provided_resources();
+        context.init_resources(provided.iter().copied());
+        context.provide(metadata)?;
+        context.provide(PendingVersion(version.clone()))?;
+        let template_context = context.text_template_context();
+
+        let steps = plan.waves().iter().flatten().collect::<Vec<_>>();
+        let publish = steps.iter().any(|s| s.name() == "publish");
+        let nightly = steps.iter().any(|s| s.name() == "publish-nightly-release");
+        let (tag, release_title) = match (&release.nightly, &release.github) {
+            (Some(nightly_config), _) if nightly => (
+                Some(nightly_config.tag.clone()),
+                Some(nightly_config.title_template.render(&template_context)?),
+            ),
+            (_, Some(github)) if !nightly => {
+                let tag = provided
+                    .contains(&ResourceId::of::<GithubTag>())
+                    .then(|| github.tag_name_template.render(&template_context))
+                    .transpose()?;
+                let title = provided
+                    .contains(&ResourceId::of::<CreatedRelease>())
+                    .then(|| github.release_page_title_template.render(&template_context))
+                    .transpose()?;
+                (tag, title)
+            }
+            _ => (None, None),
+        };
+
+        let mut publish_order = vec![];
+        if publish {
+            for p in context.ordered_packages_to_publish()? {
+                publish_order.push(PublishExport {
+                    name: p.name.clone(),
+                    version: p.version.to_string(),
+                    registry: context.crate_registry(&p.name),
+                });
+            }
+        }
+
+        let export = PlanExport {
+            root_crate,
+            version: version.to_string(),
+            tag,
+            release_title,
+            publish_order,
+            steps: export_waves(&plan),
+        };
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&export)?);
+        } else {
+            println!("{}", render_plan(&export));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn rendered_plan() {
+        let plan = PlanExport {
+            root_crate: "app".to_owned(),
+            version: "1.2.0".to_owned(),
+            tag: Some("v1.2.0".to_owned()),
+            release_title: None,
+            publish_order: vec![
+                PublishExport {
+                    name: "app-core".to_owned(),
+                    version: "1.2.0".to_owned(),
+                    registry: Some("internal".to_owned()),
+                },
+                PublishExport {
+                    name: "app".to_owned(),
+                    version: "1.2.0".to_owned(),
+                    registry: None,
+                },
+            ],
+            steps: vec![
+                vec![StepExport {
+                    name: "init",
+                    kind: "validation",
+                }],
+                vec![
+                    StepExport {
+                        name: "validate-version",
+                        kind: "validation",
+                    },
+                    StepExport {
+                        name: "validate-publish",
+                        kind: "validation",
+                    },
+                ],
+                vec![StepExport {
+                    name: "publish",
+                    kind: "release",
+                }],
+            ],
+        };
+        expect![[r#"
+            Release plan of app v1.2.0
+            Tag: v1.2.0
+            Publish order:
+            	1. app-core v1.2.0 (internal)
+            	2. app v1.2.0 (crates.io)
+            Steps:
+            	1. init (validation)
+            	2. validate-version, validate-publish (validation)
+            	3. publish (release)"#]]
+        .assert_eq(&render_plan(&plan));
+    }
+}
@@ -0,0 +1,199 @@
+//! Promotion of the release candidate to the final version: the rc commit is
+//! released again with the final version in manifests and the rc artifacts,
+//! so nothing is rebuilt between rc and GA
+use super::{dependents::replace_workspace_version, ReleaseExecutor};
+use crate::{
+    audit,
+    config::Config,
+    github::GITHUB_TOKEN_VAR,
+    utils::{metadata_command, run_and_capture_stdout, workspace_command, workspace_path},
+};
+use anyhow::{anyhow, bail, Context};
+use octocrab::Octocrab;
+use semver::{BuildMetadata, Prerelease, Version};
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Release the final version from the release candidate commit and artifacts")]
+pub struct PromoteCommand {
+    /// Tag of the release candidate, e.g. `v2.0.0-rc.3`
+    #[structopt(long)]
+    from: String,
+    /// Actually execute command instead of dry run
+    #[structopt(long)]
+    confirm: bool,
+    /// Do not publish packages to the registry
+    #[structopt(long)]
+    nopublish: bool,
+}
+
+fn final_version(rc: &Version) -> anyhow::Result<Version> {
+    if rc.pre.is_empty() {
+        bail!("{} is not a prerelease version", rc);
+    }
+    Ok(Version {
+        pre: Prerelease::EMPTY,
+        build: BuildMetadata::EMPTY,
+        ..rc.clone()
+    })
+}
+
+/// Rewritten manifests are restored when dropped, so the working tree is
+/// clean after the promotion
+struct PromotedManifests(Vec<(PathBuf, String)>);
+
+impl Drop for PromotedManifests {
+    fn drop(&mut self) {
+        for (path, content) in &self.0 {
+            if let Err(e) = std::fs::write(path, content) {
+                println!("WARN: failed to restore {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+async fn rev_parse(rev: &str) -> anyhow::Result<String> {
+    let mut cmd = workspace_command("git");
+    cmd.args(["rev-parse", "--verify", rev]);
+    Ok(run_and_capture_stdout(&mut cmd).await?.trim().to_owned())
+}
+
+async fn download_rc_assets(
+    config: &Config,
+    tag: &str,
+    rc: &Version,
+    version: &Version,
+    dir: &Path,
+) -> anyhow::Result<()> {
+    let repo = &config
+        .github
+        .as_ref()
+        .ok_or_else(|| anyhow!("github section is missing from the config"))?
+        .repo;
+    let github = match std::env::var(GITHUB_TOKEN_VAR) {
+        Ok(token) => Octocrab::builder().personal_token(token).build()?,
+        Err(_) => Octocrab::builder().build()?,
+    };
+
+    audit::api_call(
+        "GET",
+        &format!("repos/{}/{}/releases/tags/{}", repo.owner, repo.name, tag),
+    );
+    let release = github
+        .repos(&repo.owner, &repo.name)
+        .releases()
+        .get_by_tag(tag)
+        .await
+        .with_context(|| format!("Failed to get `{}` release", tag))?;
+
+    tokio::fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    for asset in &release.assets {
+        // Artifact names follow the released version
+        let name = asset.name.replace(&rc.to_string(), &version.to_string());
+        let path = dir.join(&name);
+        audit::api_call("GET", asset.url.as_str());
+        let data = github
+            .request_builder(asset.url.as_str(), reqwest::Method::GET)
+            .header(reqwest::header::ACCEPT, "application/octet-stream")
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to download {}", asset.name))?
+            .bytes()
+            .await?;
+        tokio::fs::write(&path, &data)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("\t⬇️ {} -> {}", asset.name, path.display());
+    }
+
+    Ok(())
+}
+
+impl PromoteCommand {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        let audit_log = config.release.as_ref().and_then(|r| r.audit_log.as_ref());
+        if let Some(audit_log) = audit_log {
+            audit::init(&workspace_path(audit_log), !self.confirm)?;
+        }
+
+        let rc_commit = rev_parse(&format!("{}^{{commit}}", self.from))
+            .await
+            .with_context(|| format!("Release candidate tag `{}` is not found", self.from))?;
+        if rev_parse("HEAD").await? != rc_commit {
+            bail!(
+                "HEAD should be at the release candidate commit, run `git checkout {}` first",
+                self.from
+            );
+        }
+
+        let metadata = metadata_command()
+            .no_deps()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let root_crate = &config.workspace.root_crate;
+        let rc = metadata
+            .packages
+            .iter()
+            .find(|p| &p.name == root_crate && metadata.workspace_members.contains(&p.id))
+            .map(|p| p.version.clone())
+            .ok_or_else(|| anyhow!("Root crate `{}` is not found in workspace", root_crate))?;
+        let version = final_version(&rc)?;
+        println!("🎓 Promoting {} v{} to v{}", root_crate, rc, version);
+
+        if let Some(artifacts) = &config.artifacts {
+            if config.release.as_ref().is_some_and(|r| r.github.is_some()) {
+                let dir = workspace_path(&artifacts.directory);
+                download_rc_assets(&config, &self.from, &rc, &version, &dir).await?;
+            }
+        }
+
+        let _manifests =
+            PromotedManifests(replace_workspace_version(&metadata, &rc, &version).await?);
+
+        if self.confirm {
+            println!("📦 Running release in production mode!");
+        } else {
+            println!("🤖 Running release in dry-run mode!");
+        }
+        ReleaseExecutor::new(config, !self.confirm, self.nopublish, None)
+            .with_promotion(self.from)
+            .execute()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn rc_final_version() {
+        let versions = vec!["2.0.0-rc.3", "1.4.0-beta.1+build.5", "1.4.0"]
+            .into_iter()
+            .map(|v| {
+                final_version(&Version::parse(v).unwrap())
+                    .map(|v| v.to_string())
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                Ok(
+                    "2.0.0",
+                ),
+                Ok(
+                    "1.4.0",
+                ),
+                Err(
+                    "1.4.0 is not a prerelease version",
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&versions);
+    }
+}
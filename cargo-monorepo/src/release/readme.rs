@@ -0,0 +1,170 @@
+//! crates.io renders README of the package, but resolves neither relative
+//! links nor relative images; they can be rewritten to absolute GitHub URLs
+//! of the released commit before publish
+use crate::github::Repo;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// Link target found in the markdown; `start..end` is its byte range
+#[derive(Debug, PartialEq)]
+pub struct Link<'a> {
+    pub target: &'a str,
+    pub image: bool,
+    start: usize,
+    end: usize,
+}
+
+impl Link<'_> {
+    pub fn is_relative(&self) -> bool {
+        let target = self.target;
+        !(target.is_empty()
+            || target.starts_with('#')
+            || target.starts_with("//")
+            || target.contains("://")
+            || target.starts_with("mailto:")
+            || target.starts_with("data:"))
+    }
+}
+
+/// Collects targets of inline links/images (`[text](target)`) and html images
+/// (`<img src="target">`)
+pub fn links(markdown: &str) -> Vec<Link<'_>> {
+    let mut links = vec![];
+
+    let mut offset = 0;
+    while let Some(pos) = markdown[offset..].find("](") {
+        let open = offset + pos;
+        let start = open + 2;
+        let end = markdown[start..]
+            .find(|c: char| c == ')' || c.is_whitespace())
+            .map_or(markdown.len(), |len| start + len);
+        let image = markdown[..open]
+            .rfind('[')
+            .is_some_and(|bracket| markdown[..bracket].ends_with('!'));
+        links.push(Link {
+            target: &markdown[start..end],
+            image,
+            start,
+            end,
+        });
+        offset = end;
+    }
+
+    let mut offset = 0;
+    while let Some(pos) = markdown[offset..].find("src=\"") {
+        let start = offset + pos + 5;
+        let end = markdown[start..]
+            .find('"')
+            .map_or(markdown.len(), |len| start + len);
+        links.push(Link {
+            target: &markdown[start..end],
+            image: true,
+            start,
+            end,
+        });
+        offset = end;
+    }
+
+    links.sort_by_key(|l| l.start);
+    links
+}
+
+/// Base URLs of the repo files at the given commit
+pub struct RepoUrls {
+    /// Rendered pages, used for links
+    pub blob: String,
+    /// Raw content, used for images
+    pub raw: String,
+}
+
+impl RepoUrls {
+    /// `dir` is the README directory relative to the repo root
+    pub fn new(repo: &Repo, commit: &str, dir: &str) -> Self {
+        let dir = dir.trim_matches('/');
+        let dir = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", dir)
+        };
+        RepoUrls {
+            blob: format!(
+                "https://github.com/{}/{}/blob/{}/{}",
+                repo.owner, repo.name, commit, dir
+            ),
+            raw: format!(
+                "https://raw.githubusercontent.com/{}/{}/{}/{}",
+                repo.owner, repo.name, commit, dir
+            ),
+        }
+    }
+}
+
+/// Replaces relative link targets with absolute repo URLs
+pub fn rewrite_relative_links(markdown: &str, urls: &RepoUrls) -> anyhow::Result<String> {
+    let mut rewritten = String::with_capacity(markdown.len());
+    let mut offset = 0;
+    for link in links(markdown).into_iter().filter(Link::is_relative) {
+        let base = if link.image { &urls.raw } else { &urls.blob };
+        let absolute = url::Url::parse(base)
+            .and_then(|base| base.join(link.target))
+            .with_context(|| format!("Failed to rewrite `{}` link", link.target))?;
+        rewritten.push_str(&markdown[offset..link.start]);
+        rewritten.push_str(absolute.as_str());
+        offset = link.end;
+    }
+    rewritten.push_str(&markdown[offset..]);
+    Ok(rewritten)
+}
+
+/// Restores original README content when dropped
+pub struct RewrittenReadme {
+    path: PathBuf,
+    original: String,
+}
+
+impl RewrittenReadme {
+    pub fn rewrite(path: &Path, urls: &RepoUrls) -> anyhow::Result<Self> {
+        let original = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let rewritten = rewrite_relative_links(&original, urls)?;
+        std::fs::write(path, rewritten)
+            .with_context(|| format!("Failed to rewrite {}", path.display()))?;
+        Ok(RewrittenReadme {
+            path: path.to_owned(),
+            original,
+        })
+    }
+}
+
+impl Drop for RewrittenReadme {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::write(&self.path, &self.original) {
+            println!("\tWARN: failed to restore {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn relative_links_rewrite() {
+        let readme = r#"# foo
+![logo](../../assets/logo.svg) [docs](docs/guide.md#setup)
+[site](https://example.com) [top](#foo) <img src="img/arch.png" width="300">
+"#;
+        let repo = Repo {
+            owner: "owner".to_owned(),
+            name: "repo".to_owned(),
+        };
+        let urls = RepoUrls::new(&repo, "a1b2c3d", "crates/foo");
+
+        expect![[r##"
+            # foo
+            ![logo](https://raw.githubusercontent.com/owner/repo/a1b2c3d/assets/logo.svg) [docs](https://github.com/owner/repo/blob/a1b2c3d/crates/foo/docs/guide.md#setup)
+            [site](https://example.com) [top](#foo) <img src="https://raw.githubusercontent.com/owner/repo/a1b2c3d/crates/foo/img/arch.png" width="300">
+        "##]].assert_eq(&rewrite_relative_links(readme, &urls).unwrap());
+    }
+}
@@ -0,0 +1,88 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Machine-readable summary of the release run
+#[derive(Serialize)]
+pub struct ReleaseReport {
+    pub root_crate: String,
+    pub version: Option<Version>,
+    pub prev_version: Option<Version>,
+    pub commit: Option<String>,
+    /// Page of the created GitHub release
+    pub release_url: Option<String>,
+    pub dry_run: bool,
+    pub hotfix: bool,
+    /// Validation steps bypassed with `--force-skip-validation`
+    pub forced_skips: Vec<String>,
+    pub success: bool,
+    pub reproducible_build: Option<ReproducibilityReport>,
+    pub published_crates: Vec<PublishedCrate>,
+    pub assets: Vec<AssetDigest>,
+    pub attestations: Vec<AssetAttestation>,
+    /// Resolved dependency graph from Cargo.lock
+    pub dependencies: Vec<LockedPackage>,
+    /// Actions skipped in dry run mode
+    pub planned_actions: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PublishedCrate {
+    pub name: String,
+    pub version: Version,
+    pub registry: Option<String>,
+    pub published_at: DateTime<Utc>,
+    pub checksum: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AssetDigest {
+    pub name: String,
+    pub sha256: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct AssetAttestation {
+    pub asset: String,
+    pub predicate_type: String,
+    pub url: String,
+}
+
+/// Package entry of Cargo.lock
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub source: Option<String>,
+    pub checksum: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ReproducibilityReport {
+    pub verified: bool,
+    pub artifacts: Vec<ArtifactDigests>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ArtifactDigests {
+    pub name: String,
+    pub sha256: String,
+    pub rebuilt_sha256: Option<String>,
+}
+
+impl ReleaseReport {
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        serde_json::to_string_pretty(self).with_context(|| "Failed to serialize release report")
+    }
+
+    pub async fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let json = self.to_json()?;
+        tokio::fs::write(path, json)
+            .await
+            .with_context(|| format!("Failed to write release report to {}", path.display()))
+    }
+}
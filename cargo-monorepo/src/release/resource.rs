@@ -0,0 +1,309 @@
+use super::report::{AssetAttestation, LockedPackage, ReproducibilityReport};
+use crate::{
+    github::{CreatedReleasePage, ReleaseAsset},
+    registry::RegistryCrate,
+};
+use anyhow::anyhow;
+use cargo_metadata::Metadata;
+use octocrab::Octocrab;
+use semver::Version;
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+/// Typed value which is produced by one release step and consumed by others
+pub trait Resource: Send + Sync + 'static {
+    /// Human-readable name used in release plan errors
+    const NAME: &'static str;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceId {
+    type_id: TypeId,
+    name: &'static str,
+}
+
+impl ResourceId {
+    pub fn of<R: Resource>() -> Self {
+        Self {
+            type_id: TypeId::of::<R>(),
+            name: R::NAME,
+        }
+    }
+}
+
+impl PartialEq for ResourceId {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_id == other.type_id
+    }
+}
+
+impl Eq for ResourceId {}
+
+impl std::hash::Hash for ResourceId {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.type_id.hash(state);
+    }
+}
+
+impl Display for ResourceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+/// Set-once storage for the resources provided by the release plan steps.
+///
+/// Slots are allocated upfront from the validated release plan, so the storage
+/// itself is never modified during execution and can be shared between
+/// concurrently running steps.
+#[derive(Default)]
+pub struct Resources {
+    slots: HashMap<TypeId, OnceLock<Box<dyn Any + Send + Sync>>>,
+}
+
+impl Resources {
+    pub fn new(provided: impl IntoIterator<Item = ResourceId>) -> Self {
+        let slots = provided
+            .into_iter()
+            .map(|id| (id.type_id, OnceLock::new()))
+            .collect();
+        Self { slots }
+    }
+
+    pub fn try_get<R: Resource>(&self) -> Option<&R> {
+        self.slots
+            .get(&TypeId::of::<R>())
+            .and_then(|slot| slot.get())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Returns resource which is guaranteed to be provided by the release plan
+    /// validation, as long as the calling step declares it as a requirement.
+    /// Panics otherwise, so the code outside of the steps should use `try_get`
+    pub fn get<R: Resource>(&self) -> &R {
+        self.try_get().unwrap_or_else(|| {
+            panic!(
+                "BUG: {} is accessed by a step which does not require it",
+                R::NAME
+            )
+        })
+    }
+
+    pub fn is_provided(&self, id: &ResourceId) -> bool {
+        self.slots
+            .get(&id.type_id)
+            .and_then(|slot| slot.get())
+            .is_some()
+    }
+
+    pub fn provide<R: Resource>(&self, value: R) -> anyhow::Result<()> {
+        let slot = self.slots.get(&TypeId::of::<R>()).ok_or_else(|| {
+            anyhow!(
+                "BUG: {} is provided by a step which does not declare it",
+                R::NAME
+            )
+        })?;
+        slot.set(Box::new(value))
+            .map_err(|_| anyhow!("BUG: {} has already been provided", R::NAME))
+    }
+}
+
+pub struct CurrentCommit(pub String);
+
+impl Resource for CurrentCommit {
+    const NAME: &'static str = "current commit";
+}
+
+impl Resource for Metadata {
+    const NAME: &'static str = "cargo metadata";
+}
+
+impl Resource for Octocrab {
+    const NAME: &'static str = "GitHub client";
+}
+
+pub struct PendingVersion(pub Version);
+
+impl Resource for PendingVersion {
+    const NAME: &'static str = "pending version";
+}
+
+/// Previously released version of the root crate; `None` if the crate was
+/// never released or the check has been disabled
+pub struct PrevVersion(pub Option<Version>);
+
+impl Resource for PrevVersion {
+    const NAME: &'static str = "previous version";
+}
+
+/// Versions of the root crate published to the release registry; `None` if
+/// the registry has not been queried
+pub struct PublishedRootCrate(pub Option<RegistryCrate>);
+
+impl Resource for PublishedRootCrate {
+    const NAME: &'static str = "published root crate";
+}
+
+pub struct Changelog(pub String);
+
+impl Resource for Changelog {
+    const NAME: &'static str = "changelog";
+}
+
+/// Release notes of the published crates as `(crate, notes)`, crates without
+/// notes are omitted
+pub struct CrateNotes(pub Vec<(String, String)>);
+
+impl Resource for CrateNotes {
+    const NAME: &'static str = "crate release notes";
+}
+
+pub struct Artifacts(pub Vec<PathBuf>);
+
+impl Resource for Artifacts {
+    const NAME: &'static str = "artifacts";
+}
+
+pub struct SourceArtifacts(pub Vec<PathBuf>);
+
+impl Resource for SourceArtifacts {
+    const NAME: &'static str = "source artifacts";
+}
+
+/// Per-target archives with C headers and libraries
+pub struct CApiArtifacts(pub Vec<PathBuf>);
+
+impl Resource for CApiArtifacts {
+    const NAME: &'static str = "C API artifacts";
+}
+
+/// Rendered `[[generated-file]]` files attached to the release
+pub struct GeneratedArtifacts(pub Vec<PathBuf>);
+
+impl Resource for GeneratedArtifacts {
+    const NAME: &'static str = "generated files";
+}
+
+/// Binary patches from the previous release assets
+pub struct PatchArtifacts(pub Vec<PathBuf>);
+
+impl Resource for PatchArtifacts {
+    const NAME: &'static str = "delta patches";
+}
+
+/// Released crates changed since the previous release
+pub struct ChangedCrates(pub BTreeSet<String>);
+
+impl Resource for ChangedCrates {
+    const NAME: &'static str = "changed crates";
+}
+
+/// Crates which have been published to the registry, in publish order
+pub struct PublishedCrates(pub Vec<(String, Version)>);
+
+impl Resource for PublishedCrates {
+    const NAME: &'static str = "published crates";
+}
+
+/// Assets uploaded to the GitHub release
+pub struct UploadedAssets(pub Vec<ReleaseAsset>);
+
+impl Resource for UploadedAssets {
+    const NAME: &'static str = "uploaded assets";
+}
+
+/// GitHub release created by the release, `None` in dry run
+pub struct CreatedRelease(pub Option<CreatedReleasePage>);
+
+impl Resource for CreatedRelease {
+    const NAME: &'static str = "created GitHub release";
+}
+
+/// GitHub release page of the previous version
+pub struct PrevReleasePage {
+    pub html_url: String,
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// Previous release page, `None` if the previous version is unknown or was
+/// released without the page
+pub struct PrevRelease(pub Option<PrevReleasePage>);
+
+impl Resource for PrevRelease {
+    const NAME: &'static str = "previous GitHub release";
+}
+
+/// Attestations created for the uploaded assets
+pub struct Attestations(pub Vec<AssetAttestation>);
+
+impl Resource for Attestations {
+    const NAME: &'static str = "attestations";
+}
+
+/// Resolved dependencies of the release and their JSON snapshot file
+pub struct DependencySnapshot {
+    pub packages: Vec<LockedPackage>,
+    pub file: PathBuf,
+}
+
+impl Resource for DependencySnapshot {
+    const NAME: &'static str = "dependency snapshot";
+}
+
+/// Lock ref held by the release, not set in dry run
+pub struct HeldReleaseLock(pub Option<String>);
+
+impl Resource for HeldReleaseLock {
+    const NAME: &'static str = "release lock";
+}
+
+pub struct GithubTag(pub String);
+
+impl Resource for GithubTag {
+    const NAME: &'static str = "GitHub tag";
+}
+
+impl Resource for ReproducibilityReport {
+    const NAME: &'static str = "reproducible build report";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn provide_and_get() {
+        let resources = Resources::new([ResourceId::of::<GithubTag>()]);
+
+        assert!(resources.try_get::<GithubTag>().is_none());
+        resources.provide(GithubTag("v1.0.0".to_owned())).unwrap();
+        assert_eq!(resources.get::<GithubTag>().0, "v1.0.0");
+        assert!(resources.is_provided(&ResourceId::of::<GithubTag>()));
+
+        let result = resources
+            .provide(GithubTag("v2.0.0".to_owned()))
+            .map_err(|e| e.to_string());
+        expect![[r#"
+            Err(
+                "BUG: GitHub tag has already been provided",
+            )
+        "#]]
+        .assert_debug_eq(&result);
+
+        let result = resources
+            .provide(Changelog(String::new()))
+            .map_err(|e| e.to_string());
+        expect![[r#"
+            Err(
+                "BUG: changelog is provided by a step which does not declare it",
+            )
+        "#]]
+        .assert_debug_eq(&result);
+    }
+}
@@ -0,0 +1,211 @@
+//! Best-effort revert of the remote changes made by the failed release,
+//! enabled with `release.rollback_on_failure`
+use super::{
+    context::packages_to_publish,
+    journal::{journal_path, read_journal, JournalEntry},
+    resource::{CreatedRelease, GithubTag},
+    state::state_path,
+    ReleaseContext,
+};
+use crate::{
+    audit,
+    cargo::sort_workspace,
+    config::TagMethod,
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, bail, Context};
+use cargo_metadata::Metadata;
+use octocrab::Octocrab as GithubClient;
+use semver::Version;
+
+async fn delete_github_ref(ctx: &ReleaseContext, route: &str) -> anyhow::Result<()> {
+    let github = ctx
+        .resources()
+        .try_get::<GithubClient>()
+        .ok_or_else(|| anyhow!("BUG: GitHub client is not initialized"))?;
+    audit::api_call("DELETE", route);
+    let response = github
+        ._delete(github.absolute_url(route)?, None::<&()>)
+        .await
+        .with_context(|| format!("Failed to send DELETE {}", route))?;
+    if !response.status().is_success() {
+        bail!(
+            "DELETE {} failed: server responded with {}",
+            route,
+            response.status()
+        );
+    }
+    Ok(())
+}
+
+async fn delete_release(ctx: &ReleaseContext) -> anyhow::Result<()> {
+    let release = match ctx.resources().try_get::<CreatedRelease>() {
+        Some(CreatedRelease(Some(release))) => release,
+        _ => return Ok(()),
+    };
+    let repo = &ctx.github_config()?.repo;
+    let route = format!("repos/{}/{}/releases/{}", repo.owner, repo.name, release.id);
+    delete_github_ref(ctx, &route).await?;
+    println!("\tGitHub release {} has been deleted", release.html_url);
+    Ok(())
+}
+
+async fn delete_tag(ctx: &ReleaseContext) -> anyhow::Result<()> {
+    let tag = match ctx.resources().try_get::<GithubTag>() {
+        // Tag created before the release is not owned by it
+        Some(_) if ctx.release_tag_exists() => return Ok(()),
+        Some(GithubTag(tag)) => tag,
+        None => return Ok(()),
+    };
+    let github = ctx.release_github_config()?;
+    match github.tag_method {
+        TagMethod::Github => {
+            let repo = &ctx.github_config()?.repo;
+            let route = format!("repos/{}/{}/git/refs/tags/{}", repo.owner, repo.name, tag);
+            delete_github_ref(ctx, &route).await?;
+        }
+        TagMethod::Git => {
+            let refspec = format!("refs/tags/{}", tag);
+            let mut push = workspace_command("git");
+            push.args(["push", "--delete", &github.tag_remote, &refspec]);
+            audit::command(&push);
+            run_and_capture_stdout(&mut push)
+                .await
+                .with_context(|| format!("Failed to delete remote tag `{}`", tag))?;
+            let mut delete = workspace_command("git");
+            delete.args(["tag", "--delete", tag]);
+            run_and_capture_stdout(&mut delete)
+                .await
+                .with_context(|| format!("Failed to delete local tag `{}`", tag))?;
+        }
+    }
+    println!("\tTag `{}` has been deleted", tag);
+    Ok(())
+}
+
+/// Journal entries of the crates released by this run in reverse publish
+/// order, so dependents are yanked before their dependencies; entries left by
+/// the previous releases are skipped
+fn entries_to_yank(
+    journal: &[JournalEntry],
+    publish_order: &[(&str, &Version)],
+) -> Vec<JournalEntry> {
+    publish_order
+        .iter()
+        .rev()
+        .filter_map(|(name, version)| {
+            journal
+                .iter()
+                .find(|e| e.name == *name && e.version == **version)
+                .cloned()
+        })
+        .collect()
+}
+
+/// Yanks crates from the publish journal, so dependents don't pick up
+/// the incomplete release
+async fn yank_published(ctx: &ReleaseContext, metadata: &Metadata) -> anyhow::Result<Vec<String>> {
+    let release = ctx.release_config()?;
+    let journal = read_journal(&journal_path(release, metadata))?;
+    let released = packages_to_publish(metadata, &ctx.config);
+    let publish_order = sort_workspace(metadata)?
+        .into_iter()
+        .filter_map(|id| released.iter().find(|p| p.id == id))
+        .map(|p| (p.name.as_str(), &p.version))
+        .collect::<Vec<_>>();
+    let mut failures = vec![];
+    for entry in entries_to_yank(&journal, &publish_order) {
+        let version = entry.version.to_string();
+        let mut args = vec!["yank", entry.name.as_str(), "--version", version.as_str()];
+        if let Some(registry) = &entry.registry {
+            args.extend(["--registry", registry.as_str()].iter());
+        }
+        println!("EXEC: cargo {}", args.join(" "));
+        let mut yank = workspace_command("cargo");
+        yank.args(&args);
+        audit::command(&yank);
+        match run_and_capture_stdout(&mut yank).await {
+            Ok(_) => println!("\t{} v{} has been yanked", entry.name, version),
+            Err(e) => failures.push(format!(
+                "failed to yank {} v{}: {:#}",
+                entry.name, version, e
+            )),
+        }
+    }
+    Ok(failures)
+}
+
+/// Deletes the created GitHub release and tag and optionally yanks the
+/// published crates; every action is attempted even if others fail
+pub async fn rollback(ctx: &ReleaseContext) -> anyhow::Result<()> {
+    let release = ctx.release_config()?;
+    // Metadata is loaded by the init step, nothing is released without it
+    let metadata = match ctx.resources().try_get::<Metadata>() {
+        Some(metadata) => metadata,
+        None => return Ok(()),
+    };
+    let mut failures = vec![];
+
+    if let Err(e) = delete_release(ctx).await {
+        failures.push(format!("{:#}", e));
+    }
+    if let Err(e) = delete_tag(ctx).await {
+        failures.push(format!("{:#}", e));
+    }
+    if release.rollback_yank_crates {
+        match yank_published(ctx, metadata).await {
+            Ok(yank_failures) => failures.extend(yank_failures),
+            Err(e) => failures.push(format!("{:#}", e)),
+        }
+    }
+
+    // Rolled back steps should not be skipped by `--resume`
+    let state = state_path(release, metadata);
+    if let Err(e) = std::fs::remove_file(&state) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            failures.push(format!("failed to remove {}: {}", state.display(), e));
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!("Rollback is incomplete:\n{}", failures.join("\n"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yanked_entries() {
+        let entry = |name: &str, version| JournalEntry {
+            name: name.to_owned(),
+            version: Version::parse(version).unwrap(),
+            registry: None,
+            published_at: chrono::Utc::now(),
+            checksum: None,
+        };
+        // Journal is not reset until the next publish, so it still contains
+        // the crates of the previous release
+        let journal = [
+            entry("app-core", "1.1.0"),
+            entry("app", "1.1.0"),
+            entry("app-core", "1.2.0"),
+            entry("app-macros", "1.2.0"),
+        ];
+        let version = Version::new(1, 2, 0);
+        let publish_order = [
+            ("app-core", &version),
+            ("app-macros", &version),
+            ("app", &version),
+        ];
+
+        let yanked = entries_to_yank(&journal, &publish_order)
+            .into_iter()
+            .map(|e| format!("{} v{}", e.name, e.version))
+            .collect::<Vec<_>>();
+        assert_eq!(yanked, vec!["app-macros v1.2.0", "app-core v1.2.0"]);
+        assert!(entries_to_yank(&journal[..2], &publish_order).is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+//! Release state file records completed release steps with the resources
+//! they provided, so `--resume` continues the interrupted release without
+//! repeating its remote side effects
+use super::resource::{
+    CreatedRelease, GithubTag, PublishedCrates, ResourceId, Resources, UploadedAssets,
+};
+use crate::{
+    config,
+    github::{CreatedReleasePage, ReleaseAsset},
+    utils::workspace_path,
+};
+use anyhow::{bail, Context};
+use cargo_metadata::Metadata;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const STATE_FILE_NAME: &str = ".monorepo-release-state.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReleaseState {
+    pub root_crate: String,
+    pub version: Version,
+    pub commit: String,
+    /// Release steps in completion order
+    pub completed_steps: Vec<String>,
+    pub tag: Option<String>,
+    pub release: Option<CreatedReleasePage>,
+    #[serde(default)]
+    pub uploaded_assets: Vec<ReleaseAsset>,
+    #[serde(default)]
+    pub published_crates: Vec<(String, Version)>,
+}
+
+pub fn state_path(release: &config::Release, metadata: &Metadata) -> PathBuf {
+    match &release.state_file {
+        Some(path) => workspace_path(path),
+        None => metadata
+            .target_directory
+            .join("monorepo")
+            .join(STATE_FILE_NAME)
+            .into(),
+    }
+}
+
+impl ReleaseState {
+    pub fn new(root_crate: String, version: Version, commit: String) -> Self {
+        Self {
+            root_crate,
+            version,
+            commit,
+            completed_steps: vec![],
+            tag: None,
+            release: None,
+            uploaded_assets: vec![],
+            published_crates: vec![],
+        }
+    }
+
+    /// Reads the state of the interrupted release, `None` if there is none
+    pub fn read(path: &std::path::Path) -> anyhow::Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .with_context(|| format!("Invalid release state file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to read release state {}", path.display()))
+            }
+        }
+    }
+
+    pub fn write(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write release state {}", path.display()))
+    }
+
+    /// Fails if the state belongs to the release of another version or commit
+    pub fn check_matches(&self, expected: &ReleaseState) -> anyhow::Result<()> {
+        if self.root_crate != expected.root_crate
+            || self.version != expected.version
+            || self.commit != expected.commit
+        {
+            bail!(
+                "Release state belongs to {} v{} at {}, but {} v{} at {} is released; \
+                remove the state file to start the release over",
+                self.root_crate,
+                self.version,
+                self.commit,
+                expected.root_crate,
+                expected.version,
+                expected.commit,
+            );
+        }
+        Ok(())
+    }
+
+    pub fn is_completed(&self, step: &str) -> bool {
+        self.completed_steps.iter().any(|s| s == step)
+    }
+
+    /// Records the step with the resources it provided; `false` if some of
+    /// them can't be persisted, so the step is executed again on resume
+    pub fn record(&mut self, step: &str, provides: &[ResourceId], resources: &Resources) -> bool {
+        for resource in provides {
+            if *resource == ResourceId::of::<GithubTag>() {
+                self.tag = resources.try_get::<GithubTag>().map(|t| t.0.clone());
+            } else if *resource == ResourceId::of::<CreatedRelease>() {
+                self.release = resources
+                    .try_get::<CreatedRelease>()
+                    .and_then(|r| r.0.clone());
+            } else if *resource == ResourceId::of::<UploadedAssets>() {
+                self.uploaded_assets = resources
+                    .try_get::<UploadedAssets>()
+                    .map(|a| a.0.clone())
+                    .unwrap_or_default();
+            } else if *resource == ResourceId::of::<PublishedCrates>() {
+                self.published_crates = resources
+                    .try_get::<PublishedCrates>()
+                    .map(|p| p.0.clone())
+                    .unwrap_or_default();
+            } else {
+                return false;
+            }
+        }
+        self.completed_steps.push(step.to_owned());
+        true
+    }
+
+    /// Provides the recorded resources of the completed step instead of
+    /// executing it
+    pub fn restore(&self, provides: &[ResourceId], resources: &Resources) -> anyhow::Result<()> {
+        for resource in provides {
+            if *resource == ResourceId::of::<GithubTag>() {
+                let tag = self
+                    .tag
+                    .clone()
+                    .with_context(|| "Release tag is missing from the release state")?;
+                resources.provide(GithubTag(tag))?;
+            } else if *resource == ResourceId::of::<CreatedRelease>() {
+                resources.provide(CreatedRelease(self.release.clone()))?;
+            } else if *resource == ResourceId::of::<UploadedAssets>() {
+                resources.provide(UploadedAssets(self.uploaded_assets.clone()))?;
+            } else if *resource == ResourceId::of::<PublishedCrates>() {
+                resources.provide(PublishedCrates(self.published_crates.clone()))?;
+            } else {
+                bail!("BUG: {} can't be restored from the release state", resource);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn restored_state() {
+        let provides = [
+            ResourceId::of::<GithubTag>(),
+            ResourceId::of::<PublishedCrates>(),
+        ];
+        let resources = Resources::new(provides);
+        resources.provide(GithubTag("v1.2.0".to_owned())).unwrap();
+        resources
+            .provide(PublishedCrates(vec![(
+                "app".to_owned(),
+                Version::new(1, 2, 0),
+            )]))
+            .unwrap();
+
+        let mut state =
+            ReleaseState::new("app".to_owned(), Version::new(1, 2, 0), "abc".to_owned());
+        assert!(state.record("create-tag", &provides[..1], &resources));
+        assert!(state.record("publish", &provides[1..], &resources));
+        let state: ReleaseState =
+            serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+
+        let restored = Resources::new(provides);
+        state.restore(&provides, &restored).unwrap();
+        expect![[r#"
+            (
+                [
+                    "create-tag",
+                    "publish",
+                ],
+                "v1.2.0",
+                [
+                    (
+                        "app",
+                        Version {
+                            major: 1,
+                            minor: 2,
+                            patch: 0,
+                        },
+                    ),
+                ],
+            )
+        "#]]
+        .assert_debug_eq(&(
+            &state.completed_steps,
+            &restored.get::<GithubTag>().0,
+            &restored.get::<PublishedCrates>().0,
+        ));
+
+        let other = ReleaseState::new("app".to_owned(), Version::new(1, 3, 0), "def".to_owned());
+        expect![[r#"Release state belongs to app v1.2.0 at abc, but app v1.3.0 at def is released; remove the state file to start the release over"#]]
+            .assert_eq(&state.check_matches(&other).unwrap_err().to_string());
+    }
+}
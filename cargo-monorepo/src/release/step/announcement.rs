@@ -0,0 +1,271 @@
+use crate::{
+    audit,
+    i18n::tr,
+    release::{
+        resource::{CurrentCommit, PendingVersion, PrevVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    template::{TextTemplate, TextTemplateContext},
+    utils::{run_and_capture_stdout, workspace_command, workspace_path},
+};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use base64::Engine as _;
+use octocrab::{params::repos::Reference, Octocrab as GithubClient};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+const DEFAULT_TEMPLATE: &str = "\
+# {{root_crate}} {{version}}
+
+{{root_crate}} {{version}} has been released!
+{{#if highlights}}
+
+## Highlights
+
+{{#each highlights}}
+- {{this}}
+{{/each}}
+{{/if}}
+{{#if changelog}}
+
+## Changelog
+
+{{changelog}}
+{{/if}}
+{{#if contributors}}
+
+## Contributors
+
+Thanks to everyone who contributed to this release:
+
+{{#each contributors}}
+- {{this}}
+{{/each}}
+{{/if}}
+";
+
+#[derive(Serialize)]
+struct AnnouncementContext {
+    #[serde(flatten)]
+    base: TextTemplateContext,
+    highlights: Vec<String>,
+    contributors: Vec<String>,
+}
+
+/// Renders long-form release announcement and either writes it to the file or
+/// opens a pull request with it against the website repo
+pub struct GenerateAnnouncement {
+    open_pr: bool,
+}
+
+impl GenerateAnnouncement {
+    pub fn new(open_pr: bool) -> Self {
+        Self { open_pr }
+    }
+}
+
+#[async_trait]
+impl ReleaseStep for GenerateAnnouncement {
+    fn name(&self) -> &'static str {
+        "generate-announcement"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        let mut requires = vec![
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<PrevVersion>(),
+            ResourceId::of::<CurrentCommit>(),
+        ];
+        if self.open_pr {
+            requires.push(ResourceId::of::<GithubClient>());
+        }
+        requires
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Generating release announcement"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Release announcement has been generated"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let announcement_config = ctx.release_announcement_config()?;
+
+        let template = match &announcement_config.template {
+            Some(path) => {
+                let content = tokio::fs::read_to_string(workspace_path(path))
+                    .await
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                TextTemplate::new(&content)?
+            }
+            None => TextTemplate::new(DEFAULT_TEMPLATE)?,
+        };
+
+        let context = AnnouncementContext {
+            base: ctx.text_template_context(),
+            highlights: extract_highlights(
+                ctx.changelog().unwrap_or_default(),
+                announcement_config.highlights,
+            ),
+            contributors: collect_contributors(ctx).await?,
+        };
+        let announcement = template.render(&context)?;
+        let path = announcement_config
+            .path_template
+            .render(&ctx.text_template_context())?;
+
+        if ctx.is_dry_run() {
+            println!("\tAnnouncement `{}`:", path);
+            announcement.lines().for_each(|l| println!("\t{}", l));
+            match &announcement_config.pull_request {
+                Some(pr_config) => ctx.would(format!(
+                    "POST repos/{}/{}/pulls (head: announce/{}-v{}, base: {}, file: {})",
+                    pr_config.repo.owner,
+                    pr_config.repo.name,
+                    ctx.root_crate_name(),
+                    ctx.version(),
+                    pr_config.base,
+                    path
+                )),
+                None => ctx.would(format!("write announcement to {}", path)),
+            }
+            return Ok(());
+        }
+
+        match &announcement_config.pull_request {
+            Some(pr_config) => {
+                let repo = &pr_config.repo;
+                let branch = format!("announce/{}-v{}", ctx.root_crate_name(), ctx.version());
+                let title = format!("Announce {} {}", ctx.root_crate_name(), ctx.version());
+                let github = ctx.github_client();
+
+                let base_route = format!(
+                    "repos/{}/{}/git/ref/heads/{}",
+                    repo.owner, repo.name, pr_config.base
+                );
+                audit::api_call("GET", &base_route);
+                let base: serde_json::Value = github
+                    .get(base_route, None::<&()>)
+                    .await
+                    .with_context(|| format!("Failed to query `{}` branch", pr_config.base))?;
+                let base_sha = base["object"]["sha"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Invalid `{}` branch reference", pr_config.base))?;
+                audit::api_call(
+                    "POST",
+                    &format!("repos/{}/{}/git/refs", repo.owner, repo.name),
+                );
+                github
+                    .repos(&repo.owner, &repo.name)
+                    .create_ref(&Reference::Branch(branch.clone()), base_sha)
+                    .await
+                    .with_context(|| format!("Failed to create `{}` branch", branch))?;
+
+                let content = serde_json::json!({
+                    "message": title,
+                    "content": base64::engine::general_purpose::STANDARD.encode(&announcement),
+                    "branch": branch,
+                });
+                let content_route = format!("repos/{}/{}/contents/{}", repo.owner, repo.name, path);
+                audit::api_call("PUT", &content_route);
+                let _: serde_json::Value = github
+                    .put(content_route, Some(&content))
+                    .await
+                    .with_context(|| "Failed to commit announcement")?;
+
+                let pull_request = serde_json::json!({
+                    "title": title,
+                    "head": branch,
+                    "base": pr_config.base,
+                    "body": format!("Release announcement for {} {}", ctx.root_crate_name(), ctx.version()),
+                });
+                let pulls_route = format!("repos/{}/{}/pulls", repo.owner, repo.name);
+                audit::api_call("POST", &pulls_route);
+                let created: serde_json::Value = github
+                    .post(pulls_route, Some(&pull_request))
+                    .await
+                    .with_context(|| "Failed to open announcement pull request")?;
+                if let Some(url) = created["html_url"].as_str() {
+                    println!("\tAnnouncement pull request: {}", url);
+                }
+            }
+            None => {
+                let file = workspace_path(&path);
+                if let Some(parent) = file.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&file, announcement)
+                    .await
+                    .with_context(|| format!("Failed to write announcement to {}", path))?;
+                println!("\tAnnouncement has been written to {}", path);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Top-level changelog list items
+fn extract_highlights(changelog: &str, limit: usize) -> Vec<String> {
+    changelog
+        .lines()
+        .filter_map(|l| l.strip_prefix("- ").or_else(|| l.strip_prefix("* ")))
+        .map(|l| l.trim().to_owned())
+        .filter(|l| !l.is_empty())
+        .take(limit)
+        .collect()
+}
+
+/// Commit authors since the previous release tag
+async fn collect_contributors(ctx: &ReleaseContext) -> anyhow::Result<Vec<String>> {
+    let range = ctx.release_commit_range().await?;
+
+    let mut log = workspace_command("git");
+    log.args(["log", "--format=%aN", &range]);
+    let authors = run_and_capture_stdout(&mut log)
+        .await
+        .with_context(|| "Failed to collect contributors")?;
+
+    Ok(authors
+        .lines()
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(str::to_owned)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn changelog_highlights() {
+        let changelog = "\
+### Added
+- New `release` flag
+  - nested details
+* Support for groups
+
+### Fixed
+- Typo in messages
+";
+
+        expect![[r#"
+            [
+                "New `release` flag",
+                "Support for groups",
+            ]
+        "#]]
+        .assert_debug_eq(&extract_highlights(changelog, 2));
+    }
+}
@@ -1,28 +1,54 @@
-use crate::release::{ReleaseContext, ReleaseStep};
-use anyhow::bail;
+use crate::{
+    audit,
+    config::ArtifactSizeLimit,
+    i18n::tr,
+    release::{
+        report::{ArtifactDigests, LockedPackage, ReproducibilityReport},
+        resource::{Artifacts, CurrentCommit, DependencySnapshot, PendingVersion, SourceArtifacts},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, sha256_file, workspace_command, workspace_path},
+};
+use anyhow::{anyhow, bail, Context};
 use async_trait::async_trait;
+use cargo_metadata::Metadata;
+use flate2::{write::GzEncoder, Compression};
+use std::path::{Path, PathBuf};
+use tokio::fs;
 
 pub struct CollectArtifacts;
 
 #[async_trait]
 impl ReleaseStep for CollectArtifacts {
+    fn name(&self) -> &'static str {
+        "collect-artifacts"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Artifacts>()]
+    }
+
     fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
         let directory = &ctx.artifacts_config()?.directory;
-        Ok(format!(
-            "Collecting artifacts from '{}'",
-            directory.display()
+        Ok(tr!(
+            "Collecting artifacts from '{directory}'",
+            directory = directory.display()
         ))
     }
 
     fn success_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
-        let count = ctx.artifacts.as_ref().map(|a| a.len()).unwrap_or(0);
-        Ok(format!("Collected {} artifact(s)", count))
+        let count = ctx.artifacts().len();
+        Ok(tr!("Collected {count} artifact(s)", count = count))
     }
 
-    async fn execute(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
         let artifacts_config = ctx.artifacts_config()?;
 
-        let artifacts_folder = artifacts_config.directory.clone();
+        let artifacts_folder = workspace_path(&artifacts_config.directory);
 
         if !artifacts_folder.exists() {
             bail!("Artifacts folder does not exist");
@@ -46,8 +72,398 @@ impl ReleaseStep for CollectArtifacts {
             })
             .collect::<Vec<_>>();
 
-        ctx.artifacts = Some(artifacts);
+        check_artifact_sizes(&artifacts, &artifacts_config.size_limits)?;
+
+        ctx.provide(Artifacts(artifacts))?;
+
+        Ok(())
+    }
+}
+
+fn check_artifact_sizes(artifacts: &[PathBuf], limits: &[ArtifactSizeLimit]) -> anyhow::Result<()> {
+    if limits.is_empty() {
+        return Ok(());
+    }
+
+    let mut invalid_artifacts = vec![];
+
+    for artifact in artifacts {
+        let name = artifact
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let size = std::fs::metadata(artifact)?.len();
+
+        for limit in limits.iter().filter(|l| l.pattern.matches(&name)) {
+            if let Some(min_size) = limit.min_size.filter(|min| size < *min) {
+                println!(
+                    "\t❌ {} is too small ({} bytes, expected at least {} bytes)",
+                    name, size, min_size
+                );
+                invalid_artifacts.push(name.clone());
+            }
+            if let Some(max_size) = limit.max_size.filter(|max| size > *max) {
+                println!(
+                    "\t❌ {} is too large ({} bytes, expected at most {} bytes)",
+                    name, size, max_size
+                );
+                invalid_artifacts.push(name.clone());
+            }
+        }
+    }
+
+    if !invalid_artifacts.is_empty() {
+        invalid_artifacts.dedup();
+        bail!(
+            "Artifact size limits are violated: {}",
+            invalid_artifacts.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+pub struct CollectSourceArtifacts;
+
+#[async_trait]
+impl ReleaseStep for CollectSourceArtifacts {
+    fn name(&self) -> &'static str {
+        "collect-source-artifacts"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<CurrentCommit>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<SourceArtifacts>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Preparing source artifacts"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Source artifacts have been prepared"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let artifacts_config = ctx.artifacts_config()?.clone();
+        let metadata = ctx.cargo_metadata();
+        let workspace_root = PathBuf::from(&metadata.workspace_root);
+        let staging_dir = PathBuf::from(metadata.target_directory.join("monorepo"));
+
+        let mut source_artifacts = vec![];
+
+        if artifacts_config.include_cargo_lock {
+            let lockfile = workspace_root.join("Cargo.lock");
+            if !lockfile.exists() {
+                bail!("Cargo.lock is missing in the workspace root");
+            }
+            source_artifacts.push(lockfile);
+        }
+
+        if let Some(source_archive) = &artifacts_config.source_archive {
+            let name = source_archive
+                .name_template
+                .render(&ctx.text_template_context())?;
+            fs::create_dir_all(&staging_dir).await?;
+            let archive = create_source_archive(
+                &name,
+                &ctx.current_commit(),
+                &staging_dir,
+                source_archive.vendor,
+            )
+            .await?;
+            source_artifacts.push(archive);
+        }
+
+        for artifact in &source_artifacts {
+            println!("\tAdded source artifact: {}", artifact.display());
+        }
+
+        ctx.provide(SourceArtifacts(source_artifacts))?;
+
+        Ok(())
+    }
+}
+
+/// Records dependency versions the release is built against from Cargo.lock
+pub struct SnapshotDependencies;
+
+#[derive(serde::Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[async_trait]
+impl ReleaseStep for SnapshotDependencies {
+    fn name(&self) -> &'static str {
+        "snapshot-dependencies"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Metadata>()]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<DependencySnapshot>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Recording resolved dependencies"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Resolved dependencies have been recorded"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let metadata = ctx.cargo_metadata();
+        let lockfile = metadata.workspace_root.join("Cargo.lock");
+        let content = fs::read_to_string(&lockfile)
+            .await
+            .with_context(|| format!("Failed to read {}", lockfile))?;
+        let lockfile: Lockfile =
+            toml::from_str(&content).with_context(|| format!("Failed to parse {}", lockfile))?;
+
+        let staging_dir = PathBuf::from(metadata.target_directory.join("monorepo"));
+        fs::create_dir_all(&staging_dir).await?;
+        let file = staging_dir.join("dependencies.json");
+        let json = serde_json::to_string_pretty(&lockfile.package)
+            .with_context(|| "Failed to serialize dependency snapshot")?;
+        fs::write(&file, json)
+            .await
+            .with_context(|| format!("Failed to write {}", file.display()))?;
+
+        println!(
+            "\tRecorded {} locked packages to {}",
+            lockfile.package.len(),
+            file.display()
+        );
+
+        ctx.provide(DependencySnapshot {
+            packages: lockfile.package,
+            file,
+        })
+    }
+}
+
+/// `cargo vendor` prints config for the absolute path, make it relative to the
+/// archive root instead. Path is a TOML string, so Windows backslashes are escaped.
+fn relative_vendor_config(config: &str, vendor_dir: &Path) -> String {
+    let vendor_dir = vendor_dir.display().to_string();
+    config
+        .replace(&vendor_dir.replace('\\', "\\\\"), "vendor")
+        .replace(&vendor_dir, "vendor")
+}
+
+/// Builds `<name>.tar.gz` from the `git archive` of the given commit, optionally
+/// extended with vendored dependencies and cargo config which points to them
+async fn create_source_archive(
+    name: &str,
+    commit: &str,
+    staging_dir: &Path,
+    vendor: bool,
+) -> anyhow::Result<PathBuf> {
+    let git_archive = staging_dir.join(format!("{}.tar", name));
+    let mut cmd = workspace_command("git");
+    cmd.arg("archive")
+        .arg("--format=tar")
+        .arg(format!("--prefix={}/", name))
+        .arg("-o")
+        .arg(&git_archive)
+        .arg(commit);
+    run_and_capture_stdout(&mut cmd)
+        .await
+        .with_context(|| "Failed to create git archive")?;
+
+    let vendor_dir = staging_dir.join(format!("{}-vendor", name));
+    let vendor_config = if vendor {
+        if vendor_dir.exists() {
+            fs::remove_dir_all(&vendor_dir).await?;
+        }
+        let mut cmd = workspace_command("cargo");
+        cmd.args(["vendor", "--locked"]).arg(&vendor_dir);
+        let config = run_and_capture_stdout(&mut cmd)
+            .await
+            .with_context(|| "Failed to vendor dependencies")?;
+        Some(relative_vendor_config(&config, &vendor_dir))
+    } else {
+        None
+    };
+
+    let archive_path = staging_dir.join(format!("{}.tar.gz", name));
+    let gz = GzEncoder::new(
+        std::fs::File::create(&archive_path)?,
+        Compression::default(),
+    );
+    let mut builder = tar::Builder::new(gz);
+
+    let mut git_entries = tar::Archive::new(std::fs::File::open(&git_archive)?);
+    for entry in git_entries.entries()? {
+        let mut entry = entry?;
+        let header = entry.header().clone();
+        builder.append(&header, &mut entry)?;
+    }
+
+    if let Some(vendor_config) = vendor_config {
+        builder.append_dir_all(format!("{}/vendor", name), &vendor_dir)?;
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(vendor_config.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(
+            &mut header,
+            format!("{}/.cargo/config.toml", name),
+            vendor_config.as_bytes(),
+        )?;
+    }
+
+    builder.into_inner()?.finish()?;
+    fs::remove_file(&git_archive).await?;
+
+    Ok(archive_path)
+}
+
+pub struct VerifyReproducibleBuild;
+
+#[async_trait]
+impl ReleaseStep for VerifyReproducibleBuild {
+    fn name(&self) -> &'static str {
+        "verify-reproducible-build"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Artifacts>()]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<ReproducibilityReport>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Verifying that release artifacts are reproducible"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Rebuilt artifacts are identical to the collected ones"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let reproducibility = ctx
+            .artifacts_config()?
+            .reproducibility
+            .clone()
+            .ok_or_else(|| {
+                anyhow!("artifacts.reproducibility section is missing from the config")
+            })?;
+
+        let (program, args) = reproducibility
+            .build_command
+            .split_first()
+            .ok_or_else(|| anyhow!("Reproducible build command is empty"))?;
+
+        println!("EXEC: {}", reproducibility.build_command.join(" "));
+        let mut cmd = workspace_command(program);
+        cmd.args(args);
+        audit::command(&cmd);
+        let result = cmd
+            .spawn()
+            .map_err(|e| anyhow!("Failed to spawn reproducible build: {}", e))?
+            .wait()
+            .await
+            .map_err(|e| anyhow!("Failed to start reproducible build: {}", e))?;
+
+        if !result.success() {
+            bail!("Reproducible build command failed");
+        }
+
+        let mut digests = vec![];
+        for artifact in ctx.artifacts() {
+            let name = artifact
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let rebuilt = workspace_path(&reproducibility.output_directory).join(&name);
+            let rebuilt_sha256 = if rebuilt.is_file() {
+                Some(sha256_file(&rebuilt)?)
+            } else {
+                None
+            };
+            digests.push(ArtifactDigests {
+                name,
+                sha256: sha256_file(artifact)?,
+                rebuilt_sha256,
+            });
+        }
+
+        let mismatched = digests
+            .iter()
+            .filter(|d| d.rebuilt_sha256.as_ref() != Some(&d.sha256))
+            .map(|d| d.name.clone())
+            .collect::<Vec<_>>();
+
+        for d in &digests {
+            let status = if mismatched.contains(&d.name) {
+                "❌"
+            } else {
+                "✅"
+            };
+            println!("\t{} {} (sha256: {})", status, d.name, d.sha256);
+        }
+
+        ctx.provide(ReproducibilityReport {
+            verified: mismatched.is_empty(),
+            artifacts: digests,
+        })?;
+
+        if !mismatched.is_empty() {
+            bail!(
+                "Rebuilt artifacts differ from the collected ones: {}",
+                mismatched.join(", ")
+            );
+        }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn vendor_config_windows_path() {
+        let config = r#"[source.vendored-sources]
+directory = "C:\\Users\\ci\\target\\app-vendor"
+"#;
+        expect![[r#"
+            [source.vendored-sources]
+            directory = "vendor"
+        "#]]
+        .assert_eq(&relative_vendor_config(
+            config,
+            Path::new(r"C:\Users\ci\target\app-vendor"),
+        ));
+    }
+}
@@ -0,0 +1,350 @@
+use crate::{
+    audit,
+    config::Attestation,
+    i18n::tr,
+    release::{
+        report::AssetAttestation,
+        resource::{Attestations, UploadedAssets},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{sha256_file, workspace_command},
+};
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+use octocrab::Octocrab as GithubClient;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+
+const PROVENANCE_PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+const SBOM_PREDICATE_TYPE: &str = "https://spdx.dev/Document/v2.3";
+const WORKFLOW_BUILD_TYPE: &str = "https://actions.github.io/buildtypes/workflow/v1";
+/// Set by GitHub Actions only when the workflow has `id-token: write` permission
+const OIDC_REQUEST_URL_ENV: &str = "ACTIONS_ID_TOKEN_REQUEST_URL";
+
+#[derive(Deserialize)]
+struct CreatedAttestation {
+    id: u64,
+}
+
+/// Signs provenance and SBOM of the uploaded assets with keyless `cosign`
+/// and stores the resulting bundles as GitHub artifact attestations
+pub struct AttestReleaseAssets;
+
+#[async_trait]
+impl ReleaseStep for AttestReleaseAssets {
+    fn name(&self) -> &'static str {
+        "attest-release-assets"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<GithubClient>(),
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<UploadedAssets>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Attestations>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Creating attestations for the release assets"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Release assets have been attested"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let attestation = attestation_config(ctx)?;
+        let repo = &ctx.github_config()?.repo;
+
+        let mut predicates = vec![];
+        if attestation.provenance {
+            predicates.push((PROVENANCE_PREDICATE_TYPE, "slsaprovenance1"));
+        }
+        if attestation.sbom.is_some() {
+            predicates.push((SBOM_PREDICATE_TYPE, "spdxjson"));
+        }
+
+        let uploaded = ctx.uploaded_assets();
+        let artifacts = ctx
+            .release_artifacts()
+            .into_iter()
+            .filter(|path| {
+                let name = path.file_name().map(|n| n.to_string_lossy().into_owned());
+                uploaded
+                    .iter()
+                    .any(|asset| Some(&asset.name) == name.as_ref())
+            })
+            .collect::<Vec<_>>();
+
+        if ctx.is_dry_run() {
+            for artifact in &artifacts {
+                for (predicate_type, _) in &predicates {
+                    ctx.would(format!(
+                        "POST repos/{}/{}/attestations ({} of {})",
+                        repo.owner,
+                        repo.name,
+                        predicate_type,
+                        artifact.display()
+                    ));
+                }
+            }
+            return ctx.provide(Attestations(vec![]));
+        }
+
+        if std::env::var_os(OIDC_REQUEST_URL_ENV).is_none() {
+            println!("\tOIDC token is not available outside of GitHub Actions, skipping");
+            return ctx.provide(Attestations(vec![]));
+        }
+
+        let work_dir = ctx
+            .cargo_metadata()
+            .target_directory
+            .join("attestations")
+            .into_std_path_buf();
+        tokio::fs::create_dir_all(&work_dir)
+            .await
+            .with_context(|| format!("Failed to create {}", work_dir.display()))?;
+
+        let provenance_path = work_dir.join("provenance.json");
+        if attestation.provenance {
+            let predicate = provenance_predicate(&GithubEnv::from_env()?);
+            tokio::fs::write(&provenance_path, serde_json::to_string_pretty(&predicate)?)
+                .await
+                .with_context(|| "Failed to write provenance predicate")?;
+        }
+
+        let mut attestations = vec![];
+        for artifact in &artifacts {
+            let name = artifact
+                .file_name()
+                .ok_or_else(|| anyhow!("Invalid artifact path {}", artifact.display()))?
+                .to_string_lossy()
+                .into_owned();
+            println!(
+                "\t{} (sha256: {})",
+                name,
+                sha256_file(artifact).with_context(|| format!("Failed to hash {}", name))?
+            );
+
+            for (predicate_type, cosign_type) in &predicates {
+                let predicate_path = match &attestation.sbom {
+                    Some(sbom) if *predicate_type == SBOM_PREDICATE_TYPE => sbom.as_path(),
+                    _ => provenance_path.as_path(),
+                };
+                let bundle_path = work_dir.join(format!("{}.{}.sigstore.json", name, cosign_type));
+                cosign_attest_blob(artifact, predicate_path, cosign_type, &bundle_path).await?;
+
+                let bundle: serde_json::Value = serde_json::from_str(
+                    &tokio::fs::read_to_string(&bundle_path)
+                        .await
+                        .with_context(|| "Failed to read sigstore bundle")?,
+                )
+                .with_context(|| "Failed to parse sigstore bundle")?;
+
+                let route = format!("repos/{}/{}/attestations", repo.owner, repo.name);
+                audit::api_call("POST", &route);
+                let created: CreatedAttestation = ctx
+                    .github_client()
+                    .post(route, Some(&json!({ "bundle": bundle })))
+                    .await
+                    .with_context(|| format!("Failed to store attestation of {}", name))?;
+
+                let url = format!(
+                    "https://github.com/{}/{}/attestations/{}",
+                    repo.owner, repo.name, created.id
+                );
+                println!("\t\t{}: {}", predicate_type, url);
+                attestations.push(AssetAttestation {
+                    asset: name.clone(),
+                    predicate_type: predicate_type.to_string(),
+                    url,
+                });
+            }
+        }
+
+        ctx.provide(Attestations(attestations))
+    }
+}
+
+fn attestation_config(ctx: &ReleaseContext) -> anyhow::Result<&Attestation> {
+    ctx.release_github_config()?
+        .attestation
+        .as_ref()
+        .ok_or_else(|| anyhow!("release.github.attestation section is missing from the config"))
+}
+
+/// Keyless signing picks up the OIDC token of the workflow run
+async fn cosign_attest_blob(
+    artifact: &Path,
+    predicate: &Path,
+    predicate_type: &str,
+    bundle: &Path,
+) -> anyhow::Result<()> {
+    let mut cmd = workspace_command("cosign");
+    cmd.args([
+        "attest-blob",
+        "--yes",
+        "--new-bundle-format",
+        "--type",
+        predicate_type,
+    ])
+    .arg("--predicate")
+    .arg(predicate)
+    .arg("--bundle")
+    .arg(bundle)
+    .arg(artifact);
+
+    println!("EXEC: cosign attest-blob {}", artifact.display());
+    audit::command(&cmd);
+    let status = cmd
+        .status()
+        .await
+        .map_err(|e| anyhow!("Failed to start cosign: {}", e))?;
+    if !status.success() {
+        bail!("cosign attest-blob failed for {}", artifact.display());
+    }
+
+    Ok(())
+}
+
+/// Workflow run details provided by GitHub Actions
+struct GithubEnv {
+    server_url: String,
+    repository: String,
+    git_ref: String,
+    sha: String,
+    workflow_ref: String,
+    event_name: String,
+    run_id: String,
+    run_attempt: String,
+}
+
+impl GithubEnv {
+    fn from_env() -> anyhow::Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name).with_context(|| format!("{} env var is not set", name))
+        };
+        Ok(Self {
+            server_url: var("GITHUB_SERVER_URL")?,
+            repository: var("GITHUB_REPOSITORY")?,
+            git_ref: var("GITHUB_REF")?,
+            sha: var("GITHUB_SHA")?,
+            workflow_ref: var("GITHUB_WORKFLOW_REF")?,
+            event_name: var("GITHUB_EVENT_NAME")?,
+            run_id: var("GITHUB_RUN_ID")?,
+            run_attempt: var("GITHUB_RUN_ATTEMPT")?,
+        })
+    }
+}
+
+/// SLSA v1 predicate in the same shape as produced by
+/// `actions/attest-build-provenance`
+fn provenance_predicate(env: &GithubEnv) -> serde_json::Value {
+    // `owner/repo/.github/workflows/release.yml@refs/heads/main`
+    let workflow_path = env
+        .workflow_ref
+        .trim_start_matches(&format!("{}/", env.repository))
+        .split('@')
+        .next()
+        .unwrap_or_default();
+
+    json!({
+        "buildDefinition": {
+            "buildType": WORKFLOW_BUILD_TYPE,
+            "externalParameters": {
+                "workflow": {
+                    "ref": env.git_ref,
+                    "repository": format!("{}/{}", env.server_url, env.repository),
+                    "path": workflow_path,
+                },
+            },
+            "internalParameters": {
+                "github": {
+                    "event_name": env.event_name,
+                },
+            },
+            "resolvedDependencies": [{
+                "uri": format!("git+{}/{}@{}", env.server_url, env.repository, env.git_ref),
+                "digest": { "gitCommit": env.sha },
+            }],
+        },
+        "runDetails": {
+            "builder": {
+                "id": format!("{}/{}", env.server_url, env.workflow_ref),
+            },
+            "metadata": {
+                "invocationId": format!(
+                    "{}/{}/actions/runs/{}/attempts/{}",
+                    env.server_url, env.repository, env.run_id, env.run_attempt
+                ),
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn workflow_provenance() {
+        let env = GithubEnv {
+            server_url: "https://github.com".to_owned(),
+            repository: "pacmancoder/monorepo".to_owned(),
+            git_ref: "refs/heads/main".to_owned(),
+            sha: "1a2b3c4d".to_owned(),
+            workflow_ref: "pacmancoder/monorepo/.github/workflows/release.yml@refs/heads/main"
+                .to_owned(),
+            event_name: "workflow_dispatch".to_owned(),
+            run_id: "42".to_owned(),
+            run_attempt: "1".to_owned(),
+        };
+
+        expect![[r#"
+            {
+              "buildDefinition": {
+                "buildType": "https://actions.github.io/buildtypes/workflow/v1",
+                "externalParameters": {
+                  "workflow": {
+                    "path": ".github/workflows/release.yml",
+                    "ref": "refs/heads/main",
+                    "repository": "https://github.com/pacmancoder/monorepo"
+                  }
+                },
+                "internalParameters": {
+                  "github": {
+                    "event_name": "workflow_dispatch"
+                  }
+                },
+                "resolvedDependencies": [
+                  {
+                    "digest": {
+                      "gitCommit": "1a2b3c4d"
+                    },
+                    "uri": "git+https://github.com/pacmancoder/monorepo@refs/heads/main"
+                  }
+                ]
+              },
+              "runDetails": {
+                "builder": {
+                  "id": "https://github.com/pacmancoder/monorepo/.github/workflows/release.yml@refs/heads/main"
+                },
+                "metadata": {
+                  "invocationId": "https://github.com/pacmancoder/monorepo/actions/runs/42/attempts/1"
+                }
+              }
+            }"#]]
+            .assert_eq(&serde_json::to_string_pretty(&provenance_predicate(&env)).unwrap());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    audit,
+    config::CApi,
+    i18n::tr,
+    release::{
+        resource::{CApiArtifacts, PendingVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use cargo_metadata::{Metadata, Package};
+use flate2::{write::GzEncoder, Compression};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Generates C header with cbindgen and packages it together with the built
+/// libraries into per-target archives
+pub struct BuildCApiArtifacts;
+
+#[async_trait]
+impl ReleaseStep for BuildCApiArtifacts {
+    fn name(&self) -> &'static str {
+        "build-c-api"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<CApiArtifacts>()]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        let c_api = c_api_config(ctx)?;
+        Ok(tr!(
+            "Building C API artifacts for `{name}`",
+            name = c_api.crate_name
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("C API artifacts have been built"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let c_api = c_api_config(ctx)?;
+        let metadata = ctx.cargo_metadata();
+        let package = metadata
+            .packages
+            .iter()
+            .find(|p| metadata.workspace_members.contains(&p.id) && p.name == c_api.crate_name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "C API crate `{}` is not found in the workspace",
+                    c_api.crate_name
+                )
+            })?;
+        let lib_name = lib_target_name(package)?;
+        let crate_dir = PathBuf::from(
+            package
+                .manifest_path
+                .parent()
+                .ok_or_else(|| anyhow!("Invalid manifest path of `{}` crate", package.name))?,
+        );
+        let target_dir = PathBuf::from(&metadata.target_directory);
+        let staging_dir = target_dir.join("monorepo").join("c-api");
+        fs::create_dir_all(&staging_dir).await?;
+
+        let header_name = c_api
+            .header
+            .clone()
+            .unwrap_or_else(|| format!("{}.h", lib_name));
+        let header = staging_dir.join(&header_name);
+        generate_header(&crate_dir, c_api, &header).await?;
+
+        let targets = if c_api.targets.is_empty() {
+            vec![host_target().await?]
+        } else {
+            c_api.targets.clone()
+        };
+
+        let mut archives = vec![];
+        for target in &targets {
+            build_library(&package.name, target).await?;
+
+            let lib_dir = target_dir.join(target).join("release");
+            let libraries = library_files(&lib_name, target)
+                .into_iter()
+                .map(|file| lib_dir.join(file))
+                .filter(|path| path.is_file())
+                .collect::<Vec<_>>();
+            if libraries.is_empty() {
+                bail!(
+                    "No libraries were built for `{}`, check that `cdylib` or `staticlib` \
+                    crate type is enabled",
+                    target
+                );
+            }
+
+            let name = format!("{}-{}-{}", package.name, ctx.version(), target);
+            let archive = staging_dir.join(format!("{}.tar.gz", name));
+            create_archive(&archive, &name, &header, &header_name, &libraries)?;
+            println!("\tAdded C API artifact: {}", archive.display());
+            archives.push(archive);
+        }
+
+        ctx.provide(CApiArtifacts(archives))?;
+
+        Ok(())
+    }
+}
+
+fn c_api_config(ctx: &ReleaseContext) -> anyhow::Result<&CApi> {
+    ctx.artifacts_config()?
+        .c_api
+        .as_ref()
+        .ok_or_else(|| anyhow!("artifacts.c_api section is missing from the config"))
+}
+
+fn lib_target_name(package: &Package) -> anyhow::Result<String> {
+    package
+        .targets
+        .iter()
+        .find(|t| {
+            t.kind
+                .iter()
+                .any(|k| matches!(k.as_str(), "cdylib" | "staticlib"))
+        })
+        .map(|t| t.name.replace('-', "_"))
+        .ok_or_else(|| {
+            anyhow!(
+                "`{}` has no library target with `cdylib` or `staticlib` crate type",
+                package.name
+            )
+        })
+}
+
+async fn generate_header(crate_dir: &Path, c_api: &CApi, header: &Path) -> anyhow::Result<()> {
+    let mut cmd = workspace_command("cbindgen");
+    cmd.arg("--crate")
+        .arg(&c_api.crate_name)
+        .arg("--output")
+        .arg(header);
+    let default_config = crate_dir.join("cbindgen.toml");
+    match &c_api.cbindgen_config {
+        Some(config) => {
+            cmd.arg("--config").arg(config);
+        }
+        None if default_config.is_file() => {
+            cmd.arg("--config").arg(default_config);
+        }
+        None => {}
+    }
+    cmd.arg(crate_dir);
+
+    run_and_capture_stdout(&mut cmd)
+        .await
+        .with_context(|| "Failed to generate C header with cbindgen")?;
+
+    Ok(())
+}
+
+async fn host_target() -> anyhow::Result<String> {
+    let mut cmd = workspace_command("rustc");
+    cmd.arg("-vV");
+    let output = run_and_capture_stdout(&mut cmd).await?;
+    output
+        .lines()
+        .find_map(|l| l.strip_prefix("host: "))
+        .map(str::to_owned)
+        .ok_or_else(|| anyhow!("Failed to determine host target"))
+}
+
+async fn build_library(package: &str, target: &str) -> anyhow::Result<()> {
+    println!(
+        "EXEC: cargo build --release -p {} --target {}",
+        package, target
+    );
+    let mut cmd = workspace_command("cargo");
+    cmd.args(["build", "--release", "-p", package, "--target", target]);
+    audit::command(&cmd);
+    let status = cmd
+        .status()
+        .await
+        .map_err(|e| anyhow!("Failed to start cargo build: {}", e))?;
+    if !status.success() {
+        bail!("Failed to build `{}` for {}", package, target);
+    }
+    Ok(())
+}
+
+/// Library file names which can be produced for the target, depending on the
+/// enabled crate types
+fn library_files(lib_name: &str, target: &str) -> Vec<String> {
+    if target.contains("windows") {
+        let mut files = vec![format!("{}.dll", lib_name), format!("{}.dll.lib", lib_name)];
+        if target.ends_with("-gnu") {
+            files.push(format!("lib{}.a", lib_name));
+        } else {
+            files.push(format!("{}.lib", lib_name));
+        }
+        files
+    } else if target.contains("apple") {
+        vec![
+            format!("lib{}.dylib", lib_name),
+            format!("lib{}.a", lib_name),
+        ]
+    } else {
+        vec![format!("lib{}.so", lib_name), format!("lib{}.a", lib_name)]
+    }
+}
+
+fn create_archive(
+    archive: &Path,
+    name: &str,
+    header: &Path,
+    header_name: &str,
+    libraries: &[PathBuf],
+) -> anyhow::Result<()> {
+    let gz = GzEncoder::new(std::fs::File::create(archive)?, Compression::default());
+    let mut builder = tar::Builder::new(gz);
+
+    builder.append_path_with_name(header, format!("{}/include/{}", name, header_name))?;
+    for library in libraries {
+        let file_name = library
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid library path {}", library.display()))?;
+        builder.append_path_with_name(library, Path::new(name).join("lib").join(file_name))?;
+    }
+
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn target_library_files() {
+        expect![[r#"
+            [
+                [
+                    "libfoo_ffi.so",
+                    "libfoo_ffi.a",
+                ],
+                [
+                    "foo_ffi.dll",
+                    "foo_ffi.dll.lib",
+                    "foo_ffi.lib",
+                ],
+                [
+                    "libfoo_ffi.dylib",
+                    "libfoo_ffi.a",
+                ],
+            ]
+        "#]]
+        .assert_debug_eq(
+            &[
+                "x86_64-unknown-linux-gnu",
+                "x86_64-pc-windows-msvc",
+                "aarch64-apple-darwin",
+            ]
+            .iter()
+            .map(|target| library_files("foo_ffi", target))
+            .collect::<Vec<_>>(),
+        );
+    }
+}
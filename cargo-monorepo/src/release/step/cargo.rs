@@ -1,26 +1,61 @@
-use crate::release::{ReleaseContext, ReleaseStep};
-use anyhow::{anyhow, bail};
+use super::readme::readme_path;
+use crate::{
+    audit, config,
+    explain::ErrorCode,
+    i18n::tr,
+    registry::{publish_registry, registry_adapter, wait_until_published},
+    release::{
+        journal::{
+            append_journal, is_journaled, journal_path, read_journal, reset_journal, JournalEntry,
+        },
+        readme::{RepoUrls, RewrittenReadme},
+        resource::{ChangedCrates, CurrentCommit, PublishedCrates},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, sha256_file, workspace_command, workspace_path},
+};
+use anyhow::{anyhow, bail, Context};
 use async_trait::async_trait;
-use std::time::Duration;
-use tokio::process::Command;
+use cargo_metadata::{camino::Utf8PathBuf, Metadata, Package};
+use chrono::Utc;
+use semver::Version;
+use std::path::{Path, PathBuf};
 
 pub struct CargoPublish {
     validate: bool,
+    /// Crates unchanged since the previous release are not published
+    skip_unchanged: bool,
 }
 
 impl CargoPublish {
-    pub fn new() -> Self {
-        Self { validate: false }
+    pub fn new(skip_unchanged: bool) -> Self {
+        Self {
+            validate: false,
+            skip_unchanged,
+        }
     }
 
-    pub fn validate_only() -> Self {
-        Self { validate: true }
+    pub fn validate_only(skip_unchanged: bool) -> Self {
+        Self {
+            validate: true,
+            skip_unchanged,
+        }
     }
 
-    async fn publish(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
+    /// Packages to publish in publish order
+    fn ordered_packages<'a>(&self, ctx: &'a ReleaseContext) -> anyhow::Result<Vec<&'a Package>> {
+        let mut packages = ctx.ordered_packages_to_publish()?;
+        if self.skip_unchanged {
+            let changed = &ctx.resources().get::<ChangedCrates>().0;
+            packages.retain(|p| changed.contains(&p.name));
+        }
+        Ok(packages)
+    }
+
+    async fn publish(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
         let dry_run = ctx.is_dry_run() || self.validate;
 
-        let ordered_packages = ctx.ordered_packages_to_publish()?;
+        let ordered_packages = self.ordered_packages(ctx)?;
 
         if self.validate {
             println!("\tPackage publish order:");
@@ -29,65 +64,281 @@ impl CargoPublish {
                 .for_each(|p| println!("\t- {}", p.name));
         }
 
-        let registry = ctx.release_config()?.registry.clone();
-        let publish_interval = ctx.release_config()?.publish_interval_seconds;
+        let release = ctx.release_config()?;
 
         if dry_run {
-            'packages_loop: for p in ordered_packages {
+            for p in ordered_packages {
                 println!("Validating {}...", p.name);
-                for target in &p.targets {
-                    if target.kind.contains(&"bin".to_owned()) {
-                        println!("WARN: Skipped validation of bin crate {}", p.name);
-                        continue 'packages_loop;
-                    }
+                let is_bin = p
+                    .targets
+                    .iter()
+                    .any(|target| target.kind.contains(&"bin".to_owned()));
+                if is_bin && !release.validate_bin_crates {
+                    println!("WARN: Skipped validation of bin crate {}", p.name);
+                    continue;
                 }
-                execute_publish(p.manifest_path.as_ref(), &registry, true).await?;
+                let registry = publish_registry(release, p.publish.as_deref());
+                let allow_dirty = ctx.promoted_from.is_some();
+                let verify = if is_bin {
+                    Some(verification_args(
+                        release,
+                        &verification_target_dir(ctx, release)?,
+                    ))
+                } else {
+                    None
+                };
+                execute_publish(
+                    p.manifest_path.as_ref(),
+                    &registry,
+                    true,
+                    verify.as_deref(),
+                    allow_dirty,
+                )
+                .await?;
                 println!("{} has been successfully validated!", p.name);
             }
 
+            if ctx.is_dry_run() && !ctx.is_nopublish() {
+                for p in self.ordered_packages(ctx)? {
+                    let registry = publish_registry(release, p.publish.as_deref());
+                    ctx.would(format!(
+                        "cargo publish --manifest-path {} ({} v{} to {})",
+                        p.manifest_path,
+                        p.name,
+                        p.version,
+                        registry.as_deref().unwrap_or("crates.io")
+                    ));
+                    if let Some(path) = self.rewritten_readme_path(ctx, p)? {
+                        ctx.would(format!(
+                            "rewrite relative links in {} (published with --allow-dirty)",
+                            path
+                        ));
+                    }
+                    if let Some(hooks) = &ctx.config.hooks {
+                        let commands = [&hooks.pre_publish_package, &hooks.post_publish_package];
+                        for command in commands.iter().filter(|c| !c.is_empty()) {
+                            ctx.would(format!(
+                                "{} (MONOREPO_PACKAGE={}, MONOREPO_PACKAGE_VERSION={})",
+                                command.join(" "),
+                                p.name,
+                                p.version
+                            ));
+                        }
+                    }
+                    if let Some(mirror) = &release.mirror {
+                        ctx.would(format!(
+                            "PUT {}/{}/{}-{}.crate",
+                            mirror.url.trim_end_matches('/'),
+                            p.name,
+                            p.name,
+                            p.version
+                        ));
+                    }
+                }
+            }
+
             // We don't need actual publish here
             return Ok(());
         }
 
-        let mut previously_published = false;
+        let journal_path = journal_path(release, ctx.cargo_metadata());
+        let journal = if ctx.resume {
+            read_journal(&journal_path)?
+        } else {
+            reset_journal(&journal_path)?;
+            vec![]
+        };
+
+        // Resumed release could fail after publishing the crate, but before
+        // recording it to the journal, so the registry is checked as well
+        let adapter = if ctx.resume {
+            registry_adapter(release)?
+        } else {
+            None
+        };
+
+        // Dependents of the published crate can't be published until it is
+        // resolvable from the registry index
+        let mut unconfirmed: Option<(Option<String>, String, Version)> = None;
+        let mut published = vec![];
+        let target_dir = verification_target_dir(ctx, release)?;
 
         for p in ordered_packages {
-            if previously_published {
+            if is_journaled(&journal, &p.name, &p.version) {
                 println!(
-                    "Waiting for {} seconds before publishing next crate...",
-                    publish_interval
+                    "{} v{} has been published before, skipping",
+                    p.name, p.version
                 );
-                tokio::time::sleep(Duration::from_secs(publish_interval as u64)).await;
+                published.push((p.name.clone(), p.version.clone()));
+                continue;
+            }
+            let registry = publish_registry(release, p.publish.as_deref());
+            let live = match &adapter {
+                // Registry API is known only for the default release registry
+                Some(adapter) if registry == release.registry => {
+                    adapter.version_exists(&p.name, &p.version).await?
+                }
+                _ => false,
+            };
+            if live {
+                println!(
+                    "{} v{} is already published to the registry, skipping",
+                    p.name, p.version
+                );
+                append_journal(
+                    &journal_path,
+                    &JournalEntry {
+                        name: p.name.clone(),
+                        version: p.version.clone(),
+                        registry: registry.clone(),
+                        published_at: Utc::now(),
+                        checksum: None,
+                    },
+                )?;
+                published.push((p.name.clone(), p.version.clone()));
+                continue;
+            }
+            if let Some((registry, name, version)) = unconfirmed.take() {
+                wait_until_published(release, registry.as_deref(), &name, &version).await?;
+            }
+            if let Some(hooks) = &ctx.config.hooks {
+                run_package_hook("pre_publish_package", &hooks.pre_publish_package, p).await?;
             }
             println!("Publishing {}...", p.name);
-            execute_publish(p.manifest_path.as_ref(), &registry, false).await?;
-            previously_published = true;
+            let rewritten = self.rewrite_readme(ctx, p).await?;
+            execute_publish(
+                p.manifest_path.as_ref(),
+                &registry,
+                false,
+                Some(&verification_args(release, &target_dir)),
+                rewritten.is_some() || ctx.promoted_from.is_some(),
+            )
+            .await?;
+            drop(rewritten);
+
+            let checksum = package_checksum(&target_dir, &p.name, &p.version);
+            // Published crate is journaled even without the checksum, so it is
+            // still yanked on rollback, the release fails afterwards
+            append_journal(
+                &journal_path,
+                &JournalEntry {
+                    name: p.name.clone(),
+                    version: p.version.clone(),
+                    registry: registry.clone(),
+                    published_at: Utc::now(),
+                    checksum: checksum.as_ref().ok().cloned(),
+                },
+            )?;
+            checksum?;
+            unconfirmed = Some((registry, p.name.clone(), p.version.clone()));
+            published.push((p.name.clone(), p.version.clone()));
             println!("{} has been successfully published!", p.name);
+            if let Some(hooks) = &ctx.config.hooks {
+                run_package_hook("post_publish_package", &hooks.post_publish_package, p).await?;
+            }
         }
+        ctx.provide(PublishedCrates(published))?;
 
         Ok(())
     }
+
+    /// README which relative links should be rewritten before publish
+    fn rewritten_readme_path(
+        &self,
+        ctx: &ReleaseContext,
+        package: &Package,
+    ) -> anyhow::Result<Option<Utf8PathBuf>> {
+        let rewrite = ctx
+            .release_config()?
+            .readme
+            .as_ref()
+            .is_some_and(|r| r.rewrite_relative_links);
+        Ok(readme_path(package).filter(|_| rewrite))
+    }
+
+    async fn rewrite_readme(
+        &self,
+        ctx: &ReleaseContext,
+        package: &Package,
+    ) -> anyhow::Result<Option<RewrittenReadme>> {
+        let path = match self.rewritten_readme_path(ctx, package)? {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        // Workspace may be located in the subdirectory of the repo
+        let mut prefix = workspace_command("git");
+        prefix.args(["rev-parse", "--show-prefix"]);
+        let prefix = run_and_capture_stdout(&mut prefix)
+            .await
+            .with_context(|| "Failed to get workspace location in the repo")?;
+        let workspace_root = &ctx.cargo_metadata().workspace_root;
+        let dir = path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(workspace_root).ok())
+            .ok_or_else(|| anyhow!("{} is outside of the workspace", path))?;
+        let dir = format!("{}{}", prefix.trim(), dir);
+
+        let urls = RepoUrls::new(&ctx.github_config()?.repo, &ctx.current_commit(), &dir);
+        println!("\tRewriting relative links in {}", path);
+        RewrittenReadme::rewrite(path.as_std_path(), &urls).map(Some)
+    }
 }
 
 #[async_trait]
 impl ReleaseStep for CargoPublish {
+    fn name(&self) -> &'static str {
+        if self.validate {
+            "validate-publish"
+        } else {
+            "publish"
+        }
+    }
+
+    fn kind(&self) -> StepKind {
+        if self.validate {
+            StepKind::Validation
+        } else {
+            StepKind::Release
+        }
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        let mut requires = vec![
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<CurrentCommit>(),
+        ];
+        if self.skip_unchanged {
+            requires.push(ResourceId::of::<ChangedCrates>());
+        }
+        requires
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        if self.validate {
+            vec![]
+        } else {
+            vec![ResourceId::of::<PublishedCrates>()]
+        }
+    }
+
     fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
         if self.validate {
-            Ok("Validating cargo publish (with --dry-run)".to_string())
+            Ok(tr!("Validating cargo publish (with --dry-run)"))
         } else {
-            Ok("Running cargo publish".to_string())
+            Ok(tr!("Running cargo publish"))
         }
     }
 
     fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
         if self.validate {
-            Ok("Cargo publish validation passed".to_string())
+            Ok(tr!("Cargo publish validation passed"))
         } else {
-            Ok("Cargo publish succeeded".to_string())
+            Ok(tr!("Cargo publish succeeded"))
         }
     }
 
-    async fn execute(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
         if ctx.is_dry_run() && !self.validate {
             bail!(
                 "BUG: CargoPublish should not be called \
@@ -101,12 +352,164 @@ impl ReleaseStep for CargoPublish {
     }
 }
 
+/// Fails early if any of the package versions is already present in the
+/// registry; some registries silently accept duplicate publish
+pub struct ValidateNotPublished;
+
+#[async_trait]
+impl ReleaseStep for ValidateNotPublished {
+    fn name(&self) -> &'static str {
+        "validate-not-published"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Metadata>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Checking that package versions are not published yet"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("None of the package versions is published yet"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let release = ctx.release_config()?;
+        let adapter = registry_adapter(release)?
+            .ok_or_else(|| anyhow!("BUG: registry API is not available"))?;
+
+        let journal = if ctx.resume {
+            read_journal(&journal_path(release, ctx.cargo_metadata()))?
+        } else {
+            vec![]
+        };
+
+        let mut already_published = false;
+        for p in ctx.packages_to_publish() {
+            if is_journaled(&journal, &p.name, &p.version) {
+                println!(
+                    "\t⏭️ {} v{} is published by the resumed release",
+                    p.name, p.version
+                );
+                continue;
+            }
+            // Registry API is known only for the default release registry
+            if publish_registry(release, p.publish.as_deref()) != release.registry {
+                println!("\tSkipped {}, it is published to another registry", p.name);
+                continue;
+            }
+            let exists = adapter.version_exists(&p.name, &p.version).await?;
+            if exists && ctx.resume {
+                println!(
+                    "\t⏭️ {} v{} is already published, skipped by the resumed release",
+                    p.name, p.version
+                );
+            } else if exists {
+                println!("\t❌ {} v{} is already published", p.name, p.version);
+                already_published = true;
+            } else {
+                println!("\t✅ {} v{} is not published yet", p.name, p.version);
+            }
+        }
+
+        if already_published {
+            return Err(ErrorCode::AlreadyPublished
+                .error("Some of the package versions are already published to the registry"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Target directory of the verification build, `cargo publish` also puts
+/// the packaged crates into its `package` subdirectory
+fn verification_target_dir(
+    ctx: &ReleaseContext,
+    release: &config::Release,
+) -> anyhow::Result<PathBuf> {
+    match &release.publish_verification.target_dir {
+        Some(dir) => Ok(workspace_path(dir)),
+        None => ctx.validation_target_dir("verify"),
+    }
+}
+
+/// `cargo publish` arguments of the verification build
+fn verification_args(release: &config::Release, target_dir: &Path) -> Vec<String> {
+    let verification = &release.publish_verification;
+    let mut args = vec![];
+    if !verification.features.is_empty() {
+        args.push("--features".to_owned());
+        args.push(verification.features.join(","));
+    }
+    if verification.all_features {
+        args.push("--all-features".to_owned());
+    }
+    if verification.no_default_features {
+        args.push("--no-default-features".to_owned());
+    }
+    for config in &verification.config {
+        args.push("--config".to_owned());
+        args.push(config.clone());
+    }
+    args.push("--target-dir".to_owned());
+    args.push(target_dir.display().to_string());
+    args
+}
+
+/// Checksum of the crate packaged by `cargo publish` into `target_dir`
+fn package_checksum(target_dir: &Path, name: &str, version: &Version) -> anyhow::Result<String> {
+    let package_file = target_dir
+        .join("package")
+        .join(format!("{}-{}.crate", name, version));
+    sha256_file(&package_file).with_context(|| {
+        format!(
+            "Failed to hash published package {}",
+            package_file.display()
+        )
+    })
+}
+
+/// Runs the hook command for the published package, empty command is skipped
+async fn run_package_hook(name: &str, command: &[String], package: &Package) -> anyhow::Result<()> {
+    let (program, args) = match command.split_first() {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+
+    println!("\tRunning {} hook for {}...", name, package.name);
+    let mut cmd = workspace_command(program);
+    cmd.args(args)
+        .env("MONOREPO_PACKAGE", &package.name)
+        .env("MONOREPO_PACKAGE_VERSION", package.version.to_string());
+    audit::command(&cmd);
+
+    let result = cmd
+        .spawn()
+        .with_context(|| format!("Failed to spawn {} hook `{}`", name, program))?
+        .wait()
+        .await
+        .with_context(|| format!("Failed to run {} hook `{}`", name, program))?;
+    if !result.success() {
+        bail!("{} hook has failed for {}", name, package.name);
+    }
+
+    Ok(())
+}
+
+/// `verify` is `None` to skip the verification build of the dry run
 async fn execute_publish(
     manifest_path: &str,
     registry: &Option<String>,
     dry_run: bool,
+    verify: Option<&[String]>,
+    allow_dirty: bool,
 ) -> anyhow::Result<()> {
-    let mut cmd = Command::new("cargo");
+    let mut cmd = workspace_command("cargo");
     let mut args = vec!["publish", "--manifest-path", manifest_path];
 
     if let Some(registry) = registry {
@@ -116,12 +519,20 @@ async fn execute_publish(
 
     if dry_run {
         args.push("--dry-run");
-        args.push("--no-verify");
+    }
+    match verify {
+        Some(verify) => args.extend(verify.iter().map(String::as_str)),
+        None => args.push("--no-verify"),
+    }
+
+    if allow_dirty {
+        args.push("--allow-dirty");
     }
 
     println!("EXEC: cargo {}", args.join(" "));
 
     cmd.args(args);
+    audit::command(&cmd);
 
     let result = cmd
         .spawn()
@@ -136,3 +547,36 @@ async fn execute_publish(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn checksum_from_cache_target_dir() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("monorepo-publish-cache-{}", std::process::id()));
+        let config: Config = toml::from_str(&format!(
+            "[workspace]\nroot_crate = \"app\"\n[release]\ncache_dir = {:?}\n",
+            cache_dir
+        ))
+        .unwrap();
+        let ctx = ReleaseContext::new(config, false, false);
+        let target_dir = verification_target_dir(&ctx, ctx.release_config().unwrap()).unwrap();
+        assert_eq!(target_dir, cache_dir.join("verify"));
+
+        let package_dir = target_dir.join("package");
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(package_dir.join("app-1.2.0.crate"), "crate").unwrap();
+        let checksum = package_checksum(&target_dir, "app", &Version::new(1, 2, 0));
+        let missing = package_checksum(&target_dir, "app", &Version::new(1, 3, 0));
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+
+        assert_eq!(
+            checksum.ok().as_deref(),
+            Some("f5fe331d2367a7a67ee20bd579c77b929ae49439d8b0d8e9c3b98609797b6b69")
+        );
+        assert!(missing.is_err());
+    }
+}
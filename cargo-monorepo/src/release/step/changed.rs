@@ -0,0 +1,83 @@
+use crate::{
+    i18n::tr,
+    release::{
+        changed::changed_crates,
+        resource::{ChangedCrates, PendingVersion, PrevVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+
+/// Finds crates changed since the previous release tag, unchanged ones are
+/// not published
+pub struct DetectChangedCrates;
+
+impl DetectChangedCrates {
+    /// Previous release tag, `None` if it is unknown or missing in the repo
+    async fn prev_release_tag(&self, ctx: &ReleaseContext) -> anyhow::Result<Option<String>> {
+        let tag = match ctx.prev_release_tag()? {
+            Some(tag) => tag,
+            None => return Ok(None),
+        };
+        let mut verify = workspace_command("git");
+        verify.args(["rev-parse", "--verify", "--quiet"]);
+        verify.arg(format!("refs/tags/{}", tag));
+        Ok(run_and_capture_stdout(&mut verify).await.ok().map(|_| tag))
+    }
+}
+
+#[async_trait]
+impl ReleaseStep for DetectChangedCrates {
+    fn name(&self) -> &'static str {
+        "detect-changed-crates"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<PrevVersion>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<ChangedCrates>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Detecting crates changed since the previous release"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Changed crates have been detected"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let packages = ctx.packages_to_publish();
+        let tag = match self.prev_release_tag(ctx).await? {
+            Some(tag) => tag,
+            None => {
+                println!("\tWARN: previous release tag is not found, all crates are released");
+                let all = packages.iter().map(|p| p.name.clone()).collect();
+                return ctx.provide(ChangedCrates(all));
+            }
+        };
+
+        let prev_version = ctx.resources().get::<PrevVersion>().0.as_ref();
+        let changed = changed_crates(ctx.cargo_metadata(), &ctx.config, &tag, prev_version).await?;
+        for p in packages {
+            if changed.contains(&p.name) {
+                println!("\t✏️ {} has been changed since `{}`", p.name, tag);
+            } else {
+                println!("\t⏭️ {} is unchanged since `{}`, skipping", p.name, tag);
+            }
+        }
+        ctx.provide(ChangedCrates(changed))
+    }
+}
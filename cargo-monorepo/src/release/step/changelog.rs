@@ -1,97 +1,444 @@
-use crate::release::{ReleaseContext, ReleaseStep};
-use anyhow::{bail, Context};
+use crate::{
+    audit,
+    changelog::{
+        capture_regions, check_version_heading, commits_changelog, has_section,
+        is_breaking_release, parse_git_log, pull_request_number, pull_requests_changelog,
+        CrateRoute, PullRequest, GIT_LOG_FORMAT,
+    },
+    config::{ChangelogLint, ChangelogSource},
+    explain::ErrorCode,
+    i18n::tr,
+    release::{
+        resource::{self, CurrentCommit, PendingVersion, PrevVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command, workspace_path},
+};
+use anyhow::{anyhow, bail, Context};
 use async_trait::async_trait;
+use cargo_metadata::Metadata;
+use octocrab::Octocrab as GithubClient;
+use serde::Deserialize;
 use tokio::fs;
 
-pub struct CaptureChangelog;
+pub struct CaptureChangelog {
+    source: ChangelogSource,
+}
+
+impl CaptureChangelog {
+    pub fn new(source: ChangelogSource) -> Self {
+        Self { source }
+    }
+
+    async fn capture(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        let changelog = match self.source {
+            ChangelogSource::File => capture_changelog(ctx).await?,
+            ChangelogSource::Commits => generate_changelog(ctx).await?,
+            ChangelogSource::TagMessage => capture_tag_message(ctx).await?,
+            ChangelogSource::PullRequests => generate_pull_requests_changelog(ctx).await?,
+        };
+        if let Some(lint) = &ctx.changelog_config()?.lint {
+            if !changelog.is_empty() {
+                lint_changelog(lint, &changelog).await?;
+            }
+        }
+        Ok(changelog)
+    }
+}
 
 #[async_trait]
 impl ReleaseStep for CaptureChangelog {
-    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
-        let file = &ctx.changelog_config()?.file;
-        Ok(format!("Capturing changelog from '{}'", file.display()))
+    fn name(&self) -> &'static str {
+        "capture-changelog"
     }
 
-    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
-        Ok("Changelog has been captured".to_owned())
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        match self.source {
+            ChangelogSource::File => vec![ResourceId::of::<PendingVersion>()],
+            ChangelogSource::Commits => vec![
+                ResourceId::of::<PendingVersion>(),
+                ResourceId::of::<PrevVersion>(),
+                ResourceId::of::<CurrentCommit>(),
+                ResourceId::of::<Metadata>(),
+            ],
+            ChangelogSource::TagMessage => vec![
+                ResourceId::of::<PendingVersion>(),
+                ResourceId::of::<CurrentCommit>(),
+            ],
+            ChangelogSource::PullRequests => vec![
+                ResourceId::of::<PendingVersion>(),
+                ResourceId::of::<PrevVersion>(),
+                ResourceId::of::<CurrentCommit>(),
+                ResourceId::of::<GithubClient>(),
+            ],
+        }
     }
 
-    async fn execute(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
-        let changelog_config = ctx.changelog_config()?;
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<resource::Changelog>()]
+    }
 
-        let changelog_bytes = fs::read(&changelog_config.file).await?;
-        let changelog =
-            String::from_utf8(changelog_bytes).with_context(|| "Changelog is not a text file")?;
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        match self.source {
+            ChangelogSource::File => {
+                let file = &ctx.changelog_config()?.file;
+                Ok(tr!(
+                    "Capturing changelog from '{file}'",
+                    file = file.display()
+                ))
+            }
+            ChangelogSource::Commits => Ok(tr!("Generating changelog from commits")),
+            ChangelogSource::TagMessage => Ok(tr!(
+                "Capturing changelog from `{tag}` tag message",
+                tag = release_tag(ctx)?
+            )),
+            ChangelogSource::PullRequests => Ok(tr!("Generating changelog from pull requests")),
+        }
+    }
 
-        let changelog = if changelog_config.start_marker_template.is_none() {
-            changelog
-        } else {
-            let start_marker_template = changelog_config
-                .start_marker_template
-                .clone()
-                .with_context(|| "start_marker_template is missing")?;
-            let end_marker_template = changelog_config
-                .end_marker_template
-                .clone()
-                .with_context(|| "end_marker_template is missing")?;
-
-            let tempalte_context = ctx.text_template_context()?;
-
-            let begin_marker = start_marker_template.render(&tempalte_context)?;
-            let end_marker = end_marker_template.render(&tempalte_context)?;
-
-            let changelog_lines = changelog.lines().collect::<Vec<_>>();
-
-            let begin_line = changelog_lines
-                .iter()
-                .position(|l| l.contains(&begin_marker));
-            let end_line = changelog_lines.iter().position(|l| l.contains(&end_marker));
-
-            match (begin_line, end_line) {
-                (Some(begin), Some(end)) => {
-                    if end <= begin {
-                        bail!(
-                            "Changelog end barker should be placed \
-                            after corresponding begin marker"
-                        );
-                    }
-
-                    let first_line = begin + 1;
-                    if first_line == end {
-                        if changelog_config.allow_empty_changelog {
-                            println!("\tWARN: empty changelog");
-                        } else {
-                            bail!("Changelog is empty");
-                        }
-                        String::new()
-                    } else {
-                        changelog_lines[first_line..end].join("\n")
-                    }
-                }
-                (None, Some(_)) => {
-                    bail!(
-                        "Can't find required changelog begin marker {}",
-                        begin_marker
-                    );
-                }
-                (Some(_), None) => {
-                    bail!("Can't find required changelog end marker {}", end_marker);
-                }
-                (None, None) => {
-                    bail!(
-                        "Can't find required changelog markers {} and {}",
-                        begin_marker,
-                        end_marker
-                    );
-                }
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Changelog has been captured"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let changelog = match self.capture(ctx).await {
+            Ok(changelog) => changelog,
+            Err(e) if ctx.hotfix => {
+                println!("\tWARN: {:#}, ignored for hotfix release", e);
+                String::new()
             }
+            Err(e) => return Err(e),
         };
-        if changelog_config.print_to_stdout {
+
+        if ctx.changelog_config()?.print_to_stdout {
             changelog.lines().for_each(|l| println!("\t{}", l))
         }
 
-        ctx.changelog = Some(changelog);
+        ctx.provide(resource::Changelog(changelog))?;
+
+        Ok(())
+    }
+}
+
+/// Fails semver-incompatible release which changelog has no migration notes
+pub struct ValidateMigrationNotes;
+
+#[async_trait]
+impl ReleaseStep for ValidateMigrationNotes {
+    fn name(&self) -> &'static str {
+        "validate-migration-notes"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<resource::Changelog>(),
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<PrevVersion>(),
+        ]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Checking migration notes of the breaking release"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Migration notes have been checked"))
+    }
 
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let heading = match &ctx.changelog_config()?.migration_heading {
+            Some(heading) => heading,
+            None => return Ok(()),
+        };
+        let prev_version = match &ctx.resources().get::<PrevVersion>().0 {
+            Some(prev_version) => prev_version,
+            None => {
+                println!("\tPrevious version is unknown, check is skipped");
+                return Ok(());
+            }
+        };
+        if !is_breaking_release(prev_version, ctx.version()) {
+            println!(
+                "\t{} is compatible with {}, migration notes are not required",
+                ctx.version(),
+                prev_version
+            );
+            return Ok(());
+        }
+        if !has_section(ctx.changelog().unwrap_or_default(), heading) {
+            return Err(ErrorCode::MissingMigrationNotes.error(format!(
+                "{} is a breaking release after {}, but changelog has no `{}` section",
+                ctx.version(),
+                prev_version,
+                heading
+            )));
+        }
         Ok(())
     }
 }
+
+async fn capture_changelog(ctx: &ReleaseContext) -> anyhow::Result<String> {
+    let changelog_config = ctx.changelog_config()?;
+
+    let changelog_bytes = fs::read(workspace_path(&changelog_config.file)).await?;
+    let changelog =
+        String::from_utf8(changelog_bytes).with_context(|| "Changelog is not a text file")?;
+
+    let marker_templates = changelog_config.marker_templates();
+    let tempalte_context = ctx.text_template_context();
+    // Version heading is written by the release when the unreleased section
+    // is promoted
+    let heading_template = changelog_config
+        .version_heading_template
+        .as_ref()
+        .filter(|_| changelog_config.promote_unreleased.is_none());
+    if let Some(heading_template) = heading_template {
+        let start_marker = marker_templates
+            .first()
+            .map(|(start, _)| start.render(&tempalte_context))
+            .transpose()?;
+        check_version_heading(
+            &changelog,
+            start_marker.as_deref(),
+            &heading_template.render(&tempalte_context)?,
+        )?;
+    }
+    if marker_templates.is_empty() {
+        return Ok(changelog);
+    }
+
+    let markers = marker_templates
+        .into_iter()
+        .map(|(start, end)| {
+            Ok((
+                start.render(&tempalte_context)?,
+                end.render(&tempalte_context)?,
+            ))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let changelog = capture_regions(&changelog, &markers)?;
+    if changelog.is_empty() {
+        if changelog_config.allow_empty_changelog || ctx.hotfix {
+            println!("\tWARN: empty changelog");
+        } else {
+            return Err(ErrorCode::EmptyChangelog.error("Changelog is empty"));
+        }
+    }
+
+    Ok(changelog)
+}
+
+/// Commit subjects since the previous release, routed to the released crates
+/// by conventional commit scope or changed paths
+async fn generate_changelog(ctx: &ReleaseContext) -> anyhow::Result<String> {
+    let changelog_config = ctx.changelog_config()?;
+    let metadata = ctx.cargo_metadata();
+
+    let routes = ctx
+        .ordered_packages_to_publish()?
+        .into_iter()
+        .map(|p| CrateRoute::for_package(p, &metadata.workspace_root, &changelog_config.mapping))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let range = ctx.release_commit_range().await?;
+    let mut log = workspace_command("git");
+    log.current_dir(&metadata.workspace_root).args([
+        "log",
+        "--relative",
+        "--name-only",
+        GIT_LOG_FORMAT,
+        &range,
+    ]);
+    let log = run_and_capture_stdout(&mut log)
+        .await
+        .with_context(|| "Failed to query commits for changelog")?;
+
+    let changelog = commits_changelog(&parse_git_log(&log), &routes);
+    if changelog.is_empty() {
+        if changelog_config.allow_empty_changelog || ctx.hotfix {
+            println!("\tWARN: empty changelog");
+        } else {
+            return Err(ErrorCode::NoChangesSinceRelease
+                .error("No commits affecting released crates since the previous release"));
+        }
+    }
+
+    Ok(changelog)
+}
+
+#[derive(Deserialize)]
+struct PullRequestResponse {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    labels: Vec<LabelResponse>,
+}
+
+#[derive(Deserialize)]
+struct LabelResponse {
+    name: String,
+}
+
+/// Titles of the PRs merged since the previous release, grouped into the
+/// configured label sections
+async fn generate_pull_requests_changelog(ctx: &ReleaseContext) -> anyhow::Result<String> {
+    let changelog_config = ctx.changelog_config()?;
+    if ctx.offline {
+        bail!("Pull requests can't be queried in offline mode");
+    }
+
+    let range = ctx.release_commit_range().await?;
+    let mut log = workspace_command("git");
+    log.args(["log", "--format=%s", &range]);
+    let log = run_and_capture_stdout(&mut log)
+        .await
+        .with_context(|| "Failed to query commits for changelog")?;
+    // Oldest PRs first
+    let mut numbers = vec![];
+    for number in log.lines().rev().filter_map(pull_request_number) {
+        if !numbers.contains(&number) {
+            numbers.push(number);
+        }
+    }
+
+    let repo = &ctx.github_config()?.repo;
+    let mut pull_requests = vec![];
+    for number in numbers {
+        let route = format!("repos/{}/{}/pulls/{}", repo.owner, repo.name, number);
+        audit::api_call("GET", &route);
+        let pull_request: PullRequestResponse = ctx
+            .github_client()
+            .get(&route, None::<&()>)
+            .await
+            .with_context(|| format!("Failed to query pull request #{}", number))?;
+        pull_requests.push(PullRequest {
+            number: pull_request.number,
+            title: pull_request.title,
+            labels: pull_request.labels.into_iter().map(|l| l.name).collect(),
+        });
+    }
+
+    let changelog = pull_requests_changelog(
+        &pull_requests,
+        &changelog_config.label_sections,
+        &changelog_config.other_changes_title,
+    );
+    if changelog.is_empty() {
+        if changelog_config.allow_empty_changelog || ctx.hotfix {
+            println!("\tWARN: empty changelog");
+        } else {
+            return Err(ErrorCode::NoChangesSinceRelease
+                .error("No pull requests have been merged since the previous release"));
+        }
+    }
+
+    Ok(changelog)
+}
+
+fn release_tag(ctx: &ReleaseContext) -> anyhow::Result<String> {
+    ctx.release_github_config()?
+        .tag_name_template
+        .render(&ctx.text_template_context())
+}
+
+/// Message of the annotated release tag; the tag should already point to the
+/// released commit
+async fn capture_tag_message(ctx: &ReleaseContext) -> anyhow::Result<String> {
+    let changelog_config = ctx.changelog_config()?;
+    let tag = release_tag(ctx)?;
+    let tag_ref = format!("refs/tags/{}", tag);
+
+    let mut object_type = workspace_command("git");
+    object_type.args(["cat-file", "-t", &tag_ref]);
+    let object_type = run_and_capture_stdout(&mut object_type)
+        .await
+        .with_context(|| format!("Release tag `{}` does not exist", tag))?;
+    if object_type.trim() != "tag" {
+        bail!(
+            "Release tag `{}` is not annotated, changelog can't be captured",
+            tag
+        );
+    }
+
+    let mut tagged_commit = workspace_command("git");
+    tagged_commit.args(["rev-list", "-n", "1", &tag_ref]);
+    let tagged_commit = run_and_capture_stdout(&mut tagged_commit).await?;
+    if tagged_commit.trim() != ctx.current_commit() {
+        bail!(
+            "Release tag `{}` points to {}, but released commit is {}",
+            tag,
+            tagged_commit.trim(),
+            ctx.current_commit()
+        );
+    }
+
+    // Signature of the signed tag is not a part of the notes
+    let mut message = workspace_command("git");
+    message.args([
+        "for-each-ref",
+        "--format=%(contents:subject)%0a%0a%(contents:body)",
+        &tag_ref,
+    ]);
+    let changelog = run_and_capture_stdout(&mut message)
+        .await
+        .with_context(|| format!("Failed to read `{}` tag message", tag))?
+        .trim()
+        .to_owned();
+    if changelog.is_empty() {
+        if changelog_config.allow_empty_changelog || ctx.hotfix {
+            println!("\tWARN: empty changelog");
+        } else {
+            return Err(ErrorCode::EmptyChangelog.error("Release tag message is empty"));
+        }
+    }
+
+    Ok(changelog)
+}
+
+/// Runs the external linter against the captured changelog, so typos are
+/// caught before they are published
+async fn lint_changelog(lint: &ChangelogLint, changelog: &str) -> anyhow::Result<()> {
+    let (program, args) = lint
+        .command
+        .split_first()
+        .ok_or_else(|| anyhow!("Changelog lint command is empty"))?;
+
+    let path = std::env::temp_dir().join(format!(
+        "cargo-monorepo-changelog-{}.{}",
+        std::process::id(),
+        lint.extension
+    ));
+    fs::write(&path, changelog)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    println!("\tLinting changelog with `{}`...", program);
+    let mut cmd = workspace_command(program);
+    cmd.args(args).arg(&path);
+    audit::command(&cmd);
+    let output = cmd.output().await;
+    let _ = fs::remove_file(&path).await;
+    let output = output.with_context(|| format!("Failed to run `{}`", program))?;
+
+    if !output.status.success() {
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .chain(String::from_utf8_lossy(&output.stderr).lines())
+            .for_each(|l| println!("\t{}", l));
+        return Err(ErrorCode::ChangelogLint.error(format!(
+            "`{}` reported problems in the changelog",
+            lint.command.join(" ")
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use crate::{
+    i18n::tr,
+    release::{resource::CrateNotes, ReleaseContext, ReleaseStep, ResourceId, StepKind},
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use cargo_metadata::{Metadata, Package};
+
+/// Collects release notes of the published crates from their notes files or
+/// crate root doc comments
+pub struct CollectCrateNotes;
+
+fn heading_level(line: &str) -> usize {
+    line.chars().take_while(|c| *c == '#').count()
+}
+
+/// Section of the `//!` doc comment under the heading (compared ignoring
+/// case), up to the next heading of the same or higher level
+fn doc_comment_section(source: &str, heading: &str) -> Option<String> {
+    let docs = source
+        .lines()
+        .filter_map(|l| l.trim_start().strip_prefix("//!"))
+        .map(|l| l.strip_prefix(' ').unwrap_or(l));
+    let level = heading_level(heading.trim());
+    let mut docs = docs.skip_while(|l| !l.trim().eq_ignore_ascii_case(heading.trim()));
+    docs.next()?;
+    let section = docs
+        .take_while(|l| !(l.starts_with('#') && heading_level(l) <= level))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(section.trim().to_owned()).filter(|s| !s.is_empty())
+}
+
+/// Library (or binary, if there is no library) crate root
+fn crate_root(package: &Package) -> Option<&cargo_metadata::camino::Utf8Path> {
+    let has_kind = |kinds: &[&str]| {
+        package
+            .targets
+            .iter()
+            .find(|t| t.kind.iter().any(|k| kinds.contains(&k.as_str())))
+    };
+    has_kind(&["lib", "proc-macro"])
+        .or_else(|| has_kind(&["bin"]))
+        .map(|t| t.src_path.as_path())
+}
+
+#[async_trait]
+impl ReleaseStep for CollectCrateNotes {
+    fn name(&self) -> &'static str {
+        "collect-crate-notes"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Metadata>()]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<CrateNotes>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Collecting release notes of the crates"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Crate release notes have been collected"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let config = ctx
+            .changelog_config()?
+            .crate_notes
+            .as_ref()
+            .ok_or_else(|| anyhow!("BUG: changelog.crate_notes is not set"))?;
+
+        let mut notes = vec![];
+        for p in ctx.packages_to_publish() {
+            let dir = p
+                .manifest_path
+                .parent()
+                .ok_or_else(|| anyhow!("Invalid manifest path of `{}` crate", p.name))?;
+            let file = dir.as_std_path().join(&config.file);
+            let crate_notes = match tokio::fs::read_to_string(&file).await {
+                Ok(content) => Some(content.trim().to_owned()).filter(|n| !n.is_empty()),
+                Err(_) => match crate_root(p) {
+                    Some(root) => tokio::fs::read_to_string(root)
+                        .await
+                        .ok()
+                        .and_then(|source| doc_comment_section(&source, &config.heading)),
+                    None => None,
+                },
+            };
+            match crate_notes {
+                Some(crate_notes) => {
+                    println!("\t📝 {}", p.name);
+                    notes.push((p.name.clone(), crate_notes));
+                }
+                None => println!("\t{} has no release notes", p.name),
+            }
+        }
+        ctx.provide(CrateNotes(notes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn doc_comment_release_notes() {
+        let source = r#"//! Parser of the config files
+//!
+//! # Release notes
+//!
+//! - `parse_str` accepts `&str`
+//!
+//! ## Deprecations
+//! - `parse_bytes` is deprecated
+//!
+//! # Examples
+//! ```
+//! parse_str("")
+//! ```
+#![forbid(unsafe_code)]
+
+pub fn parse_str(_: &str) {}
+"#;
+        expect![[r#"
+            Some(
+                "- `parse_str` accepts `&str`\n\n## Deprecations\n- `parse_bytes` is deprecated",
+            )
+        "#]]
+        .assert_debug_eq(&doc_comment_section(source, "# release notes"));
+        assert_eq!(doc_comment_section(source, "# Changes"), None);
+    }
+}
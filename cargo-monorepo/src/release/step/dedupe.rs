@@ -0,0 +1,78 @@
+use crate::{
+    i18n::tr,
+    release::{
+        dedupe::release_record,
+        resource::{CurrentCommit, PendingVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::Context;
+use async_trait::async_trait;
+
+/// Appends released version to the notes of the release commit
+pub struct RecordCompletedRelease;
+
+#[async_trait]
+impl ReleaseStep for RecordCompletedRelease {
+    fn name(&self) -> &'static str {
+        "record-completed-release"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<CurrentCommit>(),
+            ResourceId::of::<PendingVersion>(),
+        ]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        let dedupe = ctx.release_dedupe_config()?;
+        Ok(tr!(
+            "Recording completed release in `refs/notes/{notes_ref}`",
+            notes_ref = dedupe.notes_ref
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Completed release has been recorded"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let dedupe = ctx.release_dedupe_config()?;
+        let notes_ref = format!("--ref={}", dedupe.notes_ref);
+        let record = release_record(&ctx.root_crate_name(), ctx.version());
+        let refspec = format!("refs/notes/{}", dedupe.notes_ref);
+
+        if ctx.is_dry_run() {
+            ctx.would(format!(
+                "git notes {} append --message \"{}\" {}",
+                notes_ref,
+                record,
+                ctx.current_commit()
+            ));
+            ctx.would(format!("git push {} {}", dedupe.remote, refspec));
+            return Ok(());
+        }
+
+        let mut append = workspace_command("git");
+        append
+            .args(["notes", &notes_ref, "append", "--message", &record])
+            .arg(ctx.current_commit());
+        run_and_capture_stdout(&mut append)
+            .await
+            .with_context(|| "Failed to record completed release")?;
+
+        let mut push = workspace_command("git");
+        push.args(["push", &dedupe.remote, &refspec]);
+        run_and_capture_stdout(&mut push)
+            .await
+            .with_context(|| format!("Failed to push `{}`", refspec))?;
+
+        Ok(())
+    }
+}
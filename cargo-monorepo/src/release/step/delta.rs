@@ -0,0 +1,252 @@
+use crate::{
+    audit,
+    config::{DeltaPatches, DeltaTool},
+    i18n::tr,
+    release::{
+        resource::{Artifacts, PatchArtifacts, PendingVersion, PrevVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+use octocrab::Octocrab as GithubClient;
+use semver::Version;
+use std::path::{Path, PathBuf};
+
+/// Produces binary patches from the assets of the previous GitHub release to
+/// the new artifacts, so the auto-updater can download only the difference
+pub struct CreateDeltaPatches;
+
+/// Name of the previous release asset which corresponds to the new artifact;
+/// artifact names are expected to contain the released version
+fn prev_asset_name(name: &str, version: &Version, prev_version: &Version) -> Option<String> {
+    let version = version.to_string();
+    name.contains(&version)
+        .then(|| name.replace(&version, &prev_version.to_string()))
+}
+
+fn patch_name(name: &str, prev_version: &Version) -> String {
+    format!("{}.from-{}.patch", name, prev_version)
+}
+
+async fn create_patch(tool: DeltaTool, old: &Path, new: &Path, patch: &Path) -> anyhow::Result<()> {
+    let mut cmd = match tool {
+        DeltaTool::Zstd => {
+            let mut cmd = workspace_command("zstd");
+            cmd.args(["-q", "-f", "-19", "--long=31", "--patch-from"])
+                .arg(old)
+                .arg(new)
+                .arg("-o")
+                .arg(patch);
+            cmd
+        }
+        DeltaTool::Bsdiff => {
+            let mut cmd = workspace_command("bsdiff");
+            cmd.arg(old).arg(new).arg(patch);
+            cmd
+        }
+    };
+    run_and_capture_stdout(&mut cmd)
+        .await
+        .with_context(|| format!("Failed to create patch with {:?}", tool))?;
+    Ok(())
+}
+
+impl CreateDeltaPatches {
+    async fn download_asset(
+        &self,
+        ctx: &ReleaseContext,
+        url: &str,
+        path: &Path,
+    ) -> anyhow::Result<()> {
+        audit::api_call("GET", url);
+        let response = ctx
+            .github_client()
+            .request_builder(url, reqwest::Method::GET)
+            .header(reqwest::header::ACCEPT, "application/octet-stream")
+            .send()
+            .await
+            .with_context(|| format!("Failed to download {}", url))?
+            .error_for_status()
+            .with_context(|| format!("Failed to download {}", url))?;
+        let data = response.bytes().await?;
+        tokio::fs::write(path, &data)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    async fn create_patches(
+        &self,
+        ctx: &ReleaseContext,
+        config: &DeltaPatches,
+        prev_version: &Version,
+        prev_tag: &str,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let repo = &ctx.github_config()?.repo;
+        let prev_release = ctx
+            .github_client()
+            .repos(&repo.owner, &repo.name)
+            .releases()
+            .get_by_tag(prev_tag)
+            .await
+            .with_context(|| format!("Failed to get previous release `{}`", prev_tag))?;
+
+        let work_dir = ctx
+            .cargo_metadata()
+            .target_directory
+            .join("monorepo")
+            .join("delta")
+            .into_std_path_buf();
+        tokio::fs::create_dir_all(&work_dir)
+            .await
+            .with_context(|| format!("Failed to create {}", work_dir.display()))?;
+
+        let mut patches = vec![];
+        let mut missing = vec![];
+        for artifact in ctx.artifacts() {
+            let name = artifact
+                .file_name()
+                .ok_or_else(|| anyhow!("Invalid artifact path {}", artifact.display()))?
+                .to_string_lossy()
+                .into_owned();
+            if !config.patterns.iter().any(|p| p.matches(&name)) {
+                continue;
+            }
+
+            let prev_asset = prev_asset_name(&name, ctx.version(), prev_version)
+                .and_then(|prev_name| prev_release.assets.iter().find(|a| a.name == prev_name));
+            let prev_asset = match prev_asset {
+                Some(asset) => asset,
+                None => {
+                    println!("\t❌ {} has no counterpart in `{}` release", name, prev_tag);
+                    missing.push(name);
+                    continue;
+                }
+            };
+
+            let prev_path = work_dir.join(&prev_asset.name);
+            self.download_asset(ctx, prev_asset.url.as_str(), &prev_path)
+                .await?;
+            let patch = work_dir.join(patch_name(&name, prev_version));
+            create_patch(config.tool, &prev_path, artifact, &patch).await?;
+            println!(
+                "\t✅ {} ({} bytes, full artifact is {} bytes)",
+                patch.display(),
+                std::fs::metadata(&patch)?.len(),
+                std::fs::metadata(artifact)?.len()
+            );
+            patches.push(patch);
+        }
+
+        if !missing.is_empty() && !config.allow_missing {
+            bail!(
+                "Previous release assets are missing for {}, set \
+                artifacts.delta_patches.allow_missing to skip them",
+                missing.join(", ")
+            );
+        }
+
+        Ok(patches)
+    }
+}
+
+#[async_trait]
+impl ReleaseStep for CreateDeltaPatches {
+    fn name(&self) -> &'static str {
+        "create-delta-patches"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<Artifacts>(),
+            ResourceId::of::<GithubClient>(),
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<PrevVersion>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<PatchArtifacts>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Creating delta patches from the previous release"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Delta patches have been created"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let config = ctx
+            .artifacts_config()?
+            .delta_patches
+            .as_ref()
+            .ok_or_else(|| anyhow!("artifacts.delta_patches section is missing from the config"))?;
+
+        let prev = match (
+            &ctx.resources().get::<PrevVersion>().0,
+            ctx.prev_release_tag()?,
+        ) {
+            (Some(prev_version), Some(prev_tag)) => Some((prev_version.clone(), prev_tag)),
+            _ => None,
+        };
+        let patches = match prev {
+            Some((prev_version, prev_tag)) => {
+                self.create_patches(ctx, config, &prev_version, &prev_tag)
+                    .await?
+            }
+            None => {
+                println!("\tPrevious release is unknown, no patches are created");
+                vec![]
+            }
+        };
+
+        ctx.provide(PatchArtifacts(patches))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn patch_names() {
+        let version = Version::parse("1.3.0").unwrap();
+        let prev_version = Version::parse("1.2.1").unwrap();
+
+        let names = vec!["app-1.3.0-x86_64-linux.tar.gz", "app-latest.zip"]
+            .into_iter()
+            .map(|name| {
+                (
+                    prev_asset_name(name, &version, &prev_version),
+                    patch_name(name, &prev_version),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                (
+                    Some(
+                        "app-1.2.1-x86_64-linux.tar.gz",
+                    ),
+                    "app-1.3.0-x86_64-linux.tar.gz.from-1.2.1.patch",
+                ),
+                (
+                    None,
+                    "app-latest.zip.from-1.2.1.patch",
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&names);
+    }
+}
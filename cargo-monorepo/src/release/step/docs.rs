@@ -0,0 +1,177 @@
+use crate::{
+    audit,
+    i18n::tr,
+    release::{resource::PendingVersion, ReleaseContext, ReleaseStep, ResourceId, StepKind},
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+use std::path::{Path, PathBuf};
+
+pub struct DeployDocs;
+
+#[async_trait]
+impl ReleaseStep for DeployDocs {
+    fn name(&self) -> &'static str {
+        "deploy-docs"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+        ]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        let docs_config = ctx.release_docs_config()?;
+        Ok(tr!(
+            "Deploying documentation to `{branch}` branch",
+            branch = docs_config.branch
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Documentation has been deployed"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let docs_config = ctx.release_docs_config()?.clone();
+
+        build_docs().await?;
+
+        let docs_dir = PathBuf::from(ctx.cargo_metadata().target_directory.join("doc"));
+        let index_crate = docs_config
+            .index_crate
+            .clone()
+            .unwrap_or_else(|| ctx.root_crate_name())
+            .replace('-', "_");
+
+        if !docs_dir.join(&index_crate).exists() {
+            bail!(
+                "Documentation for `{}` crate was not generated",
+                index_crate
+            );
+        }
+
+        tokio::fs::write(docs_dir.join("index.html"), index_redirect(&index_crate)).await?;
+        // Prevent GitHub Pages from ignoring rustdoc files starting with underscore
+        tokio::fs::write(docs_dir.join(".nojekyll"), "").await?;
+
+        let message = docs_config
+            .commit_message_template
+            .render(&ctx.text_template_context())?;
+
+        println!(
+            "\tDocumentation from '{}' will be pushed to `{}/{}`",
+            docs_dir.display(),
+            docs_config.remote,
+            docs_config.branch
+        );
+
+        if ctx.is_dry_run() {
+            ctx.would(format!(
+                "git push --force {} <{} contents>:refs/heads/{}",
+                docs_config.remote,
+                docs_dir.display(),
+                docs_config.branch
+            ));
+            return Ok(());
+        }
+
+        push_docs(
+            &docs_dir,
+            &docs_config.remote,
+            &docs_config.branch,
+            &message,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn index_redirect(crate_name: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n\
+        <meta charset=\"utf-8\">\n\
+        <meta http-equiv=\"refresh\" content=\"0; url={0}/index.html\">\n\
+        <link rel=\"canonical\" href=\"{0}/index.html\">\n",
+        crate_name
+    )
+}
+
+async fn build_docs() -> anyhow::Result<()> {
+    let args = ["doc", "--workspace", "--no-deps"];
+    println!("EXEC: cargo {}", args.join(" "));
+
+    let mut cmd = workspace_command("cargo");
+    cmd.args(args);
+    audit::command(&cmd);
+    let result = cmd
+        .spawn()
+        .map_err(|e| anyhow!("Failed to spawn cargo doc: {}", e))?
+        .wait()
+        .await
+        .map_err(|e| anyhow!("Failed to start cargo doc: {}", e))?;
+
+    if !result.success() {
+        bail!("Cargo doc failed");
+    }
+
+    Ok(())
+}
+
+/// Commits `docs_dir` contents as a single parentless commit and force-pushes it
+/// to the target branch. A temporary index is used, so the user's worktree and
+/// index are never touched.
+async fn push_docs(
+    docs_dir: &Path,
+    remote: &str,
+    branch: &str,
+    message: &str,
+) -> anyhow::Result<()> {
+    let mut git_dir = workspace_command("git");
+    git_dir.args(["rev-parse", "--absolute-git-dir"]);
+    let git_dir = run_and_capture_stdout(&mut git_dir)
+        .await
+        .with_context(|| "Failed to locate git directory")?;
+    let git_dir = git_dir.trim();
+    let index_file = docs_dir.with_file_name("monorepo-docs.index");
+
+    let docs_git = |args: &[&str]| {
+        let mut cmd = workspace_command("git");
+        cmd.current_dir(docs_dir)
+            .env("GIT_INDEX_FILE", &index_file)
+            .args(["--git-dir", git_dir, "--work-tree", "."])
+            .args(args);
+        cmd
+    };
+
+    run_and_capture_stdout(&mut docs_git(&["add", "--all", "--force"]))
+        .await
+        .with_context(|| "Failed to stage documentation files")?;
+    let tree = run_and_capture_stdout(&mut docs_git(&["write-tree"]))
+        .await
+        .with_context(|| "Failed to write documentation tree")?;
+    let commit =
+        run_and_capture_stdout(&mut docs_git(&["commit-tree", tree.trim(), "-m", message]))
+            .await
+            .with_context(|| "Failed to create documentation commit")?;
+
+    let _ = tokio::fs::remove_file(&index_file).await;
+
+    let refspec = format!("{}:refs/heads/{}", commit.trim(), branch);
+    let mut push = workspace_command("git");
+    push.args(["push", "--force", remote, &refspec]);
+    run_and_capture_stdout(&mut push)
+        .await
+        .with_context(|| format!("Failed to push documentation to `{}` branch", branch))?;
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use super::readme::readme_path;
+use crate::{
+    explain::ErrorCode,
+    i18n::tr,
+    release::{ReleaseContext, ReleaseStep, ResourceId, StepKind},
+};
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+
+/// Checks that every feature of the published crates is discoverable by users
+pub struct ValidateFeatureDocs;
+
+/// Features which are neither listed in `[package.metadata.features]` nor
+/// mentioned in the README
+fn undocumented_features<'a>(
+    features: impl IntoIterator<Item = &'a String>,
+    package_metadata: &serde_json::Value,
+    readme: Option<&str>,
+    ignore: &[String],
+) -> Vec<&'a str> {
+    let metadata_features = package_metadata.get("features");
+    features
+        .into_iter()
+        .filter(|f| f.as_str() != "default" && !ignore.contains(f))
+        .filter(|f| metadata_features.and_then(|m| m.get(f.as_str())).is_none())
+        .filter(|f| !readme.is_some_and(|r| r.contains(&format!("`{}`", f))))
+        .map(String::as_str)
+        .collect()
+}
+
+#[async_trait]
+impl ReleaseStep for ValidateFeatureDocs {
+    fn name(&self) -> &'static str {
+        "validate-feature-docs"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Metadata>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Checking that crate features are documented"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("All crate features are documented"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let check = ctx.release_feature_docs_config()?;
+
+        let mut undocumented_found = false;
+        for p in ctx.packages_to_publish() {
+            let readme = match readme_path(p).filter(|_| check.search_readme) {
+                Some(path) => tokio::fs::read_to_string(&path).await.ok(),
+                None => None,
+            };
+            let undocumented = undocumented_features(
+                p.features.keys(),
+                &p.metadata,
+                readme.as_deref(),
+                &check.ignore,
+            );
+            if undocumented.is_empty() {
+                println!("\t✅ {}", p.name);
+            } else {
+                println!(
+                    "\t❌ {} has undocumented features {:?}",
+                    p.name, undocumented
+                );
+                undocumented_found = true;
+            }
+        }
+
+        if undocumented_found {
+            return Err(ErrorCode::UndocumentedFeatures.error(
+                "Undocumented features found; describe them in `[package.metadata.features]` \
+                or in the crate README",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn documented_features() {
+        let features = vec!["default", "tls", "gzip", "serde", "unstable"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        let metadata = serde_json::json!({
+            "features": { "tls": "TLS support via rustls" }
+        });
+        let readme = "Enable `serde` feature to derive Serialize";
+
+        expect![[r#"
+            [
+                "gzip",
+            ]
+        "#]]
+        .assert_debug_eq(&undocumented_features(
+            &features,
+            &metadata,
+            Some(readme),
+            &["unstable".to_owned()],
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+use super::init::get_current_commit;
+use crate::{
+    audit,
+    config::GeneratedFile,
+    i18n::tr,
+    release::{
+        resource::{CurrentCommit, GeneratedArtifacts, PendingVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command, workspace_path},
+};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+
+/// Renders `[[generated-file]]` files; committed files are written by the
+/// `commit-generated-files` release step, so their artifact copies are
+/// rendered to the target directory instead of touching tracked files
+pub struct RenderGeneratedFiles;
+
+/// Writes `[[generated-file]]` files with `commit = true`, then commits and
+/// pushes them, so the released commit contains them
+pub struct CommitGeneratedFiles;
+
+fn remotes<'a>(files: &[&'a GeneratedFile]) -> Vec<&'a str> {
+    let mut remotes = files.iter().map(|f| f.remote.as_str()).collect::<Vec<_>>();
+    remotes.sort_unstable();
+    remotes.dedup();
+    remotes
+}
+
+#[async_trait]
+impl ReleaseStep for RenderGeneratedFiles {
+    fn name(&self) -> &'static str {
+        "render-generated-files"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<GeneratedArtifacts>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Rendering generated files"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Generated files have been rendered"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let context = ctx.text_template_context();
+        let generated_dir = ctx
+            .cargo_metadata()
+            .target_directory
+            .join("monorepo")
+            .join("generated")
+            .into_std_path_buf();
+
+        let mut artifacts = vec![];
+        for file in &ctx.config.generated_files {
+            let content = file.template.render(&context)?;
+            let path = if file.commit {
+                if !file.artifact {
+                    continue;
+                }
+                let name = file.path.file_name().ok_or_else(|| {
+                    anyhow!("Invalid generated file path {}", file.path.display())
+                })?;
+                generated_dir.join(name)
+            } else {
+                workspace_path(&file.path)
+            };
+
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::write(&path, content)
+                .await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("\tRendered {}", path.display());
+
+            if file.artifact {
+                artifacts.push(path);
+            }
+        }
+
+        ctx.provide(GeneratedArtifacts(artifacts))
+    }
+}
+
+#[async_trait]
+impl ReleaseStep for CommitGeneratedFiles {
+    fn name(&self) -> &'static str {
+        "commit-generated-files"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<CurrentCommit>(),
+        ]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Committing generated files"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Generated files are up to date"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let context = ctx.text_template_context();
+        let message = format!(
+            "Update generated files for {} v{}",
+            context.root_crate, context.version
+        );
+
+        let mut changed = vec![];
+        for file in ctx.config.generated_files.iter().filter(|f| f.commit) {
+            let path = workspace_path(&file.path);
+            let content = file.template.render(&context)?;
+            let current = tokio::fs::read_to_string(&path).await.ok();
+            if current.as_deref() == Some(content.as_str()) {
+                println!("\t{} is up to date", file.path.display());
+                continue;
+            }
+            if ctx.is_dry_run() {
+                ctx.would(format!("write {}", file.path.display()));
+                changed.push(file);
+                continue;
+            }
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::write(&path, content)
+                .await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("\t{} has been written", file.path.display());
+            changed.push(file);
+        }
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        if ctx.is_dry_run() {
+            ctx.would(format!("git commit -m \"{}\"", message));
+            for remote in remotes(&changed) {
+                ctx.would(format!("git push {} HEAD", remote));
+            }
+            return Ok(());
+        }
+
+        // New files should be added before they can be committed by path
+        let mut add = workspace_command("git");
+        add.args(["add", "--"]);
+        for file in &changed {
+            add.arg(workspace_path(&file.path));
+        }
+        run_and_capture_stdout(&mut add)
+            .await
+            .with_context(|| "Failed to add generated files")?;
+
+        let mut commit = workspace_command("git");
+        commit.args(["commit", "--message", &message, "--"]);
+        for file in &changed {
+            commit.arg(workspace_path(&file.path));
+        }
+        audit::command(&commit);
+        run_and_capture_stdout(&mut commit)
+            .await
+            .with_context(|| "Failed to commit generated files")?;
+
+        for remote in remotes(&changed) {
+            let mut push = workspace_command("git");
+            push.args(["push", remote, "HEAD"]);
+            audit::command(&push);
+            run_and_capture_stdout(&mut push)
+                .await
+                .with_context(|| format!("Failed to push generated files to {}", remote))?;
+        }
+        ctx.set_release_commit(get_current_commit().await?);
+        println!("\tGenerated files have been committed and pushed");
+
+        Ok(())
+    }
+}
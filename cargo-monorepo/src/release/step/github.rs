@@ -1,35 +1,102 @@
-use crate::release::{ReleaseContext, ReleaseStep};
-use crate::{github::upload_github_release_asset, utils::shorten_commit};
+use crate::{
+    audit,
+    github::{upload_release_artifact, CreatedReleasePage, ReleaseAsset},
+};
+use crate::{
+    i18n::tr,
+    release::{
+        resource::{
+            CreatedRelease, CurrentCommit, GithubTag, PendingVersion, PrevRelease, PrevReleasePage,
+            PrevVersion, UploadedAssets,
+        },
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+};
 use anyhow::Context;
 use async_trait::async_trait;
-use octocrab::params::repos::Reference;
+use octocrab::Octocrab as GithubClient;
+use octocrab::{models::repos::Release, params::repos::Reference};
 
-pub struct ValidateCommitPushedToGithub;
+/// Fetches the release page of the previous version, so its assets and URL
+/// are available to the release page templates
+pub struct FetchPrevRelease;
 
 #[async_trait]
-impl ReleaseStep for ValidateCommitPushedToGithub {
-    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
-        let github_config = ctx.github_config()?;
-        let commit = shorten_commit(ctx.current_commit()?);
-        Ok(format!(
-            "Checking that commit {} is pushed to {}",
-            commit, github_config.repo
-        ))
+impl ReleaseStep for FetchPrevRelease {
+    fn name(&self) -> &'static str {
+        "fetch-prev-release"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<GithubClient>(),
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<PrevVersion>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<PrevRelease>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Fetching the previous GitHub release"))
     }
 
     fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
-        Ok("Success! Current commit is pushed to the remote".to_owned())
+        Ok(tr!("Previous GitHub release has been fetched"))
     }
 
-    async fn execute(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
-        let repo = ctx.github_config()?.repo.clone();
-        let commit = ctx.current_commit()?;
-        ctx.github_client()?
-            .repos(repo.owner, repo.name)
-            .combined_status_for_ref(&Reference::Commit(commit.clone()))
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let tag = match ctx.prev_release_tag()? {
+            Some(tag) => tag,
+            None => {
+                println!("\tPrevious version is unknown");
+                return ctx.provide(PrevRelease(None));
+            }
+        };
+
+        let repo = &ctx.github_config()?.repo;
+        audit::api_call(
+            "GET",
+            &format!("repos/{}/{}/releases/tags/{}", repo.owner, repo.name, tag),
+        );
+        // Previous version could be released without the release page
+        let release = match ctx
+            .github_client()
+            .repos(&repo.owner, &repo.name)
+            .releases()
+            .get_by_tag(&tag)
             .await
-            .with_context(|| "Current commit is missing in the GitHub remote")?;
-        Ok(())
+        {
+            Ok(release) => release,
+            Err(e) => {
+                println!("\tWARN: previous release `{}` is not found: {}", tag, e);
+                return ctx.provide(PrevRelease(None));
+            }
+        };
+
+        println!(
+            "\tPrevious release `{}` has {} asset(s)",
+            tag,
+            release.assets.len()
+        );
+        ctx.provide(PrevRelease(Some(PrevReleasePage {
+            html_url: release.html_url.to_string(),
+            assets: release
+                .assets
+                .into_iter()
+                .map(|asset| ReleaseAsset {
+                    name: asset.name,
+                    size: asset.size.max(0) as u64,
+                    browser_download_url: asset.browser_download_url.to_string(),
+                })
+                .collect(),
+        })))
     }
 }
 
@@ -37,35 +104,69 @@ pub struct CreateTagOnGithub;
 
 #[async_trait]
 impl ReleaseStep for CreateTagOnGithub {
+    fn name(&self) -> &'static str {
+        "create-tag"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<GithubClient>(),
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<CurrentCommit>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<GithubTag>()]
+    }
+
     fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
-        let version = ctx.version()?;
-        Ok(format!("Creating new tag for version {}", version))
+        let version = ctx.version();
+        Ok(tr!(
+            "Creating new tag for version {version}",
+            version = version
+        ))
     }
 
     fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
-        Ok("Tag has been created".to_owned())
+        Ok(tr!("Tag has been created"))
     }
 
-    async fn execute(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
-        let tempalte_context = ctx.text_template_context()?;
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let tempalte_context = ctx.text_template_context();
 
         let tag = ctx
             .release_github_config()?
             .tag_name_template
             .render(&tempalte_context)?;
-        ctx.set_github_release_tag(tag.clone());
+        ctx.provide(GithubTag(tag.clone()))?;
+        if ctx.release_tag_exists() {
+            println!("\t Tag `{}` has been created before the release", tag);
+            return Ok(());
+        }
 
         let repo = ctx.github_config()?.repo.clone();
-        let commit = ctx.current_commit()?;
+        let commit = ctx.current_commit();
 
         println!("\t Tag `{}` will be created for commit {}", tag, commit);
 
         if ctx.is_dry_run() {
-            println!("Skipping tag creation in dry run mode");
+            ctx.would(format!(
+                "POST repos/{}/{}/git/refs (ref: refs/tags/{}, sha: {})",
+                repo.owner, repo.name, tag, commit
+            ));
             return Ok(());
         }
 
-        ctx.github_client()?
+        audit::api_call(
+            "POST",
+            &format!("repos/{}/{}/git/refs", repo.owner, repo.name),
+        );
+        ctx.github_client()
             .repos(repo.owner, repo.name)
             .create_ref(&Reference::Tag(tag), commit)
             .await
@@ -79,17 +180,42 @@ pub struct CreateGithubRelease;
 
 #[async_trait]
 impl ReleaseStep for CreateGithubRelease {
+    fn name(&self) -> &'static str {
+        "create-github-release"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<GithubClient>(),
+            ResourceId::of::<GithubTag>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<UploadedAssets>(),
+            ResourceId::of::<CreatedRelease>(),
+        ]
+    }
+
     fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
-        let tag = ctx.github_release_tag()?;
-        Ok(format!("Creating new GitHub release for tag `{}`", tag))
+        let tag = ctx.github_release_tag();
+        Ok(tr!(
+            "Creating new GitHub release for tag `{tag}`",
+            tag = tag
+        ))
     }
 
     fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
-        Ok("GitHub release has been created".to_owned())
+        Ok(tr!("GitHub release has been created"))
     }
 
-    async fn execute(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
-        let tempalte_context = ctx.text_template_context()?;
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let tempalte_context = ctx.text_template_context();
 
         let title = ctx
             .release_github_config()?
@@ -102,7 +228,7 @@ impl ReleaseStep for CreateGithubRelease {
             .render(&tempalte_context)?;
 
         let repo = ctx.github_config()?.repo.clone();
-        let tag = ctx.github_release_tag()?;
+        let tag = ctx.github_release_tag();
 
         if ctx.release_github_config()?.print_to_stdout {
             println!("GitHub release title:");
@@ -112,34 +238,104 @@ impl ReleaseStep for CreateGithubRelease {
         }
 
         if ctx.is_dry_run() {
-            println!("Skipping GitHub release creation in dry run mode");
+            ctx.would(format!(
+                "POST repos/{}/{}/releases (tag_name: {}, name: {})",
+                repo.owner, repo.name, tag, title
+            ));
+            if ctx.release_github_config()?.release_page_upload_artifacts {
+                for artifact in ctx.release_artifacts() {
+                    ctx.would(format!(
+                        "upload {} to GitHub release `{}`",
+                        artifact.display(),
+                        tag
+                    ));
+                }
+            }
+            ctx.provide(UploadedAssets(vec![]))?;
+            ctx.provide(CreatedRelease(None))?;
             return Ok(());
         }
 
-        let release = ctx
-            .github_client()?
-            .repos(&repo.owner, &repo.name)
-            .releases()
-            .create(&tag)
-            .name(&title)
-            .body(&body)
-            .draft(false)
-            .prerelease(false)
-            .send()
-            .await
-            .with_context(|| "Failed to create GitHub release")?;
+        let release: Release = if ctx.maintenance_line.is_some() {
+            // Maintenance releases should not replace the latest release of
+            // the newest version line; `make_latest` is not supported by the
+            // octocrab release builder yet
+            let route = format!("repos/{}/{}/releases", repo.owner, repo.name);
+            let request = serde_json::json!({
+                "tag_name": tag,
+                "name": title,
+                "body": body,
+                "draft": false,
+                "prerelease": false,
+                "make_latest": "false",
+            });
+            audit::api_call("POST", &route);
+            ctx.github_client()
+                .post(route, Some(&request))
+                .await
+                .with_context(|| "Failed to create GitHub release")?
+        } else {
+            audit::api_call(
+                "POST",
+                &format!("repos/{}/{}/releases", repo.owner, repo.name),
+            );
+            ctx.github_client()
+                .repos(&repo.owner, &repo.name)
+                .releases()
+                .create(&tag)
+                .name(&title)
+                .body(&body)
+                .draft(false)
+                .prerelease(false)
+                .send()
+                .await
+                .with_context(|| "Failed to create GitHub release")?
+        };
 
-        if ctx.release_github_config()?.release_page_upload_artifacts
-            && !ctx.artifacts()?.is_empty()
-        {
-            let artifacts = ctx.artifacts()?.to_vec();
-            for artifact in artifacts {
+        let mut assets = vec![];
+        let release_github = ctx.release_github_config()?;
+        if release_github.release_page_upload_artifacts {
+            for artifact in ctx.release_artifacts() {
                 println!("Uploading release artifact {}", artifact.display());
-                upload_github_release_asset(ctx.github_client()?, &repo, release.id, &artifact)
-                    .await?;
+                let uploaded = upload_release_artifact(
+                    ctx.github_client(),
+                    &repo,
+                    release.id,
+                    &artifact,
+                    release_github.large_assets.as_ref(),
+                )
+                .await?;
+                ctx.record_upload(std::fs::metadata(&artifact)?.len());
+                assets.extend(uploaded);
             }
         }
+        let has_assets = !assets.is_empty();
+        ctx.provide(UploadedAssets(assets))?;
 
-        Ok(())
+        // Download links are known only after the upload, so the body is
+        // rendered once more and updated if it references them
+        if has_assets {
+            let updated_body = ctx
+                .release_github_config()?
+                .release_page_body_template
+                .render(&ctx.text_template_context())?;
+            if updated_body != body {
+                let route = format!(
+                    "repos/{}/{}/releases/{}",
+                    repo.owner, repo.name, release.id.0
+                );
+                audit::api_call("PATCH", &route);
+                let _: serde_json::Value = ctx
+                    .github_client()
+                    .patch(route, Some(&serde_json::json!({ "body": updated_body })))
+                    .await
+                    .with_context(|| "Failed to update GitHub release body")?;
+            }
+        }
+
+        ctx.provide(CreatedRelease(Some(CreatedReleasePage {
+            id: release.id.0,
+            html_url: release.html_url.to_string(),
+        })))
     }
 }
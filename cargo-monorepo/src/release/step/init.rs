@@ -1,30 +1,110 @@
 use crate::{
-    release::{ReleaseContext, ReleaseStep},
-    utils::run_and_capture_stdout,
+    config::{Release, VersionSource},
+    github::{missing_permissions, query_token_access, Repo, TokenPermission, GITHUB_TOKEN_VAR},
+    i18n::tr,
+    registry::registry_token_var,
+    release::{
+        channel::nightly_version,
+        conventional::ConventionalBump,
+        resource::{CurrentCommit, PendingVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{
+        copy_dir_all, metadata_command, run_and_capture_stdout, workspace_command, workspace_path,
+    },
 };
 use anyhow::{anyhow, bail, Context};
 use async_trait::async_trait;
-use cargo_metadata::{Metadata, MetadataCommand};
+use cargo_metadata::Metadata;
+use octocrab::Octocrab as GithubClient;
+use semver::Version;
 use std::env;
-use tokio::process::Command;
 
-pub struct Init;
+pub struct Init {
+    github: bool,
+}
 
 impl Init {
-    async fn acquire_tokens(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
-        let registry = ctx.release_config()?.registry.clone();
-        let crates_io_token = get_crate_registry_token(registry)?;
-        ctx.crates_io_token = Some(crates_io_token);
-
-        if ctx.config.github.is_some() {
-            let github_token = get_github_token()?;
-            ctx.set_github_token(github_token)?;
+    pub fn new(github: bool) -> Self {
+        Self { github }
+    }
+
+    /// Tokens are optional for the dry run, nothing is changed remotely
+    async fn acquire_tokens(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        if ctx.offline {
+            println!("\tTokens are not used in offline mode");
+            if self.github {
+                ctx.set_anonymous_github_client()?;
+            }
+            return Ok(());
+        }
+
+        if !ctx.github_only {
+            let registry = ctx.release_config()?.registry.clone();
+            match get_crate_registry_token(registry) {
+                Ok(token) => ctx.set_crates_io_token(token)?,
+                Err(e) if ctx.is_dry_run() => println!("\t{:#}, continuing dry run without it", e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if self.github {
+            match get_github_token() {
+                Ok(token) => {
+                    ctx.set_github_token(token)?;
+                    self.verify_github_token(ctx).await?;
+                }
+                Err(e) if ctx.is_dry_run() => {
+                    println!("\t{:#}, continuing dry run with anonymous access", e);
+                    ctx.set_anonymous_github_client()?;
+                }
+                Err(e) => return Err(e),
+            }
         }
 
         Ok(())
     }
 
-    async fn process_git_state(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
+    /// Fails fast instead of getting 403 halfway through the release
+    async fn verify_github_token(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let mut required: Vec<(&Repo, Vec<TokenPermission>)> = vec![(
+            &ctx.github_config()?.repo,
+            vec![TokenPermission::ContentsWrite],
+        )];
+        let website_pr = ctx
+            .release_config()?
+            .announcement
+            .as_ref()
+            .and_then(|a| a.pull_request.as_ref());
+        if let Some(pr) = website_pr {
+            required.push((
+                &pr.repo,
+                vec![
+                    TokenPermission::ContentsWrite,
+                    TokenPermission::PullRequestsWrite,
+                ],
+            ));
+        }
+
+        for (repo, permissions) in required {
+            let access = query_token_access(ctx.github_client(), repo).await?;
+            let missing = missing_permissions(&access, &permissions);
+            if !missing.is_empty() {
+                let missing = missing.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                bail!(
+                    "{} token lacks {} permission(s) for {} repo",
+                    GITHUB_TOKEN_VAR,
+                    missing.join(", "),
+                    repo
+                );
+            }
+            println!("\tGitHub token has required permissions for {}", repo);
+        }
+
+        Ok(())
+    }
+
+    async fn process_git_state(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
         if !git_installed().await {
             bail!("git is missing");
         }
@@ -32,11 +112,47 @@ impl Init {
             .await
             .with_context(|| "Failed to get current git commit")?;
         println!("\tCurrent commit is {}", current_commit);
-        ctx.current_commit = Some(current_commit);
+        ctx.provide(CurrentCommit(current_commit))?;
+        Ok(())
+    }
+
+    /// Fresh cache dir is primed with the previously saved cache, e.g. CI
+    /// cache restored to a different location
+    fn prime_cache(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let release = ctx.release_config()?;
+        let (cache_dir, prime_from) = match (&release.cache_dir, &release.cache_prime_from) {
+            (Some(cache_dir), Some(prime_from)) => {
+                (workspace_path(cache_dir), workspace_path(prime_from))
+            }
+            _ => return Ok(()),
+        };
+        if cache_dir.exists() {
+            println!("\tUsing build cache {}", cache_dir.display());
+            return Ok(());
+        }
+        if !prime_from.is_dir() {
+            println!(
+                "\tBuild cache {} is missing, starting with an empty one",
+                prime_from.display()
+            );
+            return Ok(());
+        }
+        copy_dir_all(&prime_from, &cache_dir).with_context(|| {
+            format!(
+                "Failed to prime build cache {} from {}",
+                cache_dir.display(),
+                prime_from.display()
+            )
+        })?;
+        println!(
+            "\tBuild cache {} has been primed from {}",
+            cache_dir.display(),
+            prime_from.display()
+        );
         Ok(())
     }
 
-    async fn process_metadata(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
+    async fn process_metadata(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
         let medatada = query_metadata().await?;
         let root_crate_name = ctx.root_crate_name();
 
@@ -51,13 +167,21 @@ impl Init {
                 )
             })?;
 
-        let version = root_package.version.clone();
+        let (mut version, source) =
+            resolve_version(ctx.release_config()?, &root_package.version).await?;
+        if let Some(source) = source {
+            println!("\t{}", source);
+        }
+        if ctx.is_nightly() {
+            let today = chrono::Utc::now().date_naive();
+            version = nightly_version(&version, today, &ctx.current_commit());
+        }
         println!(
             "\tPending version of {} to release is {}",
             root_crate_name, version
         );
-        ctx.metadata = Some(medatada);
-        ctx.version = Some(version);
+        ctx.provide(medatada)?;
+        ctx.provide(PendingVersion(version))?;
 
         Ok(())
     }
@@ -65,31 +189,51 @@ impl Init {
 
 #[async_trait]
 impl ReleaseStep for Init {
+    fn name(&self) -> &'static str {
+        "init"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        let mut provides = vec![
+            ResourceId::of::<CurrentCommit>(),
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+        ];
+        if self.github {
+            provides.push(ResourceId::of::<GithubClient>());
+        }
+        provides
+    }
+
     fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
-        Ok(format!(
-            "Initializing release process for {}",
-            ctx.config.workspace.root_crate
+        Ok(tr!(
+            "Initializing release process for {name}",
+            name = ctx.config.workspace.root_crate
         ))
     }
 
     fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
-        Ok("Initialization completed".to_owned())
+        Ok(tr!("Initialization completed"))
     }
 
-    async fn execute(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
         self.acquire_tokens(ctx).await?;
         self.process_git_state(ctx).await?;
         self.process_metadata(ctx).await?;
+        self.prime_cache(ctx)?;
         Ok(())
     }
 }
 
 fn get_github_token() -> anyhow::Result<String> {
-    const VAR_NAME: &str = "GITHUB_TOKEN";
-    let var = env::var(VAR_NAME).with_context(|| {
+    let var = env::var(GITHUB_TOKEN_VAR).with_context(|| {
         format!(
             "GitHub token is missing, please provide it via {} env var",
-            VAR_NAME
+            GITHUB_TOKEN_VAR
         )
     })?;
 
@@ -97,12 +241,7 @@ fn get_github_token() -> anyhow::Result<String> {
 }
 
 fn get_crate_registry_token(registry: Option<String>) -> anyhow::Result<String> {
-    use convert_case::{Case, Casing};
-
-    let var_name = registry
-        .as_ref()
-        .map(|r| format!("CARGO_REGISTRIES_{}_TOKEN", r.to_case(Case::UpperSnake)))
-        .unwrap_or_else(|| "CARGO_REGISTRY_TOKEN".to_owned());
+    let var_name = registry_token_var(registry.as_deref());
 
     let token = env::var(&var_name).with_context(|| {
         format!(
@@ -114,20 +253,64 @@ fn get_crate_registry_token(registry: Option<String>) -> anyhow::Result<String>
     Ok(token)
 }
 
+/// Stable version to release from the configured version source, along with
+/// the explanation where it comes from if it is not the manifest version
+pub async fn resolve_version(
+    release: &Release,
+    manifest_version: &Version,
+) -> anyhow::Result<(Version, Option<String>)> {
+    match (&release.version_command, release.version_from) {
+        (Some(command), _) => {
+            let version = query_version(command).await?;
+            let source = format!("Version command `{}` returned `{}`", command, version);
+            Ok((version, Some(source)))
+        }
+        (None, VersionSource::ConventionalCommits) => {
+            let inferred = ConventionalBump::query().await?;
+            let source = format!(
+                "Commits since `{}` require {:?} bump of {}",
+                inferred.tag, inferred.bump, inferred.prev_version
+            );
+            Ok((inferred.version(), Some(source)))
+        }
+        (None, VersionSource::Manifest) => Ok((manifest_version.clone(), None)),
+    }
+}
+
+/// Runs external version source, its trimmed stdout should be a semver version
+async fn query_version(command: &str) -> anyhow::Result<Version> {
+    let mut args = command.split_whitespace();
+    let program = args
+        .next()
+        .ok_or_else(|| anyhow!("release.version_command is empty"))?;
+    let mut cmd = workspace_command(program);
+    cmd.args(args);
+    let stdout = run_and_capture_stdout(&mut cmd)
+        .await
+        .with_context(|| format!("Failed to run version command `{}`", command))?;
+    let version = stdout.trim();
+    Version::parse(version).with_context(|| {
+        format!(
+            "Version command `{}` returned `{}`, which is not a valid semver version",
+            command, version
+        )
+    })
+}
+
 async fn git_installed() -> bool {
-    let mut cmd = Command::new("git");
+    let mut cmd = workspace_command("git");
     cmd.arg("--version");
     run_and_capture_stdout(&mut cmd).await.is_ok()
 }
 
 async fn query_metadata() -> anyhow::Result<Metadata> {
-    MetadataCommand::new()
+    metadata_command()
         .exec()
         .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))
 }
 
-async fn get_current_commit() -> anyhow::Result<String> {
-    let mut cmd = Command::new("git");
+pub async fn get_current_commit() -> anyhow::Result<String> {
+    let mut cmd = workspace_command("git");
     cmd.args(["rev-parse", "--verify", "HEAD"]);
     run_and_capture_stdout(&mut cmd)
         .await
@@ -0,0 +1,137 @@
+use crate::{
+    audit,
+    i18n::tr,
+    release::{
+        resource::{CurrentCommit, HeldReleaseLock},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+};
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use octocrab::Octocrab as GithubClient;
+use serde_json::json;
+
+/// Creates the lock ref before any remote state is changed; ref creation is
+/// atomic on GitHub side, so only one of the concurrent releases succeeds
+pub struct AcquireReleaseLock;
+
+#[async_trait]
+impl ReleaseStep for AcquireReleaseLock {
+    fn name(&self) -> &'static str {
+        "acquire-release-lock"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<GithubClient>(),
+            ResourceId::of::<CurrentCommit>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<HeldReleaseLock>()]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        let lock_ref = &ctx.release_lock_config()?.lock_ref;
+        Ok(tr!(
+            "Acquiring release lock `{lock_ref}`",
+            lock_ref = lock_ref
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Release lock has been acquired"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let lock_ref = ctx.release_lock_config()?.lock_ref.clone();
+        let repo = &ctx.github_config()?.repo;
+        let github = ctx.github_client();
+
+        // Singular `ref` endpoint matches the exact ref only
+        let ref_route = format!(
+            "repos/{}/{}/git/ref/{}",
+            repo.owner,
+            repo.name,
+            lock_ref.trim_start_matches("refs/")
+        );
+        audit::api_call("GET", &ref_route);
+        let holder = github
+            .get::<serde_json::Value, _, _>(&ref_route, None::<&()>)
+            .await
+            .ok();
+        if let Some(holder) = holder {
+            bail!(
+                "Another release is in progress: `{}` is held for commit {}; \
+                delete the ref if the release has been interrupted",
+                lock_ref,
+                holder["object"]["sha"].as_str().unwrap_or("<unknown>")
+            );
+        }
+
+        if ctx.is_dry_run() {
+            ctx.would(format!(
+                "POST repos/{}/{}/git/refs (ref: {}, sha: {})",
+                repo.owner,
+                repo.name,
+                lock_ref,
+                ctx.current_commit()
+            ));
+            return ctx.provide(HeldReleaseLock(None));
+        }
+
+        let route = format!("repos/{}/{}/git/refs", repo.owner, repo.name);
+        audit::api_call("POST", &route);
+        let _: serde_json::Value = github
+            .post(
+                route,
+                Some(&json!({ "ref": lock_ref, "sha": ctx.current_commit() })),
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to acquire `{}`, another release may be in progress",
+                    lock_ref
+                )
+            })?;
+
+        ctx.provide(HeldReleaseLock(Some(lock_ref)))
+    }
+}
+
+/// Deletes the lock ref if it has been acquired by this release; called when
+/// the release is finished, successfully or not
+pub async fn release_lock(ctx: &ReleaseContext) -> anyhow::Result<()> {
+    let lock_ref = match ctx.resources().try_get::<HeldReleaseLock>() {
+        Some(HeldReleaseLock(Some(lock_ref))) => lock_ref,
+        _ => return Ok(()),
+    };
+    let repo = &ctx.github_config()?.repo;
+    let github = ctx
+        .resources()
+        .try_get::<GithubClient>()
+        .ok_or_else(|| anyhow!("BUG: release lock is held without GitHub client"))?;
+
+    // `refs/<name>` path is the same as the fully qualified ref name
+    let route = format!("repos/{}/{}/git/{}", repo.owner, repo.name, lock_ref);
+    audit::api_call("DELETE", &route);
+    let response = github
+        ._delete(github.absolute_url(&route)?, None::<&()>)
+        .await
+        .with_context(|| format!("Failed to release `{}`", lock_ref))?;
+    if !response.status().is_success() {
+        bail!(
+            "Failed to release `{}`: server responded with {}",
+            lock_ref,
+            response.status()
+        );
+    }
+
+    println!("🔓 Release lock `{}` has been released", lock_ref);
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::{
+    explain::ErrorCode,
+    i18n::tr,
+    release::{ReleaseContext, ReleaseStep, ResourceId, StepKind},
+};
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+
+/// Checks that every published crate has the keywords and categories required
+/// by the workspace policy, so the crates are discoverable as a family
+pub struct ValidateCrateMetadata;
+
+fn missing<'a>(required: &'a [String], actual: &[String]) -> Vec<&'a str> {
+    required
+        .iter()
+        .filter(|r| !actual.contains(r))
+        .map(String::as_str)
+        .collect()
+}
+
+#[async_trait]
+impl ReleaseStep for ValidateCrateMetadata {
+    fn name(&self) -> &'static str {
+        "validate-crate-metadata"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Metadata>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Checking crate keywords and categories"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("All crates have required keywords and categories"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let policy = ctx.release_crate_metadata_config()?;
+
+        let mut offenders = vec![];
+        for p in ctx.packages_to_publish() {
+            let keywords = missing(&policy.required_keywords, &p.keywords);
+            let categories = missing(&policy.required_categories, &p.categories);
+            if keywords.is_empty() && categories.is_empty() {
+                println!("\t✅ {}", p.name);
+                continue;
+            }
+
+            let mut problems = vec![];
+            if !keywords.is_empty() {
+                problems.push(format!("missing keywords {:?}", keywords));
+            }
+            if !categories.is_empty() {
+                problems.push(format!("missing categories {:?}", categories));
+            }
+            println!("\t❌ {}: {}", p.name, problems.join(", "));
+            offenders.push(p.name.as_str());
+        }
+
+        if !offenders.is_empty() {
+            return Err(ErrorCode::CrateMetadataPolicy.error(format!(
+                "Crates do not follow keyword/category policy: {}",
+                offenders.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    audit,
+    i18n::tr,
+    registry::download_crate,
+    release::{resource::PublishedCrates, ReleaseContext, ReleaseStep, ResourceId, StepKind},
+};
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use semver::Version;
+use std::{env, path::Path, time::Duration};
+
+pub struct MirrorToRegistry;
+
+#[async_trait]
+impl ReleaseStep for MirrorToRegistry {
+    fn name(&self) -> &'static str {
+        "mirror"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<PublishedCrates>()]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!(
+            "Mirroring published crates to {url}",
+            url = ctx.release_mirror_config()?.url
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Published crates have been mirrored"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let mirror_config = ctx.release_mirror_config()?;
+        let token = mirror_config
+            .token_env
+            .as_ref()
+            .map(|var| {
+                env::var(var).with_context(|| {
+                    format!(
+                        "Mirror token is missing, please specify it via {} env var",
+                        var
+                    )
+                })
+            })
+            .transpose()?;
+
+        let published = &ctx.resources().get::<PublishedCrates>().0;
+        for (name, version) in published {
+            let data = download_published_crate(
+                name,
+                version,
+                mirror_config.download_attempts,
+                Duration::from_secs(mirror_config.retry_interval_seconds),
+            )
+            .await?;
+
+            let url = format!(
+                "{}/{}/{}-{}.crate",
+                mirror_config.url.trim_end_matches('/'),
+                name,
+                name,
+                version
+            );
+            let size = data.len() as u64;
+            audit::upload(Path::new(&format!("{}-{}.crate", name, version)), &url);
+            let mut request = reqwest::Client::new().put(&url).body(data);
+            if let Some(token) = &token {
+                request = request.bearer_auth(token);
+            }
+            let response = request
+                .send()
+                .await
+                .with_context(|| format!("Failed to upload {} v{} to the mirror", name, version))?;
+            if !response.status().is_success() {
+                bail!(
+                    "Failed to upload {} v{} to the mirror: server responded with {}",
+                    name,
+                    version,
+                    response.status()
+                );
+            }
+
+            ctx.record_upload(size);
+            println!("\t{} v{} has been mirrored", name, version);
+        }
+
+        Ok(())
+    }
+}
+
+/// Freshly published crate may not be available for download immediately
+async fn download_published_crate(
+    name: &str,
+    version: &Version,
+    attempts: usize,
+    retry_interval: Duration,
+) -> anyhow::Result<Vec<u8>> {
+    let mut attempt = 1;
+    loop {
+        match download_crate(name, version).await {
+            Ok(data) => return Ok(data),
+            Err(e) if attempt < attempts => {
+                println!(
+                    "\tWARN: {:#}, retrying in {} seconds...",
+                    e,
+                    retry_interval.as_secs()
+                );
+                tokio::time::sleep(retry_interval).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -1,15 +1,73 @@
+mod announcement;
 mod artifacts;
+mod attestation;
+mod c_api;
 mod cargo;
+mod changed;
 mod changelog;
+mod crate_notes;
+mod dedupe;
+mod delta;
+mod docs;
+mod features;
+mod generated;
 mod github;
 mod init;
+mod lock;
+mod metadata;
+mod mirror;
+mod nightly;
+mod notes;
+mod npm;
+mod path_deps;
+mod policy;
+mod post_release;
+mod promote;
+mod pushed;
+mod readme;
+mod releases;
+mod retention;
+mod rustdoc;
+mod tag;
+mod train;
+mod updater;
 mod version;
 
 pub use self::{
-    artifacts::CollectArtifacts,
-    cargo::CargoPublish,
-    changelog::CaptureChangelog,
-    github::{CreateGithubRelease, CreateTagOnGithub, ValidateCommitPushedToGithub},
-    init::Init,
-    version::VaidateVersion,
+    announcement::GenerateAnnouncement,
+    artifacts::{
+        CollectArtifacts, CollectSourceArtifacts, SnapshotDependencies, VerifyReproducibleBuild,
+    },
+    attestation::AttestReleaseAssets,
+    c_api::BuildCApiArtifacts,
+    cargo::{CargoPublish, ValidateNotPublished},
+    changed::DetectChangedCrates,
+    changelog::{CaptureChangelog, ValidateMigrationNotes},
+    crate_notes::CollectCrateNotes,
+    dedupe::RecordCompletedRelease,
+    delta::CreateDeltaPatches,
+    docs::DeployDocs,
+    features::ValidateFeatureDocs,
+    generated::{CommitGeneratedFiles, RenderGeneratedFiles},
+    github::{CreateGithubRelease, CreateTagOnGithub, FetchPrevRelease},
+    init::{get_current_commit, resolve_version, Init},
+    lock::{release_lock, AcquireReleaseLock},
+    metadata::ValidateCrateMetadata,
+    mirror::MirrorToRegistry,
+    nightly::PublishNightlyRelease,
+    notes::AddReleaseNote,
+    npm::PublishNpmPackages,
+    path_deps::ValidatePathDependencies,
+    policy::ValidatePathPolicies,
+    post_release::NotifyServices,
+    promote::PromoteChangelog,
+    pushed::ValidateCommitPushed,
+    readme::{readme_path, ValidateReadme},
+    releases::UpdateReleasesFile,
+    retention::PruneReleaseAssets,
+    rustdoc::ValidateRustdoc,
+    tag::{CreateGitTag, VerifyExternalTag},
+    train::ValidateReleaseTrain,
+    updater::PublishUpdateManifest,
+    version::{versions_consistent, VaidateVersion},
 };
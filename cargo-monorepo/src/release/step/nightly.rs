@@ -0,0 +1,176 @@
+use crate::{
+    audit,
+    github::{delete_github_release_asset, upload_release_artifact},
+    i18n::tr,
+    release::{
+        resource::{CurrentCommit, PendingVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use octocrab::{models::ReleaseId, params::repos::Reference, Octocrab as GithubClient};
+use serde_json::json;
+
+/// Moves rolling nightly tag to the current commit and replaces assets of its
+/// GitHub prerelease with the fresh artifacts
+pub struct PublishNightlyRelease;
+
+#[async_trait]
+impl ReleaseStep for PublishNightlyRelease {
+    fn name(&self) -> &'static str {
+        "publish-nightly-release"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<GithubClient>(),
+            ResourceId::of::<CurrentCommit>(),
+            ResourceId::of::<PendingVersion>(),
+        ]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!(
+            "Publishing nightly prerelease `{tag}`",
+            tag = ctx.release_nightly_config()?.tag
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Nightly prerelease has been published"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let nightly_config = ctx.release_nightly_config()?;
+        let repo = ctx.github_config()?.repo.clone();
+        let template_context = ctx.text_template_context();
+        let title = nightly_config.title_template.render(&template_context)?;
+        let body = nightly_config.body_template.render(&template_context)?;
+        let tag = &nightly_config.tag;
+        let commit = ctx.current_commit();
+        let artifacts = ctx.release_artifacts();
+
+        if ctx.is_dry_run() {
+            ctx.would(format!(
+                "PATCH repos/{}/{}/git/refs/tags/{} (sha: {}, force: true)",
+                repo.owner, repo.name, tag, commit
+            ));
+            ctx.would(format!(
+                "replace GitHub prerelease `{}` with title `{}`",
+                tag, title
+            ));
+            for artifact in &artifacts {
+                ctx.would(format!(
+                    "upload {} to GitHub release `{}`",
+                    artifact.display(),
+                    tag
+                ));
+            }
+            return Ok(());
+        }
+
+        let github = ctx.github_client();
+
+        let ref_route = format!("repos/{}/{}/git/refs/tags/{}", repo.owner, repo.name, tag);
+        audit::api_call("GET", &ref_route);
+        let tag_exists = github
+            .get::<serde_json::Value, _, _>(&ref_route, None::<&()>)
+            .await
+            .is_ok();
+        if tag_exists {
+            audit::api_call("PATCH", &ref_route);
+            let _: serde_json::Value = github
+                .patch(&ref_route, Some(&json!({ "sha": commit, "force": true })))
+                .await
+                .with_context(|| format!("Failed to move `{}` tag", tag))?;
+        } else {
+            audit::api_call(
+                "POST",
+                &format!("repos/{}/{}/git/refs", repo.owner, repo.name),
+            );
+            github
+                .repos(&repo.owner, &repo.name)
+                .create_ref(&Reference::Tag(tag.clone()), &commit)
+                .await
+                .with_context(|| format!("Failed to create `{}` tag", tag))?;
+        }
+        println!("\tTag `{}` now points to {}", tag, commit);
+
+        let release_route = format!("repos/{}/{}/releases/tags/{}", repo.owner, repo.name, tag);
+        audit::api_call("GET", &release_route);
+        let existing = github
+            .get::<serde_json::Value, _, _>(&release_route, None::<&()>)
+            .await
+            .ok();
+
+        let release = match existing {
+            Some(release) => {
+                for asset in release["assets"].as_array().into_iter().flatten() {
+                    let asset_id = asset["id"]
+                        .as_u64()
+                        .ok_or_else(|| anyhow!("Invalid GitHub release asset"))?;
+                    println!("\tDeleting stale asset {}", asset["name"]);
+                    delete_github_release_asset(github, &repo, asset_id).await?;
+                }
+
+                let id = release["id"]
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("Invalid GitHub release"))?;
+                let route = format!("repos/{}/{}/releases/{}", repo.owner, repo.name, id);
+                audit::api_call("PATCH", &route);
+                github
+                    .patch(
+                        &route,
+                        Some(&json!({ "name": title, "body": body, "prerelease": true })),
+                    )
+                    .await
+                    .with_context(|| "Failed to update nightly release")?
+            }
+            None => {
+                let route = format!("repos/{}/{}/releases", repo.owner, repo.name);
+                audit::api_call("POST", &route);
+                github
+                    .post(
+                        &route,
+                        Some(&json!({
+                            "tag_name": tag,
+                            "name": title,
+                            "body": body,
+                            "prerelease": true,
+                        })),
+                    )
+                    .await
+                    .with_context(|| "Failed to create nightly release")?
+            }
+        };
+        let release: serde_json::Value = release;
+        let release_id = release["id"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("Invalid GitHub release"))?;
+
+        let large_assets = ctx
+            .release_config()?
+            .github
+            .as_ref()
+            .and_then(|g| g.large_assets.as_ref());
+        for artifact in artifacts {
+            println!("Uploading release artifact {}", artifact.display());
+            upload_release_artifact(
+                github,
+                &repo,
+                ReleaseId(release_id),
+                &artifact,
+                large_assets,
+            )
+            .await?;
+            ctx.record_upload(std::fs::metadata(&artifact)?.len());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    i18n::tr,
+    release::{
+        resource::{CurrentCommit, PendingVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::Context;
+use async_trait::async_trait;
+
+/// Attaches JSON release report to the release commit via `git notes`
+pub struct AddReleaseNote;
+
+#[async_trait]
+impl ReleaseStep for AddReleaseNote {
+    fn name(&self) -> &'static str {
+        "add-release-note"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<CurrentCommit>(),
+            ResourceId::of::<PendingVersion>(),
+        ]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        let notes_config = ctx.release_git_notes_config()?;
+        Ok(tr!(
+            "Adding release note to `refs/notes/{notes_ref}`",
+            notes_ref = notes_config.notes_ref
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Release note has been added"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let notes_config = ctx.release_git_notes_config()?;
+        let notes_ref = format!("--ref={}", notes_config.notes_ref);
+        let note = ctx.report(true).to_json()?;
+
+        if ctx.is_dry_run() {
+            println!("\tRelease note for {}:", ctx.current_commit());
+            note.lines().for_each(|l| println!("\t{}", l));
+            ctx.would(format!(
+                "git notes {} add --force {}",
+                notes_ref,
+                ctx.current_commit()
+            ));
+            if notes_config.push {
+                ctx.would(format!(
+                    "git push {} refs/notes/{}",
+                    notes_config.remote, notes_config.notes_ref
+                ));
+            }
+            return Ok(());
+        }
+
+        let mut add = workspace_command("git");
+        add.args(["notes", &notes_ref, "add", "--force", "--message", &note])
+            .arg(ctx.current_commit());
+        run_and_capture_stdout(&mut add)
+            .await
+            .with_context(|| "Failed to add release note")?;
+
+        if notes_config.push {
+            let refspec = format!("refs/notes/{}", notes_config.notes_ref);
+            let mut push = workspace_command("git");
+            push.args(["push", &notes_config.remote, &refspec]);
+            run_and_capture_stdout(&mut push)
+                .await
+                .with_context(|| format!("Failed to push `{}`", refspec))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{
+    audit,
+    config::NpmRelease,
+    i18n::tr,
+    release::{resource::PendingVersion, ReleaseContext, ReleaseStep, ResourceId, StepKind},
+    utils::workspace_command,
+};
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+use semver::Version;
+use std::path::Path;
+
+const NPMJS_REGISTRY_URL: &str = "https://registry.npmjs.org/";
+/// npm is installed as a batch script on Windows, which is not resolved
+/// without an explicit extension
+const NPM_PROGRAM: &str = if cfg!(windows) { "npm.cmd" } else { "npm" };
+
+/// Builds wasm crates with `wasm-pack` and publishes them as npm packages
+pub struct PublishNpmPackages;
+
+#[async_trait]
+impl ReleaseStep for PublishNpmPackages {
+    fn name(&self) -> &'static str {
+        "publish-npm"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+        ]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        let npm_config = ctx.release_npm_config()?;
+        Ok(tr!(
+            "Publishing npm packages for {crates}",
+            crates = npm_config.crates.join(", ")
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("npm packages have been published"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let npm_config = ctx.release_npm_config()?;
+        let metadata = ctx.cargo_metadata();
+
+        for name in &npm_config.crates {
+            let package = metadata
+                .packages
+                .iter()
+                .find(|p| metadata.workspace_members.contains(&p.id) && &p.name == name)
+                .ok_or_else(|| anyhow!("Wasm crate `{}` is not found in the workspace", name))?;
+            let crate_dir = package
+                .manifest_path
+                .parent()
+                .ok_or_else(|| anyhow!("Invalid manifest path of `{}` crate", name))?;
+            let out_dir = metadata.target_directory.join("npm").join(name);
+
+            wasm_pack_build(crate_dir.as_std_path(), out_dir.as_std_path(), npm_config).await?;
+            sync_package_version(out_dir.as_std_path(), ctx.version()).await?;
+            tokio::fs::write(out_dir.join(".npmrc"), npmrc(npm_config))
+                .await
+                .with_context(|| "Failed to write .npmrc")?;
+
+            npm_publish(out_dir.as_std_path(), npm_config, ctx.is_dry_run()).await?;
+            if ctx.is_dry_run() {
+                ctx.would(format!(
+                    "npm publish {} ({} v{} to {})",
+                    out_dir,
+                    name,
+                    ctx.version(),
+                    npm_config.registry.as_deref().unwrap_or(NPMJS_REGISTRY_URL)
+                ));
+            } else {
+                println!("\t{} v{} has been published to npm", name, ctx.version());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn wasm_pack_build(
+    crate_dir: &Path,
+    out_dir: &Path,
+    npm_config: &NpmRelease,
+) -> anyhow::Result<()> {
+    let mut cmd = workspace_command("wasm-pack");
+    cmd.arg("build")
+        .arg(crate_dir)
+        .args(["--release", "--target", &npm_config.target, "--out-dir"])
+        .arg(out_dir);
+    if let Some(scope) = &npm_config.scope {
+        cmd.args(["--scope", scope]);
+    }
+
+    println!("EXEC: wasm-pack build {}", crate_dir.display());
+    audit::command(&cmd);
+    let status = cmd
+        .status()
+        .await
+        .map_err(|e| anyhow!("Failed to start wasm-pack: {}", e))?;
+    if !status.success() {
+        bail!("wasm-pack build failed for {}", crate_dir.display());
+    }
+
+    Ok(())
+}
+
+/// npm package version should match the release version even if the crate
+/// version is inherited or differs in the independent release mode
+async fn sync_package_version(out_dir: &Path, version: &Version) -> anyhow::Result<()> {
+    let path = out_dir.join("package.json");
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut package: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    package["version"] = version.to_string().into();
+    tokio::fs::write(&path, serde_json::to_string_pretty(&package)?)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Token is referenced via env var, so it never hits the disk
+fn npmrc(npm_config: &NpmRelease) -> String {
+    let registry = npm_config.registry.as_deref().unwrap_or(NPMJS_REGISTRY_URL);
+    let registry_path = registry
+        .trim_start_matches("https:")
+        .trim_start_matches("http:")
+        .trim_end_matches('/');
+    format!(
+        "registry={}\n{}/:_authToken=${{{}}}\n",
+        registry, registry_path, npm_config.token_env
+    )
+}
+
+async fn npm_publish(out_dir: &Path, npm_config: &NpmRelease, dry_run: bool) -> anyhow::Result<()> {
+    let mut cmd = workspace_command(NPM_PROGRAM);
+    cmd.arg("publish").current_dir(out_dir);
+    if npm_config.public {
+        cmd.args(["--access", "public"]);
+    }
+    if dry_run {
+        cmd.arg("--dry-run");
+    }
+
+    println!("EXEC: npm publish {}", out_dir.display());
+    audit::command(&cmd);
+    let status = cmd
+        .status()
+        .await
+        .map_err(|e| anyhow!("Failed to start npm: {}", e))?;
+    if !status.success() {
+        bail!("npm publish failed for {}", out_dir.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn npmrc_references_token_env() {
+        let npm_config: NpmRelease = toml::from_str(
+            r#"
+crates = ["foo-wasm"]
+registry = "https://npm.example.com/repo/"
+token_env = "EXAMPLE_NPM_TOKEN"
+"#,
+        )
+        .unwrap();
+
+        expect![[r#"
+            registry=https://npm.example.com/repo/
+            //npm.example.com/repo/:_authToken=${EXAMPLE_NPM_TOKEN}
+        "#]]
+        .assert_eq(&npmrc(&npm_config));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    explain::ErrorCode,
+    i18n::tr,
+    release::{ReleaseContext, ReleaseStep, ResourceId, StepKind},
+    utils::normalize_path,
+};
+use async_trait::async_trait;
+use cargo_metadata::{camino::Utf8Path, DependencyKind, Metadata};
+
+/// Checks that published crates do not depend on path crates outside the
+/// workspace; such dependencies would not resolve once published
+pub struct ValidatePathDependencies;
+
+fn is_outside_workspace(path: &Utf8Path, workspace_root: &Utf8Path) -> bool {
+    !normalize_path(path.as_std_path()).starts_with(normalize_path(workspace_root.as_std_path()))
+}
+
+#[async_trait]
+impl ReleaseStep for ValidatePathDependencies {
+    fn name(&self) -> &'static str {
+        "validate-path-dependencies"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Metadata>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Checking path dependencies"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("All path dependencies are within the workspace"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let workspace_root = &ctx.cargo_metadata().workspace_root;
+
+        let mut offenders = vec![];
+        for p in ctx.packages_to_publish() {
+            for dep in &p.dependencies {
+                let path = match &dep.path {
+                    Some(path) => path,
+                    None => continue,
+                };
+                // Versionless dev-dependencies are stripped by `cargo publish`
+                if dep.kind == DependencyKind::Development && dep.req.comparators.is_empty() {
+                    continue;
+                }
+                if is_outside_workspace(path, workspace_root) {
+                    println!("\t❌ {} -> {} ({})", p.name, dep.name, path);
+                    offenders.push(format!("{} -> {}", p.name, dep.name));
+                }
+            }
+        }
+
+        if !offenders.is_empty() {
+            return Err(ErrorCode::OutOfWorkspacePathDependency.error(format!(
+                "Crates depend on path crates outside the workspace: {}",
+                offenders.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn outside_workspace_paths() {
+        let root = Utf8Path::new("/src/foo");
+        let outside = [
+            "/src/foo/crates/foo-core",
+            "/src/foo/crates/../../bar",
+            "/src/foobar",
+            "/src/foo/./vendor/baz",
+        ]
+        .iter()
+        .map(|path| (*path, is_outside_workspace(Utf8Path::new(path), root)))
+        .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                (
+                    "/src/foo/crates/foo-core",
+                    false,
+                ),
+                (
+                    "/src/foo/crates/../../bar",
+                    true,
+                ),
+                (
+                    "/src/foobar",
+                    true,
+                ),
+                (
+                    "/src/foo/./vendor/baz",
+                    false,
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&outside);
+    }
+}
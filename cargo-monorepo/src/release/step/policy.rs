@@ -0,0 +1,181 @@
+use crate::{
+    changelog::has_section,
+    config::PathPolicy,
+    i18n::tr,
+    release::{
+        resource::{self, PrevVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+
+/// Applies `release.path_policies` to the files changed since the previous
+/// release tag
+pub struct ValidatePathPolicies {
+    /// Changelog is captured by the release, so policies can check its
+    /// sections
+    changelog: bool,
+}
+
+impl ValidatePathPolicies {
+    pub fn new(changelog: bool) -> Self {
+        Self { changelog }
+    }
+}
+
+/// Failure messages of the rules violated by the changed files
+fn policy_violations(policies: &[PathPolicy], files: &[&str], changelog: &str) -> Vec<String> {
+    let mut violations = vec![];
+    for policy in policies {
+        let changed = files
+            .iter()
+            .filter(|f| policy.paths.iter().any(|p| p.matches(f)))
+            .collect::<Vec<_>>();
+        let changed = match changed.as_slice() {
+            [] => continue,
+            [file] => file.to_string(),
+            [file, rest @ ..] => format!("{} and {} more", file, rest.len()),
+        };
+        let paths = policy
+            .paths
+            .iter()
+            .map(|p| format!("`{}`", p))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut failures = vec![];
+        if policy.block {
+            failures.push(format!("release is blocked while {} is changed", paths));
+        }
+        if let Some(section) = &policy.require_changelog_section {
+            if !has_section(changelog, section) {
+                failures.push(format!(
+                    "changelog requires `{}` section as {} is changed",
+                    section, paths
+                ));
+            }
+        }
+        for failure in failures {
+            let reason = policy
+                .reason
+                .as_ref()
+                .map(|r| format!(": {}", r))
+                .unwrap_or_default();
+            violations.push(format!("{} ({}){}", failure, changed, reason));
+        }
+    }
+    violations
+}
+
+#[async_trait]
+impl ReleaseStep for ValidatePathPolicies {
+    fn name(&self) -> &'static str {
+        "validate-path-policies"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        let mut requires = vec![ResourceId::of::<PrevVersion>()];
+        if self.changelog {
+            requires.push(ResourceId::of::<resource::Changelog>());
+        }
+        requires
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Checking files changed since the previous release"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Changed files satisfy path policies"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let tag = match ctx.prev_release_tag()? {
+            Some(tag) => tag,
+            None => {
+                println!("\tWARN: previous release tag is unknown, check is skipped");
+                return Ok(());
+            }
+        };
+        let mut diff = workspace_command("git");
+        diff.args(["diff", "--name-only", "--relative", &tag, "HEAD"]);
+        let files = run_and_capture_stdout(&mut diff)
+            .await
+            .with_context(|| format!("Failed to list files changed since `{}`", tag))?;
+        let files = files
+            .lines()
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .collect::<Vec<_>>();
+
+        let policies = &ctx.release_config()?.path_policies;
+        let violations = policy_violations(policies, &files, ctx.changelog().unwrap_or_default());
+        if !violations.is_empty() {
+            bail!(
+                "Files changed since `{}` violate path policies:\n{}",
+                tag,
+                violations.join("\n")
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Release;
+    use expect_test::expect;
+
+    #[test]
+    fn violated_policies() {
+        let release: Release = toml::from_str(
+            r#"
+            [[path_policies]]
+            paths = ["migrations/**"]
+            require_changelog_section = '## Database'
+
+            [[path_policies]]
+            paths = ["unsafe_experimental/**"]
+            block = true
+            reason = "experimental code is not ready"
+
+            [[path_policies]]
+            paths = ["docs/**"]
+            block = true
+            "#,
+        )
+        .unwrap();
+        let files = [
+            "migrations/001_init.sql",
+            "migrations/002_users.sql",
+            "unsafe_experimental/src/lib.rs",
+            "src/lib.rs",
+        ];
+
+        expect![[r#"
+            [
+                "changelog requires `## Database` section as `migrations/**` is changed (migrations/001_init.sql and 1 more)",
+                "release is blocked while `unsafe_experimental/**` is changed (unsafe_experimental/src/lib.rs): experimental code is not ready",
+            ]
+        "#]]
+        .assert_debug_eq(&policy_violations(
+            &release.path_policies,
+            &files,
+            "## Features\n- Users\n",
+        ));
+        assert_eq!(
+            policy_violations(
+                &release.path_policies[..1],
+                &files,
+                "## Database\n- Users table\n"
+            ),
+            Vec::<String>::new()
+        );
+    }
+}
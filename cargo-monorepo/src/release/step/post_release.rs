@@ -0,0 +1,101 @@
+use crate::{
+    audit,
+    config::{HttpMethod, PostReleaseHttp},
+    i18n::tr,
+    release::{resource::PendingVersion, ReleaseContext, ReleaseStep, ResourceId, StepKind},
+    template::TextTemplateContext,
+};
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use std::env;
+
+/// Pings external services (CDN, docs, search index) about the new release
+pub struct NotifyServices;
+
+impl NotifyServices {
+    async fn call(
+        &self,
+        ctx: &ReleaseContext,
+        http: &PostReleaseHttp,
+        context: &TextTemplateContext,
+    ) -> anyhow::Result<()> {
+        let url = http.url.render(context)?;
+        let method = format!("{:?}", http.method).to_uppercase();
+
+        if ctx.is_dry_run() {
+            ctx.would(format!("{} {} ({})", method, url, http.name));
+            return Ok(());
+        }
+
+        let client = reqwest::Client::new();
+        let mut request = match http.method {
+            HttpMethod::Get => client.get(&url),
+            HttpMethod::Post => client.post(&url),
+            HttpMethod::Put => client.put(&url),
+            HttpMethod::Patch => client.patch(&url),
+            HttpMethod::Delete => client.delete(&url),
+        };
+        request = request.header(reqwest::header::USER_AGENT, env!("CARGO_PKG_NAME"));
+        for (name, value) in &http.headers {
+            request = request.header(name, value.render(context)?);
+        }
+        if let Some(body) = &http.body {
+            request = request.body(body.render(context)?);
+        }
+        if let Some(var) = &http.token_env {
+            let token = env::var(var).map_err(|_| {
+                anyhow!(
+                    "{} token is missing, please specify it via {} env var",
+                    http.name,
+                    var
+                )
+            })?;
+            request = request.bearer_auth(token);
+        }
+
+        audit::api_call(&method, &url);
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to call {}", url))?;
+        if !response.status().is_success() {
+            bail!("{} {} responded with {}", method, url, response.status());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ReleaseStep for NotifyServices {
+    fn name(&self) -> &'static str {
+        "post-release-http"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<PendingVersion>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Notifying external services about the release"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("External services have been notified"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let context = ctx.text_template_context();
+        for http in &ctx.post_release_config()?.http {
+            match self.call(ctx, http, &context).await {
+                Ok(()) => println!("\t✅ {}", http.name),
+                Err(e) if !http.required => println!("\tWARN: {} failed: {:#}", http.name, e),
+                Err(e) => return Err(e.context(format!("{} failed", http.name))),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use super::init::get_current_commit;
+use crate::{
+    audit,
+    i18n::tr,
+    release::{
+        resource::{CurrentCommit, PendingVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command, workspace_path},
+};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+
+/// Promotes the "Unreleased" changelog section to the pending version and
+/// commits it, so the released commit contains it
+pub struct PromoteChangelog;
+
+fn normalize_heading(heading: &str) -> String {
+    heading
+        .trim()
+        .chars()
+        .filter(|c| *c != '[' && *c != ']')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Replaces the first line matching the unreleased heading, `None` if the
+/// heading is not found
+fn promote_heading(changelog: &str, heading: &str, promoted: &str) -> Option<String> {
+    let heading = normalize_heading(heading);
+    let mut found = false;
+    let lines = changelog
+        .split_inclusive('\n')
+        .map(|line| {
+            if !found && normalize_heading(line) == heading {
+                found = true;
+                let ending = &line[line.trim_end().len()..];
+                format!("{}{}", promoted, ending)
+            } else {
+                line.to_owned()
+            }
+        })
+        .collect::<String>();
+    found.then_some(lines)
+}
+
+#[async_trait]
+impl ReleaseStep for PromoteChangelog {
+    fn name(&self) -> &'static str {
+        "promote-changelog"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<CurrentCommit>(),
+        ]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Promoting unreleased changelog section"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Changelog has been promoted"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let changelog = ctx.changelog_config()?;
+        let promote = changelog
+            .promote_unreleased
+            .as_ref()
+            .ok_or_else(|| anyhow!("BUG: changelog.promote_unreleased is not set"))?;
+
+        let path = workspace_path(&changelog.file);
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        let mut template_context = serde_json::to_value(ctx.text_template_context())?;
+        template_context["date"] = chrono::Utc::now()
+            .date_naive()
+            .format("%Y-%m-%d")
+            .to_string()
+            .into();
+        let promoted = promote.heading_template.render(&template_context)?;
+
+        let content = match promote_heading(&content, &promote.heading, &promoted) {
+            Some(content) => content,
+            None => {
+                println!(
+                    "\t`{}` heading is not found in {}, nothing to promote",
+                    promote.heading,
+                    changelog.file.display()
+                );
+                return Ok(());
+            }
+        };
+
+        let message = format!("Release {} v{}", ctx.root_crate_name(), ctx.version());
+        if ctx.is_dry_run() {
+            ctx.would(format!(
+                "rename `{}` to `{}` in {}",
+                promote.heading,
+                promoted,
+                changelog.file.display()
+            ));
+            if promote.commit {
+                ctx.would(format!("git commit -m \"{}\"", message));
+                ctx.would(format!("git push {} HEAD", promote.remote));
+            }
+            return Ok(());
+        }
+
+        tokio::fs::write(&path, content)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!(
+            "\t`{}` has been renamed to `{}` in {}",
+            promote.heading,
+            promoted,
+            changelog.file.display()
+        );
+
+        if promote.commit {
+            let mut commit = workspace_command("git");
+            commit
+                .args(["commit", "--message", &message, "--"])
+                .arg(&path);
+            audit::command(&commit);
+            run_and_capture_stdout(&mut commit)
+                .await
+                .with_context(|| "Failed to commit promoted changelog")?;
+
+            let mut push = workspace_command("git");
+            push.args(["push", &promote.remote, "HEAD"]);
+            audit::command(&push);
+            run_and_capture_stdout(&mut push)
+                .await
+                .with_context(|| "Failed to push promoted changelog")?;
+            ctx.set_release_commit(get_current_commit().await?);
+            println!("\tPromoted changelog has been committed and pushed");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn unreleased_heading() {
+        let changelog =
+            "# Changelog\n\n## [Unreleased]\n\n- Added `diff` command\n\n## [1.0.0] - 2024-01-02\n";
+
+        expect![[r#"
+            # Changelog
+
+            ## [1.1.0] - 2024-05-17
+
+            - Added `diff` command
+
+            ## [1.0.0] - 2024-01-02
+        "#]]
+        .assert_eq(
+            &promote_heading(changelog, "## Unreleased", "## [1.1.0] - 2024-05-17").unwrap(),
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    i18n::tr,
+    release::{resource::CurrentCommit, ReleaseContext, ReleaseStep, ResourceId, StepKind},
+    utils::{run_and_capture_stdout, shorten_commit, workspace_command},
+};
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use octocrab::{params::repos::Reference, Octocrab as GithubClient};
+
+/// Checks that the released commit is present in the GitHub repo and in
+/// every configured git remote, e.g. mirrors and non-GitHub primaries
+pub struct ValidateCommitPushed {
+    github: bool,
+    remotes: Vec<String>,
+}
+
+impl ValidateCommitPushed {
+    pub fn new(github: bool, remotes: Vec<String>) -> Self {
+        Self { github, remotes }
+    }
+}
+
+/// `true` if the commit is a tip of any branch in `git ls-remote` output
+fn is_branch_tip(ls_remote: &str, commit: &str) -> bool {
+    ls_remote
+        .lines()
+        .filter_map(|l| l.split_once('\t'))
+        .any(|(hash, reference)| hash == commit && reference.starts_with("refs/heads/"))
+}
+
+async fn check_remote(remote: &str, commit: &str) -> anyhow::Result<()> {
+    let mut ls_remote = workspace_command("git");
+    ls_remote.args(["ls-remote", "--heads", remote]);
+    let tips = run_and_capture_stdout(&mut ls_remote)
+        .await
+        .with_context(|| format!("Failed to list branches of `{}` remote", remote))?;
+    if is_branch_tip(&tips, commit) {
+        return Ok(());
+    }
+
+    // Commit could be pushed, but not be the tip of any branch anymore
+    let mut fetch = workspace_command("git");
+    fetch.args(["fetch", "--quiet", remote]);
+    run_and_capture_stdout(&mut fetch)
+        .await
+        .with_context(|| format!("Failed to fetch `{}` remote", remote))?;
+    let mut contains = workspace_command("git");
+    contains.args([
+        "branch",
+        "--remotes",
+        "--contains",
+        commit,
+        "--list",
+        &format!("{}/*", remote),
+    ]);
+    let branches = run_and_capture_stdout(&mut contains)
+        .await
+        .with_context(|| format!("Failed to query branches of `{}` remote", remote))?;
+    if branches.trim().is_empty() {
+        bail!(
+            "Current commit is not pushed to any branch of `{}` remote",
+            remote
+        );
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl ReleaseStep for ValidateCommitPushed {
+    fn name(&self) -> &'static str {
+        "validate-commit-pushed"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        let mut requires = vec![ResourceId::of::<CurrentCommit>()];
+        if self.github {
+            requires.push(ResourceId::of::<GithubClient>());
+        }
+        requires
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        let mut targets = vec![];
+        if self.github {
+            targets.push(ctx.github_config()?.repo.to_string());
+        }
+        targets.extend(self.remotes.iter().map(|r| format!("`{}`", r)));
+        Ok(tr!(
+            "Checking that commit {commit} is pushed to {repo}",
+            commit = shorten_commit(ctx.current_commit()),
+            repo = targets.join(", ")
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Success! Current commit is pushed to the remote"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let commit = ctx.current_commit();
+        if self.github {
+            let repo = ctx.github_config()?.repo.clone();
+            ctx.github_client()
+                .repos(repo.owner, repo.name)
+                .combined_status_for_ref(&Reference::Commit(commit.to_owned()))
+                .await
+                .with_context(|| "Current commit is missing in the GitHub remote")?;
+        }
+        for remote in &self.remotes {
+            check_remote(remote, &commit).await?;
+            println!("\tCommit is pushed to `{}` remote", remote);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_branch_tips() {
+        let ls_remote = "\
+1a2b3c\trefs/heads/main
+4d5e6f\trefs/heads/release/1.x
+7a8b9c\trefs/tags/v1.0.0
+";
+        assert!(is_branch_tip(ls_remote, "4d5e6f"));
+        assert!(!is_branch_tip(ls_remote, "7a8b9c"));
+        assert!(!is_branch_tip(ls_remote, "1a2b"));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{
+    explain::ErrorCode,
+    i18n::tr,
+    release::{readme, ReleaseContext, ReleaseStep, ResourceId, StepKind},
+};
+use async_trait::async_trait;
+use cargo_metadata::{camino::Utf8PathBuf, Metadata, Package};
+
+/// Checks that README of every published crate renders on crates.io
+pub struct ValidateReadme;
+
+/// README path of the package; relative `readme` field is resolved against
+/// the package directory
+pub fn readme_path(package: &Package) -> Option<Utf8PathBuf> {
+    let readme = package.readme.as_ref()?;
+    Some(package.manifest_path.parent()?.join(readme))
+}
+
+#[async_trait]
+impl ReleaseStep for ValidateReadme {
+    fn name(&self) -> &'static str {
+        "validate-readme"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Metadata>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Validating crate READMEs"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Crate READMEs are valid"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let check = ctx.release_readme_config()?;
+
+        let mut invalid = false;
+        for p in ctx.packages_to_publish() {
+            let path = match readme_path(p) {
+                Some(path) => path,
+                None => {
+                    println!("\t❌ {} has no readme", p.name);
+                    invalid = true;
+                    continue;
+                }
+            };
+            let content = match tokio::fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(e) => {
+                    println!("\t❌ {} readme {} is not readable: {}", p.name, path, e);
+                    invalid = true;
+                    continue;
+                }
+            };
+
+            let mut problems = vec![];
+            if content.len() as u64 > check.max_size_bytes {
+                problems.push(format!(
+                    "size {} exceeds {} bytes",
+                    content.len(),
+                    check.max_size_bytes
+                ));
+            }
+            if !check.rewrite_relative_links {
+                let relative = readme::links(&content)
+                    .into_iter()
+                    .filter(readme::Link::is_relative)
+                    .map(|l| format!("`{}`", l.target))
+                    .collect::<Vec<_>>();
+                if !relative.is_empty() {
+                    problems.push(format!("relative links {}", relative.join(", ")));
+                }
+            }
+
+            if problems.is_empty() {
+                println!("\t✅ {} ({})", p.name, path);
+            } else {
+                println!("\t❌ {} ({}): {}", p.name, path, problems.join("; "));
+                invalid = true;
+            }
+        }
+
+        if invalid {
+            return Err(ErrorCode::InvalidReadme
+                .error("Some of the crate READMEs would not render on crates.io"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{
+    config::ReleasesFileFormat,
+    i18n::tr,
+    release::{
+        resource::{GithubTag, PendingVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command, workspace_path},
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::json;
+
+const MARKDOWN_TITLE: &str = "# Releases\n";
+
+/// Adds the release entry to the repository-level releases history and
+/// commits it
+pub struct UpdateReleasesFile {
+    release_page: bool,
+}
+
+impl UpdateReleasesFile {
+    /// Entry links the GitHub release page if it is created
+    pub fn new(release_page: bool) -> Self {
+        Self { release_page }
+    }
+}
+
+/// Top-level list items of the changelog
+fn highlights(changelog: &str, max: usize) -> Vec<String> {
+    changelog
+        .lines()
+        .filter_map(|l| l.strip_prefix("- ").or_else(|| l.strip_prefix("* ")))
+        .map(|l| l.trim().to_owned())
+        .filter(|l| !l.is_empty())
+        .take(max)
+        .collect()
+}
+
+/// Newest entry goes right after the document title
+fn insert_markdown_entry(content: &str, entry: &str) -> String {
+    let entry = format!("{}\n\n", entry.trim());
+    if content.trim().is_empty() {
+        return format!("{}\n{}", MARKDOWN_TITLE, entry);
+    }
+    let mut lines = content.split_inclusive('\n').peekable();
+    let mut updated = String::with_capacity(content.len() + entry.len());
+    if lines.peek().is_some_and(|l| l.starts_with("# ")) {
+        updated.extend(lines.next());
+        while let Some(blank) = lines.next_if(|l| l.trim().is_empty()) {
+            updated.push_str(blank);
+        }
+        if !updated.ends_with("\n\n") {
+            updated.push('\n');
+        }
+    }
+    updated.push_str(&entry);
+    updated.extend(lines);
+    updated
+}
+
+/// Newest entry goes first
+fn insert_json_entry(content: &str, entry: serde_json::Value) -> anyhow::Result<String> {
+    let mut entries: Vec<serde_json::Value> = if content.trim().is_empty() {
+        vec![]
+    } else {
+        serde_json::from_str(content).with_context(|| "Releases file is not a JSON array")?
+    };
+    entries.insert(0, entry);
+    Ok(serde_json::to_string_pretty(&entries)? + "\n")
+}
+
+#[async_trait]
+impl ReleaseStep for UpdateReleasesFile {
+    fn name(&self) -> &'static str {
+        "update-releases-file"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        let mut requires = vec![ResourceId::of::<PendingVersion>()];
+        if self.release_page {
+            requires.push(ResourceId::of::<GithubTag>());
+        }
+        requires
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!(
+            "Adding release entry to {path}",
+            path = ctx.release_releases_file_config()?.path.display()
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Release entry has been added"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let releases = ctx.release_releases_file_config()?;
+        let path = workspace_path(&releases.path);
+
+        let release_url = if self.release_page {
+            let repo = &ctx.github_config()?.repo;
+            Some(format!(
+                "https://github.com/{}/{}/releases/tag/{}",
+                repo.owner,
+                repo.name,
+                ctx.github_release_tag()
+            ))
+        } else {
+            None
+        };
+        let context = ctx.text_template_context();
+        let highlights = highlights(ctx.changelog().unwrap_or_default(), releases.highlights);
+        let date = chrono::Utc::now()
+            .date_naive()
+            .format("%Y-%m-%d")
+            .to_string();
+
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+        let (entry, updated) = match releases.format {
+            ReleasesFileFormat::Markdown => {
+                let mut template_context = serde_json::to_value(&context)?;
+                template_context["date"] = date.into();
+                template_context["highlights"] = highlights.into();
+                template_context["release_url"] = release_url.into();
+                let entry = releases.entry_template.render(&template_context)?;
+                let updated = insert_markdown_entry(&content, &entry);
+                (entry, updated)
+            }
+            ReleasesFileFormat::Json => {
+                let crates = context
+                    .published_crates
+                    .iter()
+                    .map(|c| {
+                        json!({
+                            "name": c.name,
+                            "version": c.version,
+                            "registry_url": c.registry_url,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let entry = json!({
+                    "crate": context.root_crate,
+                    "version": context.version,
+                    "date": date,
+                    "highlights": highlights,
+                    "release_url": release_url,
+                    "crates": crates,
+                });
+                let updated = insert_json_entry(&content, entry.clone())?;
+                (serde_json::to_string_pretty(&entry)?, updated)
+            }
+        };
+
+        let message = format!(
+            "Add {} v{} to {}",
+            context.root_crate,
+            context.version,
+            releases.path.display()
+        );
+        if ctx.is_dry_run() {
+            entry.lines().for_each(|l| println!("\t{}", l));
+            ctx.would(format!("write {}", releases.path.display()));
+            if releases.commit {
+                ctx.would(format!("git commit -m \"{}\"", message));
+                ctx.would(format!("git push {} HEAD", releases.remote));
+            }
+            return Ok(());
+        }
+
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, updated)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("\t{} has been updated", releases.path.display());
+
+        if releases.commit {
+            // New file should be added before it can be committed by path
+            let mut add = workspace_command("git");
+            add.args(["add", "--"]).arg(&path);
+            run_and_capture_stdout(&mut add)
+                .await
+                .with_context(|| format!("Failed to add {}", path.display()))?;
+
+            let mut commit = workspace_command("git");
+            commit
+                .args(["commit", "--message", &message, "--"])
+                .arg(&path);
+            run_and_capture_stdout(&mut commit)
+                .await
+                .with_context(|| format!("Failed to commit {}", path.display()))?;
+
+            let mut push = workspace_command("git");
+            push.args(["push", &releases.remote, "HEAD"]);
+            run_and_capture_stdout(&mut push)
+                .await
+                .with_context(|| format!("Failed to push {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn markdown_entry_insertion() {
+        let changelog = "### Added\n- `diff` command\n  - nested detail\n* faster publish\n";
+        let entry = format!(
+            "## foo 1.1.0 (2024-05-17)\n\n{}",
+            highlights(changelog, 5)
+                .iter()
+                .map(|h| format!("- {}\n", h))
+                .collect::<String>()
+        );
+        let releases = "# Releases\n\n## foo 1.0.0 (2024-01-02)\n\n- Initial release\n";
+
+        expect![[r#"
+            # Releases
+
+            ## foo 1.1.0 (2024-05-17)
+
+            - `diff` command
+            - faster publish
+
+            ## foo 1.0.0 (2024-01-02)
+
+            - Initial release
+        "#]]
+        .assert_eq(&insert_markdown_entry(releases, &entry));
+    }
+}
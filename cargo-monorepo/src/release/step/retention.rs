@@ -0,0 +1,174 @@
+use crate::{
+    audit,
+    config::AssetRetention,
+    github::delete_github_release_asset,
+    i18n::tr,
+    release::{ReleaseContext, ReleaseStep, ResourceId, StepKind},
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use octocrab::Octocrab as GithubClient;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    prerelease: bool,
+    created_at: DateTime<Utc>,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GithubAsset {
+    id: u64,
+    name: String,
+    size: u64,
+}
+
+/// Deletes assets of the old prereleases, keeping only the most recent ones
+pub struct PruneReleaseAssets;
+
+#[async_trait]
+impl ReleaseStep for PruneReleaseAssets {
+    fn name(&self) -> &'static str {
+        "prune-release-assets"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<GithubClient>()]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        let retention = asset_retention(ctx)?;
+        Ok(tr!(
+            "Pruning assets of prereleases older than the last {count}",
+            count = retention.keep_prereleases
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Stale prerelease assets have been pruned"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let retention = asset_retention(ctx)?;
+        let repo = &ctx.github_config()?.repo;
+        let github = ctx.github_client();
+
+        let route = format!("repos/{}/{}/releases", repo.owner, repo.name);
+        audit::api_call("GET", &route);
+        let releases: Vec<GithubRelease> = github
+            .get(&route, Some(&serde_json::json!({ "per_page": 100 })))
+            .await
+            .with_context(|| "Failed to list GitHub releases")?;
+
+        let stale = stale_assets(releases, retention);
+        if stale.is_empty() {
+            println!("\tNo stale prerelease assets found");
+            return Ok(());
+        }
+
+        let mut pruned_bytes = 0;
+        for (tag, asset) in stale {
+            let route = format!(
+                "repos/{}/{}/releases/assets/{}",
+                repo.owner, repo.name, asset.id
+            );
+            if ctx.is_dry_run() {
+                ctx.would(format!("DELETE {} ({} from `{}`)", route, asset.name, tag));
+                continue;
+            }
+
+            println!("\tDeleting {} from `{}`", asset.name, tag);
+            delete_github_release_asset(github, repo, asset.id)
+                .await
+                .with_context(|| format!("Failed to delete asset {}", asset.name))?;
+            pruned_bytes += asset.size;
+        }
+        if pruned_bytes != 0 {
+            println!("\t{} bytes have been freed", pruned_bytes);
+        }
+
+        Ok(())
+    }
+}
+
+fn asset_retention(ctx: &ReleaseContext) -> anyhow::Result<&AssetRetention> {
+    ctx.release_github_config()?
+        .asset_retention
+        .as_ref()
+        .context("release.github.asset_retention section is missing from the config")
+}
+
+/// Assets of the matching prereleases except the `keep_prereleases` newest ones
+fn stale_assets(
+    mut releases: Vec<GithubRelease>,
+    retention: &AssetRetention,
+) -> Vec<(String, GithubAsset)> {
+    releases.retain(|r| {
+        r.prerelease
+            && retention
+                .tag_pattern
+                .as_ref()
+                .is_none_or(|p| p.matches(&r.tag_name))
+    });
+    releases.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+
+    releases
+        .into_iter()
+        .skip(retention.keep_prereleases)
+        .flat_map(|r| {
+            let tag = r.tag_name;
+            r.assets.into_iter().map(move |a| (tag.clone(), a))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn keeps_newest_prereleases() {
+        let releases: Vec<GithubRelease> = serde_json::from_value(serde_json::json!([
+            { "tag_name": "v1.1.0", "prerelease": false, "created_at": "2024-06-04T00:00:00Z",
+              "assets": [{ "id": 1, "name": "app.tar.gz", "size": 10 }] },
+            { "tag_name": "nightly-3", "prerelease": true, "created_at": "2024-06-03T00:00:00Z",
+              "assets": [{ "id": 2, "name": "app.tar.gz", "size": 10 }] },
+            { "tag_name": "nightly-1", "prerelease": true, "created_at": "2024-06-01T00:00:00Z",
+              "assets": [{ "id": 3, "name": "app.tar.gz", "size": 10 }] },
+            { "tag_name": "nightly-2", "prerelease": true, "created_at": "2024-06-02T00:00:00Z",
+              "assets": [{ "id": 4, "name": "app.tar.gz", "size": 10 }] },
+            { "tag_name": "v1.1.0-rc.1", "prerelease": true, "created_at": "2024-05-01T00:00:00Z",
+              "assets": [{ "id": 5, "name": "app.tar.gz", "size": 10 }] },
+        ]))
+        .unwrap();
+        let retention: AssetRetention = toml::from_str(
+            r#"
+keep_prereleases = 2
+tag_pattern = "nightly-*"
+"#,
+        )
+        .unwrap();
+
+        expect![[r#"
+            [
+                (
+                    "nightly-1",
+                    GithubAsset {
+                        id: 3,
+                        name: "app.tar.gz",
+                        size: 10,
+                    },
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&stale_assets(releases, &retention));
+    }
+}
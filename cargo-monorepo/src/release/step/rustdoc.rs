@@ -0,0 +1,66 @@
+use crate::{
+    i18n::tr,
+    release::{ReleaseContext, ReleaseStep, ResourceId, StepKind},
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+use std::env;
+
+/// Builds docs of the published crates, failing on broken intra-doc links
+/// which would otherwise show up on docs.rs
+pub struct ValidateRustdoc;
+
+#[async_trait]
+impl ReleaseStep for ValidateRustdoc {
+    fn name(&self) -> &'static str {
+        "validate-rustdoc"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<Metadata>()]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Validating crate docs"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Crate docs have no broken links"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let check = ctx.release_rustdoc_config()?;
+
+        // User provided flags are kept, e.g. `--cfg docsrs`
+        let mut flags = env::var("RUSTDOCFLAGS").unwrap_or_default();
+        flags.push_str(" -D rustdoc::broken_intra_doc_links");
+        if check.deny_warnings {
+            flags.push_str(" -D warnings");
+        }
+
+        let mut cmd = workspace_command("cargo");
+        cmd.args(["doc", "--no-deps"])
+            .env("RUSTDOCFLAGS", flags.trim());
+        if check.all_features {
+            cmd.arg("--all-features");
+        }
+        for p in ctx.packages_to_publish() {
+            cmd.args(["--package", &p.name]);
+        }
+        // Separate target dir keeps the regular doc builds cache intact
+        cmd.arg("--target-dir")
+            .arg(ctx.validation_target_dir("rustdoc")?);
+
+        run_and_capture_stdout(&mut cmd)
+            .await
+            .with_context(|| "Crate docs have broken links")?;
+
+        Ok(())
+    }
+}
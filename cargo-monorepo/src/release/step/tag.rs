@@ -0,0 +1,317 @@
+use crate::{
+    audit,
+    i18n::tr,
+    release::{
+        resource::{CurrentCommit, GithubTag, PendingVersion},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+const TRUNCATION_NOTICE: &str = "\n\n[release notes truncated]";
+
+/// Creates annotated tag with the release notes and pushes it to the remote
+pub struct CreateGitTag;
+
+#[async_trait]
+impl ReleaseStep for CreateGitTag {
+    fn name(&self) -> &'static str {
+        "create-tag"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<CurrentCommit>(),
+        ]
+    }
+
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![ResourceId::of::<GithubTag>()]
+    }
+
+    fn start_message(&self, ctx: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!(
+            "Creating annotated tag for version {version}",
+            version = ctx.version()
+        ))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Tag has been created"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let github_config = ctx.release_github_config()?;
+        let template_context = ctx.text_template_context();
+
+        let tag = github_config.tag_name_template.render(&template_context)?;
+        ctx.provide(GithubTag(tag.clone()))?;
+        if ctx.release_tag_exists() {
+            println!("\t Tag `{}` has been created before the release", tag);
+            return Ok(());
+        }
+
+        let message = github_config
+            .tag_message_template
+            .render(&template_context)?;
+        let message = truncate_message(message.trim_end(), github_config.tag_message_max_bytes);
+
+        let commit = ctx.current_commit();
+        println!("\t Tag `{}` will be created for commit {}", tag, commit);
+
+        if ctx.is_dry_run() {
+            message.lines().for_each(|l| println!("\t{}", l));
+            ctx.would(format!(
+                "git tag --annotate `{}` {} && git push {} refs/tags/{}",
+                tag, commit, github_config.tag_remote, tag
+            ));
+            return Ok(());
+        }
+
+        create_annotated_tag(&tag, &commit, &message).await?;
+
+        let refspec = format!("refs/tags/{}", tag);
+        let mut push = workspace_command("git");
+        push.args(["push", &github_config.tag_remote, &refspec]);
+        run_and_capture_stdout(&mut push)
+            .await
+            .with_context(|| format!("Failed to push tag `{}`", tag))?;
+
+        Ok(())
+    }
+}
+
+/// Verifies that the externally created tag points to the released commit
+/// and is signed with one of the allowed keys
+pub struct VerifyExternalTag;
+
+#[async_trait]
+impl ReleaseStep for VerifyExternalTag {
+    fn name(&self) -> &'static str {
+        "verify-external-tag"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<CurrentCommit>(),
+        ]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Verifying externally created release tag"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Release tag has been verified"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let github_config = ctx.release_github_config()?;
+        let external_tag = github_config
+            .external_tag
+            .as_ref()
+            .ok_or_else(|| anyhow!("BUG: release.github.external_tag is missing"))?;
+        let tag = github_config
+            .tag_name_template
+            .render(&ctx.text_template_context())?;
+        let tag_ref = format!("refs/tags/{}", tag);
+
+        if external_tag.fetch && !ctx.offline {
+            let mut fetch = workspace_command("git");
+            fetch.args([
+                "fetch",
+                &github_config.tag_remote,
+                &format!("+{}:{}", tag_ref, tag_ref),
+            ]);
+            run_and_capture_stdout(&mut fetch)
+                .await
+                .with_context(|| format!("Failed to fetch tag `{}`", tag))?;
+        }
+
+        let mut tagged_commit = workspace_command("git");
+        tagged_commit.args(["rev-list", "-n", "1", &tag_ref]);
+        let tagged_commit = run_and_capture_stdout(&mut tagged_commit)
+            .await
+            .with_context(|| format!("Release tag `{}` does not exist", tag))?;
+        if tagged_commit.trim() != ctx.current_commit() {
+            bail!(
+                "Release tag `{}` points to {}, but released commit is {}",
+                tag,
+                tagged_commit.trim(),
+                ctx.current_commit()
+            );
+        }
+        println!("\tTag `{}` points to the released commit", tag);
+
+        if external_tag.allowed_signing_keys.is_empty() {
+            return Ok(());
+        }
+        let output = workspace_command("git")
+            .args(["verify-tag", "--raw", &tag])
+            .output()
+            .await
+            .with_context(|| "Failed to run git verify-tag")?;
+        if !output.status.success() {
+            bail!("Release tag `{}` has no valid GPG signature", tag);
+        }
+        let status = String::from_utf8_lossy(&output.stderr);
+        let keys = signing_keys(&status);
+        match allowed_key(&keys, &external_tag.allowed_signing_keys) {
+            Some(key) => println!("\tTag `{}` is signed with allowed key {}", tag, key),
+            None => bail!(
+                "Release tag `{}` is signed with {}, which is not in allowed_signing_keys",
+                tag,
+                keys.first().copied().unwrap_or("unknown key")
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// Key ids and fingerprints of the good signature from `git verify-tag --raw`
+/// GPG status output
+fn signing_keys(status: &str) -> Vec<&str> {
+    let mut keys = vec![];
+    for line in status.lines() {
+        let mut fields = line
+            .split_whitespace()
+            .skip_while(|f| *f != "[GNUPG:]")
+            .skip(1);
+        match fields.next() {
+            Some("GOODSIG") => keys.extend(fields.next()),
+            // Signing subkey fingerprint, primary key fingerprint is the last field
+            Some("VALIDSIG") => {
+                let fields = fields.collect::<Vec<_>>();
+                keys.extend(fields.first());
+                keys.extend(fields.last().filter(|_| fields.len() > 1));
+            }
+            _ => {}
+        }
+    }
+    keys
+}
+
+/// The first allowed key matching any of the signing keys; short ids match
+/// the end of the fingerprint
+fn allowed_key<'a>(keys: &[&str], allowed: &'a [String]) -> Option<&'a str> {
+    let normalize = |key: &str| key.replace(' ', "").to_ascii_uppercase();
+    allowed
+        .iter()
+        .find(|a| {
+            let a = normalize(a);
+            !a.is_empty() && keys.iter().any(|k| normalize(k).ends_with(&a))
+        })
+        .map(String::as_str)
+}
+
+async fn create_annotated_tag(tag: &str, commit: &str, message: &str) -> anyhow::Result<()> {
+    // Message is passed via stdin to avoid command line length limits
+    let mut cmd = workspace_command("git");
+    cmd.args([
+        "tag",
+        "--annotate",
+        "--cleanup=verbatim",
+        "--file=-",
+        tag,
+        commit,
+    ]);
+    audit::command(&cmd);
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| "Failed to spawn git tag")?;
+
+    let mut stdin = child.stdin.take().expect("BUG: stdin is not piped");
+    stdin.write_all(message.as_bytes()).await?;
+    drop(stdin);
+
+    let out = child.wait_with_output().await?;
+    if !out.status.success() {
+        bail!(
+            "Failed to create tag `{}`: {}",
+            tag,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Cuts message to fit into `max_bytes`, including truncation notice
+fn truncate_message(message: &str, max_bytes: usize) -> String {
+    if message.len() <= max_bytes {
+        return message.to_owned();
+    }
+
+    let mut end = max_bytes.saturating_sub(TRUNCATION_NOTICE.len());
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    // Prefer cutting at the line boundary
+    if let Some(line_end) = message[..end].rfind('\n') {
+        end = line_end;
+    }
+
+    format!("{}{}", message[..end].trim_end(), TRUNCATION_NOTICE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn message_truncation() {
+        let message =
+            "foo v1.0.0\n\n- Added ✨ feature\n- Fixed bug\n- Fixed another bug in parser";
+
+        assert_eq!(truncate_message(message, 1024), message);
+        expect![[r#"
+            foo v1.0.0
+
+            - Added ✨ feature
+
+            [release notes truncated]"#]]
+        .assert_eq(&truncate_message(message, 60));
+    }
+
+    #[test]
+    fn tag_signing_keys() {
+        let status = "\
+[GNUPG:] NEWSIG
+[GNUPG:] KEY_CONSIDERED 4AEE18F83AFDEB23A1B2C3D4E5F6A7B8C9D0E1F2 0
+[GNUPG:] GOODSIG C9D0E1F2A3B4C5D6 Release Bot <release@example.com>
+[GNUPG:] VALIDSIG 1111222233334444555566667777888899990000 2024-05-01 1714550400 0 4 0 22 10 00 4AEE18F83AFDEB23A1B2C3D4E5F6A7B8C9D0E1F2
+[GNUPG:] TRUST_UNDEFINED 0 pgp
+";
+        let keys = signing_keys(status);
+        expect![[r#"
+            [
+                "C9D0E1F2A3B4C5D6",
+                "1111222233334444555566667777888899990000",
+                "4AEE18F83AFDEB23A1B2C3D4E5F6A7B8C9D0E1F2",
+            ]
+        "#]]
+        .assert_debug_eq(&keys);
+
+        let allowed = vec!["4AEE 18F8 3AFD EB23 A1B2  C3D4 E5F6 A7B8 C9D0 E1F2".to_owned()];
+        assert_eq!(allowed_key(&keys, &allowed), Some(allowed[0].as_str()));
+        assert_eq!(allowed_key(&keys, &["DEADBEEF".to_owned()]), None);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    explain::ErrorCode,
+    i18n::tr,
+    registry::query_registry_crate,
+    release::{
+        resource::{PendingVersion, PublishedRootCrate},
+        train::TrainWindow,
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::{run_and_capture_stdout, workspace_command},
+};
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+
+pub struct ValidateReleaseTrain {
+    ignore_window: bool,
+}
+
+impl ValidateReleaseTrain {
+    pub fn new(ignore_window: bool) -> Self {
+        Self { ignore_window }
+    }
+}
+
+#[async_trait]
+impl ReleaseStep for ValidateReleaseTrain {
+    fn name(&self) -> &'static str {
+        "validate-release-train"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<PendingVersion>(),
+            ResourceId::of::<PublishedRootCrate>(),
+        ]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Validating release train schedule"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Release train is ready to depart"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let train = ctx
+            .config
+            .train
+            .as_ref()
+            .ok_or_else(|| anyhow!("train section is missing from the config"))?;
+
+        let window = TrainWindow::at(train, chrono::Local::now().date_naive());
+        if window.open {
+            println!("\tTrain #{} ({}) is open", window.number, window.date);
+        } else if self.ignore_window {
+            println!(
+                "\tWARN: Releasing outside of the train window, next train is on {}",
+                window.next_date
+            );
+        } else {
+            return Err(ErrorCode::TrainWindowClosed.error(format!(
+                "Release train window is closed, next train is on {}",
+                window.next_date
+            )));
+        }
+
+        if let Some(branch) = &train.branch {
+            let mut cmd = workspace_command("git");
+            cmd.args(["rev-parse", "--abbrev-ref", "HEAD"]);
+            let current_branch = run_and_capture_stdout(&mut cmd).await?;
+            if current_branch.trim() != branch {
+                bail!(
+                    "Train releases should be cut from `{}` branch, but current branch is `{}`",
+                    branch,
+                    current_branch.trim()
+                );
+            }
+        }
+
+        let version = ctx.version();
+        // Registry is queried again if the version validation didn't do it
+        let prev_version = match ctx.published_root_crate() {
+            Some(published) => published.latest_version().cloned(),
+            None => {
+                let release = ctx.release_config()?;
+                query_registry_crate(release, release.registry.as_deref(), &ctx.root_crate_name())
+                    .await?
+                    .and_then(|c| c.latest_version().cloned())
+            }
+        };
+        match prev_version {
+            Some(prev_version) => {
+                let train_version = train.version_bump.apply(&prev_version);
+                if *version != train_version {
+                    bail!(
+                        "Train version after {} is {}, but pending version is {}",
+                        prev_version,
+                        train_version,
+                        version
+                    );
+                }
+                println!("\tTrain version is {}", train_version);
+            }
+            None => println!("\tWARN: First train release, pending version is not validated"),
+        }
+
+        Ok(())
+    }
+}
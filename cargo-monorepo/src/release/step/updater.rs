@@ -0,0 +1,205 @@
+use crate::{
+    audit,
+    config::UpdateManifest,
+    github::{upload_github_release_asset, Repo},
+    i18n::tr,
+    release::{
+        resource::{GithubTag, UploadedAssets},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
+    utils::sha256_file,
+};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use cargo_metadata::Metadata;
+use chrono::{DateTime, Utc};
+use octocrab::Octocrab as GithubClient;
+use semver::Version;
+use serde::Serialize;
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// tauri-updater compatible manifest, extended with `sha256` of the assets
+#[derive(Serialize, Debug)]
+struct Manifest {
+    version: Version,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    pub_date: DateTime<Utc>,
+    platforms: BTreeMap<String, PlatformUpdate>,
+}
+
+#[derive(Serialize, Debug)]
+struct PlatformUpdate {
+    url: String,
+    sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+/// Assets are uploaded under their file names, so download URLs are known
+/// even before the upload
+fn download_url(repo: &Repo, tag: &str, name: &str) -> anyhow::Result<String> {
+    let mut url = url::Url::parse("https://github.com/")?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("Invalid GitHub URL"))?
+        .extend([&repo.owner, &repo.name, "releases", "download", tag, name]);
+    Ok(url.into())
+}
+
+/// Uploads the update manifest with the stable name to the created release, so
+/// `releases/latest/download/latest.json` always points to the newest one
+pub struct PublishUpdateManifest;
+
+impl PublishUpdateManifest {
+    fn build_manifest(
+        &self,
+        ctx: &ReleaseContext,
+        config: &UpdateManifest,
+    ) -> anyhow::Result<Manifest> {
+        let repo = &ctx.github_config()?.repo;
+        let tag = ctx.github_release_tag();
+        let artifacts = ctx.release_artifacts();
+        let file_name = |path: &PathBuf| {
+            path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+
+        let mut platforms = BTreeMap::new();
+        for (platform, pattern) in &config.platforms {
+            let artifact = artifacts
+                .iter()
+                .find(|a| pattern.matches(&file_name(a)))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No artifact matches `{}` pattern of `{}` update platform",
+                        pattern,
+                        platform
+                    )
+                })?;
+            let name = file_name(artifact);
+            let signature_name = format!("{}{}", name, config.signature_suffix);
+            let signature = match artifacts.iter().find(|a| file_name(a) == signature_name) {
+                Some(path) => Some(
+                    std::fs::read_to_string(path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?
+                        .trim()
+                        .to_owned(),
+                ),
+                None => None,
+            };
+            platforms.insert(
+                platform.clone(),
+                PlatformUpdate {
+                    url: download_url(repo, tag, &name)?,
+                    sha256: sha256_file(artifact)
+                        .with_context(|| format!("Failed to hash {}", name))?,
+                    signature,
+                },
+            );
+        }
+
+        Ok(Manifest {
+            version: ctx.version().clone(),
+            notes: ctx
+                .changelog()
+                .filter(|_| config.include_notes)
+                .map(str::to_owned),
+            pub_date: Utc::now(),
+            platforms,
+        })
+    }
+}
+
+#[async_trait]
+impl ReleaseStep for PublishUpdateManifest {
+    fn name(&self) -> &'static str {
+        "publish-update-manifest"
+    }
+
+    fn kind(&self) -> StepKind {
+        StepKind::Release
+    }
+
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<GithubClient>(),
+            ResourceId::of::<GithubTag>(),
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<UploadedAssets>(),
+        ]
+    }
+
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Publishing auto-updater manifest"))
+    }
+
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Auto-updater manifest has been published"))
+    }
+
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let config = ctx
+            .release_github_config()?
+            .update_manifest
+            .as_ref()
+            .ok_or_else(|| anyhow!("release.github.update_manifest section is missing"))?;
+        let manifest = self.build_manifest(ctx, config)?;
+        for (platform, update) in &manifest.platforms {
+            println!("\t{}: {}", platform, update.url);
+        }
+
+        let path = ctx
+            .cargo_metadata()
+            .target_directory
+            .join("monorepo")
+            .join(&config.name)
+            .into_std_path_buf();
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, serde_json::to_string_pretty(&manifest)?)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        let tag = ctx.github_release_tag();
+        if ctx.is_dry_run() {
+            ctx.would(format!(
+                "upload {} to GitHub release `{}`",
+                path.display(),
+                tag
+            ));
+            return Ok(());
+        }
+
+        let repo = &ctx.github_config()?.repo;
+        audit::api_call(
+            "GET",
+            &format!("repos/{}/{}/releases/tags/{}", repo.owner, repo.name, tag),
+        );
+        let release = ctx
+            .github_client()
+            .repos(&repo.owner, &repo.name)
+            .releases()
+            .get_by_tag(tag)
+            .await
+            .with_context(|| format!("Failed to get `{}` release", tag))?;
+        upload_github_release_asset(ctx.github_client(), repo, release.id, &path).await?;
+        ctx.record_upload(std::fs::metadata(&path)?.len());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn asset_download_url() {
+        let repo = Repo::new("owner", "app");
+        expect!["https://github.com/owner/app/releases/download/v1.2.0/app%201.2.0+linux.tar.gz"]
+            .assert_eq(&download_url(&repo, "v1.2.0", "app 1.2.0+linux.tar.gz").unwrap());
+    }
+}
@@ -1,56 +1,126 @@
 use crate::{
-    release::{ReleaseContext, ReleaseStep},
-    utils::run_and_capture_stdout,
+    calver::CalVerPattern,
+    config::VersionSource,
+    explain::ErrorCode,
+    i18n::tr,
+    registry::{
+        publish_registry, query_crate, query_index_crate, registry_token_var,
+        CRATES_IO_REGISTRY_NAME,
+    },
+    release::{
+        resource::{PendingVersion, PrevVersion, PublishedRootCrate},
+        ReleaseContext, ReleaseStep, ResourceId, StepKind,
+    },
 };
-use anyhow::bail;
+use anyhow::anyhow;
 use async_trait::async_trait;
-use cargo_metadata::{DependencyKind, Package};
+use cargo_metadata::{DependencyKind, Metadata, Package};
 use semver::Version;
-use tokio::process::Command;
+use std::collections::HashMap;
 
 pub struct VaidateVersion;
 
-pub const CRATES_IO_REGISTRY_NAME: &str = "crates-io";
-
 impl VaidateVersion {
     async fn check_version_raised(
         &self,
         version: Version,
-        ctx: &mut ReleaseContext,
+        ctx: &ReleaseContext,
     ) -> anyhow::Result<()> {
         if !ctx.release_config()?.check_version_raised {
             println!("\tVersion raise check was skipped");
+            ctx.provide(PrevVersion(None))?;
+            ctx.provide(PublishedRootCrate(None))?;
             return Ok(());
         } else {
             println!("\tChecking that version has been raised...");
         }
 
         // If crate is not new, check that version has been raised
-        let prev_version = query_last_released_version(&ctx.root_crate_name()).await?;
-        ctx.prev_version = if let Some(prev_version) = prev_version {
+        let root_crate = ctx.root_crate_name();
+        let published = match &ctx.release_config()?.registry {
+            Some(registry) => {
+                query_index_crate(ctx.release_config()?, registry, &root_crate).await?
+            }
+            None => query_crate(&root_crate).await?,
+        }
+        .unwrap_or_default();
+        if let Some(line) = &ctx.maintenance_line {
+            println!("\tLooking for the last release in {} line...", line);
+        }
+        let prev_version = published
+            .released_versions()
+            .filter(|v| ctx.maintenance_line.as_ref().is_none_or(|l| l.contains(v)))
+            .max()
+            .cloned();
+        if published.is_yanked(&version) == Some(true) {
+            return Err(ErrorCode::VersionNotRaised.error(format!(
+                "Pending version {} has been published and yanked before, it can't be published again",
+                version
+            )));
+        }
+        if let Some(calver) = ctx.release_config()?.calver() {
+            self.check_calver(calver, &version, prev_version.as_ref())?;
+        }
+        if let Some(prev_version) = &prev_version {
             println!("\tQueried previous crate version: {}", prev_version);
-            if version <= prev_version {
-                bail!("Pending version is lower or equal to already published version")
+            if version <= *prev_version {
+                return Err(ErrorCode::VersionNotRaised
+                    .error("Pending version is lower or equal to already published version"));
             }
-            Some(Some(prev_version))
         } else {
             println!("\tWARN: Previously published root crate not found");
-            Some(None)
-        };
+        }
+        ctx.provide(PrevVersion(prev_version))?;
+        ctx.provide(PublishedRootCrate(Some(published)))?;
+
+        Ok(())
+    }
 
+    /// Date components of CalVer version should correspond to the release date
+    fn check_calver(
+        &self,
+        calver: &CalVerPattern,
+        version: &Version,
+        prev_version: Option<&Version>,
+    ) -> anyhow::Result<()> {
+        let today = chrono::Utc::now().date_naive();
+        let expected = calver.next(prev_version, today);
+        if !calver.matches_date(version, today) {
+            return Err(ErrorCode::CalVerMismatch.error(format!(
+                "Pending version {} does not match {} CalVer pattern for {}, expected {}",
+                version, calver, today, expected
+            )));
+        }
+        println!("\tVersion {} matches {} CalVer pattern", version, calver);
+        Ok(())
+    }
+
+    fn check_maintenance_line(
+        &self,
+        version: &Version,
+        ctx: &ReleaseContext,
+    ) -> anyhow::Result<()> {
+        if let Some(line) = &ctx.maintenance_line {
+            if !line.contains(version) {
+                return Err(ErrorCode::MaintenanceLineMismatch.error(format!(
+                    "Pending version {} does not belong to {} maintenance line",
+                    version, line
+                )));
+            }
+        }
         Ok(())
     }
 
-    async fn check_dev_dependencies(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
+    async fn check_dev_dependencies(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
         if ctx.release_config()?.allow_non_path_dev_dependencies {
             return Ok(());
         }
 
         println!("\tChecking create workspace dependencies...");
 
-        let workspace_packages = ctx.packages_to_publish()?;
+        let workspace_packages = ctx.packages_to_publish();
 
-        let workspace_package_names = ctx.workspace_package_names()?;
+        let workspace_package_names = ctx.workspace_package_names();
 
         let mut invalid_dev_dependencies = false;
 
@@ -82,20 +152,25 @@ impl VaidateVersion {
         }
 
         if invalid_dev_dependencies {
-            bail!(
+            return Err(ErrorCode::DevDependencyVersion.error(
                 "Detected invalid dev dependencies: version field should not be \
-                specified for in-workspace dev-dependencies"
-            );
+                specified for in-workspace dev-dependencies",
+            ));
         }
 
         Ok(())
     }
 
-    async fn check_registry_consistency(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
+    async fn check_registry_consistency(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
         println!("\tChecking package registry consistency...");
-        let workspace_packages = ctx.packages_to_publish()?;
+        let workspace_packages = ctx.packages_to_publish();
+
+        let release = ctx.release_config()?;
+        if release.route_by_publish {
+            return self.check_routed_registries(ctx);
+        }
 
-        let registry = ctx.release_config()?.registry.clone();
+        let registry = release.registry.clone();
 
         let mut inconsistent_registries = false;
 
@@ -120,7 +195,38 @@ impl VaidateVersion {
         }
 
         if inconsistent_registries {
-            bail!("Package registry inconsistency detected");
+            return Err(
+                ErrorCode::RegistryNotAllowed.error("Package registry inconsistency detected")
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Every crate is published to the registry from its `publish` field, so
+    /// only the token of each target registry is required
+    fn check_routed_registries(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        let release = ctx.release_config()?;
+
+        let mut missing_tokens = false;
+        for p in ctx.packages_to_publish() {
+            let registry = publish_registry(release, p.publish.as_deref());
+            let registry_name = registry.as_deref().unwrap_or(CRATES_IO_REGISTRY_NAME);
+            let token_var = registry_token_var(registry.as_deref());
+            if std::env::var_os(&token_var).is_some() {
+                println!("\t✅ {} is published to `{}`", p.name, registry_name);
+            } else {
+                println!(
+                    "\t❌ {} is published to `{}`, but {} env var is not set",
+                    p.name, registry_name, token_var
+                );
+                missing_tokens = true;
+            }
+        }
+
+        if missing_tokens {
+            return Err(ErrorCode::RegistryTokenMissing
+                .error("Registry tokens are missing for some of the routed packages"));
         }
 
         Ok(())
@@ -129,94 +235,164 @@ impl VaidateVersion {
     async fn check_version_consistency(
         &self,
         version: Version,
-        ctx: &mut ReleaseContext,
+        ctx: &ReleaseContext,
     ) -> anyhow::Result<()> {
         println!("\tChecking for crates version consistency...");
 
-        let packages_to_publish = ctx.packages_to_publish()?;
-        let workspace_package_names = ctx.workspace_package_names()?;
+        // Released crates have independent versions, only requirements
+        // between them should be consistent
+        let independent_versions = ctx.release_config()?.bump_dependents;
 
-        let mut inconsistent = false;
+        let inconsistent =
+            !versions_consistent(&ctx.packages_to_publish(), &version, independent_versions);
 
-        for package in packages_to_publish.iter() {
-            let full_name = full_package_name(package);
-
-            if package.version.clone() != version {
-                inconsistent = true;
-                println!("\t❌ {} have inconsistent version", full_name);
-                continue;
+        if inconsistent {
+            if ctx.release_config()?.version_command.is_some() {
+                return Err(ErrorCode::VersionSkew.error(format!(
+                    "Detected version inconsistency in crates; crate versions should be set \
+                    to v{} returned by release.version_command before the release",
+                    version
+                )));
             }
+            if ctx.release_config()?.version_from == VersionSource::ConventionalCommits {
+                return Err(ErrorCode::VersionSkew.error(format!(
+                    "Detected version inconsistency in crates; crate versions should be set \
+                    to v{} inferred from the conventional commits, run `cargo monorepo bump`",
+                    version
+                )));
+            }
+            return Err(ErrorCode::VersionSkew.error("Detected version inconsistency in crates"));
+        }
 
-            let mut dependenies_inconsistent = false;
-            let mut inconsistent_deps_list = vec![];
+        Ok(())
+    }
+}
 
-            for dep in &package.dependencies {
-                let dep_inconsistent =
-                    workspace_package_names.contains(&dep.name) && !dep.req.matches(&version);
+/// Prints consistency status of every released crate, `false` if any of
+/// them has a version or a requirement on the other released crates which
+/// does not match
+pub fn versions_consistent(
+    packages_to_publish: &[&Package],
+    version: &Version,
+    independent_versions: bool,
+) -> bool {
+    // Crates outside of the released set (e.g. other release groups) are
+    // versioned independently
+    let released_versions = packages_to_publish
+        .iter()
+        .map(|p| (p.name.as_str(), &p.version))
+        .collect::<HashMap<_, _>>();
+
+    let mut inconsistent = false;
+
+    for package in packages_to_publish.iter() {
+        let full_name = full_package_name(package);
+
+        if !independent_versions && package.version != *version {
+            inconsistent = true;
+            println!("\t❌ {} have inconsistent version", full_name);
+            continue;
+        }
 
-                if dep_inconsistent {
-                    inconsistent_deps_list.push(format!("{} {}", dep.name, dep.req));
-                    dependenies_inconsistent = true;
-                }
-            }
+        let mut dependenies_inconsistent = false;
+        let mut inconsistent_deps_list = vec![];
 
-            if dependenies_inconsistent {
-                inconsistent = true;
-                println!(
-                    "\t❌ {} has inconsistent monorepo dependencies ({})",
-                    full_name,
-                    inconsistent_deps_list.join(", "),
-                );
-                continue;
-            }
+        for dep in &package.dependencies {
+            let dep_inconsistent = matches!(
+                released_versions.get(dep.name.as_str()),
+                Some(dep_version) if !dep.req.matches(dep_version)
+            );
 
-            println!("\t✅ {} is OK", full_name);
+            if dep_inconsistent {
+                inconsistent_deps_list.push(format!("{} {}", dep.name, dep.req));
+                dependenies_inconsistent = true;
+            }
         }
 
-        if inconsistent {
-            bail!("Detected version inconsistency in crates");
+        if dependenies_inconsistent {
+            inconsistent = true;
+            println!(
+                "\t❌ {} has inconsistent monorepo dependencies ({})",
+                full_name,
+                inconsistent_deps_list.join(", "),
+            );
+            continue;
         }
 
-        Ok(())
+        println!("\t✅ {} is OK", full_name);
     }
+
+    !inconsistent
 }
 
 #[async_trait]
 impl ReleaseStep for VaidateVersion {
-    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
-        Ok("Validating repo versioning".to_string())
+    fn name(&self) -> &'static str {
+        "validate-version"
     }
 
-    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
-        Ok("Version validation done".to_string())
+    fn kind(&self) -> StepKind {
+        StepKind::Validation
     }
 
-    async fn execute(&self, ctx: &mut ReleaseContext) -> anyhow::Result<()> {
-        let version = ctx.version()?;
-        self.check_registry_consistency(ctx).await?;
-        self.check_version_raised(version.clone(), ctx).await?;
-        self.check_dev_dependencies(ctx).await?;
-        self.check_version_consistency(version, ctx).await?;
+    fn requires(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<Metadata>(),
+            ResourceId::of::<PendingVersion>(),
+        ]
+    }
 
-        Ok(())
+    fn provides(&self) -> Vec<ResourceId> {
+        vec![
+            ResourceId::of::<PrevVersion>(),
+            ResourceId::of::<PublishedRootCrate>(),
+        ]
     }
-}
 
-async fn query_last_released_version(crate_name: &str) -> anyhow::Result<Option<Version>> {
-    let mut cmd = Command::new("cargo");
-    cmd.args(["search", crate_name]);
-    let stdout = run_and_capture_stdout(&mut cmd).await?;
+    fn start_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Validating repo versioning"))
+    }
 
-    let crate_prefix = format!("{} = ", crate_name);
+    fn success_message(&self, _: &ReleaseContext) -> anyhow::Result<String> {
+        Ok(tr!("Version validation done"))
+    }
 
-    let version_str = stdout
-        .split('\n')
-        .find(|s| s.starts_with(&crate_prefix))
-        .and_then(|s| s.trim().split('"').nth(1));
+    async fn execute(&self, ctx: &ReleaseContext) -> anyhow::Result<()> {
+        if ctx.is_nightly() {
+            // Nightly version is derived from the workspace version, which is
+            // not required to be raised as nothing is published to the registry
+            ctx.provide(PrevVersion(None))?;
+            ctx.provide(PublishedRootCrate(None))?;
+            let root_crate = ctx.root_crate_name();
+            let workspace_version = ctx
+                .packages_to_publish()
+                .iter()
+                .find(|p| p.name == root_crate)
+                .map(|p| p.version.clone())
+                .ok_or_else(|| {
+                    anyhow!("Failed to find root crate ({}) in workspace", root_crate)
+                })?;
+            self.check_dev_dependencies(ctx).await?;
+            self.check_version_consistency(workspace_version, ctx)
+                .await?;
+            return Ok(());
+        }
 
-    let version = version_str.map(Version::parse).transpose()?;
+        let version = ctx.version();
+        self.check_maintenance_line(version, ctx)?;
+        if ctx.github_only || ctx.offline {
+            println!("\tRegistry checks were skipped");
+            ctx.provide(PrevVersion(None))?;
+            ctx.provide(PublishedRootCrate(None))?;
+        } else {
+            self.check_registry_consistency(ctx).await?;
+            self.check_version_raised(version.clone(), ctx).await?;
+        }
+        self.check_dev_dependencies(ctx).await?;
+        self.check_version_consistency(version.clone(), ctx).await?;
 
-    Ok(version)
+        Ok(())
+    }
 }
 
 fn full_package_name(p: &Package) -> String {
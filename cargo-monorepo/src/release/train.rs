@@ -0,0 +1,70 @@
+use crate::config::Train;
+use chrono::{Duration, NaiveDate};
+
+/// Release train which is closest to the given date (current or previous one)
+#[derive(Debug, PartialEq, Eq)]
+pub struct TrainWindow {
+    /// Sequential train number counted from the anchor date
+    pub number: i64,
+    pub date: NaiveDate,
+    pub open: bool,
+    pub next_date: NaiveDate,
+}
+
+impl TrainWindow {
+    pub fn at(train: &Train, today: NaiveDate) -> Self {
+        let cadence = i64::from(train.cadence_days);
+        let days = (today - train.anchor_date).num_days();
+        let number = days.div_euclid(cadence);
+        let date = train.anchor_date + Duration::days(number * cadence);
+
+        Self {
+            number,
+            date,
+            open: days.rem_euclid(cadence) < i64::from(train.window_days),
+            next_date: date + Duration::days(cadence),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::VersionBump;
+    use expect_test::expect;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn train_window() {
+        let train = Train {
+            anchor_date: date("2023-01-10"),
+            cadence_days: 14,
+            window_days: 2,
+            branch: None,
+            version_bump: VersionBump::Minor,
+        };
+
+        expect![[r#"
+            TrainWindow {
+                number: 2,
+                date: 2023-02-07,
+                open: true,
+                next_date: 2023-02-21,
+            }
+        "#]]
+        .assert_debug_eq(&TrainWindow::at(&train, date("2023-02-08")));
+
+        expect![[r#"
+            TrainWindow {
+                number: -1,
+                date: 2022-12-27,
+                open: false,
+                next_date: 2023-01-10,
+            }
+        "#]]
+        .assert_debug_eq(&TrainWindow::at(&train, date("2023-01-03")));
+    }
+}
@@ -0,0 +1,144 @@
+//! Yank of the released version from every published workspace crate, used
+//! to clean up a botched release
+use super::{context::packages_to_publish, deprecate::run_cargo};
+use crate::{
+    cargo::sort_workspace,
+    config::Config,
+    registry::{publish_registry, query_crate, query_index_crate, RegistryCrate},
+    utils::metadata_command,
+};
+use anyhow::{anyhow, bail};
+use semver::Version;
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Yank the version of every published workspace crate")]
+pub struct YankCommand {
+    /// Version to yank
+    version: Version,
+    /// Actually execute command instead of dry run
+    #[structopt(long)]
+    confirm: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum YankAction {
+    Yank,
+    AlreadyYanked,
+    NotPublished,
+}
+
+/// `published` is `None` if the registry can't be queried, so yank is
+/// attempted anyway
+fn yank_action(published: Option<&RegistryCrate>, version: &Version) -> YankAction {
+    match published.map(|p| p.is_yanked(version)) {
+        Some(None) => YankAction::NotPublished,
+        Some(Some(true)) => YankAction::AlreadyYanked,
+        Some(Some(false)) | None => YankAction::Yank,
+    }
+}
+
+impl YankCommand {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        let metadata = metadata_command()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let released = packages_to_publish(&metadata, &config);
+        // Dependents are yanked first, so nothing resolves to the partially
+        // yanked release in between
+        let ordered = sort_workspace(&metadata)?
+            .into_iter()
+            .rev()
+            .filter_map(|id| released.iter().find(|p| p.id == id))
+            .collect::<Vec<_>>();
+
+        let version = self.version.to_string();
+        let mut failures = vec![];
+        for package in ordered {
+            let registry = config
+                .release
+                .as_ref()
+                .and_then(|r| publish_registry(r, package.publish.as_deref()));
+            let published = match (&registry, &config.release) {
+                (None, _) => Some(query_crate(&package.name).await?.unwrap_or_default()),
+                (Some(registry), Some(release)) => {
+                    query_index_crate(release, registry, &package.name)
+                        .await
+                        .ok()
+                        .map(Option::unwrap_or_default)
+                }
+                (Some(_), None) => None,
+            };
+            match yank_action(published.as_ref(), &self.version) {
+                YankAction::NotPublished => {
+                    println!(
+                        "⏭️ {} v{} is not published, skipping",
+                        package.name, version
+                    );
+                    continue;
+                }
+                YankAction::AlreadyYanked => {
+                    println!("⏭️ {} v{} is already yanked", package.name, version);
+                    continue;
+                }
+                YankAction::Yank => {}
+            }
+
+            let mut yank = vec!["yank", package.name.as_str(), "--version", version.as_str()];
+            if let Some(registry) = &registry {
+                yank.extend(["--registry", registry.as_str()].iter());
+            }
+            if !self.confirm {
+                println!("WOULD: cargo {}", yank.join(" "));
+                continue;
+            }
+            match run_cargo(&yank).await {
+                Ok(()) => println!("✅ {} v{} has been yanked", package.name, version),
+                Err(e) => failures.push(format!("{} v{}: {:#}", package.name, version, e)),
+            }
+        }
+
+        if !failures.is_empty() {
+            bail!("Failed to yank some crates:\n{}", failures.join("\n"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::CrateVersion;
+
+    #[test]
+    fn yank_actions() {
+        let published = RegistryCrate {
+            versions: vec![
+                CrateVersion {
+                    num: Version::new(1, 0, 0),
+                    yanked: true,
+                },
+                CrateVersion {
+                    num: Version::new(1, 1, 0),
+                    yanked: false,
+                },
+            ],
+        };
+        let actions = vec![
+            Version::new(1, 0, 0),
+            Version::new(1, 1, 0),
+            Version::new(1, 2, 0),
+        ]
+        .into_iter()
+        .map(|v| yank_action(Some(&published), &v))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                YankAction::AlreadyYanked,
+                YankAction::Yank,
+                YankAction::NotPublished
+            ]
+        );
+        assert_eq!(yank_action(None, &Version::new(1, 2, 0)), YankAction::Yank);
+    }
+}
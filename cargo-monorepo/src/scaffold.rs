@@ -0,0 +1,285 @@
+use crate::{
+    config::Config,
+    pattern::FilePattern,
+    utils::{metadata_command, normalize_path, workspace_path},
+};
+use anyhow::{anyhow, bail, Context};
+use semver::Version;
+use std::path::{Path, PathBuf};
+use toml_edit::{value, Document, InlineTable, Item, Table};
+
+/// `[workspace.package]` keys which can be inherited by the member crates
+const INHERITABLE_PACKAGE_KEYS: &[&str] = &[
+    "version",
+    "authors",
+    "description",
+    "documentation",
+    "edition",
+    "homepage",
+    "keywords",
+    "categories",
+    "license",
+    "license-file",
+    "publish",
+    "readme",
+    "repository",
+    "rust-version",
+];
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Create new workspace member crate")]
+pub struct Command {
+    /// Crate name
+    name: String,
+    /// Crate directory, relative to the workspace root
+    #[structopt(long)]
+    path: PathBuf,
+    /// Create binary crate instead of library
+    #[structopt(long)]
+    bin: bool,
+    /// Create per-crate CHANGELOG.md
+    #[structopt(long)]
+    changelog: bool,
+}
+
+impl Command {
+    pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        validate_crate_name(&self.name)?;
+
+        let metadata = metadata_command()
+            .no_deps()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let workspace_root = metadata.workspace_root.as_std_path();
+
+        if metadata.packages.iter().any(|p| p.name == self.name) {
+            bail!("Crate `{}` already exists in the workspace", self.name);
+        }
+
+        let crate_dir = normalize_path(&workspace_path(&self.path));
+        if crate_dir.exists() {
+            bail!("{} already exists", crate_dir.display());
+        }
+        let member_path = member_path(&crate_dir, workspace_root)?;
+
+        let workspace_manifest_path = workspace_root.join("Cargo.toml");
+        let workspace_manifest = read_manifest(&workspace_manifest_path).await?;
+
+        // New crate follows lockstep versioning unless version is inherited
+        let root_version = metadata
+            .packages
+            .iter()
+            .find(|p| p.name == config.workspace.root_crate)
+            .map(|p| p.version.clone());
+        let manifest = render_manifest(&self.name, &workspace_manifest, root_version.as_ref());
+
+        let src_dir = crate_dir.join("src");
+        tokio::fs::create_dir_all(&src_dir)
+            .await
+            .with_context(|| format!("Failed to create {}", src_dir.display()))?;
+        tokio::fs::write(crate_dir.join("Cargo.toml"), manifest).await?;
+        if self.bin {
+            tokio::fs::write(
+                src_dir.join("main.rs"),
+                "fn main() {\n    println!(\"Hello, world!\");\n}\n",
+            )
+            .await?;
+        } else {
+            tokio::fs::write(src_dir.join("lib.rs"), "").await?;
+        }
+        if self.changelog {
+            let changelog = format!(
+                "# Changelog\n\n\
+                All notable changes to `{}` will be documented in this file.\n\n\
+                ## Unreleased\n",
+                self.name
+            );
+            tokio::fs::write(crate_dir.join("CHANGELOG.md"), changelog).await?;
+        }
+        println!(
+            "📦 Created `{}` crate in {}",
+            self.name,
+            crate_dir.display()
+        );
+
+        let mut workspace_manifest = workspace_manifest;
+        if register_member(&mut workspace_manifest, &member_path)? {
+            tokio::fs::write(&workspace_manifest_path, workspace_manifest.to_string())
+                .await
+                .with_context(|| {
+                    format!("Failed to write {}", workspace_manifest_path.display())
+                })?;
+            println!("📝 Added `{}` to the workspace members", member_path);
+        }
+
+        Ok(())
+    }
+}
+
+/// Path of the normalized crate directory as written to `workspace.members`
+fn member_path(crate_dir: &Path, workspace_root: &Path) -> anyhow::Result<String> {
+    Ok(crate_dir
+        .strip_prefix(normalize_path(workspace_root))
+        .map_err(|_| anyhow!("Crate path should be inside of the workspace"))?
+        .to_string_lossy()
+        .replace('\\', "/"))
+}
+
+fn validate_crate_name(name: &str) -> anyhow::Result<()> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!(
+            "Invalid crate name `{}`: only ASCII letters, digits, `-` and `_` are allowed",
+            name
+        );
+    }
+    Ok(())
+}
+
+async fn read_manifest(path: &Path) -> anyhow::Result<Document> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn render_manifest(name: &str, workspace_manifest: &Document, version: Option<&Version>) -> String {
+    let workspace = workspace_manifest.get("workspace");
+    let shared_package = workspace
+        .and_then(|w| w.get("package"))
+        .and_then(Item::as_table_like);
+
+    let mut package = Table::new();
+    package["name"] = value(name);
+    let inherited = |key: &str| shared_package.is_some_and(|p| p.contains_key(key));
+    if !inherited("version") {
+        let version = version.map_or_else(|| "0.1.0".to_owned(), ToString::to_string);
+        package["version"] = value(version);
+    }
+    for key in INHERITABLE_PACKAGE_KEYS {
+        if inherited(key) {
+            let mut inherit = InlineTable::new();
+            inherit.insert("workspace", true.into());
+            inherit.set_dotted(true);
+            package.insert(key, value(inherit));
+        }
+    }
+
+    let mut manifest = Document::new();
+    manifest["package"] = Item::Table(package);
+    if workspace.and_then(|w| w.get("lints")).is_some() {
+        let mut lints = Table::new();
+        lints["workspace"] = value(true);
+        manifest["lints"] = Item::Table(lints);
+    }
+    manifest["dependencies"] = Item::Table(Table::new());
+
+    manifest.to_string()
+}
+
+/// Adds member to the workspace, returns false if it is already covered by
+/// the existing members list
+fn register_member(workspace_manifest: &mut Document, member_path: &str) -> anyhow::Result<bool> {
+    let members = workspace_manifest
+        .get_mut("workspace")
+        .and_then(|w| w.get_mut("members"))
+        .and_then(Item::as_array_mut)
+        .ok_or_else(|| anyhow!("workspace.members is missing from the workspace manifest"))?;
+
+    let covered = members
+        .iter()
+        .filter_map(|m| m.as_str())
+        .any(|m| FilePattern::new(m).is_ok_and(|p| p.matches(member_path)));
+    if covered {
+        return Ok(false);
+    }
+
+    members.push(member_path);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    const WORKSPACE_MANIFEST: &str = r#"
+[workspace]
+members = ["crates/core", "tools/*"]
+
+[workspace.package]
+edition = "2021"
+license = "MIT"
+repository = "https://github.com/owner/repo"
+
+[workspace.lints.rust]
+unsafe_code = "forbid"
+"#;
+
+    #[test]
+    fn member_paths() {
+        let root = Path::new("/ws");
+        let paths = [
+            "/ws/crates/../tools/cli",
+            "/ws/./crates/core",
+            "/ws/../other",
+        ]
+        .iter()
+        .map(
+            |dir| match member_path(&normalize_path(Path::new(dir)), root) {
+                Ok(path) => path,
+                Err(e) => e.to_string(),
+            },
+        )
+        .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                "tools/cli",
+                "crates/core",
+                "Crate path should be inside of the workspace",
+            ]
+        "#]]
+        .assert_debug_eq(&paths);
+    }
+
+    #[test]
+    fn manifest_inherits_workspace_metadata() {
+        let workspace_manifest = WORKSPACE_MANIFEST.parse::<Document>().unwrap();
+        let manifest = render_manifest("foo", &workspace_manifest, Some(&Version::new(1, 2, 0)));
+
+        expect![[r#"
+            [package]
+            name = "foo"
+            version = "1.2.0"
+            edition.workspace = true
+            license.workspace = true
+            repository.workspace = true
+
+            [lints]
+            workspace = true
+
+            [dependencies]
+        "#]]
+        .assert_eq(&manifest);
+    }
+
+    #[test]
+    fn member_registration() {
+        let mut workspace_manifest = WORKSPACE_MANIFEST.parse::<Document>().unwrap();
+
+        assert!(!register_member(&mut workspace_manifest, "tools/gen").unwrap());
+        assert!(register_member(&mut workspace_manifest, "crates/api").unwrap());
+
+        expect![[r#"["crates/core", "tools/*", "crates/api"]"#]].assert_eq(
+            workspace_manifest["workspace"]["members"]
+                .to_string()
+                .trim(),
+        );
+    }
+}
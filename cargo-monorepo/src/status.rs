@@ -0,0 +1,145 @@
+use crate::{config::Config, utils::metadata_command};
+use anyhow::anyhow;
+use cargo_metadata::{DependencyKind, Package};
+use semver::{Op, Version, VersionReq};
+use std::collections::BTreeMap;
+
+#[derive(clap::Parser, Debug)]
+#[structopt(about = "Show workspace crate versions and internal dependency requirement skew")]
+pub struct Command {}
+
+/// Internal dependency requirement which lags behind the dependency version
+#[derive(Debug, PartialEq, Eq)]
+struct Skew {
+    dependent: String,
+    dependency: String,
+    req: VersionReq,
+    version: Version,
+    /// Requirement is not satisfied by the workspace version at all
+    unsatisfied: bool,
+}
+
+/// Lowest version accepted by the requirement, `None` for wildcards and
+/// upper bounds only
+fn requirement_floor(req: &VersionReq) -> Option<Version> {
+    req.comparators
+        .iter()
+        .filter(|c| matches!(c.op, Op::Exact | Op::Caret | Op::Tilde | Op::GreaterEq))
+        .map(|c| Version::new(c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0)))
+        .max()
+}
+
+fn find_skews(packages: &[&Package]) -> Vec<Skew> {
+    let versions = packages
+        .iter()
+        .map(|p| (p.name.as_str(), &p.version))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut skews = vec![];
+    for p in packages {
+        for dep in &p.dependencies {
+            // Dev dependencies are not published with the crate
+            if dep.kind == DependencyKind::Development || dep.path.is_none() {
+                continue;
+            }
+            let version = match versions.get(dep.name.as_str()) {
+                Some(version) => *version,
+                None => continue,
+            };
+            let floor = match requirement_floor(&dep.req) {
+                Some(floor) => floor,
+                None => continue,
+            };
+            if &floor < version || !dep.req.matches(version) {
+                skews.push(Skew {
+                    dependent: p.name.clone(),
+                    dependency: dep.name.clone(),
+                    req: dep.req.clone(),
+                    version: version.clone(),
+                    unsatisfied: !dep.req.matches(version),
+                });
+            }
+        }
+    }
+    skews.sort_by(|a, b| (&a.dependent, &a.dependency).cmp(&(&b.dependent, &b.dependency)));
+    skews.dedup();
+    skews
+}
+
+impl Command {
+    pub async fn run(self, _config: Config) -> anyhow::Result<()> {
+        let metadata = metadata_command()
+            .no_deps()
+            .exec()
+            .map_err(|e| anyhow!("Failed to parse cargo metadata: {}", e))?;
+        let mut packages = metadata
+            .packages
+            .iter()
+            .filter(|p| metadata.workspace_members.contains(&p.id))
+            .collect::<Vec<_>>();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+        println!("📦 Workspace crates:");
+        for p in &packages {
+            let unpublished = matches!(&p.publish, Some(registries) if registries.is_empty());
+            if unpublished {
+                println!("\t{} v{} (not published)", p.name, p.version);
+            } else {
+                println!("\t{} v{}", p.name, p.version);
+            }
+        }
+
+        let skews = find_skews(&packages);
+        if skews.is_empty() {
+            println!("✅ Internal dependency requirements match workspace versions");
+            return Ok(());
+        }
+
+        println!("⚠️ Internal dependency requirement skew:");
+        for skew in &skews {
+            println!(
+                "\t{} {} -> {} {}, workspace version is {}",
+                if skew.unsatisfied { "❌" } else { "↘️" },
+                skew.dependent,
+                skew.dependency,
+                skew.req,
+                skew.version
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn requirement_floors() {
+        let floors = vec!["^1.2", "~1.4.1", "=2.0.0", ">=1.1, <2", "*", "<3"]
+            .into_iter()
+            .map(|req| {
+                let req = VersionReq::parse(req).unwrap();
+                format!(
+                    "{} -> {:?}",
+                    req,
+                    requirement_floor(&req).map(|v| v.to_string())
+                )
+            })
+            .collect::<Vec<_>>();
+
+        expect![[r#"
+            [
+                "^1.2 -> Some(\"1.2.0\")",
+                "~1.4.1 -> Some(\"1.4.1\")",
+                "=2.0.0 -> Some(\"2.0.0\")",
+                ">=1.1, <2 -> Some(\"1.1.0\")",
+                "* -> None",
+                "<3 -> None",
+            ]
+        "#]]
+        .assert_debug_eq(&floors);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::calver::CalVerContext;
 use anyhow::{anyhow, Context};
 use handlebars::Handlebars;
 use semver::Version;
@@ -8,6 +9,55 @@ pub struct TextTemplateContext {
     pub root_crate: String,
     pub version: Version,
     pub changelog: Option<String>,
+    /// Set for emergency releases made with `--hotfix`
+    pub hotfix: bool,
+    /// Name of the registry crates are published to
+    pub registry: String,
+    /// Registry crate pages URL, e.g. `{{registry_url}}/{{root_crate}}`
+    pub registry_url: Option<String>,
+    /// Crates published by the release, empty until publish is finished
+    pub published_crates: Vec<PublishedCrateContext>,
+    /// All workspace crates, available to `{{#each packages}}`
+    pub packages: Vec<PackageContext>,
+    /// Release notes of the published crates under per-crate headings, set
+    /// with `changelog.crate_notes`
+    pub crate_notes: Option<String>,
+    /// Assets of the GitHub release, empty until upload is finished
+    pub assets: Vec<AssetContext>,
+    /// Date components of the version, set for `version_scheme = "calver"`
+    pub calver: Option<CalVerContext>,
+    /// Previous released version, if known
+    pub prev_version: Option<Version>,
+    /// GitHub release page of the previous version, if exists
+    pub prev_release_url: Option<String>,
+    /// Assets of the previous GitHub release
+    pub prev_assets: Vec<AssetContext>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct AssetContext {
+    pub name: String,
+    pub size: u64,
+    pub browser_download_url: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PublishedCrateContext {
+    pub name: String,
+    pub version: Version,
+    /// Crate page in the registry UI, if known
+    pub registry_url: Option<String>,
+    /// Set only for crates.io, as docs.rs builds only its crates
+    pub docs_url: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PackageContext {
+    pub name: String,
+    pub version: Version,
+    pub description: Option<String>,
+    /// Crate is published by the release
+    pub publish: bool,
 }
 
 #[derive(Clone)]
@@ -26,7 +76,8 @@ impl TextTemplate {
         Ok(Self { renderer })
     }
 
-    pub fn render(&self, context: &TextTemplateContext) -> anyhow::Result<String> {
+    /// Renders template with [`TextTemplateContext`] or its extension
+    pub fn render(&self, context: &impl Serialize) -> anyhow::Result<String> {
         self.renderer
             .render("t", context)
             .map_err(|e| anyhow!("Failed to render template: {}", e))
@@ -66,6 +117,17 @@ mod tests {
             root_crate: "monorepo".to_owned(),
             version: Version::new(1, 1, 1),
             changelog: None,
+            hotfix: false,
+            registry: "crates.io".to_owned(),
+            registry_url: Some("https://crates.io/crates".to_owned()),
+            published_crates: vec![],
+            packages: vec![],
+            crate_notes: None,
+            assets: vec![],
+            calver: None,
+            prev_version: None,
+            prev_release_url: None,
+            prev_assets: vec![],
         };
 
         let template = toml::from_str::<TestToml>("template = \"{{root_crate}} - {{version}}\"")
@@ -102,4 +164,159 @@ mod tests {
         "#]]
             .assert_debug_eq(&result);
     }
+
+    #[test]
+    fn published_crates_table() {
+        let context = TextTemplateContext {
+            root_crate: "monorepo".to_owned(),
+            version: Version::new(1, 1, 1),
+            changelog: None,
+            hotfix: false,
+            registry: "crates.io".to_owned(),
+            registry_url: Some("https://crates.io/crates".to_owned()),
+            published_crates: vec![PublishedCrateContext {
+                name: "monorepo-core".to_owned(),
+                version: Version::new(1, 1, 1),
+                registry_url: Some("https://crates.io/crates/monorepo-core".to_owned()),
+                docs_url: Some("https://docs.rs/monorepo-core/1.1.1".to_owned()),
+            }],
+            packages: vec![],
+            crate_notes: None,
+            assets: vec![],
+            calver: None,
+            prev_version: None,
+            prev_release_url: None,
+            prev_assets: vec![],
+        };
+
+        let template = TextTemplate::new(
+            "{{#each published_crates}}\
+            | [{{name}}]({{registry_url}}) | {{version}} | [docs]({{docs_url}}) |\n\
+            {{/each}}",
+        )
+        .unwrap();
+
+        expect![[r#"
+            | [monorepo-core](https://crates.io/crates/monorepo-core) | 1.1.1 | [docs](https://docs.rs/monorepo-core/1.1.1) |
+        "#]].assert_eq(&template.render(&context).unwrap());
+    }
+
+    #[test]
+    fn assets_download_table() {
+        let context = TextTemplateContext {
+            root_crate: "monorepo".to_owned(),
+            version: Version::new(1, 1, 1),
+            changelog: None,
+            hotfix: false,
+            registry: "crates.io".to_owned(),
+            registry_url: None,
+            published_crates: vec![],
+            packages: vec![],
+            crate_notes: None,
+            assets: vec![AssetContext {
+                name: "monorepo-x86_64-linux.tar.gz".to_owned(),
+                size: 1024,
+                browser_download_url:
+                    "https://github.com/o/r/releases/download/v1.1.1/monorepo-x86_64-linux.tar.gz"
+                        .to_owned(),
+            }],
+            calver: None,
+            prev_version: None,
+            prev_release_url: None,
+            prev_assets: vec![],
+        };
+
+        let template = TextTemplate::new(
+            "{{#each assets}}\
+            | [{{name}}]({{browser_download_url}}) | {{size}} |\n\
+            {{/each}}",
+        )
+        .unwrap();
+
+        expect![[r#"
+            | [monorepo-x86_64-linux.tar.gz](https://github.com/o/r/releases/download/v1.1.1/monorepo-x86_64-linux.tar.gz) | 1024 |
+        "#]].assert_eq(&template.render(&context).unwrap());
+    }
+
+    #[test]
+    fn prev_release_links() {
+        let context = TextTemplateContext {
+            root_crate: "monorepo".to_owned(),
+            version: Version::new(1, 2, 0),
+            changelog: None,
+            hotfix: false,
+            registry: "crates.io".to_owned(),
+            registry_url: None,
+            published_crates: vec![],
+            packages: vec![],
+            crate_notes: None,
+            assets: vec![],
+            calver: None,
+            prev_version: Some(Version::new(1, 1, 1)),
+            prev_release_url: Some("https://github.com/o/r/releases/tag/v1.1.1".to_owned()),
+            prev_assets: vec![AssetContext {
+                name: "monorepo-x86_64-linux.tar.gz".to_owned(),
+                size: 1024,
+                browser_download_url:
+                    "https://github.com/o/r/releases/download/v1.1.1/monorepo-x86_64-linux.tar.gz"
+                        .to_owned(),
+            }],
+        };
+
+        let template = TextTemplate::new(
+            "Upgrade from [{{prev_version}}]({{prev_release_url}}):\n\
+            {{#each prev_assets}}\
+            - [{{name}}]({{browser_download_url}})\n\
+            {{/each}}",
+        )
+        .unwrap();
+
+        expect![[r#"
+            Upgrade from [1.1.1](https://github.com/o/r/releases/tag/v1.1.1):
+            - [monorepo-x86_64-linux.tar.gz](https://github.com/o/r/releases/download/v1.1.1/monorepo-x86_64-linux.tar.gz)
+        "#]].assert_eq(&template.render(&context).unwrap());
+    }
+
+    #[test]
+    fn workspace_packages_list() {
+        let package = |name: &str, description: Option<&str>, publish| PackageContext {
+            name: name.to_owned(),
+            version: Version::new(1, 2, 0),
+            description: description.map(str::to_owned),
+            publish,
+        };
+        let context = TextTemplateContext {
+            root_crate: "monorepo".to_owned(),
+            version: Version::new(1, 2, 0),
+            changelog: None,
+            hotfix: false,
+            registry: "crates.io".to_owned(),
+            registry_url: None,
+            published_crates: vec![],
+            packages: vec![
+                package("monorepo", Some("Umbrella crate"), true),
+                package("monorepo-utils", None, true),
+                package("monorepo-bench", Some("Benchmarks"), false),
+            ],
+            crate_notes: None,
+            assets: vec![],
+            calver: None,
+            prev_version: None,
+            prev_release_url: None,
+            prev_assets: vec![],
+        };
+
+        let template = TextTemplate::new(
+            "{{#each packages}}{{#if publish}}\
+            - **{{name}}** {{version}}{{#if description}}: {{description}}{{/if}}\n\
+            {{/if}}{{/each}}",
+        )
+        .unwrap();
+
+        expect![[r#"
+            - **monorepo** 1.2.0: Umbrella crate
+            - **monorepo-utils** 1.2.0
+        "#]]
+        .assert_eq(&template.render(&context).unwrap());
+    }
 }
@@ -1,20 +1,154 @@
-use anyhow::bail;
+use anyhow::{bail, Context};
+use cargo_metadata::MetadataCommand;
+use sha2::{Digest, Sha256};
+use std::{
+    ffi::OsStr,
+    io::Write,
+    path::{Component, Path, PathBuf},
+    sync::OnceLock,
+};
 use tokio::{
     io::{self, AsyncWriteExt},
     process::Command as OsCommand,
 };
 
+static WORKSPACE_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Sets the root which relative config paths and spawned commands are resolved
+/// against, so the process working directory is never changed
+pub fn set_workspace_root(root: PathBuf) {
+    let _ = WORKSPACE_ROOT.set(root);
+}
+
+pub fn workspace_root() -> &'static Path {
+    WORKSPACE_ROOT
+        .get()
+        .map(PathBuf::as_path)
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// Resolves `..` components without touching the filesystem
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Resolves config path relative to the workspace root, absolute paths are
+/// kept as is
+pub fn workspace_path(path: impl AsRef<Path>) -> PathBuf {
+    workspace_root().join(path)
+}
+
+/// Command which runs in the workspace root
+pub fn workspace_command(program: impl AsRef<OsStr>) -> OsCommand {
+    let mut cmd = OsCommand::new(program);
+    cmd.current_dir(workspace_root());
+    cmd
+}
+
+pub fn metadata_command() -> MetadataCommand {
+    let mut cmd = MetadataCommand::new();
+    cmd.current_dir(workspace_root());
+    cmd
+}
+
+/// Program and its arguments, as recorded to the audit log
+fn command_line(cmd: &OsCommand) -> String {
+    let cmd = cmd.as_std();
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|a| a.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 pub async fn run_and_capture_stdout(cmd: &mut OsCommand) -> anyhow::Result<String> {
-    let out = cmd.output().await?;
+    crate::audit::command(cmd);
+    let out = cmd
+        .output()
+        .await
+        .with_context(|| format!("Failed to run `{}`", command_line(cmd)))?;
     if !out.status.success() {
         io::stdout().write_all(&out.stdout).await?;
         io::stderr().write_all(&out.stderr).await?;
-        bail!("Failed to query crates.io packages");
+        bail!("`{}` failed with {}", command_line(cmd), out.status);
     }
 
     Ok(String::from_utf8(out.stdout)?)
 }
 
+/// Command output may end with `\r\n` on Windows, and hash may be shorter
+/// than 7 chars when abbreviated by git, so no byte slicing here
 pub fn shorten_commit(commit: impl AsRef<str>) -> String {
-    commit.as_ref()[0..7].to_owned()
+    commit.as_ref().trim().chars().take(7).collect()
+}
+
+/// Recursively copies directory content, existing files are overwritten
+pub fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn sha256_file(path: &Path) -> anyhow::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Appends `name=value` to the file of `GITHUB_OUTPUT` env var, so the value
+/// is available to the next steps of the GitHub Actions job; no-op outside
+/// of GitHub Actions
+pub fn set_github_output(name: &str, value: &str) -> anyhow::Result<()> {
+    let path = match std::env::var_os("GITHUB_OUTPUT") {
+        Some(path) => PathBuf::from(path),
+        None => return Ok(()),
+    };
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open GITHUB_OUTPUT file {}", path.display()))?;
+    writeln!(file, "{}={}", name, value)
+        .with_context(|| format!("Failed to write GITHUB_OUTPUT file {}", path.display()))
+}
+
+/// Opens the URL with the default browser of the platform
+pub async fn open_in_browser(url: &str) -> anyhow::Result<()> {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = OsCommand::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        OsCommand::new("open")
+    } else {
+        OsCommand::new("xdg-open")
+    };
+    cmd.arg(url);
+    let status = cmd
+        .status()
+        .await
+        .with_context(|| "Failed to launch browser")?;
+    if !status.success() {
+        bail!("Browser launcher exited with {}", status);
+    }
+    Ok(())
 }